@@ -1,8 +1,11 @@
 use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
 use std::path::Path;
-use zap_core::build_site;
+use std::sync::{Arc, Mutex};
+use zap_core::build_site_with_events;
+use zap_core::history::{BuildRecord, CACHE_DIR, append_record};
 use crate::config::load_build_config;
+use crate::progress::{console_handler, log_summary, print_timings, recording_handler};
 
 pub fn add_build_args(command: Command) -> Command {
     command
@@ -38,26 +41,102 @@ pub fn add_build_args(command: Command) -> Command {
                 .help("Configuration file")
                 .default_value("./zap.toml")
         )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Configuration profile to apply, e.g. production (or ZAP_PROFILE)")
+        )
 }
 
 pub fn make_subcommand() -> Command {
     add_build_args(Command::new("build"))
         .about("Build static site from markdown files")
+        .arg(
+            Arg::new("minify")
+                .long("minify")
+                .help("Minify the generated HTML")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("staging")
+                .long("staging")
+                .help("Build a preview deployment that search engines won't index")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("future")
+                .long("future")
+                .help("Include pages dated in the future and expired pages")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Fail on missing templates, unknown config keys, broken links and images, and empty collections")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("timings")
+                .long("timings")
+                .value_name("N")
+                .help("Show how long each build phase took, and the N slowest pages (default 10)")
+                .num_args(0..=1)
+                .default_missing_value("10")
+                .value_parser(clap::value_parser!(usize)),
+        )
 }
 
 pub fn execute(args: &ArgMatches) -> Result<()> {
     // Load cascading configuration
-    let zap_config = load_build_config(args)?;
+    let mut zap_config = load_build_config(args)?;
+    if args.try_get_one::<bool>("minify").ok().flatten() == Some(&true) {
+        zap_config.site.performance.get_or_insert_default().minify = true;
+    }
+    if args.try_get_one::<bool>("staging").ok().flatten() == Some(&true) {
+        zap_config.site.staging = true;
+    }
+    if args.try_get_one::<bool>("future").ok().flatten() == Some(&true) {
+        zap_config.site.future = true;
+    }
+    if args.try_get_one::<bool>("strict").ok().flatten() == Some(&true) {
+        zap_config.site.strict = true;
+    }
     let build_config = zap_config.build_config();
 
     let source_dir = Path::new(&build_config.source);
     let output_dir = Path::new(&build_config.output);
     let theme_dir = Path::new(&build_config.theme);
 
+    let timings = args.try_get_one::<usize>("timings").ok().flatten().copied();
+
+    let record = Arc::new(Mutex::new(BuildRecord::new()));
+    let on_event = recording_handler(console_handler(), record.clone());
+
     // Build site using shared function (dev_mode will be false for production)
-    build_site(&zap_config.site, source_dir, output_dir, theme_dir)?;
+    let result = build_site_with_events(
+        &zap_config.site,
+        source_dir,
+        output_dir,
+        theme_dir,
+        on_event,
+    );
+
+    // Failed builds are recorded too, so trends show when they started
+    let mut record = record.lock().unwrap().clone();
+    if let Err(e) = &result {
+        record.error = Some(e.to_string());
+    }
+    if let Err(e) = append_record(Path::new(CACHE_DIR), &record) {
+        tracing::warn!("could not record build history: {}", e);
+    }
+    let report = result?;
 
-    println!("Site built successfully in {}", output_dir.display());
+    log_summary(&report);
+    if let Some(slowest) = timings {
+        print_timings(&report, slowest);
+    }
+    tracing::info!("Site built successfully in {}", output_dir.display());
 
     Ok(())
 }
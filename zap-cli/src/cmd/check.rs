@@ -0,0 +1,47 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::collections::HashSet;
+use std::path::Path;
+use zap_core::build_site_with_events;
+use crate::cmd::build::add_build_args;
+use crate::config::load_build_config;
+use crate::progress::console_handler;
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("check"))
+        .about("Build the site and check internal links and anchors")
+        .arg(
+            Arg::new("warn-only")
+                .long("warn-only")
+                .help("Report broken links without failing")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let mut zap_config = load_build_config(args)?;
+    zap_config.site.check_links = true;
+    let build_config = zap_config.build_config();
+
+    let report = build_site_with_events(
+        &zap_config.site,
+        Path::new(&build_config.source),
+        Path::new(&build_config.output),
+        Path::new(&build_config.theme),
+        console_handler(),
+    )?;
+
+    let broken = &report.broken_links;
+    if broken.is_empty() {
+        tracing::info!("No broken links");
+        return Ok(());
+    }
+    let pages: HashSet<&Path> = broken.iter().map(|link| link.source.as_path()).collect();
+    let summary = format!("{} broken link(s) in {} page(s)", broken.len(), pages.len());
+    if !args.get_flag("warn-only") {
+        anyhow::bail!(summary);
+    }
+    tracing::warn!("{}", summary);
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::{Result, anyhow};
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+use zap_core::build_site_with_events;
+use zap_core::ipfs::{add, dnslink_name, dnslink_value, pin_remote, update_dnslink_cloudflare};
+use crate::cmd::build::add_build_args;
+use crate::config::{ZapConfig, load_build_config};
+use crate::progress::console_handler;
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("deploy"))
+        .about("Build the site and publish it")
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .value_name("TARGET")
+                .help("Where to publish the site")
+                .value_parser(["ipfs"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("no-dnslink")
+                .long("no-dnslink")
+                .help("Don't point the DNSLink domain at the new CID")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+
+    build_site_with_events(
+        &zap_config.site,
+        Path::new(&build_config.source),
+        Path::new(&build_config.output),
+        Path::new(&build_config.theme),
+        console_handler(),
+    )?;
+
+    match args.get_one::<String>("target").map(String::as_str) {
+        Some("ipfs") => deploy_ipfs(&zap_config, args),
+        _ => unreachable!(),
+    }
+}
+
+fn deploy_ipfs(zap_config: &ZapConfig, args: &ArgMatches) -> Result<()> {
+    let config = zap_config
+        .site
+        .deploy
+        .as_ref()
+        .and_then(|deploy| deploy.ipfs.clone())
+        .unwrap_or_default();
+    let api = config.api.as_deref();
+    let output_dir = Path::new(&zap_config.build_config().output);
+
+    let cid = add(output_dir, api).map_err(|e| anyhow!("Could not add {} to IPFS: {}", output_dir.display(), e))?;
+    tracing::info!("Added {} to IPFS", output_dir.display());
+    println!("CID: {}", cid);
+    println!("Gateway: https://{}.ipfs.dweb.link/", cid);
+
+    if let Some(service) = &config.pinning_service {
+        let token = env_token(&config.pinning_token_env, "the pinning service's access token")?;
+        let name = zap_config
+            .site
+            .site
+            .as_ref()
+            .and_then(|s| s.title.clone())
+            .unwrap_or_else(|| "site".to_string());
+        let status = pin_remote(service, &token, &cid, &name, api)
+            .map_err(|e| anyhow!("Could not pin {} with {}: {}", cid, service, e))?;
+        tracing::info!("Pin requested from {} ({})", service, status);
+    }
+
+    let Some(domain) = config.dnslink.as_deref().filter(|_| !args.get_flag("no-dnslink")) else {
+        return Ok(());
+    };
+    match &config.cloudflare_zone {
+        Some(zone) => {
+            let token = env_token(&config.cloudflare_token_env, "a Cloudflare API token")?;
+            update_dnslink_cloudflare(zone, &token, domain, &cid)
+                .map_err(|e| anyhow!("Could not update the DNSLink for {}: {}", domain, e))?;
+            tracing::info!("DNSLink for {} now points at {}", domain, cid);
+        }
+        None => {
+            println!("Point {} at the new CID with this TXT record:", domain);
+            println!("  {}  {}", dnslink_name(domain), dnslink_value(&cid));
+        }
+    }
+
+    Ok(())
+}
+
+fn env_token(env: &str, what: &str) -> Result<String> {
+    std::env::var(env)
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .ok_or_else(|| anyhow!("Set {} to {}", env, what))
+}
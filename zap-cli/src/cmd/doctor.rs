@@ -0,0 +1,62 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+use zap_core::doctor::{self, Finding};
+use crate::cmd::build::add_build_args;
+use crate::config::{load_build_config, BuildConfig};
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("doctor"))
+        .about("Check the project and environment for common problems")
+        .arg(
+            Arg::new("port")
+                .short('p')
+                .long("port")
+                .value_name("PORT")
+                .help("Port the dev server will use")
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let config_path = args.get_one::<String>("config").expect("has default");
+
+    // An invalid zap.toml is one of the things being diagnosed, so fall back
+    // to the command line paths instead of failing
+    let build_config = match load_build_config(args) {
+        Ok(config) => config.build_config().clone(),
+        Err(_) => BuildConfig {
+            source: args.get_one::<String>("source").expect("has default").clone(),
+            output: args.get_one::<String>("output").expect("has default").clone(),
+            theme: args.get_one::<String>("theme").expect("has default").clone(),
+            ..BuildConfig::default()
+        },
+    };
+    let source = Path::new(&build_config.source);
+
+    let checks = [
+        ("Config", doctor::check_config(Path::new(config_path))),
+        ("Content", doctor::check_source(source)),
+        ("Theme", doctor::check_theme(Path::new(&build_config.theme), source)),
+        ("Output", doctor::check_output(Path::new(&build_config.output))),
+        ("Dev server", doctor::check_port(&build_config.host, build_config.port)),
+    ];
+
+    let mut problems = 0;
+    for (name, findings) in &checks {
+        if findings.is_empty() {
+            println!("✓ {}", name);
+            continue;
+        }
+        println!("✗ {}", name);
+        for Finding { problem, fix } in findings {
+            println!("  {}\n    fix: {}", problem, fix);
+        }
+        problems += findings.len();
+    }
+
+    if problems > 0 {
+        anyhow::bail!("{} problem(s) found", problems);
+    }
+    println!("\nNo problems found");
+    Ok(())
+}
@@ -0,0 +1,333 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+use zap_core::archive::{ArchiveFormat, source_date_epoch, write_archive};
+use zap_core::artifacts::seal;
+use zap_core::help_bundle::{search_index, write_help_bundle};
+use zap_core::{SiteScanner, build_site_with_events};
+use crate::cmd::build::add_build_args;
+use crate::config::{ZapConfig, load_build_config, load_serve_config};
+use crate::progress::console_handler;
+
+pub fn make_subcommand() -> Command {
+    Command::new("export")
+        .about("Export the built site in other formats")
+        .subcommand_required(true)
+        .subcommand(
+            add_build_args(Command::new("helpbundle"))
+                .about("Package rendered pages, anchors and a search index as offline help")
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Bundle file to write")
+                        .default_value("./help.tar"),
+                ),
+        )
+        .subcommand(
+            add_build_args(Command::new("tar"))
+                .about("Build the site and package it as a reproducible .tar.gz or .zip")
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Archive to write, .tar.gz, .tgz or .zip")
+                        .default_value("./site.tar.gz"),
+                ),
+        )
+        .subcommand(
+            add_build_args(Command::new("systemd"))
+                .about("Write a systemd unit that runs `zap serve --production` for this site")
+                .arg(
+                    Arg::new("host")
+                        .long("host")
+                        .value_name("HOST")
+                        .help("Host to bind to")
+                        .default_value("127.0.0.1"),
+                )
+                .arg(
+                    Arg::new("port")
+                        .short('p')
+                        .long("port")
+                        .value_name("PORT")
+                        .help("Port to serve on")
+                        .default_value("3000"),
+                )
+                .arg(
+                    Arg::new("user")
+                        .long("user")
+                        .value_name("USER")
+                        .help("User to run as (default: the current user, or a dynamic one for root)"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Unit file to write, instead of printing it"),
+                ),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        Some(("helpbundle", sub_matches)) => export_helpbundle(sub_matches),
+        Some(("tar", sub_matches)) => export_tar(sub_matches),
+        Some(("systemd", sub_matches)) => export_systemd(sub_matches),
+        _ => unreachable!(),
+    }
+}
+
+fn export_helpbundle(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+
+    let source_dir = Path::new(&build_config.source);
+    let output_dir = Path::new(&build_config.output);
+    let theme_dir = Path::new(&build_config.theme);
+    let bundle_path = Path::new(args.get_one::<String>("file").expect("has default"));
+
+    build_site_with_events(
+        &zap_config.site,
+        source_dir,
+        output_dir,
+        theme_dir,
+        console_handler(),
+    )?;
+
+    let (mut pages, collections) = SiteScanner::new(source_dir).scan()?;
+    pages.extend(collections.into_iter().flat_map(|c| c.pages));
+
+    let title = zap_config
+        .site
+        .site
+        .as_ref()
+        .and_then(|s| s.title.clone())
+        .unwrap_or_else(|| "Help".to_string());
+
+    write_help_bundle(
+        output_dir,
+        bundle_path,
+        &title,
+        &search_index(&pages, source_dir),
+    )?;
+
+    tracing::info!("Help bundle written to {}", bundle_path.display());
+
+    let export_config = zap_config.site.export.clone().unwrap_or_default();
+    for path in seal(&export_config, bundle_path)? {
+        tracing::info!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn export_tar(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+
+    let source_dir = Path::new(&build_config.source);
+    let output_dir = Path::new(&build_config.output);
+    let theme_dir = Path::new(&build_config.theme);
+    let archive_path = Path::new(args.get_one::<String>("file").expect("has default"));
+
+    let Some(format) = ArchiveFormat::from_path(archive_path) else {
+        anyhow::bail!("{} is not a .tar.gz, .tgz or .zip file", archive_path.display());
+    };
+    // The archive would end up containing part of itself
+    let absolute = |path: &Path| std::env::current_dir().map(|cwd| cwd.join(path)).unwrap_or_default();
+    if absolute(archive_path).starts_with(absolute(output_dir)) {
+        anyhow::bail!("Write the archive outside the output directory {}", output_dir.display());
+    }
+
+    build_site_with_events(
+        &zap_config.site,
+        source_dir,
+        output_dir,
+        theme_dir,
+        console_handler(),
+    )?;
+
+    let title = zap_config
+        .site
+        .site
+        .as_ref()
+        .and_then(|s| s.title.clone())
+        .unwrap_or_else(|| "site".to_string());
+
+    let manifest = write_archive(output_dir, archive_path, format, &title, source_date_epoch())?;
+    tracing::info!("Archive written to {} ({} files)", archive_path.display(), manifest.files.len());
+
+    let export_config = zap_config.site.export.clone().unwrap_or_default();
+    for path in seal(&export_config, archive_path)? {
+        tracing::info!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn export_systemd(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_serve_config(args)?;
+    let user = args.get_one::<String>("user").cloned().or_else(|| {
+        std::env::var("USER").ok().filter(|user| !user.is_empty() && user != "root")
+    });
+    // ReadWritePaths fails to start the service for directories that
+    // don't exist, and it couldn't create them itself
+    std::fs::create_dir_all(&zap_config.build_config().output)?;
+    std::fs::create_dir_all(zap_core::history::CACHE_DIR)?;
+    let unit = systemd_unit(&zap_config, &std::env::current_exe()?, user.as_deref())?;
+
+    match args.get_one::<String>("file") {
+        Some(file) => {
+            std::fs::write(file, unit)?;
+            tracing::info!("Unit written to {}", file);
+            tracing::info!("Install it with: sudo cp {} /etc/systemd/system/ && sudo systemctl enable --now {}", file, unit_name(file));
+        }
+        None => print!("{}", unit),
+    }
+
+    Ok(())
+}
+
+fn unit_name(file: &str) -> String {
+    Path::new(file)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string())
+}
+
+// A unit for `zap serve --production` with the site's paths made absolute,
+// since systemd doesn't run it from here. The service can only write to the
+// output directory and build cache, and the socket's directory when serving
+// on a unix socket.
+fn systemd_unit(config: &ZapConfig, exe: &Path, user: Option<&str>) -> Result<String> {
+    let build = config.build_config();
+    let serve = config.serve_config();
+    let cwd = std::env::current_dir()?;
+    let absolute = |path: &str| -> PathBuf {
+        // Without `./` in the middle for paths that don't exist yet
+        let path: PathBuf = cwd.join(path).components().collect();
+        path.canonicalize().unwrap_or(path)
+    };
+    let source = absolute(&build.source);
+    let output = absolute(&build.output);
+    let theme = absolute(&build.theme);
+    let config_file = absolute(&build.config);
+
+    let title = config
+        .site
+        .site
+        .as_ref()
+        .and_then(|s| s.title.clone())
+        .unwrap_or_else(|| "site".to_string());
+
+    let mut unit = String::new();
+    unit.push_str("[Unit]\n");
+    unit.push_str(&format!("Description=zap serve for {}\n", title.replace('%', "%%")));
+    unit.push_str("After=network.target\n\n");
+
+    unit.push_str("[Service]\n");
+    unit.push_str("Type=simple\n");
+    unit.push_str(&format!("WorkingDirectory={}\n", cwd.display().to_string().replace('%', "%%")));
+    // `$` would expand environment variables in the command
+    let arg = |path: &Path| systemd_quote(path).replace('$', "$$");
+    unit.push_str(&format!(
+        "ExecStart={} serve --production --source {} --output {} --theme {} --config {} --host {} --port {}\n",
+        arg(exe),
+        arg(&source),
+        arg(&output),
+        arg(&theme),
+        arg(&config_file),
+        build.host,
+        build.port
+    ));
+    unit.push_str("Restart=on-failure\n");
+    unit.push_str("RestartSec=2\n");
+    match user {
+        Some(user) => unit.push_str(&format!("User={}\n", user)),
+        None => unit.push_str("DynamicUser=yes\n"),
+    }
+
+    // Sandboxing: a read-only view of the system, no privileges to gain
+    unit.push_str("NoNewPrivileges=yes\n");
+    unit.push_str("ProtectSystem=strict\n");
+    unit.push_str("ProtectHome=read-only\n");
+    unit.push_str("PrivateTmp=yes\n");
+    unit.push_str("PrivateDevices=yes\n");
+    unit.push_str("ProtectKernelTunables=yes\n");
+    unit.push_str("ProtectKernelModules=yes\n");
+    unit.push_str("ProtectKernelLogs=yes\n");
+    unit.push_str("ProtectControlGroups=yes\n");
+    unit.push_str("ProtectClock=yes\n");
+    unit.push_str("ProtectHostname=yes\n");
+    unit.push_str("RestrictNamespaces=yes\n");
+    unit.push_str("RestrictRealtime=yes\n");
+    unit.push_str("RestrictSUIDSGID=yes\n");
+    unit.push_str("LockPersonality=yes\n");
+    unit.push_str("MemoryDenyWriteExecute=yes\n");
+    unit.push_str("SystemCallArchitectures=native\n");
+    unit.push_str("RestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX\n");
+    if build.port < 1024 && serve.socket.is_none() {
+        unit.push_str("AmbientCapabilities=CAP_NET_BIND_SERVICE\n");
+        unit.push_str("CapabilityBoundingSet=CAP_NET_BIND_SERVICE\n");
+    } else {
+        unit.push_str("CapabilityBoundingSet=\n");
+    }
+
+    let mut writable = vec![output, cwd.join(zap_core::history::CACHE_DIR)];
+    if let Some(socket) = &serve.socket {
+        let dir = absolute(socket).parent().map(Path::to_path_buf).unwrap_or_default();
+        // systemd creates directories under /run for the service's user
+        match dir.strip_prefix("/run") {
+            Ok(name) if !name.as_os_str().is_empty() => {
+                unit.push_str(&format!("RuntimeDirectory={}\n", name.display()));
+            }
+            _ => writable.push(dir),
+        }
+    }
+    let writable: Vec<String> = writable.iter().map(|dir| systemd_quote(dir)).collect();
+    unit.push_str(&format!("ReadWritePaths={}\n\n", writable.join(" ")));
+
+    unit.push_str("[Install]\n");
+    unit.push_str("WantedBy=multi-user.target\n");
+
+    Ok(unit)
+}
+
+// Quoted for a unit file, so paths with spaces stay one argument and `%`
+// isn't taken for a specifier
+fn systemd_quote(path: &Path) -> String {
+    let escaped = path
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systemd_unit() {
+        let mut config = ZapConfig::default();
+        config.build.output = "./my site/100%".to_string();
+        let unit = systemd_unit(&config, Path::new("/usr/bin/zap"), Some("www-data")).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+
+        let exec = unit.lines().find_map(|line| line.strip_prefix("ExecStart=")).unwrap();
+        assert!(exec.starts_with("\"/usr/bin/zap\" serve --production --source "));
+        assert!(exec.contains(&format!("--output \"{}/my site/100%%\"", cwd.display())));
+        assert!(exec.ends_with("--host 127.0.0.1 --port 3000"));
+        assert!(unit.contains(&format!(
+            "ReadWritePaths=\"{}/my site/100%%\" \"{}/.zap-cache\"\n",
+            cwd.display(),
+            cwd.display()
+        )));
+        assert!(unit.contains("User=www-data\n"));
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{Result, anyhow};
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+use zap_core::faq::{FaqCache, fetch, write_collection};
+use zap_core::history::CACHE_DIR;
+use crate::cmd::build::add_build_args;
+use crate::config::load_build_config;
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("faq"))
+        .about("Import GitHub discussions and issues labeled as FAQs into a collection")
+        .arg(
+            Arg::new("refresh")
+                .long("refresh")
+                .help("Fetch from GitHub again instead of importing what was fetched last time")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+    let faq = zap_config.site_config().faq.clone().unwrap_or_default();
+    let repo = faq
+        .repo
+        .clone()
+        .ok_or_else(|| anyhow!("Set the repository to import from under [faq] in zap.toml, e.g. repo = \"owner/name\""))?;
+
+    let cache_dir = Path::new(CACHE_DIR);
+    let cached = FaqCache::load(cache_dir, &repo, &faq.label).filter(|_| !args.get_flag("refresh"));
+    let cache = match cached {
+        Some(cache) => {
+            tracing::info!("Importing {} entries fetched earlier, use --refresh to fetch them again", cache.entries.len());
+            cache
+        }
+        None => {
+            tracing::info!("Fetching questions labeled `{}` from {}", faq.label, repo);
+            let cache = fetch(&faq, &repo).map_err(|e| anyhow!("Could not fetch FAQs from GitHub: {}", e))?;
+            cache.save(cache_dir)?;
+            cache
+        }
+    };
+
+    let dir = Path::new(&build_config.source).join(&faq.collection);
+    let summary = write_collection(&dir, &cache.entries)?;
+    for path in &summary.written {
+        tracing::info!("Wrote {}", path.display());
+    }
+    for path in &summary.removed {
+        tracing::info!("Removed {}", path.display());
+    }
+    let skipped = cache.entries.len() - summary.written.len();
+    if skipped > 0 {
+        tracing::info!("Skipped {} entries whose page was written by hand", skipped);
+    }
+    tracing::info!("{} FAQ page(s) in {}", summary.written.len(), dir.display());
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// Color schemes shipped with the default theme, in `theme/themes/`
+const COLOR_SCHEMES: [&str; 10] = [
+    "doom",
+    "amethyst",
+    "brutal",
+    "bubblegum",
+    "candyland",
+    "catpuccin",
+    "claude",
+    "rose",
+    "violet",
+    "yellow",
+];
+
+pub fn make_subcommand() -> Command {
+    Command::new("init")
+        .about("Create a zap.toml and starter content")
+        .arg(
+            Arg::new("dir")
+                .value_name("DIR")
+                .help("Project directory")
+                .default_value("."),
+        )
+        .arg(
+            Arg::new("interactive")
+                .short('i')
+                .long("interactive")
+                .help("Ask about the site and which features to set up")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+/// What the generated project should contain
+#[derive(Debug, Clone)]
+struct InitAnswers {
+    title: String,
+    tagline: String,
+    repository: String,
+    color_scheme: String,
+    search: bool,
+    changelog: bool,
+    blog: bool,
+    llms: bool,
+}
+
+impl Default for InitAnswers {
+    fn default() -> Self {
+        Self {
+            title: "My Project".to_string(),
+            tagline: String::new(),
+            repository: String::new(),
+            // The theme's built-in scheme
+            color_scheme: String::new(),
+            search: false,
+            changelog: false,
+            blog: false,
+            llms: false,
+        }
+    }
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let dir = Path::new(args.get_one::<String>("dir").expect("has default"));
+
+    let answers = if args.get_flag("interactive") {
+        let stdin = std::io::stdin();
+        ask_questions(&mut stdin.lock())?
+    } else {
+        InitAnswers::default()
+    };
+
+    for (path, contents) in project_files(&answers) {
+        let path = dir.join(path);
+        if path.exists() {
+            println!("Skipped {}, it already exists", path.display());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+        println!("Created {}", path.display());
+    }
+
+    println!("\nRun `zap serve` to preview your site");
+    Ok(())
+}
+
+fn ask_questions(input: &mut impl BufRead) -> Result<InitAnswers> {
+    let defaults = InitAnswers::default();
+
+    let title = ask(input, "Site title", &defaults.title)?;
+    let tagline = ask(input, "Tagline", "")?;
+    let repository = ask(input, "Repository URL", "")?;
+
+    println!("Color schemes: {}", COLOR_SCHEMES.join(", "));
+    let color_scheme = loop {
+        let scheme = ask(input, "Color scheme", COLOR_SCHEMES[0])?;
+        if COLOR_SCHEMES.contains(&scheme.as_str()) {
+            break scheme;
+        }
+        println!("Unknown color scheme \"{}\"", scheme);
+    };
+
+    Ok(InitAnswers {
+        title,
+        tagline,
+        repository,
+        color_scheme,
+        search: confirm(input, "Add search with Pagefind?", false)?,
+        changelog: confirm(input, "Add a changelog?", false)?,
+        blog: confirm(input, "Add a blog?", false)?,
+        llms: confirm(input, "Generate llms.txt for AI tools?", false)?,
+    })
+}
+
+fn ask(input: &mut impl BufRead, question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        anyhow::bail!("input ended before all questions were answered");
+    }
+
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+fn confirm(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        match ask(input, &format!("{} ({})", question, hint), "")?.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n"),
+        }
+    }
+}
+
+fn project_files(answers: &InitAnswers) -> Vec<(PathBuf, String)> {
+    let mut files = vec![
+        (PathBuf::from("zap.toml"), config_file(answers)),
+        (PathBuf::from("site/README.md"), home_page(answers)),
+    ];
+
+    if answers.changelog {
+        files.push((
+            PathBuf::from("site/CHANGELOG.md"),
+            "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- Project website\n".to_string(),
+        ));
+    }
+    if answers.blog {
+        files.push((
+            PathBuf::from("site/blog/index.md"),
+            "# Blog\n\nNews and updates.\n".to_string(),
+        ));
+        files.push((
+            PathBuf::from("site/blog/hello-world.md"),
+            format!(
+                "+++\ndate = {}\ndraft = true\n+++\n\n# Hello, world\n\nThe first post for {}.\n",
+                zap_core::posts::today(),
+                answers.title
+            ),
+        ));
+    }
+
+    files
+}
+
+fn config_file(answers: &InitAnswers) -> String {
+    let mut config = String::from("[build]\nsource = \"./site\"\noutput = \"./out\"\n\n[site]\n");
+    config.push_str(&format!("title = {}\n", toml_string(&answers.title)));
+    if !answers.tagline.is_empty() {
+        config.push_str(&format!("tagline = {}\n", toml_string(&answers.tagline)));
+    }
+    if !answers.repository.is_empty() {
+        config.push_str(&format!("repository = {}\n", toml_string(&answers.repository)));
+    }
+    if !answers.color_scheme.is_empty() {
+        config.push_str(&format!("color_scheme = {}\n", toml_string(&answers.color_scheme)));
+    }
+
+    if !answers.repository.is_empty() {
+        config.push_str(&format!(
+            "\n[home.secondary_action]\ntext = \"View source\"\nlink = {}\n",
+            toml_string(&answers.repository)
+        ));
+    }
+    if answers.search {
+        config.push_str("\n[[post_build]]\ncommand = \"npx pagefind --site {output}\"\n");
+    }
+    if answers.changelog {
+        config.push_str("\n[changelog]\nrelease_pages = true\n");
+    }
+    if answers.blog {
+        config.push_str("\n[collections.blog]\npaginate = 10\n");
+    }
+    if answers.llms {
+        config.push_str("\n[llms]\nenabled = true\n");
+    }
+
+    config
+}
+
+fn home_page(answers: &InitAnswers) -> String {
+    let mut page = format!("# {}\n\n", answers.title);
+    if answers.tagline.is_empty() {
+        page.push_str("Describe your project here.\n");
+    } else {
+        page.push_str(&format!("{}\n", answers.tagline));
+    }
+    page
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interactive_answers() {
+        let mut input = "Zap\nFast sites\nhttps://github.com/javif89/zap\npurple\nrose\ny\ny\n\nyes\n".as_bytes();
+        let answers = ask_questions(&mut input).unwrap();
+
+        assert_eq!(answers.title, "Zap");
+        assert_eq!(answers.color_scheme, "rose");
+        assert!(answers.search && answers.changelog && !answers.blog && answers.llms);
+
+        let path = std::env::temp_dir().join("zap-init-test.toml");
+        std::fs::write(&path, config_file(&answers)).unwrap();
+        let config = zap_core::config::Config::read(&path).unwrap();
+        let site = config.site.unwrap();
+        assert_eq!(site.repository.as_deref(), Some("https://github.com/javif89/zap"));
+        assert!(config.llms.unwrap().enabled);
+        assert_eq!(config.post_build[0].command, "npx pagefind --site {output}");
+    }
+}
@@ -1,2 +1,11 @@
 pub mod build;
-pub mod serve;
\ No newline at end of file
+pub mod check;
+pub mod deploy;
+pub mod doctor;
+pub mod export;
+pub mod faq;
+pub mod init;
+pub mod report;
+pub mod serve;
+pub mod sync;
+pub mod upgrade;
\ No newline at end of file
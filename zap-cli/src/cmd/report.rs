@@ -0,0 +1,276 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+use zap_core::SiteScanner;
+use zap_core::asset_usage::{missing_alt, unused_assets};
+use zap_core::externals::ExternalKind;
+use zap_core::history::{
+    BuildRecord, CACHE_DIR, format_timestamp, new_warnings, read_history, resolved_warnings,
+};
+use zap_core::posts::{PostStatus, sort_summaries, summarize};
+use crate::cmd::build::add_build_args;
+use crate::config::load_build_config;
+use crate::progress::format_bytes;
+
+pub fn make_subcommand() -> Command {
+    Command::new("report")
+        .about("Summarize the project from recorded builds and its content")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("trends")
+                .about("Show whether warnings and build times are improving across builds")
+                .arg(
+                    Arg::new("last")
+                        .short('n')
+                        .long("last")
+                        .value_name("BUILDS")
+                        .help("Number of recent builds to show")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            add_build_args(Command::new("posts"))
+                .about("List drafts, scheduled and published posts per collection")
+                .arg(
+                    Arg::new("collection")
+                        .long("collection")
+                        .value_name("NAME")
+                        .help("Only report on this collection"),
+                ),
+        )
+        .subcommand(
+            add_build_args(Command::new("assets"))
+                .about("List images missing alt text and static files no page uses"),
+        )
+        .subcommand(
+            add_build_args(Command::new("externals"))
+                .about("List the external URLs the built site references, by domain")
+                .arg(
+                    Arg::new("files")
+                        .long("files")
+                        .help("List the files referencing each URL")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        Some(("trends", sub_matches)) => report_trends(sub_matches),
+        Some(("posts", sub_matches)) => report_posts(sub_matches),
+        Some(("assets", sub_matches)) => report_assets(sub_matches),
+        Some(("externals", sub_matches)) => report_externals(sub_matches),
+        _ => unreachable!(),
+    }
+}
+
+fn report_trends(args: &ArgMatches) -> Result<()> {
+    let history = read_history(Path::new(CACHE_DIR))?;
+    if history.is_empty() {
+        println!("No builds recorded yet, run `zap build` first");
+        return Ok(());
+    }
+
+    let last = *args.get_one::<usize>("last").expect("has default");
+    let recent = &history[history.len().saturating_sub(last)..];
+
+    println!("{:<16}  {:<10}  {:>6}  {:>8}  {:>8}", "Built", "Commit", "Pages", "Warnings", "Time");
+    for record in recent {
+        let status = match &record.error {
+            Some(_) => "  failed",
+            None => "",
+        };
+        println!(
+            "{:<16}  {:<10}  {:>6}  {:>8}  {:>6}ms{}",
+            format_timestamp(record.timestamp),
+            record.commit.as_deref().unwrap_or("-"),
+            record.pages,
+            record.warnings.len(),
+            record.ms,
+            status
+        );
+    }
+
+    // Compare against the last build that got as far as rendering
+    let mut successful = recent.iter().filter(|record| record.error.is_none());
+    let (Some(latest), Some(previous)) = (successful.next_back(), successful.next_back()) else {
+        return Ok(());
+    };
+
+    println!();
+    println!("{}", trend("Warnings", previous.warnings.len() as u128, latest.warnings.len() as u128, ""));
+    println!("{}", trend("Build time", previous.ms, latest.ms, "ms"));
+    print_warning_changes(previous, latest);
+
+    Ok(())
+}
+
+fn trend(label: &str, previous: u128, latest: u128, unit: &str) -> String {
+    let direction = match latest.cmp(&previous) {
+        std::cmp::Ordering::Less => "improving",
+        std::cmp::Ordering::Equal => "unchanged",
+        std::cmp::Ordering::Greater => "regressing",
+    };
+    format!("{:<10}  {}{} -> {}{} ({})", label, previous, unit, latest, unit, direction)
+}
+
+fn print_warning_changes(previous: &BuildRecord, latest: &BuildRecord) {
+    let added = new_warnings(previous, latest);
+    let resolved = resolved_warnings(previous, latest);
+
+    if !added.is_empty() {
+        println!("\nNew warnings:");
+        for warning in added {
+            println!("  + {}", warning);
+        }
+    }
+    if !resolved.is_empty() {
+        println!("\nResolved warnings:");
+        for warning in resolved {
+            println!("  - {}", warning);
+        }
+    }
+}
+
+fn report_posts(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let source_dir = Path::new(&zap_config.build_config().source);
+    let only = args.get_one::<String>("collection");
+    let now = chrono::Utc::now();
+
+    let timezone = zap_config.site.timezone()?;
+    let (_, collections) = SiteScanner::new(source_dir).timezone(timezone).scan()?;
+    let collections = collections
+        .iter()
+        .filter(|collection| only.is_none_or(|name| &collection.name == name));
+
+    for collection in collections {
+        let mut summaries = collection
+            .pages
+            .iter()
+            .map(|page| summarize(page, now))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        sort_summaries(&mut summaries);
+
+        let count = |status| summaries.iter().filter(|s| s.status == status).count();
+        println!(
+            "{}: {} drafts, {} scheduled, {} published",
+            collection.name,
+            count(PostStatus::Draft),
+            count(PostStatus::Scheduled),
+            count(PostStatus::Published)
+        );
+        for summary in &summaries {
+            println!(
+                "  {:<10} {:<10} {:>6} words  {} ({})",
+                summary.status.to_string(),
+                summary.date.map(|d| d.date()).unwrap_or_else(|| "-".to_string()),
+                summary.words,
+                summary.title,
+                summary.path.strip_prefix(source_dir).unwrap_or(&summary.path).display()
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn report_assets(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+    let source_dir = Path::new(&build_config.source);
+    let output_dir = Path::new(&build_config.output);
+
+    let timezone = zap_config.site.timezone()?;
+    let (pages, collections) = SiteScanner::new(source_dir).timezone(timezone).scan()?;
+    let all_pages = pages.iter().chain(collections.iter().flat_map(|c| &c.pages));
+    let missing = missing_alt(all_pages);
+
+    if missing.is_empty() {
+        println!("Every image has alt text");
+    } else {
+        println!("Images without alt text:");
+        for image in &missing {
+            let page = image.page.strip_prefix(source_dir).unwrap_or(&image.page);
+            println!("  {}: {}", page.display(), image.src);
+        }
+    }
+
+    let static_dir = zap_config.site.static_dir(source_dir);
+    if !static_dir.is_dir() {
+        return Ok(());
+    }
+    if !output_dir.is_dir() {
+        println!("\nNo built site in {}, run `zap build` to check for unused static files", output_dir.display());
+        return Ok(());
+    }
+    let unused = unused_assets(&static_dir, output_dir)?;
+    if unused.is_empty() {
+        println!("\nEvery file in {} is used", static_dir.display());
+    } else {
+        println!("\nFiles in {} no page uses:", static_dir.display());
+        for path in &unused {
+            let size = std::fs::metadata(static_dir.join(path)).map(|m| m.len()).unwrap_or(0);
+            println!("  {} ({})", path.display(), format_bytes(size));
+        }
+    }
+
+    Ok(())
+}
+
+fn report_externals(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let output_dir = Path::new(&zap_config.build_config().output).to_path_buf();
+    if !output_dir.is_dir() {
+        println!("No built site in {}, run `zap build` first", output_dir.display());
+        return Ok(());
+    }
+    let list_files = args.get_flag("files");
+
+    let externals = zap_core::externals::scan(&output_dir)?;
+    if externals.domains.is_empty() {
+        println!("The site references no external URLs");
+        return Ok(());
+    }
+
+    for (domain, urls) in &externals.domains {
+        let loaded = urls
+            .values()
+            .any(|used| used.kinds.iter().any(|kind| *kind != ExternalKind::Link));
+        println!(
+            "{} ({} {}{})",
+            domain,
+            urls.len(),
+            if urls.len() == 1 { "URL" } else { "URLs" },
+            if loaded { ", loaded by pages" } else { "" }
+        );
+        for (url, used) in urls {
+            let kinds: Vec<String> = used.kinds.iter().map(|kind| kind.to_string()).collect();
+            let files = used.files.len();
+            println!(
+                "  {:<12} {}  ({} {})",
+                kinds.join(","),
+                url,
+                files,
+                if files == 1 { "file" } else { "files" }
+            );
+            if list_files {
+                for file in &used.files {
+                    println!("      {}", file.display());
+                }
+            }
+        }
+    }
+
+    let lookalikes = externals.lookalikes();
+    if !lookalikes.is_empty() {
+        println!("\nSimilar domains, check these aren't typos:");
+        for (a, b) in lookalikes {
+            println!("  {} ~ {}", a, b);
+        }
+    }
+
+    Ok(())
+}
@@ -5,9 +5,13 @@ use std::{
     path::{Path, PathBuf},
     time::Duration,
 };
-use zap_core::build_site;
-use zap_dev_server::{LiveServer, LiveServerConfig};
-use crate::config::load_serve_config;
+use zap_core::{BuildCache, build_site_incremental, build_site_with_events, post_build::run_command};
+use zap_dev_server::{
+    ConnectionConfig, HeaderRule, LiveServer, LiveServerConfig, LiveServerHandle, ProductionServer,
+    ProductionServerConfig,
+};
+use crate::config::{HeaderEntry, ServeConfig, load_serve_config};
+use crate::progress::{console_handler, log_diagnostic};
 
 pub fn make_subcommand() -> Command {
     Command::new("serve")
@@ -44,6 +48,12 @@ pub fn make_subcommand() -> Command {
                 .help("Configuration file")
                 .default_value("./zap.toml"),
         )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Configuration profile to apply, e.g. production (or ZAP_PROFILE)"),
+        )
         .arg(
             Arg::new("port")
                 .short('p')
@@ -62,7 +72,48 @@ pub fn make_subcommand() -> Command {
         .arg(
             Arg::new("open")
                 .long("open")
-                .help("Open browser automatically")
+                .value_name("PATH")
+                .help("Open the site, or the page at PATH, in a browser")
+                .num_args(0..=1)
+                .default_missing_value("/"),
+        )
+        .arg(
+            Arg::new("test-cmd")
+                .long("test-cmd")
+                .value_name("CMD")
+                .help("Command to run after each successful rebuild"),
+        )
+        .arg(
+            Arg::new("review")
+                .long("review")
+                .value_name("FILE")
+                .help("Let reviewers comment on selected text, saving comments to FILE")
+                .num_args(0..=1)
+                .default_missing_value("./zap-comments.json"),
+        )
+        .arg(
+            Arg::new("webhook-secret")
+                .long("webhook-secret")
+                .value_name("SECRET")
+                .help("Rebuild on POSTs to /__webhook carrying SECRET (or ZAP_WEBHOOK_SECRET)"),
+        )
+        .arg(
+            Arg::new("admin")
+                .long("admin")
+                .help("Edit pages in the browser at /__admin, saving them to the source directory")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("production")
+                .long("production")
+                .help("Build the site once and serve it without live reload, file watching or /__ endpoints")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["open", "test-cmd", "review", "webhook-secret", "admin", "debug-templates"]),
+        )
+        .arg(
+            Arg::new("debug-templates")
+                .long("debug-templates")
+                .help("Show each page's template name and context in a panel")
                 .action(clap::ArgAction::SetTrue),
         )
 }
@@ -79,15 +130,34 @@ pub async fn execute(args: &ArgMatches) -> Result<()> {
     let host = build_config.host.clone();
     let port = build_config.port;
     let open = build_config.open;
+    let open_path = build_config.open_path.clone();
+
+    if args.get_flag("production") {
+        build_site_with_events(&config.site, &source_dir, &output_dir, &theme_dir, console_handler())?;
+        let server = ProductionServer::new(ProductionServerConfig {
+            host,
+            port,
+            root: output_dir,
+            mounts: mounts(config.serve_config()),
+            headers: header_rules(config.serve_config()),
+            connections: connection_config(config.serve_config()),
+        });
+        return server.run().await;
+    }
     
     // Enable dev mode for serve command
     config.site.dev(host.clone(), port);
+    config.site.debug_templates = args.get_flag("debug-templates");
     
-    build_site(
+    // Kept between rebuilds so only pages affected by a change are rendered
+    let mut cache = BuildCache::new();
+    build_site_incremental(
         &config.site,
         &source_dir,
         &output_dir,
         &theme_dir,
+        console_handler(),
+        &mut cache,
     )?;
 
     // Start the live dev server (handles its own file watching of output dir)
@@ -96,21 +166,33 @@ pub async fn execute(args: &ArgMatches) -> Result<()> {
         port,
         root: output_dir.clone(),
         open,
+        open_path,
+        mounts: mounts(config.serve_config()),
+        headers: header_rules(config.serve_config()),
         ignore: vec![".git".to_string(), "*.tmp".to_string()],
+        connections: connection_config(config.serve_config()),
+        review: args.get_one::<String>("review").map(PathBuf::from),
+        webhook_secret: args
+            .get_one::<String>("webhook-secret")
+            .cloned()
+            .or_else(|| std::env::var("ZAP_WEBHOOK_SECRET").ok())
+            .filter(|secret| !secret.is_empty()),
+        admin: args.get_flag("admin").then(|| source_dir.clone()),
     };
     
     let server = LiveServer::new(server_config);
+    let live = server.handle();
     let server_handle = tokio::spawn(async move {
         if let Err(e) = server.run().await {
-            eprintln!("Dev server error: {}", e);
+            tracing::error!("Dev server error: {}", e);
         }
     });
 
     // Watch source files and rebuild on changes
     let watcher_config = config.clone();
     let watcher_handle = tokio::spawn(async move {
-        if let Err(e) = watch_source_files(watcher_config).await {
-            eprintln!("Source watcher error: {}", e);
+        if let Err(e) = watch_source_files(watcher_config, live, cache).await {
+            tracing::error!("Source watcher error: {}", e);
         }
     });
 
@@ -120,7 +202,48 @@ pub async fn execute(args: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
-async fn watch_source_files(config: crate::config::ZapConfig) -> Result<()> {
+fn mounts(config: &ServeConfig) -> Vec<(String, PathBuf)> {
+    config
+        .mounts
+        .iter()
+        .map(|(prefix, dir)| (prefix.clone(), PathBuf::from(dir)))
+        .collect()
+}
+
+fn header_rules(config: &ServeConfig) -> Vec<HeaderRule> {
+    let mut global = HeaderRule {
+        pattern: None,
+        headers: Vec::new(),
+    };
+    let mut rules = Vec::new();
+    for (key, entry) in &config.headers {
+        match entry {
+            HeaderEntry::Value(value) => global.headers.push((key.clone(), value.clone())),
+            HeaderEntry::Paths(headers) => rules.push(HeaderRule {
+                pattern: Some(key.clone()),
+                headers: headers.iter().map(|(name, value)| (name.clone(), value.clone())).collect(),
+            }),
+        }
+    }
+    rules.push(global);
+    rules
+}
+
+fn connection_config(config: &ServeConfig) -> ConnectionConfig {
+    ConnectionConfig {
+        http2: config.http2,
+        keep_alive: config.keep_alive.map(Duration::from_secs),
+        // Zero would never accept a connection
+        max_connections: config.max_connections.filter(|max| *max > 0),
+        socket: config.socket.as_ref().map(PathBuf::from),
+    }
+}
+
+async fn watch_source_files(
+    config: crate::config::ZapConfig,
+    live: LiveServerHandle,
+    mut cache: BuildCache,
+) -> Result<()> {
     let build_config = config.build_config();
     let source_dir = PathBuf::from(&build_config.source);
     let output_dir = PathBuf::from(&build_config.output);
@@ -144,14 +267,14 @@ async fn watch_source_files(config: crate::config::ZapConfig) -> Result<()> {
     debouncer
         .watcher()
         .watch(&source_dir, notify::RecursiveMode::Recursive)?;
-    println!("Watching source directory: {}", source_dir.display());
+    tracing::debug!("Watching source directory: {}", source_dir.display());
 
     // Watch theme directory if it exists
     if theme_dir.exists() {
         debouncer
             .watcher()
             .watch(&theme_dir, notify::RecursiveMode::Recursive)?;
-        println!("Watching theme directory: {}", theme_dir.display());
+        tracing::debug!("Watching theme directory: {}", theme_dir.display());
     }
 
     // Watch config file if it exists
@@ -159,27 +282,48 @@ async fn watch_source_files(config: crate::config::ZapConfig) -> Result<()> {
         debouncer
             .watcher()
             .watch(&config_file, notify::RecursiveMode::NonRecursive)?;
-        println!("Watching config file: {}", config_file.display());
+        tracing::debug!("Watching config file: {}", config_file.display());
     }
 
-    println!("Watching source files for changes...");
+    tracing::info!("Watching source files for changes...");
 
-    while let Some(path) = rx.recv().await {
-        println!("Source file changed: {} (absolute: {})", path.display(), path.canonicalize().unwrap_or(path.clone()).display());
-        
-        // Check if this is actually a source file change
-        let abs_path = path.canonicalize().unwrap_or(path.clone());
-        let abs_source_dir = source_dir.canonicalize().unwrap_or(source_dir.clone());
-        let abs_theme_dir = theme_dir.canonicalize().unwrap_or(theme_dir.clone());
-        let abs_config_file = config_file.canonicalize().unwrap_or(config_file.clone());
-        
-        let is_source_change = abs_path.starts_with(&abs_source_dir) 
-            || abs_path.starts_with(&abs_theme_dir) 
-            || abs_path == abs_config_file;
-            
-        if !is_source_change {
-            println!("  Skipping non-source file change");
-            continue;
+    let mut webhooks = live.webhooks();
+    loop {
+        tokio::select! {
+            path = rx.recv() => {
+                let Some(path) = path else {
+                    break;
+                };
+                tracing::debug!("Source file changed: {}", path.display());
+
+                // Check if this is actually a source file change
+                let abs_path = path.canonicalize().unwrap_or(path.clone());
+                let abs_source_dir = source_dir.canonicalize().unwrap_or(source_dir.clone());
+                let abs_theme_dir = theme_dir.canonicalize().unwrap_or(theme_dir.clone());
+                let abs_config_file = config_file.canonicalize().unwrap_or(config_file.clone());
+
+                let is_source_change = abs_path.starts_with(&abs_source_dir)
+                    || abs_path.starts_with(&abs_theme_dir)
+                    || abs_path == abs_config_file;
+
+                if !is_source_change {
+                    tracing::debug!("Skipping non-source file change");
+                    continue;
+                }
+            }
+            sender = webhooks.recv() => {
+                // Requests missed while busy would only rebuild again
+                let sender = sender.unwrap_or_else(|_| "webhook".to_string());
+                tracing::info!("Rebuilding for {}", sender);
+                if let Some(command) = &config.hooks_config().on_webhook {
+                    tracing::info!("Running on_webhook hook: {}", command);
+                    if let Err(e) = run_command(command, &output_dir) {
+                        tracing::error!("Hook error: {}", e);
+                        live.report_error(format!("on_webhook hook {}", e));
+                        continue;
+                    }
+                }
+            }
         }
 
         // Rebuild site - the dev server will detect output changes and reload  
@@ -189,18 +333,36 @@ async fn watch_source_files(config: crate::config::ZapConfig) -> Result<()> {
         let mut site_config = config.site.clone();
         site_config.dev(host, port);
         
-        match build_site(
+        match build_site_incremental(
             &site_config,
             &source_dir,
             &output_dir,
             &theme_dir,
+            console_handler(),
+            &mut cache,
         ) {
             Ok(_) => {
-                println!("Site rebuilt successfully");
+                tracing::info!("Site rebuilt successfully");
             }
             Err(e) => {
-                eprintln!("Build error: {}", e);
+                e.diagnostics().iter().for_each(log_diagnostic);
+                live.report_error(format!("Build error: {}", e));
+                continue;
+            }
+        }
+
+        match &config.hooks_config().post_rebuild {
+            Some(command) => {
+                tracing::info!("Running post_rebuild hook: {}", command);
+                match run_command(command, &output_dir) {
+                    Ok(()) => live.clear_error(),
+                    Err(e) => {
+                        tracing::error!("Hook error: {}", e);
+                        live.report_error(format!("post_rebuild hook {}", e));
+                    }
+                }
             }
+            None => live.clear_error(),
         }
     }
 
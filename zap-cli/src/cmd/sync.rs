@@ -0,0 +1,52 @@
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+use std::path::Path;
+use zap_core::history::CACHE_DIR;
+use zap_core::sources::sync_all;
+use crate::cmd::build::add_build_args;
+use crate::config::load_build_config;
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("sync"))
+        .about("Pull content from the git repositories and CMSs under [[sources]] now")
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+    let sources = &zap_config.site_config().sources;
+    if sources.is_empty() {
+        tracing::info!("No [[sources]] in zap.toml to sync");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for (source, result) in sync_all(sources, Path::new(&build_config.source), Path::new(CACHE_DIR), true) {
+        let summary = match result {
+            Ok(summary) => summary.unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Could not sync {}: {}", source, e);
+                failed += 1;
+                continue;
+            }
+        };
+        tracing::info!("Synced {}", source);
+        for path in &summary.written {
+            tracing::info!("  Wrote {}", path.display());
+        }
+        for path in &summary.removed {
+            tracing::info!("  Removed {}", path.display());
+        }
+        for path in &summary.skipped {
+            tracing::info!("  Kept {}, it wasn't written by the source", path.display());
+        }
+        if summary.written.is_empty() && summary.removed.is_empty() {
+            tracing::info!("  Already up to date");
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} sources could not be synced", failed, sources.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+use zap_core::upgrade::plan_upgrade;
+use crate::cmd::build::add_build_args;
+use crate::config::load_build_config;
+
+pub fn make_subcommand() -> Command {
+    add_build_args(Command::new("upgrade"))
+        .about("Rewrite renamed config keys, front matter fields and theme variables")
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Show the changes as a diff without writing them")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let zap_config = load_build_config(args)?;
+    let build_config = zap_config.build_config();
+    let dry_run = args.get_flag("dry-run");
+
+    let upgrades = plan_upgrade(
+        Path::new(&build_config.config),
+        Path::new(&build_config.source),
+        Path::new(&build_config.theme),
+    )?;
+
+    if upgrades.is_empty() {
+        println!("Nothing to upgrade");
+        return Ok(());
+    }
+
+    for upgrade in &upgrades {
+        if dry_run {
+            print!("{}", upgrade.diff());
+        } else {
+            upgrade.apply()?;
+            println!("Updated {}", upgrade.path.display());
+        }
+        for change in &upgrade.changes {
+            println!("  {}", change);
+        }
+    }
+
+    if dry_run {
+        println!("{} file(s) would change, run without --dry-run to apply", upgrades.len());
+    }
+
+    Ok(())
+}
@@ -2,19 +2,28 @@ use anyhow::Result;
 use clap::ArgMatches;
 use config::{Config as ConfigBuilder, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 /// Complete configuration that merges CLI args, env vars, config files, and defaults
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ZapConfig {
     /// Build configuration
+    #[serde(default)]
     pub build: BuildConfig,
+    /// Commands run around dev server rebuilds
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Dev server settings
+    #[serde(default)]
+    pub serve: ServeConfig,
     /// Site configuration (from zap-core)
     #[serde(flatten)]
     pub site: zap_core::config::Config,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct BuildConfig {
     /// Source directory containing markdown files
     pub source: String,
@@ -30,6 +39,46 @@ pub struct BuildConfig {
     pub port: u16,
     /// Open browser automatically
     pub open: bool,
+    /// Page to open, relative to the site root
+    pub open_path: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HooksConfig {
+    /// Shell command run after each successful rebuild in serve mode
+    pub post_rebuild: Option<String>,
+    /// Shell command run before rebuilding for a webhook in serve mode,
+    /// e.g. `git pull --ff-only`
+    pub on_webhook: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServeConfig {
+    /// Extra directories served by `zap serve`, keyed by URL prefix, e.g.
+    /// `"/assets" = "./design/assets"`. They aren't copied into the output.
+    #[serde(default)]
+    pub mounts: BTreeMap<String, String>,
+    /// Response headers. Plain values apply to every path, tables to the
+    /// paths matching their key, e.g. `[serve.headers."/demos/**"]`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, HeaderEntry>,
+    /// Accept HTTP/2 without TLS, for reverse proxies that speak it upstream
+    #[serde(default)]
+    pub http2: bool,
+    /// Seconds a connection is kept open without traffic, 0 closes
+    /// connections after each response
+    pub keep_alive: Option<u64>,
+    /// Connections served at once
+    pub max_connections: Option<usize>,
+    /// Unix socket to listen on instead of the host and port
+    pub socket: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HeaderEntry {
+    Value(String),
+    Paths(BTreeMap<String, String>),
 }
 
 impl Default for BuildConfig {
@@ -42,6 +91,7 @@ impl Default for BuildConfig {
             host: "127.0.0.1".to_string(),
             port: 3000,
             open: false,
+            open_path: "/".to_string(),
         }
     }
 }
@@ -50,6 +100,8 @@ impl Default for ZapConfig {
     fn default() -> Self {
         Self {
             build: BuildConfig::default(),
+            hooks: HooksConfig::default(),
+            serve: ServeConfig::default(),
             site: zap_core::config::Config::default(),
         }
     }
@@ -59,8 +111,9 @@ impl ZapConfig {
     /// Load configuration with cascading precedence:
     /// 1. CLI arguments (highest priority)
     /// 2. Environment variables (ZAP_*)
-    /// 3. Configuration file
-    /// 4. Defaults (lowest priority)
+    /// 3. The profile's overrides, from `--profile` or `ZAP_PROFILE`
+    /// 4. Configuration file
+    /// 5. Defaults (lowest priority)
     pub fn load(args: &ArgMatches) -> Result<Self> {
         let config_file = args.get_one::<String>("config")
             .unwrap_or(&"./zap.toml".to_string())
@@ -77,14 +130,27 @@ impl ZapConfig {
             builder = builder.add_source(File::with_name(&config_file.replace(".toml", "")));
         }
 
-        // 3. Add environment variables with ZAP_ prefix
+        // 3. Add the profile's overrides
+        let profile = args
+            .try_get_one::<String>("profile")
+            .unwrap_or(None)
+            .cloned()
+            .or_else(|| std::env::var("ZAP_PROFILE").ok())
+            .filter(|profile| !profile.is_empty());
+        if let Some(profile) = &profile {
+            for source in profile_sources(&config_file, profile)? {
+                builder = builder.add_source(source);
+            }
+        }
+
+        // 4. Add environment variables with ZAP_ prefix
         builder = builder.add_source(
             Environment::with_prefix("ZAP")
                 .prefix_separator("_")
                 .separator("__") // Use double underscore for nested keys
         );
 
-        // 4. Override with CLI arguments (highest priority)
+        // 5. Override with CLI arguments (highest priority)
         let mut cli_overrides = std::collections::HashMap::new();
 
         if let Some(source) = args.get_one::<String>("source") {
@@ -104,12 +170,17 @@ impl ZapConfig {
             cli_overrides.insert("build.host".to_string(), host.clone());
         }
         if let Some(port) = args.try_get_one::<String>("port").unwrap_or(None) {
-            if let Ok(port_num) = port.parse::<u16>() {
-                cli_overrides.insert("build.port".to_string(), port_num.to_string());
-            }
+            let port_num = port
+                .parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("--port must be a number from 0 to 65535, got \"{}\"", port))?;
+            cli_overrides.insert("build.port".to_string(), port_num.to_string());
         }
-        if args.try_get_one::<bool>("open").unwrap_or(None).unwrap_or(&false) == &true {
+        if let Some(path) = args.try_get_one::<String>("open").unwrap_or(None) {
             cli_overrides.insert("build.open".to_string(), "true".to_string());
+            cli_overrides.insert("build.open_path".to_string(), path.clone());
+        }
+        if let Some(test_cmd) = args.try_get_one::<String>("test-cmd").unwrap_or(None) {
+            cli_overrides.insert("hooks.post_rebuild".to_string(), test_cmd.clone());
         }
 
         if !cli_overrides.is_empty() {
@@ -117,12 +188,44 @@ impl ZapConfig {
         }
 
         // Build and deserialize
-        let config = builder.build()?;
-        let zap_config: ZapConfig = config.try_deserialize()?;
+        let config = match builder.build() {
+            Ok(config) => config,
+            Err(e) => return Err(file_error(&config_file).unwrap_or_else(|| e.into())),
+        };
+        let zap_config: ZapConfig = match config.try_deserialize() {
+            Ok(zap_config) => zap_config,
+            Err(e) => return Err(file_error(&config_file).unwrap_or_else(|| e.into())),
+        };
+        let strict = zap_config.site.strict || args.try_get_one::<bool>("strict").ok().flatten() == Some(&true);
+        zap_config.validate(&config_file, strict)?;
 
         Ok(zap_config)
     }
 
+    // Warn about keys in the config file that nothing reads, usually typos,
+    // failing on them too in strict mode, and fail on values that can't work
+    fn validate(&self, config_file: &str, strict: bool) -> Result<()> {
+        if let Ok(source) = std::fs::read_to_string(config_file) {
+            let known = serde_json::to_value(self)?;
+            let issues = zap_core::validate::unknown_keys(&source, &known, &[]);
+            for issue in &issues {
+                let diagnostic = zap_core::Diagnostic::warning(&issue.message)
+                    .in_file(config_file)
+                    .at_line(&source, issue.line, None);
+                crate::progress::log_diagnostic(&diagnostic);
+            }
+            if strict && !issues.is_empty() {
+                anyhow::bail!("{} unknown key(s) in {} in strict mode", issues.len(), config_file);
+            }
+        }
+
+        let problems = zap_core::validate::check_values(&self.site);
+        if !problems.is_empty() {
+            anyhow::bail!("Invalid configuration in {}:\n  {}", config_file, problems.join("\n  "));
+        }
+        Ok(())
+    }
+
     /// Get just the site configuration for passing to zap-core
     pub fn site_config(&self) -> &zap_core::config::Config {
         &self.site
@@ -132,6 +235,75 @@ impl ZapConfig {
     pub fn build_config(&self) -> &BuildConfig {
         &self.build
     }
+
+    /// Get the hooks configuration
+    pub fn hooks_config(&self) -> &HooksConfig {
+        &self.hooks
+    }
+
+    /// Get the dev server configuration
+    pub fn serve_config(&self) -> &ServeConfig {
+        &self.serve
+    }
+}
+
+// A profile's overrides: `[profiles.<name>]` in the config file, then
+// `zap.<name>.toml` next to it. One of them has to exist, so a misspelled
+// profile doesn't silently build with the base config.
+fn profile_sources(config_file: &str, profile: &str) -> Result<Vec<config::Config>> {
+    let mut sources = Vec::new();
+
+    if Path::new(config_file).exists() {
+        let base = ConfigBuilder::builder()
+            .add_source(File::with_name(&config_file.replace(".toml", "")))
+            .build()?;
+        if let Ok(overrides) = base.get::<serde_json::Value>(&format!("profiles.{}", profile)) {
+            sources.push(config::Config::try_from(&overrides)?);
+        }
+    }
+
+    let profile_file = Path::new(config_file).with_extension(format!("{}.toml", profile));
+    if profile_file.exists() {
+        sources.push(ConfigBuilder::builder().add_source(File::from(profile_file.as_path())).build()?);
+    }
+
+    if sources.is_empty() {
+        anyhow::bail!(
+            "No profile named \"{}\". Add [profiles.{}] to {} or create {}",
+            profile,
+            profile,
+            config_file,
+            profile_file.display()
+        );
+    }
+    Ok(sources)
+}
+
+// The CLI's sections of the config file, read on their own for errors.
+// Reading through ZapConfig's flattened site config loses their lines.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct CliSections {
+    #[serde(default)]
+    build: BuildConfig,
+    #[serde(default)]
+    hooks: HooksConfig,
+    #[serde(default)]
+    serve: ServeConfig,
+}
+
+// The config file's own parse error. The merged config's errors don't say
+// which line is wrong, or even which file.
+fn file_error(config_file: &str) -> Option<anyhow::Error> {
+    let source = std::fs::read_to_string(config_file).ok()?;
+    let error = toml::from_str::<zap_core::config::Config>(&source)
+        .err()
+        .or_else(|| toml::from_str::<CliSections>(&source).err())?;
+    let diagnostic = zap_core::Diagnostic::error(error.message().trim_end()).in_file(config_file);
+    Some(anyhow::Error::new(match error.span() {
+        Some(span) => diagnostic.at_offset(&source, span.start),
+        None => diagnostic,
+    }))
 }
 
 /// Load configuration specifically for build commands
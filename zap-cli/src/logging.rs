@@ -0,0 +1,187 @@
+// Logging for zap and its crates through `tracing`. zap-core and
+// zap-dev-server only emit events, so programs using them decide what's
+// shown. The CLI shows them with the small subscriber here: plain text,
+// more of it with `--verbose` and less with `--quiet`, or one JSON object
+// per line with `--log-format json` for CI to parse.
+
+use clap::{Arg, ArgMatches, Command};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+pub fn add_logging_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Show more of what zap is doing, -vv for everything")
+                .action(clap::ArgAction::Count)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Only show warnings and errors")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("How to write logs: text, or json for one object per line")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .global(true),
+        )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Install the logger the arguments ask for
+pub fn init(args: &ArgMatches) {
+    let max_level = match (args.get_flag("quiet"), args.get_count("verbose")) {
+        (true, _) => LevelFilter::WARN,
+        (false, 0) => LevelFilter::INFO,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    let format = match args.get_one::<String>("log-format").map(String::as_str) {
+        Some("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+    let logger = Logger {
+        max_level,
+        format,
+        next_span: AtomicU64::new(1),
+    };
+    // Only fails when a logger is already installed
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+struct Logger {
+    max_level: LevelFilter,
+    format: LogFormat,
+    next_span: AtomicU64,
+}
+
+impl Subscriber for Logger {
+    // Dependencies' debug output is about their internals, not the site
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let level = *metadata.level();
+        self.max_level >= level && (level <= Level::INFO || metadata.target().starts_with("zap"))
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level)
+    }
+
+    // Spans aren't shown, they only need telling apart
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let level = *event.metadata().level();
+
+        match self.format {
+            LogFormat::Json => {
+                let mut line = serde_json::Map::new();
+                line.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339().into());
+                line.insert("level".to_string(), level.as_str().to_lowercase().into());
+                line.insert("target".to_string(), event.metadata().target().into());
+                line.insert("message".to_string(), fields.message.into());
+                line.extend(fields.values);
+                let _ = writeln!(std::io::stderr().lock(), "{}", serde_json::Value::Object(line));
+            }
+            LogFormat::Text => {
+                let prefix = match level {
+                    Level::ERROR => "error: ",
+                    Level::WARN => "warning: ",
+                    Level::INFO => "",
+                    Level::DEBUG => "debug: ",
+                    Level::TRACE => "trace: ",
+                };
+                let mut text = format!("{}{}", prefix, fields.message);
+                for (name, value) in &fields.values {
+                    match (name.as_str(), value) {
+                        // Lines quoted from the file a warning is about
+                        ("context", serde_json::Value::String(context)) => {
+                            text.push_str(&format!("\n{}\n", context));
+                        }
+                        _ if level > Level::INFO => text.push_str(&format!(" {}={}", name, value)),
+                        _ => {}
+                    }
+                }
+                // Warnings and errors go where output isn't piped to
+                let _ = match level <= Level::WARN {
+                    true => writeln!(std::io::stderr().lock(), "{}", text),
+                    false => writeln!(std::io::stdout().lock(), "{}", text),
+                };
+            }
+        }
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    values: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Fields {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        match (field.name(), value) {
+            ("message", serde_json::Value::String(message)) => self.message = message,
+            (name, value) => {
+                self.values.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
@@ -2,16 +2,27 @@ use clap::Command;
 
 mod cmd;
 mod config;
+mod logging;
+mod progress;
 
 fn create_clap_app() -> Command {
-    cmd::build::add_build_args(
+    logging::add_logging_args(cmd::build::add_build_args(
         Command::new("zap")
             .version(env!("CARGO_PKG_VERSION"))
             .about("Get a website for your project in seconds, with no configuration")
             .author("Javier Feliz <me@javierfeliz.com>")
-    )
+    ))
     .subcommand(cmd::build::make_subcommand())
     .subcommand(cmd::serve::make_subcommand())
+    .subcommand(cmd::check::make_subcommand())
+    .subcommand(cmd::export::make_subcommand())
+    .subcommand(cmd::deploy::make_subcommand())
+    .subcommand(cmd::faq::make_subcommand())
+    .subcommand(cmd::sync::make_subcommand())
+    .subcommand(cmd::doctor::make_subcommand())
+    .subcommand(cmd::init::make_subcommand())
+    .subcommand(cmd::report::make_subcommand())
+    .subcommand(cmd::upgrade::make_subcommand())
     .subcommand(
         Command::new("version")
             .about("Show version information")
@@ -21,10 +32,20 @@ fn create_clap_app() -> Command {
 #[tokio::main]
 async fn main() {
     let matches = create_clap_app().get_matches();
+    logging::init(&matches);
 
     let result = match matches.subcommand() {
         Some(("build", sub_matches)) => cmd::build::execute(sub_matches),
         Some(("serve", sub_matches)) => cmd::serve::execute(sub_matches).await,
+        Some(("check", sub_matches)) => cmd::check::execute(sub_matches),
+        Some(("export", sub_matches)) => cmd::export::execute(sub_matches),
+        Some(("deploy", sub_matches)) => cmd::deploy::execute(sub_matches),
+        Some(("faq", sub_matches)) => cmd::faq::execute(sub_matches),
+        Some(("sync", sub_matches)) => cmd::sync::execute(sub_matches),
+        Some(("doctor", sub_matches)) => cmd::doctor::execute(sub_matches),
+        Some(("init", sub_matches)) => cmd::init::execute(sub_matches),
+        Some(("report", sub_matches)) => cmd::report::execute(sub_matches),
+        Some(("upgrade", sub_matches)) => cmd::upgrade::execute(sub_matches),
         Some(("version", _)) => {
             println!("zap {}", env!("CARGO_PKG_VERSION"));
             Ok(())
@@ -37,7 +58,7 @@ async fn main() {
     };
 
     if let Err(e) = result {
-        eprintln!("Error: {}", e);
+        progress::log_error(&e);
         std::process::exit(1);
     }
 }
@@ -0,0 +1,180 @@
+use std::sync::{Arc, Mutex};
+use zap_core::history::BuildRecord;
+use zap_core::{BuildEvent, BuildReport, Diagnostic, EventHandler, PageTimings, Severity};
+
+/// Event handler that logs build progress. Warnings are logged together
+/// once the build finishes, or fails and drops the handler.
+pub fn console_handler() -> EventHandler {
+    let held = HeldWarnings::default();
+    Arc::new(move |event| match event {
+        BuildEvent::Warning { diagnostic } => held.0.lock().unwrap().push(diagnostic.clone()),
+        BuildEvent::Finished { stats } => {
+            held.print();
+            match stats.unchanged {
+                0 => tracing::info!(
+                    pages = stats.pages,
+                    ms = stats.ms as u64,
+                    warnings = stats.warnings,
+                    "Rendered {} pages in {}ms ({} warnings)",
+                    stats.pages,
+                    stats.ms,
+                    stats.warnings
+                ),
+                unchanged => tracing::info!(
+                    pages = stats.pages,
+                    unchanged,
+                    ms = stats.ms as u64,
+                    warnings = stats.warnings,
+                    "Rendered {} pages in {}ms, {} unchanged ({} warnings)",
+                    stats.pages,
+                    stats.ms,
+                    unchanged,
+                    stats.warnings
+                ),
+            }
+        }
+        _ => {}
+    })
+}
+
+/// Log a problem, with its file, line and column as fields and the lines
+/// quoted from the file as `context`
+pub fn log_diagnostic(diagnostic: &Diagnostic) {
+    let file = diagnostic.file.as_ref().map(|file| file.display().to_string());
+    let context = Some(diagnostic.context()).filter(|context| !context.is_empty());
+    match diagnostic.severity {
+        Severity::Warning => tracing::warn!(
+            file,
+            line = diagnostic.line,
+            column = diagnostic.column,
+            context,
+            "{}",
+            diagnostic.message
+        ),
+        Severity::Error => tracing::error!(
+            file,
+            line = diagnostic.line,
+            column = diagnostic.column,
+            context,
+            "{}",
+            diagnostic.message
+        ),
+    }
+}
+
+#[derive(Default)]
+struct HeldWarnings(Mutex<Vec<Diagnostic>>);
+
+impl HeldWarnings {
+    fn print(&self) {
+        for diagnostic in self.0.lock().unwrap().drain(..) {
+            log_diagnostic(&diagnostic);
+        }
+    }
+}
+
+impl Drop for HeldWarnings {
+    fn drop(&mut self) {
+        self.print();
+    }
+}
+
+/// Log what stopped a command, pointed at the files at fault when it's known
+pub fn log_error(error: &anyhow::Error) {
+    match (error.downcast_ref::<zap_core::BuildError>(), error.downcast_ref::<Diagnostic>()) {
+        (Some(error), _) => error.diagnostics().iter().for_each(log_diagnostic),
+        (None, Some(diagnostic)) => log_diagnostic(diagnostic),
+        (None, None) => tracing::error!("{}", error),
+    }
+}
+
+/// Break down where a build's time went: each phase, rendering pages by
+/// step, the `slowest` pages and every template
+pub fn print_timings(report: &BuildReport, slowest: usize) {
+    let ms = |us: u128| us as f64 / 1000.0;
+
+    println!("\nPhases:");
+    for phase in &report.phases {
+        println!("{:>10.2}ms  {}", ms(phase.us), phase.phase);
+    }
+
+    let mut total = PageTimings::default();
+    for page in &report.page_timings {
+        total.markdown_us += page.timings.markdown_us;
+        total.highlight_us += page.timings.highlight_us;
+        total.template_us += page.timings.template_us;
+        total.write_us += page.timings.write_us;
+    }
+    println!("\nRendering {} pages:", report.page_timings.len());
+    println!("{:>10.2}ms  markdown", ms(total.markdown_us));
+    println!("{:>10.2}ms  highlighting", ms(total.highlight_us));
+    println!("{:>10.2}ms  templates", ms(total.template_us));
+    println!("{:>10.2}ms  post-processing and writing", ms(total.write_us));
+
+    println!("\nSlowest pages:");
+    for page in report.page_timings.iter().take(slowest) {
+        println!(
+            "{:>10.2}ms  markdown {:>7.2}ms  highlighting {:>7.2}ms  template {:>7.2}ms  writing {:>7.2}ms  {:<16} {}",
+            ms(page.us),
+            ms(page.timings.markdown_us),
+            ms(page.timings.highlight_us),
+            ms(page.timings.template_us),
+            ms(page.timings.write_us),
+            page.template,
+            page.path.display()
+        );
+    }
+
+    println!("\nTemplates:");
+    for timing in &report.templates {
+        println!(
+            "{:>10.2}ms  {:>4} renders  {}",
+            ms(timing.us),
+            timing.renders,
+            timing.template
+        );
+    }
+}
+
+/// Wrap a handler, collecting warnings and totals into a history record
+pub fn recording_handler(inner: EventHandler, record: Arc<Mutex<BuildRecord>>) -> EventHandler {
+    Arc::new(move |event| {
+        match event {
+            BuildEvent::Warning { diagnostic } => {
+                record.lock().unwrap().warnings.push(diagnostic.summary());
+            }
+            BuildEvent::Finished { stats } => {
+                let mut record = record.lock().unwrap();
+                record.pages = stats.pages;
+                record.ms = stats.ms;
+            }
+            _ => {}
+        }
+        inner(event);
+    })
+}
+
+/// What a finished build wrote, and where its time went
+pub fn log_summary(report: &BuildReport) {
+    tracing::info!(
+        files = report.files_written,
+        bytes = report.bytes_written,
+        "Wrote {} files ({})",
+        report.files_written,
+        format_bytes(report.bytes_written)
+    );
+    let phases: Vec<String> = report
+        .phases
+        .iter()
+        .map(|phase| format!("{} {:.1}ms", phase.phase, phase.us as f64 / 1000.0))
+        .collect();
+    tracing::info!("  {}", phases.join(", "));
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+use std::process::Command;
+
+fn check(dir: &Path, args: &[&str]) -> Option<i32> {
+    let theme = Path::new(env!("CARGO_MANIFEST_DIR")).join("../theme");
+    Command::new(env!("CARGO_BIN_EXE_zap"))
+        .current_dir(dir)
+        .arg("check")
+        .arg("--theme")
+        .arg(theme)
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+#[test]
+fn test_check_exit_code() {
+    let dir = std::env::temp_dir().join(format!("zap-check-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("site")).unwrap();
+    std::fs::write(dir.join("site/README.md"), "# Zap\n\nSee [the guide](guide.md).\n").unwrap();
+    std::fs::write(dir.join("site/guide.md"), "# Guide\n").unwrap();
+
+    assert_eq!(check(&dir, &[]), Some(0));
+
+    std::fs::write(dir.join("site/README.md"), "# Zap\n\nSee [the guide](missing.md).\n").unwrap();
+    assert_eq!(check(&dir, &[]), Some(1));
+    assert_eq!(check(&dir, &["--warn-only"]), Some(0));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
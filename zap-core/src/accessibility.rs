@@ -0,0 +1,257 @@
+// Accessibility fixes from `[accessibility]`, for themes that leave them
+// out. Each page gets a skip link ahead of everything else, pointing at the
+// main content, which becomes a `main` landmark when the theme didn't write
+// one. Navigation becomes a `nav` landmark the same way, and its links to
+// the page itself are marked `aria-current="page"`. Whatever the theme
+// already has is left alone.
+
+use crate::config::AccessibilityConfig;
+use crate::privacy::{attribute, tag_name};
+
+/// Given to the main content when it has no ID for the skip link
+const MAIN_ID: &str = "main-content";
+
+// Off screen until focused with the keyboard
+const STYLE: &str = "<style>.zap-skip-link{position:absolute;left:-10000px;width:1px;height:1px;overflow:hidden}\
+.zap-skip-link:focus{position:fixed;left:1rem;top:1rem;width:auto;height:auto;z-index:10000;padding:0.5rem 1rem;\
+background:#fff;color:#000;border-radius:0.25rem;box-shadow:0 2px 8px rgba(0,0,0,0.3);font:14px system-ui,sans-serif}</style>\n";
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// Elements likely to hold the main content, or navigation, going by their
+// ID or classes
+const MAIN_NAMES: &[&str] = &["content", "main"];
+const NAV_NAMES: &[&str] = &["nav", "navbar", "navigation", "menu", "sidebar"];
+
+struct Tag {
+    start: usize,
+    end: usize,
+    name: String,
+    closing: bool,
+    /// Elements the tag is inside of
+    depth: usize,
+}
+
+/// Add a skip link, `main` and `nav` landmarks and `aria-current` to the
+/// page at `url`, where the theme hasn't
+pub fn apply(html: String, url: &str, config: &AccessibilityConfig) -> String {
+    let tags = tags(&html);
+    let opening = || tags.iter().filter(|tag| !tag.closing);
+    let role = |tag: &Tag| attribute(&html[tag.start..tag.end], "role").map(str::to_lowercase);
+    let named = |tag: &Tag, names: &[&str]| {
+        let tag = &html[tag.start..tag.end];
+        attribute(tag, "id").is_some_and(|id| names.contains(&id))
+            || attribute(tag, "class").is_some_and(|class| class.split_whitespace().any(|c| names.contains(&c)))
+    };
+    // Where to insert what
+    let mut edits: Vec<(usize, String)> = Vec::new();
+
+    // The main landmark, the first article or element named like one, or
+    // everything between the body's header and footer
+    let main = opening().find(|tag| tag.name == "main" || role(tag).as_deref() == Some("main"));
+    let main = main.or_else(|| {
+        let found = opening()
+            .find(|tag| tag.name == "article")
+            .or_else(|| opening().find(|tag| matches!(tag.name.as_str(), "div" | "section") && named(tag, MAIN_NAMES)))?;
+        edits.push((found.end - 1, " role=\"main\"".to_string()));
+        Some(found)
+    });
+    let main_id = match main {
+        Some(tag) => match attribute(&html[tag.start..tag.end], "id") {
+            Some(id) => id.to_string(),
+            None => {
+                edits.push((tag.end - 1, format!(" id=\"{}\"", MAIN_ID)));
+                MAIN_ID.to_string()
+            }
+        },
+        None => {
+            let body = opening().find(|tag| tag.name == "body");
+            let child = |tag: &&Tag| body.is_some_and(|body| tag.depth == body.depth + 1);
+            let start = tags
+                .iter()
+                .find(|tag| tag.closing && tag.name == "header" && child(tag))
+                .or(body)
+                .map(|tag| tag.end);
+            let end = tags
+                .iter()
+                .rfind(|tag| !tag.closing && tag.name == "footer" && child(tag))
+                .map(|tag| tag.start)
+                .or_else(|| tags.iter().rfind(|tag| tag.closing && tag.name == "body").map(|tag| tag.start));
+            let (Some(start), Some(end)) = (start, end) else {
+                return html;
+            };
+            edits.push((start, format!("<main id=\"{}\">", MAIN_ID)));
+            edits.push((end, "</main>".to_string()));
+            MAIN_ID.to_string()
+        }
+    };
+
+    // Navigation landmarks, the outermost lists or containers named like
+    // navigation when the theme has no `nav`
+    let is_nav = |tag: &Tag| tag.name == "nav" || role(tag).as_deref() == Some("navigation");
+    let mut navs: Vec<&Tag> = opening().filter(|tag| is_nav(tag)).collect();
+    if navs.is_empty() {
+        for tag in opening() {
+            let container = matches!(tag.name.as_str(), "div" | "ul" | "ol" | "section");
+            if container && named(tag, NAV_NAMES) && !navs.iter().any(|nav| inside(&tags, tag, nav)) {
+                edits.push((tag.end - 1, " role=\"navigation\"".to_string()));
+                navs.push(tag);
+            }
+        }
+    }
+
+    let current = link_path(url, url);
+    let mut skip_link = false;
+    for tag in opening().filter(|tag| tag.name == "a") {
+        let text = &html[tag.start..tag.end];
+        // Tera escapes `/` in links
+        let Some(href) = attribute(text, "href").map(html_escape::decode_html_entities) else {
+            continue;
+        };
+        if href.strip_prefix('#') == Some(main_id.as_str()) {
+            skip_link = true;
+        }
+        let in_nav = navs.iter().any(|nav| inside(&tags, tag, nav));
+        if in_nav && attribute(text, "aria-current").is_none() && link_path(&href, url) == current {
+            edits.push((tag.end - 1, " aria-current=\"page\"".to_string()));
+        }
+    }
+
+    if !skip_link && let Some(body) = opening().find(|tag| tag.name == "body") {
+        edits.push((
+            body.end,
+            format!(
+                "<a class=\"zap-skip-link\" href=\"#{}\">{}</a>",
+                html_escape::encode_double_quoted_attribute(&main_id),
+                html_escape::encode_text(&config.skip_link)
+            ),
+        ));
+        if let Some(head) = tags.iter().find(|tag| tag.closing && tag.name == "head") {
+            edits.push((head.start, STYLE.to_string()));
+        }
+    }
+
+    let mut html = html;
+    edits.sort_by_key(|(pos, _)| *pos);
+    for (pos, text) in edits.into_iter().rev() {
+        html.insert_str(pos, &text);
+    }
+    html
+}
+
+// Whether `tag` is within the element `parent` opens
+fn inside(tags: &[Tag], tag: &Tag, parent: &Tag) -> bool {
+    if tag.start <= parent.start {
+        return false;
+    }
+    let close = tags
+        .iter()
+        .find(|other| other.start > parent.start && other.closing && other.name == parent.name && other.depth == parent.depth);
+    close.is_none_or(|close| tag.start < close.start)
+}
+
+// The tags of a page, leaving out comments and what's inside scripts and
+// styles
+fn tags(html: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(i) = html[pos..].find('<') {
+        let start = pos + i;
+        let rest = &html[start..];
+        if rest.starts_with("<!--") {
+            pos = rest.find("-->").map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        let Some(len) = rest.find('>') else {
+            break;
+        };
+        let end = start + len + 1;
+        pos = end;
+
+        if let Some(name) = rest.strip_prefix("</") {
+            let name = tag_name(name);
+            // Unclosed elements inside end with it
+            if let Some(index) = open.iter().rposition(|element| *element == name) {
+                open.truncate(index);
+                tags.push(Tag { start, end, name, closing: true, depth: index });
+            }
+            continue;
+        }
+        let name = tag_name(rest);
+        if name.is_empty() {
+            continue;
+        }
+        tags.push(Tag { start, end, name: name.clone(), closing: false, depth: open.len() });
+
+        if matches!(name.as_str(), "script" | "style") {
+            let close = format!("</{}", name);
+            pos = html[end..].find(&close).map_or(html.len(), |i| end + i);
+            open.push(name);
+        } else if !VOID_ELEMENTS.contains(&name.as_str()) && !html[..end - 1].ends_with('/') {
+            open.push(name);
+        }
+    }
+    tags
+}
+
+// The page a link points to from the page at `url`, without the query,
+// fragment or `index.html`. `None` for other sites and other schemes.
+fn link_path(href: &str, url: &str) -> Option<String> {
+    let href = href.split(['#', '?']).next().unwrap_or_default();
+    if href.is_empty() || href.starts_with("//") || href.contains(':') {
+        return None;
+    }
+    let joined = match href.starts_with('/') {
+        true => href.to_string(),
+        false => format!("{}{}", &url[..url.rfind('/').map_or(0, |i| i + 1)], href),
+    };
+
+    let mut segments = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    if segments.last() == Some(&"index.html") {
+        segments.pop();
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let config = AccessibilityConfig::default();
+        let html = "<html><head><title>Zap</title></head><body><header><div class=\"menu\"><a href=\"/\">Home</a> <a href=\"..&#x2F;guide&#x2F;\">Guide</a></div></header>\
+                    <p>Intro<br/><img src=\"a.png\"/></p><footer><a href=\"/guide/index.html\">Guide</a></footer></body></html>";
+        let fixed = apply(html.to_string(), "/guide/index.html", &config);
+        assert_eq!(
+            fixed,
+            format!(
+                "<html><head><title>Zap</title>{}</head><body><a class=\"zap-skip-link\" href=\"#main-content\">Skip to content</a>\
+                 <header><div class=\"menu\" role=\"navigation\"><a href=\"/\">Home</a> <a href=\"..&#x2F;guide&#x2F;\" aria-current=\"page\">Guide</a></div></header>\
+                 <main id=\"main-content\"><p>Intro<br/><img src=\"a.png\"/></p></main><footer><a href=\"/guide/index.html\">Guide</a></footer></body></html>",
+                STYLE
+            )
+        );
+        // Nothing's added twice
+        assert_eq!(apply(fixed.clone(), "/guide/index.html", &config), fixed);
+
+        let article = "<body><nav><a href=\"/post/\">Post</a></nav><article id=\"post\">text</article></body>";
+        assert_eq!(
+            apply(article.to_string(), "/post/index.html", &config),
+            "<body><a class=\"zap-skip-link\" href=\"#post\">Skip to content</a><nav><a href=\"/post/\" aria-current=\"page\">Post</a></nav><article id=\"post\" role=\"main\">text</article></body>"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::markdown::{PageElement, render_inline_elements_text, slugify};
+
+/// A heading that external tools can deep-link to.
+///
+/// `id` is derived from the heading's position in the outline (its own text
+/// plus the text of every parent heading) so it survives sections being
+/// reordered, unlike positional anchors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anchor {
+    pub id: String,
+    pub slug: String,
+    pub text: String,
+    pub level: u32,
+    pub path: Vec<String>,
+    /// Slugs the heading had in earlier builds, kept working as aliases
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// A heading anchor from the previous build that no longer exists, so links
+/// to it will land at the top of the page
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnchorChange {
+    /// The heading is still there under a new slug
+    Renamed { from: String, to: String },
+    Removed { slug: String },
+}
+
+/// Collect anchors for every heading on a page, at any level, in document
+/// order
+pub fn page_anchors(elements: &[PageElement]) -> Vec<Anchor> {
+    let mut anchors = Vec::new();
+    let mut stack: Vec<(u32, String)> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for element in elements {
+        let PageElement::Heading { level, content } = element else {
+            continue;
+        };
+
+        let text = render_inline_elements_text(content);
+        while stack.last().is_some_and(|(l, _)| *l >= *level) {
+            stack.pop();
+        }
+        stack.push((*level, text.clone()));
+
+        let path: Vec<String> = stack.iter().map(|(_, t)| t.clone()).collect();
+        let mut key = path.iter().map(|t| slugify(t)).collect::<Vec<_>>().join("/");
+
+        // Identical sibling headings get an occurrence suffix
+        let count = seen.entry(key.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            key = format!("{}#{}", key, count);
+        }
+
+        anchors.push(Anchor {
+            id: stable_id(&key),
+            slug: slugify(&text),
+            text,
+            level: *level,
+            path,
+            aliases: Vec::new(),
+        });
+    }
+
+    anchors
+}
+
+/// Short, stable hash of a heading key. FNV-1a is used rather than std's
+/// hasher because the output must not change between Rust releases.
+pub fn stable_id(key: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("h{:08x}", (hash >> 32) as u32 ^ hash as u32)
+}
+
+/// Where the anchors of the page written to `page_file` go: `anchors.json`
+/// beside an `index.html`, `<name>.anchors.json` beside `<name>.html`
+pub fn anchors_path(page_file: &Path) -> PathBuf {
+    match page_file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("index") | None => page_file.with_file_name("anchors.json"),
+        Some(stem) => page_file.with_file_name(format!("{}.anchors.json", stem)),
+    }
+}
+
+/// Anchors keyed by stable ID, as written to a page's `anchors.json`
+pub fn anchors_json(anchors: &[Anchor]) -> serde_json::Result<String> {
+    let map: serde_json::Map<String, serde_json::Value> = anchors
+        .iter()
+        .map(|a| Ok((a.id.clone(), serde_json::to_value(a)?)))
+        .collect::<serde_json::Result<_>>()?;
+
+    serde_json::to_string_pretty(&map)
+}
+
+/// Anchors written by a previous build, empty when there are none
+pub fn read_anchors(path: &Path) -> Vec<Anchor> {
+    let Ok(json) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let map: HashMap<String, Anchor> = serde_json::from_str(&json).unwrap_or_default();
+    let mut anchors: Vec<Anchor> = map.into_values().collect();
+    // Keyed by ID in the file, so document order is lost. Outline paths
+    // sort close enough for pairing renamed headings.
+    anchors.sort_by(|a, b| a.path.cmp(&b.path));
+    anchors
+}
+
+/// Slugs from `previous` missing from `current`. A missing heading is taken
+/// to be renamed when it's the only one missing under its parent heading at
+/// its level, and exactly one new heading appeared there.
+pub fn compare(previous: &[Anchor], current: &[Anchor]) -> Vec<AnchorChange> {
+    let has_slug = |anchors: &[Anchor], slug: &str| anchors.iter().any(|a| a.slug == slug);
+    let siblings = |a: &Anchor, b: &Anchor| {
+        a.level == b.level && a.path[..a.path.len() - 1] == b.path[..b.path.len() - 1]
+    };
+    let removed: Vec<&Anchor> = previous.iter().filter(|a| !has_slug(current, &a.slug)).collect();
+    let added: Vec<&Anchor> = current.iter().filter(|a| !has_slug(previous, &a.slug)).collect();
+
+    removed
+        .iter()
+        .map(|old| {
+            let removed_here = removed.iter().filter(|other| siblings(old, other)).count();
+            let mut added_here = added.iter().filter(|new| siblings(old, new));
+            match (removed_here, added_here.next(), added_here.next()) {
+                (1, Some(new), None) => AnchorChange::Renamed {
+                    from: old.slug.clone(),
+                    to: new.slug.clone(),
+                },
+                _ => AnchorChange::Removed { slug: old.slug.clone() },
+            }
+        })
+        .collect()
+}
+
+/// Carry aliases over from the previous build's anchors, adding the old slug
+/// of each renamed heading. Aliases that became a heading's slug again are
+/// dropped.
+pub fn carry_aliases(previous: &[Anchor], current: &mut [Anchor], changes: &[AnchorChange]) {
+    let slugs: Vec<String> = current.iter().map(|a| a.slug.clone()).collect();
+    for anchor in current.iter_mut() {
+        let mut aliases: Vec<String> = previous
+            .iter()
+            .filter(|old| old.slug == anchor.slug)
+            .flat_map(|old| old.aliases.clone())
+            .collect();
+        for change in changes {
+            if let AnchorChange::Renamed { from, to } = change
+                && *to == anchor.slug
+            {
+                aliases.push(from.clone());
+                let old = previous.iter().filter(|old| old.slug == *from);
+                aliases.extend(old.flat_map(|old| old.aliases.clone()));
+            }
+        }
+        aliases.retain(|alias| !slugs.contains(alias));
+        aliases.sort();
+        aliases.dedup();
+        anchor.aliases = aliases;
+    }
+}
+
+/// Add an empty element with each alias as its ID before the heading, so
+/// links to the old slugs still scroll to it
+pub fn insert_aliases(html: &str, anchors: &[Anchor]) -> String {
+    let mut html = html.to_string();
+    for anchor in anchors.iter().filter(|a| !a.aliases.is_empty()) {
+        let needle = format!("<h{} id=\"{}\"", anchor.level, anchor.slug);
+        let Some(pos) = html.find(&needle) else {
+            continue;
+        };
+        let spans: String = anchor
+            .aliases
+            .iter()
+            .map(|alias| format!("<span id=\"{}\" class=\"anchor-alias\"></span>", alias))
+            .collect();
+        html.insert_str(pos, &spans);
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let anchors = |markdown: &str| page_anchors(&crate::markdown::parse_structured(markdown));
+        let previous = anchors("# Guide\n## Setup\n## Usage\n### Old section\n");
+        let mut current = anchors("# Guide\n## Installation\n## Usage\n");
+
+        let changes = compare(&previous, &current);
+        assert_eq!(
+            changes,
+            vec![
+                AnchorChange::Renamed { from: "setup".to_string(), to: "installation".to_string() },
+                AnchorChange::Removed { slug: "old-section".to_string() },
+            ]
+        );
+
+        carry_aliases(&previous, &mut current, &changes);
+        assert_eq!(current[1].aliases, vec!["setup"]);
+        assert_eq!(
+            insert_aliases("<h2 id=\"installation\">Installation</h2>", &current),
+            "<span id=\"setup\" class=\"anchor-alias\"></span><h2 id=\"installation\">Installation</h2>"
+        );
+    }
+}
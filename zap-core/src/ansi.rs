@@ -0,0 +1,208 @@
+// Rendering of ANSI SGR escape sequences (colors, bold, ...) to HTML, used
+// for `console` and `ansi` code blocks.
+
+const ESC: char = '\x1b';
+
+// Escapes pasted as text rather than as the raw control character
+const TEXTUAL_ESCAPES: [&str; 3] = ["\\x1b", "\\033", "\\u001b"];
+
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(fg) = &self.fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            css.push(format!("background-color:{}", bg));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.7".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+
+    fn apply(&mut self, params: &[u32]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                n @ 30..=37 => self.fg = Some(PALETTE[(n - 30) as usize].to_string()),
+                n @ 90..=97 => self.fg = Some(PALETTE[(n - 90 + 8) as usize].to_string()),
+                39 => self.fg = None,
+                n @ 40..=47 => self.bg = Some(PALETTE[(n - 40) as usize].to_string()),
+                n @ 100..=107 => self.bg = Some(PALETTE[(n - 100 + 8) as usize].to_string()),
+                49 => self.bg = None,
+                n @ (38 | 48) => {
+                    let (color, consumed) = extended_color(&params[i + 1..]);
+                    if n == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                    i += consumed;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Parse the tail of a `38;...` / `48;...` sequence, returning the color and
+/// how many parameters it used
+fn extended_color(params: &[u32]) -> (Option<String>, usize) {
+    match params {
+        [5, n, ..] => (Some(color_256(*n)), 2),
+        [2, r, g, b, ..] => (Some(format!("#{:02x}{:02x}{:02x}", r, g, b)), 4),
+        _ => (None, params.len()),
+    }
+}
+
+fn color_256(n: u32) -> String {
+    match n {
+        0..=15 => PALETTE[n as usize].to_string(),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u32| if v == 0 { 0 } else { 55 + v * 40 };
+            format!("#{:02x}{:02x}{:02x}", level(n / 36), level((n / 6) % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+    }
+}
+
+/// Render text containing ANSI escape codes as a `<pre>` block with colored
+/// spans. Escape sequences other than SGR are stripped.
+pub fn render_ansi(text: &str) -> String {
+    let mut normalized = text.to_string();
+    for escape in TEXTUAL_ESCAPES {
+        normalized = normalized.replace(escape, &ESC.to_string());
+    }
+
+    let mut html = String::from("<pre class=\"ansi\"><code>");
+    let mut style = Style::default();
+    let mut span_open = false;
+    let mut chars = normalized.chars().peekable();
+    let mut buf = String::new();
+
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            buf.push(c);
+            continue;
+        }
+
+        // Only CSI sequences (ESC [) are interpreted, lone escapes are dropped
+        if chars.peek() != Some(&'[') {
+            continue;
+        }
+        chars.next();
+
+        let mut sequence = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() {
+                terminator = Some(c);
+                break;
+            }
+            sequence.push(c);
+        }
+
+        if terminator != Some('m') {
+            continue;
+        }
+
+        let params: Vec<u32> = sequence
+            .split(';')
+            .filter(|p| !p.is_empty())
+            .filter_map(|p| p.parse().ok())
+            .collect();
+
+        let mut next = style.clone();
+        next.apply(&params);
+        if next == style {
+            continue;
+        }
+
+        html.push_str(&html_escape::encode_text(&buf));
+        buf.clear();
+        if span_open {
+            html.push_str("</span>");
+            span_open = false;
+        }
+        if next != Style::default() {
+            html.push_str(&format!("<span style=\"{}\">", next.css()));
+            span_open = true;
+        }
+        style = next;
+    }
+
+    html.push_str(&html_escape::encode_text(&buf));
+    if span_open {
+        html.push_str("</span>");
+    }
+    html.push_str("</code></pre>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_become_spans() {
+        let html = render_ansi("\x1b[32mok\x1b[0m done");
+        assert_eq!(
+            html,
+            "<pre class=\"ansi\"><code><span style=\"color:#0dbc79\">ok</span> done</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_textual_escapes_and_non_sgr_sequences() {
+        let html = render_ansi("\\x1b[1;31merror\\x1b[0m\x1b[2K <tag>");
+        assert!(html.contains("<span style=\"color:#cd3131;font-weight:bold\">error</span>"));
+        assert!(html.contains(" &lt;tag&gt;"));
+        assert!(!html.contains('\x1b'));
+    }
+}
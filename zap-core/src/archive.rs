@@ -0,0 +1,199 @@
+// The built site as a single `.tar.gz` or `.zip`, for releases, CI artifacts
+// or pinning. Archives are reproducible: the same output directory gives the
+// same bytes, since files are added in sorted order with a fixed time,
+// permissions and owner. A manifest listing every file with its SHA-256 is
+// added first.
+
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Datelike, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+pub const MANIFEST_FILE: &str = "zap-manifest.json";
+
+// Bumped whenever the manifest changes in a way readers must know about
+pub const MANIFEST_FORMAT: u32 = 1;
+
+// 1980-01-01, the earliest time a zip can hold, for builds that don't set
+// SOURCE_DATE_EPOCH
+const DEFAULT_EPOCH: i64 = 315_532_800;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// The format a file name asks for: `.tar.gz`, `.tgz` or `.zip`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: u32,
+    pub title: String,
+    /// The zap version that built the archive
+    pub generator: String,
+    /// The time every file in the archive has, as seconds since the Unix
+    /// epoch
+    pub timestamp: i64,
+    pub files: Vec<ArchiveFile>,
+}
+
+/// `SOURCE_DATE_EPOCH` when set, as reproducible build tools expect, and
+/// 1980-01-01 otherwise
+pub fn source_date_epoch() -> i64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .unwrap_or(DEFAULT_EPOCH)
+}
+
+/// Package every file in `site_dir` into `archive_path`
+pub fn write_archive(
+    site_dir: &Path,
+    archive_path: &Path,
+    format: ArchiveFormat,
+    title: &str,
+    timestamp: i64,
+) -> std::io::Result<ArchiveManifest> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(site_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(site_dir).unwrap_or(entry.path());
+        let path: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+        files.push((path.join("/"), entry.path().to_path_buf()));
+    }
+    // WalkDir sorts within a directory, but `a.html` must come before `a/`
+    // whatever the platform's separator sorts as
+    files.sort();
+
+    let manifest = ArchiveManifest {
+        format: MANIFEST_FORMAT,
+        title: title.to_string(),
+        generator: format!("zap {}", env!("CARGO_PKG_VERSION")),
+        timestamp,
+        files: files
+            .iter()
+            .map(|(path, file)| {
+                Ok(ArchiveFile {
+                    path: path.clone(),
+                    size: std::fs::metadata(file)?.len(),
+                    sha256: crate::artifacts::sha256_file(file)?,
+                })
+            })
+            .collect::<std::io::Result<_>>()?,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::from)?;
+
+    if let Some(parent) = archive_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(archive_path)?;
+    // Contents are read as they're added, rather than all held at once
+    let entries = std::iter::once(Ok((MANIFEST_FILE.to_string(), manifest_json))).chain(
+        files
+            .iter()
+            .map(|(path, file)| std::io::Result::Ok((path.clone(), std::fs::read(file)?))),
+    );
+    match format {
+        ArchiveFormat::TarGz => {
+            // The gzip header's own time is left at zero
+            let gzip = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut archive = tar::Builder::new(gzip);
+            for entry in entries {
+                let (path, data) = entry?;
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(timestamp.max(0) as u64);
+                header.set_uid(0);
+                header.set_gid(0);
+                header.set_cksum();
+                archive.append_data(&mut header, &path, data.as_slice())?;
+            }
+            archive.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let time = zip_time(timestamp);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .last_modified_time(time)
+                .unix_permissions(0o644);
+            let mut archive = zip::ZipWriter::new(file);
+            for entry in entries {
+                let (path, data) = entry?;
+                archive.start_file(path, options)?;
+                archive.write_all(&data)?;
+            }
+            archive.finish()?;
+        }
+    }
+
+    Ok(manifest)
+}
+
+// Zip times are local, to the second, from 1980 to 2107
+fn zip_time(timestamp: i64) -> zip::DateTime {
+    let time = Utc.timestamp_opt(timestamp.clamp(DEFAULT_EPOCH, 4_354_819_199), 0).single().unwrap_or_default();
+    zip::DateTime::from_date_and_time(
+        time.year() as u16,
+        time.month() as u8,
+        time.day() as u8,
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+    )
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible_archive() {
+        let dir = std::env::temp_dir().join(format!("zap-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("site/a")).unwrap();
+        std::fs::write(dir.join("site/a/index.html"), "<p>a</p>").unwrap();
+        std::fs::write(dir.join("site/a.html"), "<p>b</p>").unwrap();
+
+        for name in ["site.tar.gz", "site.zip"] {
+            let format = ArchiveFormat::from_path(Path::new(name)).unwrap();
+            let first = dir.join(format!("first-{}", name));
+            let manifest = write_archive(&dir.join("site"), &first, format, "Test", 1_700_000_000).unwrap();
+            let paths: Vec<&str> = manifest.files.iter().map(|file| file.path.as_str()).collect();
+            assert_eq!(paths, ["a.html", "a/index.html"]);
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let second = dir.join(format!("second-{}", name));
+            write_archive(&dir.join("site"), &second, format, "Test", 1_700_000_000).unwrap();
+            assert_eq!(std::fs::read(&first).unwrap(), std::fs::read(&second).unwrap());
+        }
+        assert_eq!(ArchiveFormat::from_path(Path::new("site.tar")), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+// Checksums and signatures for files `zap export` writes, so whoever
+// downloads them can check they're intact and came from the project. Each
+// export adds its file to a `SHA256SUMS` in the same directory, in the
+// format `sha256sum --check` reads, and `minisign` can sign both.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+
+use crate::config::ExportConfig;
+
+pub const SUMS_FILE: &str = "SHA256SUMS";
+
+/// Hex SHA-256 of a file's contents
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Add `artifact` to the `SHA256SUMS` next to it, replacing an older entry
+/// for the same name and keeping the others
+pub fn record_checksum(artifact: &Path) -> std::io::Result<PathBuf> {
+    let name = artifact
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| std::io::Error::other(format!("{} has no file name", artifact.display())))?;
+    let sums_path = artifact.with_file_name(SUMS_FILE);
+
+    let mut sums: BTreeMap<String, String> = std::fs::read_to_string(&sums_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (hash, name) = line.split_once("  ")?;
+            Some((name.to_string(), hash.to_string()))
+        })
+        .collect();
+    sums.insert(name.to_string(), sha256_file(artifact)?);
+
+    let text: String = sums.iter().map(|(name, hash)| format!("{}  {}\n", hash, name)).collect();
+    std::fs::write(&sums_path, text)?;
+    Ok(sums_path)
+}
+
+/// Sign `file` with the `minisign` command, writing `<file>.minisig`.
+/// minisign asks for the key's password on the terminal unless the key was
+/// generated without one.
+pub fn minisign(file: &Path, key: Option<&Path>) -> std::io::Result<PathBuf> {
+    let mut command = Command::new("minisign");
+    command.arg("-S").arg("-m").arg(file);
+    if let Some(key) = key {
+        command.arg("-s").arg(key);
+    }
+
+    let status = command.status().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            std::io::Error::other("minisign is not installed, see https://jedisct1.github.io/minisign/")
+        }
+        _ => e,
+    })?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("minisign could not sign {}", file.display())));
+    }
+
+    let mut signature = file.as_os_str().to_owned();
+    signature.push(".minisig");
+    Ok(PathBuf::from(signature))
+}
+
+/// Write what `[export]` asks for next to a freshly exported `artifact`,
+/// returning the files written
+pub fn seal(config: &ExportConfig, artifact: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    let sums = match config.checksums {
+        true => Some(record_checksum(artifact)?),
+        false => None,
+    };
+    written.extend(sums.clone());
+
+    if config.sign {
+        let key = config.minisign_key.as_deref();
+        written.push(minisign(artifact, key)?);
+        // A signed checksum list covers every export in the directory
+        if let Some(sums) = &sums {
+            written.push(minisign(sums, key)?);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_checksum() {
+        let dir = std::env::temp_dir().join(format!("zap-artifacts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("help.tar"), "abc").unwrap();
+        std::fs::write(dir.join(SUMS_FILE), format!("{}  other.tar\n{}  help.tar\n", "1".repeat(64), "0".repeat(64)))
+            .unwrap();
+
+        let sums = record_checksum(&dir.join("help.tar")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(sums).unwrap(),
+            format!(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  help.tar\n{}  other.tar\n",
+                "1".repeat(64)
+            )
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
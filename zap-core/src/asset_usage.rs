@@ -0,0 +1,247 @@
+// Images without alt text and static files no page uses, for `zap report
+// assets`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::markdown::{InlineElement, PageElement};
+use crate::privacy::{attribute, tag_name};
+use crate::site::Page;
+
+// Served from the root by convention and fetched without a link
+const WELL_KNOWN: [&str; 9] = [
+    "favicon.ico",
+    "robots.txt",
+    "CNAME",
+    ".nojekyll",
+    "_headers",
+    "_redirects",
+    "humans.txt",
+    "apple-touch-icon.png",
+    "sitemap.xml",
+];
+
+// Attributes that can hold a URL to a file
+const URL_ATTRIBUTES: [&str; 5] = ["href", "src", "poster", "data", "content"];
+
+/// An image on a page with no alt text
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingAlt {
+    pub page: PathBuf,
+    pub src: String,
+}
+
+/// Images without alt text. Markdown images with an empty description count,
+/// HTML `<img>` tags only without an `alt` attribute, since `alt=""` marks an
+/// image as decorative.
+pub fn missing_alt<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Vec<MissingAlt> {
+    let mut missing = Vec::new();
+    for page in pages {
+        let mut srcs = Vec::new();
+        elements_missing_alt(page.elements(), &mut srcs);
+        missing.extend(srcs.into_iter().map(|src| MissingAlt {
+            page: page.path.clone(),
+            src,
+        }));
+    }
+    missing
+}
+
+fn elements_missing_alt(elements: &[PageElement], srcs: &mut Vec<String>) {
+    for element in elements {
+        match element {
+            PageElement::Heading { content, .. } | PageElement::Paragraph { content } => {
+                inlines_missing_alt(content, srcs);
+            }
+            PageElement::List { items, .. } => {
+                let mut items: Vec<_> = items.iter().collect();
+                while let Some(item) = items.pop() {
+                    inlines_missing_alt(&item.content, srcs);
+                    items.extend(&item.sub_items);
+                }
+            }
+            PageElement::BlockQuote { content } => elements_missing_alt(content, srcs),
+            PageElement::Table { headers, rows } => {
+                for cell in headers.iter().chain(rows.iter().flatten()) {
+                    inlines_missing_alt(cell, srcs);
+                }
+            }
+            PageElement::Html { content } => {
+                for tag in content.split('<').skip(1) {
+                    let tag = format!("<{}>", tag.split('>').next().unwrap_or_default());
+                    if tag_name(&tag) == "img" && attribute(&tag, "alt").is_none() {
+                        srcs.push(attribute(&tag, "src").unwrap_or_default().to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn inlines_missing_alt(content: &[InlineElement], srcs: &mut Vec<String>) {
+    for inline in content {
+        match inline {
+            InlineElement::Image { alt, url, .. } if alt.trim().is_empty() => srcs.push(url.clone()),
+            InlineElement::Emphasis { content, .. } | InlineElement::Strikethrough { content } => {
+                inlines_missing_alt(content, srcs);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Files in `static_dir` that nothing in the built site at `output_dir`
+/// references, relative to `static_dir`. Pages and stylesheets are checked
+/// for links to the file, scripts and other text files for its URL.
+pub fn unused_assets(static_dir: &Path, output_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut referenced = HashSet::new();
+    let mut texts = Vec::new();
+
+    for path in files_in(output_dir) {
+        let relative = path.strip_prefix(output_dir).unwrap_or(&path);
+        let url = format!("/{}", url_path(relative));
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match extension {
+            "html" | "htm" => {
+                let html = std::fs::read_to_string(&path)?;
+                referenced.extend(page_references(&html).iter().filter_map(|r| resolve(&url, r)));
+            }
+            "css" => {
+                let css = std::fs::read_to_string(&path)?;
+                referenced.extend(css_references(&css).iter().filter_map(|r| resolve(&url, r)));
+            }
+            "js" | "mjs" | "json" | "webmanifest" | "xml" | "txt" => {
+                texts.push(std::fs::read_to_string(&path).unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+
+    let mut unused = Vec::new();
+    for path in files_in(static_dir) {
+        let relative = path.strip_prefix(static_dir).unwrap_or(&path).to_path_buf();
+        let url = format!("/{}", url_path(&relative));
+        let well_known = WELL_KNOWN.contains(&url_path(&relative).as_str())
+            || relative.starts_with(".well-known");
+        let mentioned = texts.iter().any(|text| text.contains(&url));
+        if !well_known && !mentioned && !referenced.contains(&url) {
+            unused.push(relative);
+        }
+    }
+
+    Ok(unused)
+}
+
+fn page_references(html: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    for (start, _) in html.match_indices('<') {
+        let Some(len) = html[start..].find('>') else {
+            break;
+        };
+        let tag = &html[start..start + len + 1];
+        for name in URL_ATTRIBUTES {
+            if let Some(url) = attribute(tag, name) {
+                references.push(html_escape::decode_html_entities(url).to_string());
+            }
+        }
+        if let Some(srcset) = attribute(tag, "srcset") {
+            references.extend(
+                srcset
+                    .split(',')
+                    .filter_map(|candidate| candidate.split_whitespace().next())
+                    .map(|url| url.to_string()),
+            );
+        }
+        if let Some(style) = attribute(tag, "style") {
+            references.extend(css_references(style));
+        }
+        if tag_name(tag) == "style"
+            && let Some(end) = html[start + len..].find("</style>")
+        {
+            references.extend(css_references(&html[start + len..start + len + end]));
+        }
+    }
+    references
+}
+
+fn css_references(css: &str) -> Vec<String> {
+    css.match_indices("url(")
+        .filter_map(|(start, _)| {
+            let open = start + "url(".len();
+            let len = css[open..].find(')')?;
+            Some(css[open..open + len].trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        })
+        .collect()
+}
+
+// A reference from the file served at `from` as a root-relative path, or
+// `None` when it's to another site
+fn resolve(from: &str, reference: &str) -> Option<String> {
+    let reference = reference.split(['?', '#']).next()?.trim();
+    if reference.is_empty() || reference.contains(':') || reference.starts_with("//") {
+        return None;
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    if !reference.starts_with('/') {
+        segments = from.split('/').filter(|s| !s.is_empty()).collect();
+        if !from.ends_with('/') {
+            segments.pop();
+        }
+    }
+    for segment in reference.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+fn url_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references() {
+        assert_eq!(resolve("/docs/guide/index.html", "shot.png"), Some("/docs/guide/shot.png".to_string()));
+        assert_eq!(resolve("/docs/guide/index.html", "../../img/a.png?v=2"), Some("/img/a.png".to_string()));
+        assert_eq!(resolve("/style.css", "/fonts/a.woff2"), Some("/fonts/a.woff2".to_string()));
+        assert_eq!(resolve("/index.html", "https://example.com/a.png"), None);
+
+        let html = r#"<img src="/img/a.png" srcset="/img/a@2x.png 2x"><div style="background: url('/img/bg.jpg')"></div>"#;
+        assert_eq!(page_references(html), vec!["/img/a.png", "/img/a@2x.png", "/img/bg.jpg"]);
+
+        let elements = crate::markdown::parse_structured(
+            "![](/img/a.png) ![Logo](/img/logo.png)\n\n<img src=\"/img/b.png\">\n\n<img src=\"/img/c.png\" alt=\"\">\n",
+        );
+        let mut srcs = Vec::new();
+        elements_missing_alt(&elements, &mut srcs);
+        assert_eq!(srcs, vec!["/img/a.png", "/img/b.png"]);
+    }
+}
@@ -0,0 +1,281 @@
+// Status badges fetched at build time and embedded in the page as data URL
+// images, so pages can show CI or release status without visitors' browsers
+// calling shields.io or a status service. A badge is an image whose URL
+// starts with `badge:`:
+//
+//     ![CI](badge:https://img.shields.io/github/actions/workflow/status/o/r/ci.yml "https://github.com/o/r/actions")
+//
+// The URL can return an SVG or JSON in shields.io's endpoint format, which
+// is drawn locally. A title that's a URL makes the badge a link. Fetched
+// SVGs are never inlined as markup: as an image, whatever scripts or event
+// handlers one carries can't run in the page.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+pub const SCHEME: &str = "badge:";
+
+/// Replace every badge image in `html` with what `render` returns for its
+/// URL, alt text and link
+pub fn expand(html: &str, mut render: impl FnMut(&str, &str, Option<&str>) -> String) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("<img ") {
+        let Some(length) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start..start + length + 1];
+        out.push_str(&rest[..start]);
+        match attribute(tag, "src").and_then(|src| src.strip_prefix(SCHEME).map(str::to_string)) {
+            Some(url) => {
+                let alt = attribute(tag, "alt").unwrap_or_default();
+                let link = attribute(tag, "title").filter(|title| title.starts_with("http"));
+                out.push_str(&render(&url, &alt, link.as_deref()));
+            }
+            None => out.push_str(tag),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// A double-quoted attribute's value, unescaped
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let length = tag[start..].find('"')?;
+    Some(html_escape::decode_html_entities(&tag[start..start + length]).into_owned())
+}
+
+/// The markup for a badge, an image of its SVG when it could be fetched and
+/// its alt text otherwise
+pub fn html(svg: Option<&str>, alt: &str, link: Option<&str>) -> String {
+    let badge = match svg {
+        Some(svg) => format!(
+            "<img class=\"zap-badge\" src=\"data:image/svg+xml;base64,{}\" alt=\"{}\">",
+            base64::engine::general_purpose::STANDARD.encode(svg),
+            html_escape::encode_double_quoted_attribute(alt)
+        ),
+        None => format!("<span class=\"zap-badge\">{}</span>", html_escape::encode_text(alt)),
+    };
+    match link {
+        Some(link) => format!("<a href=\"{}\">{}</a>", html_escape::encode_double_quoted_attribute(link), badge),
+        None => badge,
+    }
+}
+
+/// Fetch a badge as SVG
+pub fn fetch(url: &str) -> Result<String, String> {
+    let download = crate::privacy::download(url)?;
+    let body = String::from_utf8(download.bytes).map_err(|_| "the response isn't text".to_string())?;
+    let svg = match body.trim_start().starts_with('{') {
+        true => {
+            let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+            endpoint_svg(&json).ok_or("the JSON has no `message`, it isn't in shields.io's endpoint format")?
+        }
+        false => body,
+    };
+    svg_document(&svg)
+}
+
+// The SVG without any XML declaration or doctype before it
+fn svg_document(svg: &str) -> Result<String, String> {
+    let start = svg.find("<svg").ok_or("the response isn't an SVG")?;
+    Ok(svg[start..].trim_end().to_string())
+}
+
+/// A flat badge for shields.io's endpoint format: `label`, `message`,
+/// `color` and `labelColor`
+pub fn endpoint_svg(json: &serde_json::Value) -> Option<String> {
+    let message = json["message"].as_str()?;
+    let label = json["label"].as_str().unwrap_or_default();
+    let color = color(json["color"].as_str().unwrap_or(if json["isError"].as_bool() == Some(true) { "red" } else { "lightgrey" }));
+    let label_color = color_or(json["labelColor"].as_str(), "#555");
+
+    // Verdana at 11px averages about 7px a character
+    let width = |text: &str| match text.is_empty() {
+        true => 0,
+        false => text.chars().count() * 7 + 10,
+    };
+    let (label_width, message_width) = (width(label), width(message));
+    let total = label_width + message_width;
+    let text = |x: usize, text: &str| {
+        format!(
+            "<text x=\"{}\" y=\"14\" fill=\"#fff\" text-anchor=\"middle\">{}</text>",
+            x,
+            html_escape::encode_text(text)
+        )
+    };
+
+    Some(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{total}\" height=\"20\" viewBox=\"0 0 {total} 20\"><title>{title}</title>\
+         <rect width=\"{label_width}\" height=\"20\" rx=\"3\" fill=\"{label_color}\"/>\
+         <rect x=\"{label_width}\" width=\"{message_width}\" height=\"20\" rx=\"3\" fill=\"{color}\"/>\
+         <g font-family=\"Verdana,Geneva,sans-serif\" font-size=\"11\">{label_text}{message_text}</g></svg>",
+        title = html_escape::encode_text(&[label, message].join(": ").trim_start_matches(": ")),
+        label_text = if label.is_empty() { String::new() } else { text(label_width / 2, label) },
+        message_text = text(label_width + message_width / 2, message),
+    ))
+}
+
+fn color_or(color: Option<&str>, default: &str) -> String {
+    color.map(self::color).unwrap_or_else(|| default.to_string())
+}
+
+// shields.io's named colors, then hex without the `#`, then CSS colors
+fn color(name: &str) -> String {
+    let named = match name {
+        "brightgreen" | "success" => "#4c1",
+        "green" => "#97ca00",
+        "yellow" => "#dfb317",
+        "yellowgreen" => "#a4a61d",
+        "orange" | "important" => "#fe7d37",
+        "red" | "critical" => "#e05d44",
+        "blue" => "#007ec6",
+        "lightgrey" | "lightgray" | "inactive" => "#9f9f9f",
+        "grey" | "gray" => "#555",
+        "blueviolet" => "#8a2be2",
+        _ if (name.len() == 3 || name.len() == 6) && name.chars().all(|c| c.is_ascii_hexdigit()) => {
+            return format!("#{}", name);
+        }
+        _ if name.chars().all(|c| c.is_ascii_alphanumeric() || "#(),. %".contains(c)) => name,
+        _ => "#9f9f9f",
+    };
+    named.to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedBadge {
+    svg: String,
+    fetched_at: u64,
+}
+
+/// Badges from previous builds, so rebuilding doesn't fetch every badge
+/// every time
+#[derive(Debug)]
+pub struct BadgeCache {
+    path: PathBuf,
+    ttl_secs: u64,
+    entries: HashMap<String, CachedBadge>,
+    changed: bool,
+}
+
+impl BadgeCache {
+    /// Load the cache from the project's cache directory
+    pub fn load(cache_dir: &Path, ttl_minutes: u64) -> Self {
+        let path = cache_dir.join("badges.json");
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            ttl_secs: ttl_minutes * 60,
+            entries,
+            changed: false,
+        }
+    }
+
+    /// A cached badge that hasn't expired yet
+    pub fn get(&self, url: &str) -> Option<&str> {
+        self.entries
+            .get(url)
+            .filter(|cached| now().saturating_sub(cached.fetched_at) < self.ttl_secs)
+            .map(|cached| cached.svg.as_str())
+    }
+
+    /// A cached badge regardless of age, used when fetching fails
+    pub fn get_stale(&self, url: &str) -> Option<&str> {
+        self.entries.get(url).map(|cached| cached.svg.as_str())
+    }
+
+    pub fn insert(&mut self, url: &str, svg: String) {
+        self.entries.insert(
+            url.to_string(),
+            CachedBadge {
+                svg,
+                fetched_at: now(),
+            },
+        );
+        self.changed = true;
+    }
+
+    /// Write the cache back, if a badge was fetched
+    pub fn save(&self) -> std::io::Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(&self.entries).map_err(std::io::Error::from)?;
+        std::fs::write(&self.path, json)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badges() {
+        let html = "<p><img src=\"badge:https://example.com/ci.svg?a=1&amp;b=2\" alt=\"CI\" title=\"https://example.com/actions\"/> <img src=\"/logo.png\" alt=\"Logo\"/></p>";
+        let mut urls = Vec::new();
+        let expanded = expand(html, |url, alt, link| {
+            urls.push(url.to_string());
+            self::html(None, alt, link)
+        });
+        assert_eq!(urls, ["https://example.com/ci.svg?a=1&b=2"]);
+        assert_eq!(
+            expanded,
+            "<p><a href=\"https://example.com/actions\"><span class=\"zap-badge\">CI</span></a> <img src=\"/logo.png\" alt=\"Logo\"/></p>"
+        );
+
+        let svg = svg_document("<?xml version=\"1.0\"?><svg><rect/></svg>\n").unwrap();
+        assert_eq!(svg, "<svg><rect/></svg>");
+        assert!(svg_document("<html></html>").is_err());
+
+        let badge = self::html(Some(&svg), "CI \"passing\"", None);
+        assert_eq!(
+            badge,
+            "<img class=\"zap-badge\" src=\"data:image/svg+xml;base64,PHN2Zz48cmVjdC8+PC9zdmc+\" alt=\"CI &quot;passing&quot;\">"
+        );
+
+        // Fetched markup only ever reaches the page base64 encoded, where
+        // none of it can be parsed as HTML
+        for payload in [
+            "<svg/onload=alert(1)>",
+            "<svg onload =\"alert(1)\"></svg>",
+            "<svg></svg><img src=x/onerror=alert(1)>",
+            "<svg><script>alert(1)</script></svg>",
+        ] {
+            let svg = svg_document(payload).unwrap();
+            let badge = self::html(Some(&svg), "CI", Some("https://example.com/"));
+            let encoded = base64::engine::general_purpose::STANDARD.encode(payload);
+            assert_eq!(
+                badge,
+                format!("<a href=\"https://example.com/\"><img class=\"zap-badge\" src=\"data:image/svg+xml;base64,{}\" alt=\"CI\"></a>", encoded)
+            );
+            for raw in ["onload", "onerror", "script", "alert"] {
+                assert!(!badge.contains(raw), "{} in {}", raw, badge);
+            }
+        }
+
+        let json = serde_json::json!({ "schemaVersion": 1, "label": "status", "message": "up", "color": "brightgreen" });
+        let svg = endpoint_svg(&json).unwrap();
+        assert!(svg.contains("<title>status: up</title>"));
+        assert!(svg.contains("fill=\"#4c1\""));
+    }
+}
@@ -1,12 +1,41 @@
+use chrono::Datelike;
 use serde::Serialize;
 use serde_json;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-
-use crate::config::{HomeConfig, SiteConfig};
+use std::time::{Duration, Instant};
+
+use crate::anchors::AnchorChange;
+use crate::changelog::Release;
+use crate::config::{
+    AccessibilityConfig, AnchorsConfig, BadgesConfig, ChangelogConfig, CollectionConfig, ConfigError, ConsentConfig, CounterConfig, ExpiredPages, FormConfig,
+    GitConfig, HomeConfig, InjectConfig, LlmsConfig, PerformanceConfig, PostBuildHook, PrivacyConfig, SeoConfig, SiteConfig,
+};
+use crate::badges::BadgeCache;
+use crate::counter::ViewCache;
+use crate::dates::PageDate;
+use crate::diagnostics::Diagnostic;
+use crate::direction::Direction;
+use crate::events::{BuildEvent, BuildStats, EventHandler, PageTimings, noop_handler};
+use crate::downloads::Downloads;
+use crate::footer::Footer;
+use crate::git::{FileHistory, GitHistory};
+use crate::incremental::{BuildCache, Fingerprint};
+use crate::funding::SponsorLink;
+use crate::i18n::Catalogs;
+use crate::roadmap::Roadmap;
+use crate::llms::{LlmsEntry, LlmsSection};
+use crate::nav::{NavNode, TreeEntry};
+use crate::pagination::Paginator;
+use crate::post_build::HookError;
+use crate::post_process::{Minify, PostProcessor};
 use crate::renderer::{RenderContext, Renderer};
+use crate::report::{BuildReport, PageTiming, PhaseClock};
 use crate::site::{Collection, Page};
-use crate::template::TemplateError;
+use crate::template::{FALLBACK_TEMPLATE, TemplateError};
+use crate::theme::{ThemeAsset, ThemeManifest};
+use crate::virtual_collections::VirtualCollection;
 use crate::{PageElement, PageType};
 
 #[derive(Debug)]
@@ -15,7 +44,18 @@ pub enum BuildError {
     InvalidPath(PathBuf),
     TemplateError(crate::template::TemplateError),
     ScanError(std::io::Error),
+    /// A page or collection in the source directory that can't be read
+    ContentError(crate::scanner::ScanError),
     SerializationError(serde_json::Error),
+    HookError(HookError),
+    ThemeError(ConfigError),
+    EncodingError(PathBuf),
+    TimezoneError(crate::dates::DateError),
+    PageRuleError(crate::page_rules::PageRuleError),
+    PermalinkError(PathBuf, crate::permalink::PermalinkError),
+    VirtualCollectionError(crate::virtual_collections::VirtualCollectionError),
+    /// Problems that are warnings, except in strict mode
+    Strict(Vec<String>),
 }
 
 impl From<TemplateError> for BuildError {
@@ -41,6 +81,8 @@ impl From<RenderError> for BuildError {
         match err {
             RenderError::TemplateError(te) => BuildError::TemplateError(te),
             RenderError::IoError(ie) => BuildError::ScanError(ie),
+            RenderError::HookError(he) => BuildError::HookError(he),
+            RenderError::Strict(problems) => BuildError::Strict(problems),
         }
     }
 }
@@ -52,24 +94,113 @@ impl std::fmt::Display for BuildError {
             BuildError::InvalidPath(p) => write!(f, "Invalid path: {}", p.display()),
             BuildError::TemplateError(e) => write!(f, "Template error: {}", e),
             BuildError::ScanError(e) => write!(f, "Scan error: {}", e),
+            BuildError::ContentError(e) => write!(f, "Scan error: {}", e),
             BuildError::SerializationError(e) => write!(f, "Serialization error: {}", e),
+            BuildError::HookError(e) => write!(f, "Post-build hook error: {}", e),
+            BuildError::ThemeError(e) => write!(f, "Theme error: {}", e),
+            BuildError::TimezoneError(e) => write!(f, "Config error: {}", e),
+            BuildError::PageRuleError(e) => write!(f, "Config error: {}", e),
+            BuildError::VirtualCollectionError(e) => write!(f, "Config error: {}", e),
+            BuildError::PermalinkError(p, e) => write!(f, "Config error: permalink for {}: {}", p.display(), e),
+            BuildError::Strict(problems) => write!(
+                f,
+                "{} problem(s) fail the build in strict mode:\n  {}",
+                problems.len(),
+                problems.join("\n  ")
+            ),
+            BuildError::EncodingError(p) => write!(
+                f,
+                "{} is not valid UTF-8. Re-save it as UTF-8, or turn off strict_encoding to read it as Windows-1252",
+                p.display()
+            ),
         }
     }
 }
 
 impl std::error::Error for BuildError {}
 
-#[derive(Debug, Serialize)]
+impl BuildError {
+    /// What went wrong, with the file and line when they're known
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            BuildError::TemplateError(e) => e.diagnostics(),
+            BuildError::ContentError(e) => e.diagnostics(),
+            BuildError::EncodingError(p) | BuildError::PermalinkError(p, _) => {
+                vec![Diagnostic::error(self.to_string()).in_file(p)]
+            }
+            // Each problem was a warning already
+            BuildError::Strict(problems) => vec![Diagnostic::error(format!(
+                "{} problem(s) fail the build in strict mode",
+                problems.len()
+            ))],
+            _ => vec![Diagnostic::error(self.to_string())],
+        }
+    }
+}
+
+const RELEASE_TEMPLATE: &str = "changelog_release.html";
+const TAGS_TEMPLATE: &str = "tags.html";
+const TAG_TEMPLATE: &str = "tag.html";
+const COLLECTION_TEMPLATE: &str = "collection.html";
+const ROADMAP_TEMPLATE: &str = "roadmap.html";
+
+#[derive(Debug, Clone, Serialize)]
 pub struct NavItem {
     pub text: String,
     pub link: String,
 }
 
+/// A page made from config rather than markdown, like the legal and support
+/// pages, rendered with `page.html`
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedPage {
+    pub title: String,
+    pub url: String,
+    pub html: String,
+}
+
+/// A page as listed in `site_pages`, for templates that feature pages from
+/// anywhere in the site
+#[derive(Debug, Clone, Serialize)]
+pub struct PageSummary {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub date: Option<PageDate>,
+    pub expires: Option<PageDate>,
+    pub image: Option<String>,
+    pub tags: Vec<NavItem>,
+    /// The collection the page is in
+    pub collection: Option<String>,
+}
+
 #[derive(Default)]
 pub struct SiteContext {
     pub site: SiteConfig,
     pub home: Option<HomeConfig>,
+    pub performance: PerformanceConfig,
+    pub changelog: ChangelogConfig,
+    pub privacy: PrivacyConfig,
+    pub accessibility: AccessibilityConfig,
+    pub seo: SeoConfig,
+    pub inject: InjectConfig,
+    pub consent: Option<ConsentConfig>,
+    pub anchors: AnchorsConfig,
+    pub counter: Option<CounterConfig>,
+    pub badges: BadgesConfig,
+    pub git: GitConfig,
+    pub post_build: Vec<PostBuildHook>,
+    pub llms: LlmsConfig,
+    pub collections: HashMap<String, CollectionConfig>,
+    /// `[forms]`, for `[form NAME]` in pages
+    pub forms: BTreeMap<String, FormConfig>,
     pub navigation: Vec<NavItem>,
+    pub footer: Footer,
+    /// `YYYY-MM-DD` in the site's timezone, the default seed for `sample()`
+    /// and `shuffle()`
+    pub build_date: String,
+    /// The site's `[theme]` options
+    pub theme_options: serde_json::Map<String, serde_json::Value>,
     pub custom: HashMap<String, serde_json::Value>,
 }
 
@@ -77,10 +208,27 @@ pub struct SiteBuilder {
     source_dir: Option<PathBuf>,
     output_dir: PathBuf,
     theme_dir: PathBuf,
+    static_dir: Option<PathBuf>,
+    dev_mode: bool,
+    staging: bool,
+    check_links: bool,
+    strict: bool,
+    post_processors: Vec<Box<dyn PostProcessor>>,
     syntax_theme: String,
+    debug_templates: bool,
     pages: Vec<Page>,
     collections: Vec<Collection>,
+    virtual_collections: Vec<VirtualCollection>,
+    legal_pages: Vec<GeneratedPage>,
+    support_page: Option<GeneratedPage>,
+    sponsors: Vec<SponsorLink>,
+    roadmap: Option<Roadmap>,
+    downloads: Option<Downloads>,
+    download_pages: Vec<String>,
+    git_history: Option<GitHistory>,
     context: SiteContext,
+    previous_outputs: HashMap<PathBuf, u64>,
+    event_handler: EventHandler,
 }
 
 impl Default for SiteBuilder {
@@ -95,10 +243,27 @@ impl SiteBuilder {
             source_dir: None,
             output_dir: PathBuf::from("./out"),
             theme_dir: PathBuf::from("./theme"),
+            static_dir: None,
+            dev_mode: false,
+            staging: false,
+            check_links: false,
+            strict: false,
+            post_processors: Vec::new(),
             syntax_theme: "base16-ocean.dark".to_string(),
+            debug_templates: false,
             pages: Vec::new(),
             collections: Vec::new(),
+            virtual_collections: Vec::new(),
+            legal_pages: Vec::new(),
+            support_page: None,
+            sponsors: Vec::new(),
+            roadmap: None,
+            downloads: None,
+            download_pages: Vec::new(),
+            git_history: None,
             context: SiteContext::default(),
+            previous_outputs: HashMap::new(),
+            event_handler: noop_handler(),
         }
     }
 
@@ -120,6 +285,46 @@ impl SiteBuilder {
     }
 
     // Context configuration
+    /// Directory whose contents are copied into the output as they are
+    pub fn static_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.static_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Build for `zap serve`: compiled stylesheets stay readable
+    pub fn dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Keep the site out of search engines, for preview deployments
+    pub fn staging(mut self, staging: bool) -> Self {
+        self.staging = staging;
+        self
+    }
+
+    /// Check internal links and anchors against the output once the site
+    /// is built, warning about broken ones
+    pub fn check_links(mut self, check_links: bool) -> Self {
+        self.check_links = check_links;
+        self
+    }
+
+    /// Fail the build on problems that are otherwise warnings: templates
+    /// the theme doesn't have, broken links and images, and empty
+    /// collections. Links are checked too.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Add a step run on every page before it's written, after those
+    /// already added
+    pub fn post_processor(mut self, processor: impl PostProcessor + 'static) -> Self {
+        self.post_processors.push(Box::new(processor));
+        self
+    }
+
     pub fn site_config(mut self, config: SiteConfig) -> Self {
         self.context.site = config;
         self
@@ -130,6 +335,96 @@ impl SiteBuilder {
         self
     }
 
+    pub fn performance_config(mut self, config: PerformanceConfig) -> Self {
+        self.context.performance = config;
+        self
+    }
+
+    pub fn changelog_config(mut self, config: ChangelogConfig) -> Self {
+        self.context.changelog = config;
+        self
+    }
+
+    pub fn privacy_config(mut self, config: PrivacyConfig) -> Self {
+        self.context.privacy = config;
+        self
+    }
+
+    pub fn accessibility_config(mut self, config: AccessibilityConfig) -> Self {
+        self.context.accessibility = config;
+        self
+    }
+
+    pub fn seo_config(mut self, config: SeoConfig) -> Self {
+        self.context.seo = config;
+        self
+    }
+
+    pub fn inject_config(mut self, config: InjectConfig) -> Self {
+        self.context.inject = config;
+        self
+    }
+
+    pub fn consent_config(mut self, config: Option<ConsentConfig>) -> Self {
+        self.context.consent = config;
+        self
+    }
+
+    pub fn theme_options(mut self, options: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.context.theme_options = options;
+        self
+    }
+
+    pub fn footer(mut self, footer: Footer) -> Self {
+        self.context.footer = footer;
+        self
+    }
+
+    pub fn build_date(mut self, date: String) -> Self {
+        self.context.build_date = date;
+        self
+    }
+
+    pub fn anchors_config(mut self, config: AnchorsConfig) -> Self {
+        self.context.anchors = config;
+        self
+    }
+
+    pub fn counter_config(mut self, config: CounterConfig) -> Self {
+        self.context.counter = Some(config);
+        self
+    }
+
+    pub fn badges_config(mut self, config: BadgesConfig) -> Self {
+        self.context.badges = config;
+        self
+    }
+
+    pub fn git_config(mut self, config: GitConfig) -> Self {
+        self.context.git = config;
+        self
+    }
+
+    pub fn post_build_hooks(mut self, hooks: Vec<PostBuildHook>) -> Self {
+        self.context.post_build = hooks;
+        self
+    }
+
+    pub fn llms_config(mut self, config: LlmsConfig) -> Self {
+        self.context.llms = config;
+        self
+    }
+
+    pub fn collection_configs(mut self, configs: HashMap<String, CollectionConfig>) -> Self {
+        self.context.collections = configs;
+        self
+    }
+
+    pub fn forms(mut self, forms: BTreeMap<String, FormConfig>) -> Self {
+        self.context.forms = forms;
+        self
+    }
+
     pub fn navigation(mut self, items: Vec<NavItem>) -> Self {
         self.context.navigation = items;
         self
@@ -164,23 +459,138 @@ impl SiteBuilder {
         self
     }
 
+    pub fn virtual_collections(mut self, virtual_collections: Vec<VirtualCollection>) -> Self {
+        self.virtual_collections = virtual_collections;
+        self
+    }
+
+    pub fn legal_pages(mut self, pages: Vec<GeneratedPage>) -> Self {
+        self.legal_pages = pages;
+        self
+    }
+
+    pub fn sponsors(mut self, sponsors: Vec<SponsorLink>) -> Self {
+        self.sponsors = sponsors;
+        self
+    }
+
+    pub fn support_page(mut self, page: Option<GeneratedPage>) -> Self {
+        self.support_page = page;
+        self
+    }
+
+    pub fn downloads(mut self, downloads: Option<Downloads>) -> Self {
+        self.downloads = downloads;
+        self
+    }
+
+    /// URLs of the pages that show the download matrix
+    pub fn download_pages(mut self, urls: Vec<String>) -> Self {
+        self.download_pages = urls;
+        self
+    }
+
+    pub fn git_history(mut self, history: Option<GitHistory>) -> Self {
+        self.git_history = history;
+        self
+    }
+
+    pub fn roadmap(mut self, roadmap: Option<Roadmap>) -> Self {
+        self.roadmap = roadmap;
+        self
+    }
+
     // Syntax highlighting configuration
     pub fn syntax_theme<S: Into<String>>(mut self, theme: S) -> Self {
         self.syntax_theme = theme.into();
         self
     }
 
+    pub fn debug_templates(mut self, debug: bool) -> Self {
+        self.debug_templates = debug;
+        self
+    }
+
+    /// Output fingerprints from the previous build, see [`Site::take_outputs`].
+    /// Outputs rendered from the same inputs are skipped.
+    pub fn previous_outputs(mut self, outputs: HashMap<PathBuf, u64>) -> Self {
+        self.previous_outputs = outputs;
+        self
+    }
+
+    // Build progress reporting
+    pub fn on_event(mut self, handler: EventHandler) -> Self {
+        self.event_handler = handler;
+        self
+    }
+
     // Build the site
     pub fn build(self) -> Result<Site, BuildError> {
         let source_dir = self.source_dir.ok_or(BuildError::MissingSourceDir)?;
 
         // Create renderer with global context
         let mut renderer = Renderer::new(&self.theme_dir)?;
+        renderer.set_debug(self.debug_templates);
+
+        // Theme assets are copied when rendering, but templates need their
+        // fingerprinted URLs up front
+        let manifest = ThemeManifest::read(&self.theme_dir).map_err(BuildError::ThemeError)?;
+        let theme_assets = crate::theme::collect_assets(&self.theme_dir, &manifest, !self.dev_mode)?;
+        renderer.register_assets(crate::theme::asset_urls(&theme_assets));
+        renderer.register_sampling(crate::sampling::seed(&self.context.build_date));
+        let theme_config = manifest
+            .options(&self.context.theme_options)
+            .map_err(BuildError::ThemeError)?;
 
         // Set global context once
         renderer.set_global_context("site", &self.context.site);
+        let language = self.context.site.language.as_deref();
+        renderer.set_global_context("lang", &language.unwrap_or("en"));
+        // Languages pages are in that the theme can't show its own text in
+        let catalogs = Catalogs::load(&self.theme_dir).map_err(BuildError::ThemeError)?;
+        let mut untranslated: Vec<String> = Vec::new();
+        if !catalogs.is_empty() {
+            let page_languages = self
+                .pages
+                .iter()
+                .chain(self.collections.iter().flat_map(|c| &c.pages))
+                .filter_map(|page| page.language.as_deref());
+            for page_language in std::iter::once(language.unwrap_or("en")).chain(page_languages) {
+                if !catalogs.has_language(page_language) && !untranslated.iter().any(|l| l == page_language) {
+                    untranslated.push(page_language.to_string());
+                }
+            }
+        }
+        renderer.register_translations(catalogs.clone(), language.unwrap_or("en"));
+        renderer.set_global_context("dir", &Direction::resolve(self.context.site.dir, language));
         renderer.set_global_context("navigation", &self.context.navigation);
-        renderer.set_global_context("secondary_nav", &self.context.navigation); // Backward compat
+        renderer.set_global_context("secondary_nav", &self.context.navigation); // Context version 1
+        if !self.context.footer.is_empty() {
+            renderer.set_global_context("footer", &self.context.footer);
+        }
+        renderer.set_global_context("theme_config", &theme_config);
+        renderer.set_global_context("build_date", &self.context.build_date);
+        // For themes to link from the footer
+        let legal_links: Vec<NavItem> = self
+            .legal_pages
+            .iter()
+            .map(|page| NavItem {
+                text: page.title.clone(),
+                link: page.url.clone(),
+            })
+            .collect();
+        renderer.set_global_context("legal_pages", &legal_links);
+        // For themes to show sponsor buttons
+        renderer.set_global_context("sponsors", &self.sponsors);
+        let support_url = self.support_page.as_ref().map(|page| page.url.clone());
+        renderer.set_global_context("support_url", &support_url);
+        // For any template to show the download matrix, not just the pages
+        // that get `show_downloads`
+        if let Some(downloads) = &self.downloads {
+            renderer.set_global_context("downloads", downloads);
+        }
+        // For themes to show a staging ribbon
+        renderer.set_global_context("staging", &self.staging);
 
         // Check for changelog and add to global
         let has_changelog = self
@@ -188,19 +598,194 @@ impl SiteBuilder {
             .iter()
             .any(|p| matches!(p.page_type, PageType::Changelog));
         renderer.set_global_context("has_changelog", &has_changelog);
+        let changelog_url = self
+            .pages
+            .iter()
+            .find(|p| matches!(p.page_type, PageType::Changelog))
+            .map(|p| p.url(&source_dir))
+            .unwrap_or_else(|| "/changelog/".to_string());
+        renderer.set_global_context("changelog_url", &changelog_url);
 
         // Add any custom global context
         for (key, value) in &self.context.custom {
             renderer.set_global_context(key, value);
         }
 
+        // Privacy mode promises no third-party requests, counting included
+        let counter = self
+            .context
+            .counter
+            .filter(|counter| !counter.endpoint.is_empty() && !self.context.privacy.enabled);
+        if let Some(counter) = &counter {
+            renderer.set_global_context("counter", counter);
+        }
+        let view_cache = counter
+            .as_ref()
+            .filter(|counter| counter.show_views)
+            .map(|counter| {
+                let cache_dir = Path::new(crate::history::CACHE_DIR);
+                RefCell::new(ViewCache::load(cache_dir, &counter.endpoint, counter.cache_minutes))
+            });
+
+        let mut links = crate::links::PageLinks::default();
+        for page in self.pages.iter().chain(self.collections.iter().flat_map(|c| &c.pages)) {
+            links.insert(&page.path, page.url(&source_dir));
+        }
+        // Who links to each page, from the markdown rather than the rendered
+        // pages, since a page's backlinks are needed before the pages linking
+        // to it are rendered
+        let mut backlinks = crate::links::Backlinks::new(&links);
+        for page in self.pages.iter().chain(self.collections.iter().flat_map(|c| &c.pages)) {
+            let Ok(markdown) = crate::encoding::read_to_string(&page.path) else {
+                continue;
+            };
+            let from = NavItem {
+                text: page.title.clone(),
+                link: page.url(&source_dir),
+            };
+            backlinks.add_page(&from, &markdown, page.path.parent().unwrap_or(&source_dir), &links, &source_dir);
+        }
+
+        // Every current content page, for `sample()` and `shuffle()` to pick
+        // from
+        let top_level = self.pages.iter().map(|page| (page, None));
+        let in_collections = self
+            .collections
+            .iter()
+            .flat_map(|c| c.pages.iter().map(move |page| (page, Some(c.name.clone()))));
+        let site_pages: Vec<PageSummary> = top_level
+            .chain(in_collections)
+            .filter(|(page, _)| !matches!(page.page_type, PageType::Home | PageType::Changelog | PageType::Index))
+            .filter(|(page, _)| !page.is_expired())
+            .map(|(page, collection)| PageSummary {
+                title: page.title.clone(),
+                url: page.url(&source_dir),
+                description: page.description.clone(),
+                date: page.date,
+                expires: page.expires,
+                image: page.image.clone(),
+                tags: crate::taxonomy::tag_links(&page.tags),
+                collection,
+            })
+            .collect();
+        renderer.set_global_context("site_pages", &site_pages);
+
+        let nav_links = self
+            .context
+            .navigation
+            .iter()
+            .map(|item| item.link.clone())
+            .collect();
+
+        // What every page is rendered with, so changing any of it rebuilds
+        // everything
+        let fingerprint = Fingerprint::new()
+            .with_dir(&self.theme_dir)
+            .with(self.dev_mode)
+            .with(self.staging)
+            .with(self.debug_templates)
+            .with(&self.syntax_theme)
+            .with(self.post_processors.len())
+            .with_json(&self.context.site)
+            .with_json(&self.context.home)
+            .with_json(&self.context.performance)
+            .with_json(&self.context.changelog)
+            .with_json(&self.context.privacy)
+            .with_json(&self.context.accessibility)
+            .with_json(&self.context.seo)
+            .with_json(&self.context.inject)
+            .with_json(&self.context.consent)
+            .with_json(&self.context.anchors)
+            .with_json(&counter)
+            .with_json(&self.context.collections)
+            .with_json(&self.context.forms)
+            .with_json(&self.context.navigation)
+            .with_json(&self.context.footer)
+            .with(&self.context.build_date)
+            .with_json(&legal_links)
+            .with_json(&catalogs)
+            .with_json(&self.sponsors)
+            .with_json(&support_url)
+            .with_json(&self.downloads)
+            .with_json(&self.download_pages)
+            .with_json(&site_pages)
+            .with_json(&self.context.theme_options)
+            .with_json(&self.context.custom)
+            .with(has_changelog)
+            // Pages link to each other by source file
+            .with_json(links.urls())
+            .finish();
+
+        // A page of the site's own at the same URL replaces a generated one
+        let mut success_pages = crate::forms::success_pages(&self.context.forms);
+        success_pages.retain(|page| !links.urls().values().any(|url| *url == page.url));
+
         Ok(Site {
             pages: self.pages,
             collections: self.collections,
+            virtual_collections: self.virtual_collections,
+            generated_pages: self.legal_pages.into_iter().chain(self.support_page).chain(success_pages).collect(),
+            roadmap: self.roadmap,
+            untranslated,
+            git_history: self.git_history,
+            download_pages: match self.downloads {
+                Some(_) => self.download_pages.iter().map(|url| url.trim_matches('/').to_string()).collect(),
+                None => Vec::new(),
+            },
+            git: self.context.git,
             renderer,
+            theme: manifest,
+            theme_options: self.context.theme_options,
+            theme_assets,
+            static_dir: self.static_dir,
             output_dir: self.output_dir,
             source_dir,
             home_config: self.context.home,
+            prefetch: self.context.performance.prefetch,
+            critical_css: self.context.performance.critical_css,
+            instant_navigation: self.context.performance.instant_navigation,
+            release_pages: self.context.changelog.release_pages,
+            privacy: self.context.privacy.enabled,
+            accessibility: Some(self.context.accessibility).filter(|config| config.enabled),
+            inject_seo: self.context.seo.inject,
+            inject: self.context.inject,
+            consent: self.context.consent,
+            anchor_aliases: self.context.anchors.aliases,
+            external_assets: RefCell::new(HashMap::new()),
+            counter_endpoint: counter.map(|counter| counter.endpoint),
+            view_cache,
+            views_failed: Cell::new(false),
+            badge_cache: RefCell::new(BadgeCache::load(
+                Path::new(crate::history::CACHE_DIR),
+                self.context.badges.cache_minutes,
+            )),
+            badges_failed: Cell::new(false),
+            post_build: self.context.post_build,
+            markdown_time: Cell::new(Duration::ZERO),
+            write_time: Cell::new(Duration::ZERO),
+            page_timings: RefCell::new(Vec::new()),
+            llms: self.context.llms.enabled,
+            site_config: self.context.site,
+            missing_templates: RefCell::new(HashSet::new()),
+            collection_configs: self.context.collections,
+            forms: self.context.forms,
+            critical_css_cache: RefCell::new(HashMap::new()),
+            post_processors: self.post_processors,
+            nav_links,
+            links,
+            backlinks,
+            dev_mode: self.dev_mode,
+            staging: self.staging,
+            check_links: self.check_links || self.strict,
+            strict: self.strict,
+            problems: RefCell::new(Vec::new()),
+            fingerprint,
+            previous_outputs: self.previous_outputs,
+            outputs: RefCell::new(HashMap::new()),
+            event_handler: self.event_handler,
+            warnings: RefCell::new(Vec::new()),
+            files_written: Cell::new(0),
+            bytes_written: Cell::new(0),
         })
     }
 }
@@ -209,6 +794,8 @@ impl SiteBuilder {
 pub enum RenderError {
     TemplateError(TemplateError),
     IoError(std::io::Error),
+    HookError(HookError),
+    Strict(Vec<String>),
 }
 
 impl From<TemplateError> for RenderError {
@@ -223,24 +810,99 @@ impl From<std::io::Error> for RenderError {
     }
 }
 
+impl From<HookError> for RenderError {
+    fn from(err: HookError) -> Self {
+        RenderError::HookError(err)
+    }
+}
+
 impl std::fmt::Display for RenderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RenderError::TemplateError(e) => write!(f, "Template error: {}", e),
             RenderError::IoError(e) => write!(f, "IO error: {}", e),
+            RenderError::HookError(e) => write!(f, "Post-build hook error: {}", e),
+            RenderError::Strict(problems) => write!(f, "{} problem(s) in strict mode", problems.len()),
         }
     }
 }
 
 impl std::error::Error for RenderError {}
 
+// Where a page's render started, for attributing its time
+struct TimingMark {
+    started: Instant,
+    markdown: Duration,
+    highlight: Duration,
+    template: Duration,
+    write: Duration,
+}
+
 pub struct Site {
     pages: Vec<Page>,
     collections: Vec<Collection>,
+    virtual_collections: Vec<VirtualCollection>,
+    /// Legal pages, then the support page and forms' thank-you pages
+    generated_pages: Vec<GeneratedPage>,
+    roadmap: Option<Roadmap>,
+    /// Languages of pages the theme has no `i18n/` strings for
+    untranslated: Vec<String>,
+    git_history: Option<GitHistory>,
+    git: GitConfig,
+    // Without slashes, to compare with `page_url()`
+    download_pages: Vec<String>,
     renderer: Renderer,
+    theme: ThemeManifest,
+    /// The site's `[theme]` options, to warn about ones the theme doesn't have
+    theme_options: serde_json::Map<String, serde_json::Value>,
+    theme_assets: Vec<ThemeAsset>,
+    static_dir: Option<PathBuf>,
     output_dir: PathBuf,
     source_dir: PathBuf,
     home_config: Option<HomeConfig>,
+    prefetch: bool,
+    critical_css: bool,
+    instant_navigation: bool,
+    release_pages: bool,
+    privacy: bool,
+    accessibility: Option<AccessibilityConfig>,
+    inject_seo: bool,
+    inject: InjectConfig,
+    consent: Option<ConsentConfig>,
+    anchor_aliases: bool,
+    external_assets: RefCell<HashMap<String, Option<String>>>,
+    counter_endpoint: Option<String>,
+    view_cache: Option<RefCell<ViewCache>>,
+    views_failed: Cell<bool>,
+    badge_cache: RefCell<BadgeCache>,
+    badges_failed: Cell<bool>,
+    post_build: Vec<PostBuildHook>,
+    markdown_time: Cell<Duration>,
+    write_time: Cell<Duration>,
+    page_timings: RefCell<Vec<PageTiming>>,
+    llms: bool,
+    site_config: SiteConfig,
+    missing_templates: RefCell<HashSet<String>>,
+    collection_configs: HashMap<String, CollectionConfig>,
+    forms: BTreeMap<String, FormConfig>,
+    critical_css_cache: RefCell<HashMap<(String, String), Option<String>>>,
+    post_processors: Vec<Box<dyn PostProcessor>>,
+    nav_links: Vec<String>,
+    links: crate::links::PageLinks,
+    backlinks: crate::links::Backlinks,
+    dev_mode: bool,
+    staging: bool,
+    check_links: bool,
+    strict: bool,
+    /// Warnings that fail the build in strict mode
+    problems: RefCell<Vec<String>>,
+    fingerprint: u64,
+    previous_outputs: HashMap<PathBuf, u64>,
+    outputs: RefCell<HashMap<PathBuf, u64>>,
+    event_handler: EventHandler,
+    warnings: RefCell<Vec<String>>,
+    files_written: Cell<usize>,
+    bytes_written: Cell<u64>,
 }
 
 impl Site {
@@ -252,12 +914,155 @@ impl Site {
         &self.collections
     }
 
-    fn render_page(&self, page: &Page) -> String {
+    /// Fingerprints of this build's outputs, for
+    /// [`SiteBuilder::previous_outputs`] on the next one
+    pub fn take_outputs(&self) -> HashMap<PathBuf, u64> {
+        self.outputs.take()
+    }
+
+    fn emit(&self, event: BuildEvent) {
+        if let BuildEvent::Warning { diagnostic } = &event {
+            self.warnings.borrow_mut().push(diagnostic.summary());
+        }
+        (self.event_handler)(&event);
+    }
+
+    /// Report a non-fatal problem through the event handler
+    pub fn warn<S: Into<String>>(&self, message: S) {
+        self.diagnose(Diagnostic::warning(message));
+    }
+
+    /// Report a non-fatal problem, with where it is, through the event
+    /// handler
+    pub fn diagnose(&self, diagnostic: Diagnostic) {
+        self.emit(BuildEvent::Warning { diagnostic });
+    }
+
+    // A warning that fails the build in strict mode, once it's finished so
+    // every problem is reported
+    fn problem(&self, diagnostic: Diagnostic) {
+        if self.strict {
+            self.problems.borrow_mut().push(diagnostic.summary());
+        }
+        self.diagnose(diagnostic);
+    }
+
+    // Write a file to the output, counting it for the build report
+    fn write_file(&self, path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+        let contents = contents.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        self.wrote(1, contents.len() as u64);
+        Ok(())
+    }
+
+    fn wrote(&self, files: usize, bytes: u64) {
+        self.files_written.set(self.files_written.get() + files);
+        self.bytes_written.set(self.bytes_written.get() + bytes);
+    }
+
+    fn timing_mark(&self) -> TimingMark {
+        TimingMark {
+            started: Instant::now(),
+            markdown: self.markdown_time.get(),
+            highlight: crate::markdown::highlight_time(),
+            template: self.renderer.render_time(),
+            write: self.write_time.get(),
+        }
+    }
+
+    fn page_rendered(&self, page: &Page, template: &str, mark: TimingMark) {
+        let elapsed = mark.started.elapsed();
+        tracing::trace!(template, ms = elapsed.as_millis() as u64, "rendered {}", page.path.display());
+        // Highlighting happens while rendering markdown
+        let highlight = crate::markdown::highlight_time() - mark.highlight;
+        let timings = PageTimings {
+            markdown_us: (self.markdown_time.get() - mark.markdown).saturating_sub(highlight).as_micros(),
+            highlight_us: highlight.as_micros(),
+            template_us: (self.renderer.render_time() - mark.template).as_micros(),
+            write_us: (self.write_time.get() - mark.write).as_micros(),
+        };
+        self.page_timings.borrow_mut().push(PageTiming {
+            path: page.path.clone(),
+            template: template.to_string(),
+            us: elapsed.as_micros(),
+            timings: timings.clone(),
+        });
+        self.emit(BuildEvent::PageRendered {
+            path: page.path.clone(),
+            template: template.to_string(),
+            ms: elapsed.as_millis(),
+            us: elapsed.as_micros(),
+            timings,
+        });
+    }
+
+    // Whether `output` was rendered from the same inputs by the previous
+    // build and is still there. Either way it's recorded for the next one.
+    fn is_unchanged(&self, output: &Path, fingerprint: u64) -> bool {
+        self.outputs.borrow_mut().insert(output.to_path_buf(), fingerprint);
+        self.previous_outputs.get(output) == Some(&fingerprint) && output.exists()
+    }
+
+    // A page depends on its own source and the site-wide inputs
+    fn page_fingerprint(&self, page: &Page) -> Fingerprint {
+        Fingerprint::new()
+            .with(self.fingerprint)
+            .with(&page.path)
+            .with(&page.variant)
+            // The banner appears without the source changing
+            .with(page.is_expired())
+            .with_json(&self.page_history(page))
+            .with_json(&self.backlinks.get(&page.url(&self.source_dir)))
+            .with_file(&page.path)
+    }
+
+    // The page's source in the git history, when `[git]` asks for it
+    fn page_history(&self, page: &Page) -> Option<&FileHistory> {
+        self.git_history.as_ref()?.get(&page.path)
+    }
+
+    // Parsed on first use, which counts as markdown time
+    fn elements<'a>(&self, page: &'a Page) -> &'a [PageElement] {
+        let started = Instant::now();
         let elements = page.elements();
-        crate::markdown::render_elements_to_html(&elements)
+        self.markdown_time.set(self.markdown_time.get() + started.elapsed());
+        elements
+    }
+
+    fn render_page(&self, page: &Page) -> String {
+        let elements = self.elements(page);
+        let started = Instant::now();
+        let html = crate::markdown::render_elements_to_html(elements);
+        let html = self.rewrite_links(page, &html);
+        let html = self.inline_badges(&html);
+        let html = self.insert_forms(page, &html);
+        self.markdown_time.set(self.markdown_time.get() + started.elapsed());
+        html
+    }
+
+    // Links to other pages' markdown files, pointed at the pages
+    fn rewrite_links(&self, page: &Page, html: &str) -> String {
+        let page_dir = page.path.parent().unwrap_or(&self.source_dir);
+        self.links.rewrite(html, page_dir, &self.source_dir)
     }
 
     fn page_out_path(&self, page: &Page) -> PathBuf {
+        let path = self.published_out_path(page);
+        match &page.variant {
+            Some(variant) => crate::variants::preview_path(&path, variant),
+            None => path,
+        }
+    }
+
+    // Where the page is written, whichever variant is being rendered
+    fn published_out_path(&self, page: &Page) -> PathBuf {
+        if let Some(permalink) = &page.permalink {
+            return crate::permalink::out_path(permalink);
+        }
+
         // Convert absolute path to relative path for output
         let relative_path = page
             .path
@@ -271,140 +1076,999 @@ impl Site {
                 .with_file_name("")
                 .with_extension("")
                 .join("index.html"),
-            _ => relative_path.with_extension("").join("index.html"),
+            _ => crate::ordering::url_path(relative_path).join("index.html"),
         }
     }
 
     fn page_url(&self, page: &Page) -> String {
-        self.page_out_path(page)
+        if let Some(permalink) = &page.permalink {
+            return permalink.trim_start_matches('/').to_string();
+        }
+
+        self.published_out_path(page)
             .with_file_name("")
             .to_string_lossy()
             .to_string()
     }
 
-    fn render_home(&self, page: &Page, home_config: &HomeConfig) -> Result<(), RenderError> {
+    // Context shared by every page template
+    fn page_context(&self, page: &Page, collection: Option<&Collection>) -> RenderContext {
         let mut context = RenderContext::new();
+        context.add_to_context("toc", &crate::toc::build_toc(self.elements(page)));
+        context.add_to_context("page_tags", &crate::taxonomy::tag_links(&page.tags));
+        context.add_to_context("breadcrumbs", &self.breadcrumbs(page, collection));
+        context.add_to_context("backlinks", &self.backlinks.get(&page.url(&self.source_dir)));
+        if let Some(date) = &page.date {
+            context.add_to_context("page_date", date);
+        }
+        if let Some(expires) = &page.expires {
+            context.add_to_context("page_expires", expires);
+            context.add_to_context("page_expired", &page.is_expired());
+        }
+        if self.download_pages.iter().any(|url| url == self.page_url(page).trim_matches('/')) {
+            context.add_to_context("show_downloads", &true);
+        }
+        if let Some(history) = self.page_history(page) {
+            if self.git.last_updated {
+                context.add_to_context("last_updated", &history.last_updated);
+            }
+            if self.git.contributors {
+                context.add_to_context("contributors", &history.contributors);
+            }
+        }
+        context.add_to_context("seo", &self.page_seo(page));
+        context.add_to_context("page_extra", &page.extra);
+        // Pages in another language than the site's override `lang` and `dir`
+        if let Some(language) = &page.language {
+            context.add_to_context("lang", language);
+        }
+        if page.language.is_some() || page.dir.is_some() {
+            context.add_to_context("dir", &Direction::resolve(page.dir, page.language.as_deref()));
+        }
+        if let Some(published) = page.variants.first() {
+            context.add_to_context("variants", &page.variants);
+            context.add_to_context("variant", page.variant.as_ref().unwrap_or(published));
+        }
 
-        // Get page elements and potentially filter them
-        let mut elements = page.elements();
-
-        // If hero is enabled, remove first h1 and first paragraph
-        if home_config.hero {
-            let mut found_h1 = false;
-            let mut found_paragraph_after_h1 = false;
-
-            elements.retain(|element| {
-                match element {
-                    PageElement::Heading { level: 1, .. } if !found_h1 => {
-                        found_h1 = true;
-                        false // Remove first h1
-                    }
-                    PageElement::Paragraph { .. } if found_h1 && !found_paragraph_after_h1 => {
-                        found_paragraph_after_h1 = true;
-                        false // Remove first paragraph after h1
-                    }
-                    _ => true, // Keep everything else
-                }
-            });
+        if self.prefetch {
+            context.add_to_context("prefetch", &self.prefetch_urls(page, collection));
         }
 
-        // Render the filtered content
-        let content = crate::markdown::render_elements_to_html(&elements);
-        context.add_to_context("page_content", &content);
+        if let Some(endpoint) = &self.counter_endpoint {
+            let path = page.url(&self.source_dir);
+            context.add_to_context("counter_pixel", &crate::counter::pixel_url(endpoint, &path));
+            if let Some(views) = self.page_views(endpoint, &path) {
+                context.add_to_context("views", &views);
+            }
+        }
 
-        // Home-specific config
-        context.add_to_context("home", home_config);
+        context
+    }
 
-        let html = self.renderer.render(page.template_name(), &context)?;
+    fn page_seo(&self, page: &Page) -> crate::seo::Seo {
+        let url = format!("/{}", self.page_url(page));
+        crate::seo::page_seo(page, &url, &self.site_config)
+    }
 
-        let out_path = self.page_out_path(page);
-        let output_path = self.output_dir.join(out_path);
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)?;
+    fn inject_seo(&self, page: &Page, html: String) -> String {
+        if self.inject_seo {
+            crate::seo::inject(html, &self.page_seo(page))
+        } else {
+            html
         }
-        std::fs::write(output_path, html)?;
-
-        Ok(())
     }
 
-    fn render_changelog(&self, page: &Page) -> Result<(), RenderError> {
-        let mut context = RenderContext::new();
+    // Home, then the collection and any subdirectories with their own
+    // index.md, then the page itself
+    fn breadcrumbs(&self, page: &Page, collection: Option<&Collection>) -> Vec<NavItem> {
+        let mut crumbs = vec![home_crumb()];
+        if matches!(page.page_type, PageType::Home) {
+            return crumbs;
+        }
 
-        // Only page-specific content
-        let content = self.render_page(page);
-        context.add_to_context("page_content", &content);
+        if let Some(collection) = collection {
+            crumbs.push(NavItem {
+                text: title_case(&collection.name),
+                link: collection.url(),
+            });
 
-        let releases: Vec<NavItem> = page
-            .elements()
-            .iter()
-            .filter_map(|el| match el {
-                // We're preferring convention here. The only H1 should
-                // be the page title.
-                PageElement::Heading { level: 1, .. } => None,
-                PageElement::Heading { level: 2, content } => {
-                    let text = crate::markdown::render_inline_elements_text(content);
-                    let slug = crate::markdown::slugify(&text);
-                    Some(NavItem {
-                        text,
-                        link: format!("#{}", slug),
-                    })
+            let mut dir = self.source_dir.join(&collection.name);
+            let subdirs = page
+                .path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&dir).ok())
+                .map(|subdirs| subdirs.to_path_buf())
+                .unwrap_or_default();
+            for component in subdirs.components() {
+                dir.push(component);
+                let index = collection.pages.iter().find(|p| {
+                    matches!(p.page_type, PageType::Index) && p.path.parent() == Some(dir.as_path())
+                });
+                if let Some(index) = index {
+                    crumbs.push(NavItem {
+                        text: index.title.clone(),
+                        link: format!("/{}", self.page_url(index)),
+                    });
                 }
-                _ => None,
-            })
-            .collect();
-        context.add_to_context("releases", &releases);
+            }
+        }
 
-        let html = self.renderer.render(page.template_name(), &context)?;
+        // Index pages are already the last crumb
+        let link = format!("/{}", self.page_url(page));
+        if crumbs.last().is_none_or(|last| last.link != link) {
+            crumbs.push(NavItem {
+                text: page.title.clone(),
+                link,
+            });
+        }
+        crumbs
+    }
 
-        let output_path = self.output_dir.join("changelog/index.html");
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)?;
+    // View count for a page, from the cache or the counter API. After the
+    // first failed fetch the rest of the build only uses cached counts.
+    fn page_views(&self, endpoint: &str, path: &str) -> Option<String> {
+        let cache = self.view_cache.as_ref()?;
+        if let Some(views) = cache.borrow().get(path) {
+            return Some(views.to_string());
+        }
+        if self.views_failed.get() {
+            return cache.borrow().get_stale(path).map(str::to_string);
         }
-        std::fs::write(output_path, html)?;
 
-        Ok(())
+        match crate::counter::fetch_views(endpoint, path) {
+            Ok(views) => {
+                cache.borrow_mut().insert(path, views.clone());
+                Some(views)
+            }
+            Err(e) => {
+                self.views_failed.set(true);
+                self.warn(format!("Could not fetch view counts from {}: {}", endpoint, e));
+                cache.borrow().get_stale(path).map(str::to_string)
+            }
+        }
     }
 
-    fn render_regular_page(&self, page: &Page) -> Result<(), RenderError> {
-        let mut context = RenderContext::new();
+    // `badge:` images as SVG data URLs, from the cache or fetched. Like view
+    // counts, after the first failed fetch only cached badges are used.
+    fn inline_badges(&self, html: &str) -> String {
+        if !html.contains(crate::badges::SCHEME) {
+            return html.to_string();
+        }
+
+        crate::badges::expand(html, |url, alt, link| {
+            if let Some(svg) = self.badge_cache.borrow().get(url) {
+                return crate::badges::html(Some(svg), alt, link);
+            }
+            let fetched = match self.badges_failed.get() {
+                true => None,
+                false => match crate::badges::fetch(url) {
+                    Ok(svg) => Some(svg),
+                    Err(e) => {
+                        self.badges_failed.set(true);
+                        self.warn(format!("Could not fetch badge {}: {}", url, e));
+                        None
+                    }
+                },
+            };
+            let mut cache = self.badge_cache.borrow_mut();
+            match fetched {
+                Some(svg) => {
+                    cache.insert(url, svg);
+                    crate::badges::html(cache.get(url), alt, link)
+                }
+                None => crate::badges::html(cache.get_stale(url), alt, link),
+            }
+        })
+    }
+
+    // `[form NAME]` paragraphs as the forms from `[forms]`
+    fn insert_forms(&self, page: &Page, html: &str) -> String {
+        crate::forms::expand(html, &self.forms, self.site_config.url.as_deref(), |name| {
+            self.diagnose(
+                Diagnostic::warning(format!("[form {}] has no [forms.{}] in zap.toml", name, name))
+                    .find_in(&page.path, &format!("[form {}", name)),
+            );
+        })
+    }
+
+    // Likely next pages: collection neighbours first, then the top nav
+    fn prefetch_urls(&self, page: &Page, collection: Option<&Collection>) -> Vec<String> {
+        let current = format!("/{}", self.page_url(page));
+        let mut urls = Vec::new();
+
+        if let Some(collection) = collection
+            && let Some(i) = collection.pages.iter().position(|p| p.path == page.path)
+        {
+            let prev = i.checked_sub(1).and_then(|i| collection.pages.get(i));
+            let next = collection.pages.get(i + 1);
+            for neighbour in prev.into_iter().chain(next) {
+                urls.push(format!("/{}", self.page_url(neighbour)));
+            }
+        }
+
+        for link in &self.nav_links {
+            if !urls.contains(link) {
+                urls.push(link.clone());
+            }
+        }
+
+        urls.retain(|url| url.trim_end_matches('/') != current.trim_end_matches('/'));
+        urls
+    }
+
+    // Themes often only define some templates. Missing ones fall back to
+    // page.html, then to a minimal built-in template, warning once each.
+    fn template_for<'a>(&self, template: &'a str) -> &'a str {
+        if self.renderer.has_template(template) {
+            return template;
+        }
 
+        let fallback = if self.renderer.has_template("page.html") {
+            "page.html"
+        } else {
+            FALLBACK_TEMPLATE
+        };
+        if self.missing_templates.borrow_mut().insert(template.to_string()) {
+            self.problem(Diagnostic::warning(format!(
+                "Theme has no {}, rendering with {} instead",
+                template,
+                if fallback == FALLBACK_TEMPLATE { "a minimal built-in template" } else { fallback }
+            )));
+        }
+        fallback
+    }
+
+    // Each page renderer returns the template it used
+    fn render_home<'p>(&self, page: &'p Page, home_config: &HomeConfig) -> Result<&'p str, RenderError> {
+        let mut context = self.page_context(page, None);
+
+        // Get page elements and potentially filter them
+        let mut elements = self.elements(page).to_vec();
+
+        // If hero is enabled, remove first h1 and first paragraph
+        if home_config.hero {
+            let mut found_h1 = false;
+            let mut found_paragraph_after_h1 = false;
+
+            elements.retain(|element| {
+                match element {
+                    PageElement::Heading { level: 1, .. } if !found_h1 => {
+                        found_h1 = true;
+                        false // Remove first h1
+                    }
+                    PageElement::Paragraph { .. } if found_h1 && !found_paragraph_after_h1 => {
+                        found_paragraph_after_h1 = true;
+                        false // Remove first paragraph after h1
+                    }
+                    _ => true, // Keep everything else
+                }
+            });
+        }
+
+        // Render the filtered content
+        let started = Instant::now();
+        let content = crate::markdown::render_elements_to_html(&elements);
+        let content = self.rewrite_links(page, &content);
+        let content = self.inline_badges(&content);
+        self.markdown_time.set(self.markdown_time.get() + started.elapsed());
+        context.add_to_context("page_content", &content);
+
+        // Home-specific config
+        context.add_to_context("home", home_config);
+
+        let template = self.template_for(page.template_name());
+        let html = self.inject_seo(page, self.renderer.render(template, &context)?);
+
+        let output_path = self.output_dir.join(self.page_out_path(page));
+        let html = self.write_anchors(&output_path, &elements, html)?;
+        self.write_html(&output_path, template, html)?;
+
+        Ok(template)
+    }
+
+    fn render_changelog<'p>(&self, page: &'p Page) -> Result<&'p str, RenderError> {
+        let mut context = self.page_context(page, None);
+
+        // Only page-specific content
         let content = self.render_page(page);
         context.add_to_context("page_content", &content);
 
-        let html = self.renderer.render(page.template_name(), &context)?;
+        let mut releases = crate::changelog::parse_releases(self.elements(page));
+        if self.release_pages {
+            for release in &mut releases {
+                let dir = crate::changelog::release_dir(&release.version);
+                if !dir.is_empty() {
+                    release.url = Some(format!("/changelog/{}/", dir));
+                }
+            }
+        }
+        context.add_to_context("releases", &releases);
 
-        let out_path = self.page_out_path(page);
-        let output_path = self.output_dir.join(out_path);
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let template = self.template_for(page.template_name());
+        let html = self.inject_seo(page, self.renderer.render(template, &context)?);
+
+        let output_path = self.output_dir.join(self.page_out_path(page));
+        let html = self.write_anchors(&output_path, self.elements(page), html)?;
+        self.write_html(&output_path, template, html)?;
+
+        if self.release_pages {
+            self.render_release_pages(page, &releases)?;
+        }
+
+        Ok(template)
+    }
+
+    // One page per release. Releases are listed newest first, so the
+    // previous release is the one after it in the list.
+    fn render_release_pages(&self, page: &Page, releases: &[Release]) -> Result<(), RenderError> {
+        for (i, release) in releases.iter().enumerate() {
+            let Some(url) = &release.url else {
+                continue;
+            };
+
+            let mut context = self.page_context(page, None);
+            let mut breadcrumbs = self.breadcrumbs(page, None);
+            breadcrumbs.push(NavItem {
+                text: release.version.clone(),
+                link: url.clone(),
+            });
+            context.add_to_context("breadcrumbs", &breadcrumbs);
+            context.add_to_context("release", release);
+            // For themes falling back to page.html
+            context.add_to_context("page_content", &release.body_html);
+            context.add_to_context("releases", &releases);
+            context.add_to_context("previous_release", &releases.get(i + 1));
+            context.add_to_context("next_release", &i.checked_sub(1).and_then(|i| releases.get(i)));
+
+            let template = self.template_for(RELEASE_TEMPLATE);
+            let html = self.inject_seo(page, self.renderer.render(template, &context)?);
+
+            let output_path = self
+                .output_dir
+                .join(url.trim_matches('/'))
+                .join("index.html");
+            self.write_html(&output_path, template, html)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_regular_page<'p>(&self, page: &'p Page) -> Result<&'p str, RenderError> {
+        let mut context = self.page_context(page, None);
+
+        let content = self.render_page(page);
+        context.add_to_context("page_content", &content);
+
+        let template = self.template_for(page.template_name());
+        let html = self.inject_seo(page, self.renderer.render(template, &context)?);
+
+        let output_path = self.output_dir.join(self.page_out_path(page));
+        let html = self.write_anchors(&output_path, self.elements(page), html)?;
+        self.write_html(&output_path, template, html)?;
+
+        Ok(template)
+    }
+
+    // Post-process rendered HTML and write it out
+    fn write_html(&self, output_path: &Path, template: &str, mut html: String) -> Result<(), RenderError> {
+        let started = Instant::now();
+        // Before privacy mode, which removes analytics and self-hosts
+        // anything external the snippets load
+        html = crate::inject::apply(html, &self.inject, !self.dev_mode, self.consent.as_ref());
+        if self.instant_navigation {
+            html = crate::instant::insert_script(html);
+        }
+        // After snippets added to the body, so the skip link comes first
+        if let Some(config) = &self.accessibility {
+            let relative = output_path.strip_prefix(&self.output_dir).unwrap_or(output_path);
+            let url: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+            html = crate::accessibility::apply(html, &format!("/{}", url.join("/")), config);
+        }
+
+        // Runs first so self-hosted stylesheets are picked up for critical CSS
+        if self.privacy {
+            html = crate::privacy::make_private(&html, &|url| self.self_host(url));
+        }
+
+        if self.critical_css {
+            html = self.inline_critical_css(template, html);
+        }
+
+        if self.staging {
+            html = crate::staging::insert_noindex(&html);
+        }
+
+        for processor in &self.post_processors {
+            html = processor.process(html);
+        }
+
+        self.write_file(output_path, html)?;
+        self.write_time.set(self.write_time.get() + started.elapsed());
+
+        Ok(())
+    }
+
+    // Critical rules are extracted from the first page rendered with each
+    // template and reused for the rest of that template's pages
+    fn inline_critical_css(&self, template: &str, mut html: String) -> String {
+        for link in crate::critical_css::local_stylesheets(&html) {
+            let key = (template.to_string(), link.href.clone());
+            let mut cache = self.critical_css_cache.borrow_mut();
+
+            if !cache.contains_key(&key) {
+                let stylesheet = self.output_dir.join(link.href.trim_start_matches('/'));
+                let critical = match std::fs::read_to_string(&stylesheet) {
+                    Ok(css) => Some(crate::critical_css::extract_critical_css(&css, &html)),
+                    Err(_) => {
+                        self.warn(format!(
+                            "Skipping critical CSS for {}, stylesheet not found in output",
+                            link.href
+                        ));
+                        None
+                    }
+                };
+                cache.insert(key.clone(), critical);
+            }
+
+            if let Some(Some(critical)) = cache.get(&key) {
+                html = crate::critical_css::inline_critical_css(&html, &link, critical);
+            }
+        }
+
+        html
+    }
+
+    // Local URL of an external asset, downloading it once per build
+    fn self_host(&self, url: &str) -> Option<String> {
+        if let Some(local) = self.external_assets.borrow().get(url) {
+            return local.clone();
+        }
+
+        let local = self.download_asset(url);
+        self.external_assets
+            .borrow_mut()
+            .insert(url.to_string(), local.clone());
+        local
+    }
+
+    fn download_asset(&self, url: &str) -> Option<String> {
+        let dir = self.output_dir.join(crate::privacy::EXTERNAL_DIR);
+        let id = crate::anchors::stable_id(url);
+
+        // Copies from a previous build are reused, so `zap serve` doesn't
+        // download everything again on each rebuild
+        let prefix = format!("{}.", id);
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with(&prefix) {
+                    return Some(format!("/{}/{}", crate::privacy::EXTERNAL_DIR, name));
+                }
+            }
+        }
+
+        let download = match crate::privacy::download(url) {
+            Ok(download) => download,
+            Err(e) => {
+                self.warn(format!("Privacy mode removed {}, it could not be downloaded: {}", url, e));
+                return None;
+            }
+        };
+
+        let extension = crate::privacy::asset_extension(url, download.content_type.as_deref());
+        let name = format!("{}{}", prefix, extension);
+        let local = format!("/{}/{}", crate::privacy::EXTERNAL_DIR, name);
+        let mut bytes = download.bytes;
+
+        // Stylesheets pull in fonts and images of their own
+        if extension == "css" {
+            // Recorded first so stylesheets importing each other terminate
+            self.external_assets
+                .borrow_mut()
+                .insert(url.to_string(), Some(local.clone()));
+            let css = String::from_utf8_lossy(&bytes).to_string();
+            bytes = crate::privacy::private_css(&css, Some(url), &|url| self.self_host(url)).into_bytes();
+        }
+
+        let written = self.write_file(&dir.join(&name), bytes);
+        if let Err(e) = written {
+            self.warn(format!("Privacy mode removed {}, it could not be saved: {}", url, e));
+            return None;
+        }
+
+        Some(local)
+    }
+
+    fn render_index_pages(
+        &self,
+        mut context: RenderContext,
+        template: &str,
+        paginators: &[Paginator],
+    ) -> Result<(), RenderError> {
+        for paginator in paginators {
+            context.add_to_context("paginator", paginator);
+            let html = self.renderer.render(template, &context)?;
+            let output_path = self
+                .output_dir
+                .join(paginator.url.trim_matches('/'))
+                .join("index.html");
+            self.write_html(&output_path, template, html)?;
+        }
+
+        Ok(())
+    }
+
+    // Collections without an index.md get a generated listing instead
+    fn render_collection_listing(
+        &self,
+        title: &str,
+        url: &str,
+        page_links: &[NavItem],
+        tree: &[NavNode],
+        paginators: &[Paginator],
+    ) -> Result<(), RenderError> {
+        let template = self.template_for(COLLECTION_TEMPLATE);
+
+        for paginator in paginators {
+            let mut context = RenderContext::new();
+            context.add_to_context("collection_title", &title);
+            context.add_to_context("collection_pages", &page_links);
+            context.add_to_context("collection_tree", &tree);
+            context.add_to_context(
+                "breadcrumbs",
+                &[home_crumb(), NavItem { text: title.to_string(), link: url.to_string() }],
+            );
+            context.add_to_context("paginator", paginator);
+            context.add_to_context("page_content", &links_html(title, &paginator.items));
+
+            let html = self.renderer.render(template, &context)?;
+            let output_path = self
+                .output_dir
+                .join(paginator.url.trim_matches('/'))
+                .join("index.html");
+            self.write_html(&output_path, template, html)?;
+        }
+
+        Ok(())
+    }
+
+    // Index pages for virtual collections. Their pages are rendered with
+    // the collections they're in.
+    fn render_virtual_collections(&self) -> Result<(), RenderError> {
+        for collection in &self.virtual_collections {
+            let page_links: Vec<NavItem> = collection
+                .pages
+                .iter()
+                .map(|page| NavItem {
+                    text: page.title.clone(),
+                    link: format!("/{}", self.page_url(page)),
+                })
+                .collect();
+            let tree = crate::nav::build_tree(page_links.iter().map(|item| TreeEntry {
+                dirs: Vec::new(),
+                is_index: false,
+                item: item.clone(),
+            }));
+            let url = collection.url();
+            let paginators = crate::pagination::paginate(&page_links, collection.paginate, &url);
+
+            let listing = Fingerprint::new()
+                .with(self.fingerprint)
+                .with(&collection.title)
+                .with_json(&page_links)
+                .with_json(&paginators)
+                .finish();
+            let listing_path = self.output_dir.join(url.trim_matches('/')).join("index.html");
+            if !self.is_unchanged(&listing_path, listing) {
+                self.render_collection_listing(&collection.title, &url, &page_links, &tree, &paginators)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Pages from `[legal]` and `[funding]`
+    fn render_generated_pages(&self) -> Result<(), RenderError> {
+        for page in &self.generated_pages {
+            let output_path = self.output_dir.join(page.url.trim_matches('/')).join("index.html");
+            let fingerprint = Fingerprint::new().with(self.fingerprint).with_json(page).finish();
+            if self.is_unchanged(&output_path, fingerprint) {
+                continue;
+            }
+
+            let mut context = RenderContext::new();
+            let crumb = NavItem {
+                text: page.title.clone(),
+                link: page.url.clone(),
+            };
+            context.add_to_context("breadcrumbs", &[home_crumb(), crumb]);
+            context.add_to_context("page_content", &page.html);
+
+            let template = self.template_for("page.html");
+            let html = self.renderer.render(template, &context)?;
+            self.write_html(&output_path, template, html)?;
+        }
+        Ok(())
+    }
+
+    // The roadmap from `roadmap.toml` or a GitHub project
+    fn render_roadmap(&self) -> Result<(), RenderError> {
+        let Some(roadmap) = &self.roadmap else {
+            return Ok(());
+        };
+        let output_path = self.output_dir.join(roadmap.url.trim_matches('/')).join("index.html");
+        let fingerprint = Fingerprint::new().with(self.fingerprint).with_json(roadmap).finish();
+        if self.is_unchanged(&output_path, fingerprint) {
+            return Ok(());
+        }
+
+        let mut context = RenderContext::new();
+        let crumb = NavItem {
+            text: roadmap.title.clone(),
+            link: roadmap.url.clone(),
+        };
+        context.add_to_context("roadmap", roadmap);
+        context.add_to_context("breadcrumbs", &[home_crumb(), crumb]);
+        context.add_to_context("page_content", &crate::roadmap::page_html(roadmap));
+
+        let template = self.template_for(ROADMAP_TEMPLATE);
+        let html = self.renderer.render(template, &context)?;
+        self.write_html(&output_path, template, html)
+    }
+
+    // `/tags/` plus a page per tag, when any page has tags
+    fn render_tag_pages(&self) -> Result<(), RenderError> {
+        let pages = self
+            .pages
+            .iter()
+            .chain(self.collections.iter().flat_map(|c| &c.pages));
+        let tags = crate::taxonomy::collect_tags(pages.map(|page| {
+            let link = NavItem {
+                text: page.title.clone(),
+                link: format!("/{}", self.page_url(page)),
+            };
+            (page.tags.as_slice(), link)
+        }));
+
+        if tags.is_empty() {
+            return Ok(());
+        }
+
+        let fingerprint = Fingerprint::new().with(self.fingerprint).with_json(&tags).finish();
+        if self.is_unchanged(&self.output_dir.join("tags/index.html"), fingerprint) {
+            return Ok(());
+        }
+
+        let tag_links: Vec<NavItem> = tags
+            .iter()
+            .map(|tag| NavItem {
+                text: tag.name.clone(),
+                link: tag.url.clone(),
+            })
+            .collect();
+
+        let tags_crumb = NavItem {
+            text: "Tags".to_string(),
+            link: "/tags/".to_string(),
+        };
+
+        let mut context = RenderContext::new();
+        context.add_to_context("tags", &tags);
+        context.add_to_context("breadcrumbs", &[home_crumb(), tags_crumb.clone()]);
+        context.add_to_context("page_content", &links_html("Tags", &tag_links));
+
+        let template = self.template_for(TAGS_TEMPLATE);
+        let html = self.renderer.render(template, &context)?;
+        self.write_html(&self.output_dir.join("tags/index.html"), template, html)?;
+
+        for tag in &tags {
+            let mut context = RenderContext::new();
+            context.add_to_context("tag", tag);
+            context.add_to_context("tags", &tags);
+            let tag_crumb = NavItem {
+                text: tag.name.clone(),
+                link: tag.url.clone(),
+            };
+            context.add_to_context("breadcrumbs", &[home_crumb(), tags_crumb.clone(), tag_crumb]);
+            context.add_to_context("page_content", &links_html(&tag.name, &tag.pages));
+
+            let template = self.template_for(TAG_TEMPLATE);
+            let html = self.renderer.render(template, &context)?;
+            let output_path = self.output_dir.join("tags").join(&tag.slug).join("index.html");
+            self.write_html(&output_path, template, html)?;
+        }
+
+        Ok(())
+    }
+
+    // Pages first, then one section per collection, with the changelog
+    // under "Optional" as llmstxt.org suggests for skippable content
+    fn write_llms_txt(&self) -> Result<(), RenderError> {
+        let entry = |page: &Page| LlmsEntry {
+            title: page.title.clone(),
+            url: format!("/{}", self.page_url(page)),
+            description: page.get_first_paragraph(),
+            source: page.path.clone(),
+        };
+
+        let mut sections = vec![LlmsSection {
+            title: "Pages".to_string(),
+            entries: self
+                .pages
+                .iter()
+                .filter(|p| !matches!(p.page_type, PageType::Changelog))
+                .map(entry)
+                .collect(),
+        }];
+        for collection in &self.collections {
+            sections.push(LlmsSection {
+                title: title_case(&collection.name),
+                entries: collection.pages.iter().map(entry).collect(),
+            });
         }
-        std::fs::write(output_path, html)?;
+        sections.push(LlmsSection {
+            title: "Optional".to_string(),
+            entries: self
+                .pages
+                .iter()
+                .filter(|p| matches!(p.page_type, PageType::Changelog))
+                .map(entry)
+                .collect(),
+        });
+
+        let title = self.site_config.title.as_deref().unwrap_or("Zap");
+        let summary = self.site_config.tagline.as_deref();
+        self.write_file(
+            &self.output_dir.join("llms.txt"),
+            crate::llms::llms_txt(title, summary, &sections),
+        )?;
+        self.write_file(
+            &self.output_dir.join("llms-full.txt"),
+            crate::llms::llms_full_txt(title, &sections)?,
+        )?;
 
         Ok(())
     }
 
-    pub fn render_all(&self) -> Result<(), RenderError> {
+    // Stable heading IDs for deep linking, next to the rendered page. Slugs
+    // that were in the last build's anchors and aren't any more break links
+    // into the page, so they're warned about, and with `[anchors] aliases`
+    // kept working. Returns the page with any aliases added.
+    fn write_anchors(&self, output_path: &Path, elements: &[PageElement], html: String) -> Result<String, RenderError> {
+        let anchors_path = crate::anchors::anchors_path(output_path);
+        let previous = crate::anchors::read_anchors(&anchors_path);
+        let mut anchors = crate::anchors::page_anchors(elements);
+        let changes = crate::anchors::compare(&previous, &anchors);
+
+        let page = output_path.strip_prefix(&self.output_dir).unwrap_or(output_path);
+        let kept = if self.anchor_aliases { ", kept as an alias" } else { "" };
+        for change in &changes {
+            self.warn(match change {
+                AnchorChange::Renamed { from, to } => format!(
+                    "{}: heading anchor #{} is now #{}, links to it will break{}",
+                    page.display(),
+                    from,
+                    to,
+                    kept
+                ),
+                AnchorChange::Removed { slug } => format!(
+                    "{}: heading anchor #{} was removed, links to it will break",
+                    page.display(),
+                    slug
+                ),
+            });
+        }
+
+        let mut html = html;
+        if self.anchor_aliases {
+            crate::anchors::carry_aliases(&previous, &mut anchors, &changes);
+            html = crate::anchors::insert_aliases(&html, &anchors);
+        }
+
+        let json = crate::anchors::anchors_json(&anchors).map_err(std::io::Error::from)?;
+        self.write_file(&anchors_path, json)?;
+
+        Ok(html)
+    }
+
+    /// Links in every page's markdown that lead nowhere in the output
+    /// directory, read from the files there rather than what this build
+    /// rendered, so pages an incremental build skipped are checked too
+    pub fn broken_links(&self) -> std::io::Result<Vec<crate::link_check::BrokenLink>> {
+        let map = crate::link_check::OutputMap::read(&self.output_dir)?;
+        let mut broken = Vec::new();
+        for page in self.pages.iter().chain(self.collections.iter().flat_map(|c| &c.pages)) {
+            let Ok(content) = crate::encoding::read_to_string(&page.path) else {
+                continue;
+            };
+            broken.extend(crate::link_check::check_page(
+                &map,
+                &page.path,
+                &content,
+                &page.url(&self.source_dir),
+                &self.links,
+                &self.source_dir,
+            ));
+        }
+        Ok(broken)
+    }
+
+    /// Pages that would overwrite each other's output, and pages that can't
+    /// be reached from the home page or navigation by following links
+    pub fn diagnostics(&self) -> Vec<String> {
+        let in_collections = self.collections.iter().flat_map(|collection| &collection.pages);
+        let content: Vec<&Page> = self.pages.iter().chain(in_collections).collect();
+        let source = |page: &Page| page.path.strip_prefix(&self.source_dir).unwrap_or(&page.path).display().to_string();
+        let listing = |url: &str| PathBuf::from(url.trim_matches('/')).join("index.html");
+
+        let mut outputs: Vec<(PathBuf, String)> = content
+            .iter()
+            .map(|page| (self.published_out_path(page), source(page)))
+            .collect();
+        for collection in &self.collections {
+            let has_index = collection.pages.iter().any(|page| page.url(&self.source_dir) == collection.url());
+            if !has_index {
+                outputs.push((listing(&collection.url()), format!("the {} collection's listing", collection.name)));
+            }
+        }
+        for collection in &self.virtual_collections {
+            outputs.push((listing(&collection.url()), format!("the {} virtual collection", collection.name)));
+        }
+        for page in &self.generated_pages {
+            outputs.push((listing(&page.url), format!("the generated {} page", page.title)));
+        }
+
+        let mut warnings: Vec<String> = crate::diagnostics::duplicate_outputs(&outputs)
+            .into_iter()
+            .map(|(output, mut sources)| {
+                let last = sources.pop().unwrap_or_default();
+                format!(
+                    "{} and {} {} write {}, only the last one rendered is published",
+                    sources.join(", "),
+                    last,
+                    if sources.len() == 1 { "both" } else { "all" },
+                    output.display()
+                )
+            })
+            .collect();
+
+        // Collection pages lead to each other through the sidebar, and
+        // tagged pages to each other through tag pages
+        let mut links: Vec<(String, String)> = Vec::new();
+        for page in &content {
+            let url = page.url(&self.source_dir);
+            links.extend(self.backlinks.get(&url).iter().map(|from| (from.link.clone(), url.clone())));
+            for tag in crate::taxonomy::tag_links(&page.tags) {
+                links.push((url.clone(), tag.link.clone()));
+                links.push((tag.link, url.clone()));
+            }
+        }
+        for collection in &self.collections {
+            for page in &collection.pages {
+                links.push((collection.url(), page.url(&self.source_dir)));
+                links.push((page.url(&self.source_dir), collection.url()));
+            }
+        }
+        for collection in &self.virtual_collections {
+            links.extend(collection.pages.iter().map(|page| (collection.url(), page.url(&self.source_dir))));
+        }
+        let mut roots = vec!["/".to_string()];
+        roots.extend(self.nav_links.iter().cloned());
+        roots.extend(self.pages.iter().filter(|page| matches!(page.page_type, PageType::Changelog)).map(|page| page.url(&self.source_dir)));
+
+        let pages: Vec<String> = content
+            .iter()
+            .filter(|page| !page.is_expired())
+            .map(|page| page.url(&self.source_dir))
+            .collect();
+        for url in crate::diagnostics::unreachable(&pages, &roots, &links) {
+            let page = content.iter().find(|page| page.url(&self.source_dir) == url);
+            warnings.push(format!(
+                "{} isn't linked from the navigation or any page that is, readers can only find it at {}",
+                page.map(|page| source(page)).unwrap_or_else(|| url.clone()),
+                url
+            ));
+        }
+        warnings
+    }
+
+    pub fn render_all(&self) -> Result<BuildReport, RenderError> {
+        let build_started = Instant::now();
+        let mut clock = PhaseClock::start();
+
         // TODO: Should probably be a bit more sophisticated than this
         // Delete output dir if it exists
         // let _ = std::fs::remove_dir_all(&self.output_dir);
         // Ensure output directory exists
         std::fs::create_dir_all(&self.output_dir)?;
 
-        // Render all pages
-        for page in &self.pages {
-            match page.page_type {
+        for warning in self.theme.compatibility_warnings() {
+            self.warn(warning);
+        }
+        for warning in self.theme.option_warnings(&self.theme_options) {
+            self.warn(warning);
+        }
+        for warning in self.diagnostics() {
+            self.warn(warning);
+        }
+        for collection in self.collections.iter().filter(|collection| collection.pages.is_empty()) {
+            self.problem(Diagnostic::warning(format!("The {} collection has no pages to build", collection.name)));
+        }
+        for collection in self.virtual_collections.iter().filter(|collection| collection.pages.is_empty()) {
+            self.problem(Diagnostic::warning(format!(
+                "The {} virtual collection has no pages matching it",
+                collection.name
+            )));
+        }
+        for language in &self.untranslated {
+            self.warn(format!(
+                "{} has no {}/{}.toml, its own text on {} pages is in English",
+                self.theme.name.as_deref().unwrap_or("The theme"),
+                crate::i18n::I18N_DIR,
+                language,
+                language
+            ));
+        }
+
+        // Before any pages, so critical CSS can read theme stylesheets
+        let bytes = crate::theme::copy_assets(&self.theme_assets, &self.output_dir)?;
+        self.wrote(self.theme_assets.len(), bytes);
+
+        // After the theme's, so the site can override theme files
+        if let Some(static_dir) = self.static_dir.as_ref().filter(|dir| dir.is_dir()) {
+            let (files, bytes) = crate::theme::copy_dir(static_dir, &self.output_dir)?;
+            self.wrote(files, bytes);
+        }
+        let robots = self.output_dir.join("robots.txt");
+        if self.staging {
+            self.write_file(&robots, crate::staging::ROBOTS_TXT)?;
+        } else if std::fs::read_to_string(&robots).is_ok_and(|text| text == crate::staging::ROBOTS_TXT)
+            && !self.static_dir.as_ref().is_some_and(|dir| dir.join("robots.txt").exists())
+        {
+            // Left by a staging build into the same directory
+            std::fs::remove_file(&robots)?;
+        }
+        if self.consent.is_some() {
+            self.write_file(&self.output_dir.join(crate::consent::SCRIPT_FILE), crate::consent::SCRIPT)?;
+        }
+        if self.instant_navigation {
+            self.write_file(&self.output_dir.join(crate::instant::SCRIPT_FILE), crate::instant::SCRIPT)?;
+        }
+        clock.lap("assets");
+
+        let mut rendered = 0;
+        let mut unchanged = 0;
+
+        // Render all pages. `zap serve` also previews the variants that
+        // aren't published.
+        let previews: Vec<Page> = match self.dev_mode {
+            true => self.pages.iter().flat_map(crate::variants::previews).collect(),
+            false => Vec::new(),
+        };
+        for page in self.pages.iter().chain(&previews) {
+            let output_path = self.output_dir.join(self.page_out_path(page));
+            if self.is_unchanged(&output_path, self.page_fingerprint(page).finish()) {
+                unchanged += 1;
+                continue;
+            }
+
+            let mark = self.timing_mark();
+            let template = match page.page_type {
                 PageType::Home => {
                     if let Some(ref home_config) = self.home_config {
-                        self.render_home(page, home_config)?;
+                        self.render_home(page, home_config)?
                     } else {
-                        self.render_regular_page(page)?;
+                        self.render_regular_page(page)?
                     }
                 }
                 PageType::Changelog => self.render_changelog(page)?,
                 _ => self.render_regular_page(page)?,
-            }
+            };
+            self.page_rendered(page, template, mark);
+            rendered += 1;
         }
 
+        clock.lap("pages");
+
         // Render all collections
         for collection in &self.collections {
             // Build collection navigation
@@ -417,17 +2081,84 @@ impl Site {
                 })
                 .collect();
 
-            for page in &collection.pages {
-                let mut context = RenderContext::new();
+            // The same pages nested by subdirectory, for multi-level sidebars
+            let collection_dir = self.source_dir.join(&collection.name);
+            let tree = crate::nav::build_tree(collection.pages.iter().zip(&page_links).map(
+                |(page, item)| {
+                    let relative = page.path.strip_prefix(&collection_dir).unwrap_or(&page.path);
+                    TreeEntry {
+                        dirs: relative
+                            .parent()
+                            .into_iter()
+                            .flat_map(|dir| dir.components())
+                            .map(|c| c.as_os_str().to_string_lossy().to_string())
+                            .collect(),
+                        is_index: matches!(page.page_type, PageType::Index),
+                        item: item.clone(),
+                    }
+                },
+            ));
+
+            // The collection's own index.md, if it has one, lists the rest
+            let index = collection.pages.iter().find(|page| {
+                matches!(page.page_type, PageType::Index)
+                    && page.path.parent() == Some(collection_dir.as_path())
+            });
+            let listed: Vec<NavItem> = collection
+                .pages
+                .iter()
+                .filter(|page| index.is_none_or(|index| index.path != page.path))
+                .map(|page| NavItem {
+                    text: page.title.clone(),
+                    link: format!("/{}", self.page_url(page)),
+                })
+                .collect();
+            let page_size = self
+                .collection_configs
+                .get(&collection.name)
+                .cloned()
+                .unwrap_or_default()
+                .paginate;
+            let paginators = crate::pagination::paginate(&listed, page_size, &collection.url());
+
+            // Every page in the collection shows its navigation
+            let listing = Fingerprint::new()
+                .with(self.fingerprint)
+                .with(&collection.name)
+                .with_json(&page_links)
+                .with_json(&tree)
+                .with_json(&paginators)
+                .finish();
+
+            for (i, page) in collection.pages.iter().enumerate() {
+                let output_path = self.output_dir.join(self.page_out_path(page));
+                if self.is_unchanged(&output_path, self.page_fingerprint(page).with(listing).finish()) {
+                    unchanged += 1;
+                    continue;
+                }
+
+                let mark = self.timing_mark();
+                let mut context = self.page_context(page, Some(collection));
 
                 // Only page-specific data
                 let content = self.render_page(page);
                 context.add_to_context("page_content", &content);
+
+                // Neighbours in collection order, for "previous / next" footers
+                if let Some(prev) = i.checked_sub(1).and_then(|i| page_links.get(i)) {
+                    context.add_to_context("prev_page", prev);
+                }
+                if let Some(next) = page_links.get(i + 1) {
+                    context.add_to_context("next_page", next);
+                }
                 context.add_to_context("collection_pages", &page_links);
+                let mut page_tree = tree.clone();
+                crate::nav::mark_active(&mut page_tree, &format!("/{}", self.page_url(page)));
+                context.add_to_context("collection_tree", &page_tree);
 
                 // Get page headings for side nav
-                let headings: Vec<NavItem> = page
-                    .elements()
+                let headings: Vec<NavItem> = self
+                    .elements(page)
                     .iter()
                     .filter_map(|el| match el {
                         // We're preferring convention here. The only H1 should
@@ -446,18 +2177,113 @@ impl Site {
                     .collect();
                 context.add_to_context("on_this_page", &headings);
 
-                let html = self.renderer.render("doc.html", &context)?;
+                let is_index = index.is_some_and(|index| index.path == page.path);
+                if is_index {
+                    context.add_to_context("collection_title", &title_case(&collection.name));
+                    context.add_to_context("paginator", &paginators[0]);
+                }
+
+                let template = self.template_for(page.template.as_deref().unwrap_or("doc.html"));
+                let html = self.inject_seo(page, self.renderer.render(template, &context)?);
+
+                let html = self.write_anchors(&output_path, self.elements(page), html)?;
 
-                let out_path = self.page_out_path(page);
-                let output_path = self.output_dir.join(out_path);
-                if let Some(parent) = output_path.parent() {
-                    std::fs::create_dir_all(parent)?;
+                self.write_html(&output_path, template, html)?;
+
+                // Later index pages repeat the index content with the next
+                // slice of the listing
+                if is_index {
+                    self.render_index_pages(context, template, &paginators[1..])?;
                 }
-                std::fs::write(output_path, html)?;
+
+                self.page_rendered(page, template, mark);
+                rendered += 1;
+            }
+
+            let listing_path = self.output_dir.join(collection.url().trim_matches('/')).join("index.html");
+            if index.is_none() && !self.is_unchanged(&listing_path, listing) {
+                let title = title_case(&collection.name);
+                self.render_collection_listing(&title, &collection.url(), &page_links, &tree, &paginators)?;
             }
         }
 
-        Ok(())
+        self.render_virtual_collections()?;
+
+        clock.lap("collections");
+
+        self.render_tag_pages()?;
+        clock.lap("tags");
+
+        self.render_generated_pages()?;
+        self.render_roadmap()?;
+
+        if self.llms {
+            self.write_llms_txt()?;
+            clock.lap("llms");
+        }
+
+        // Indexers and other post-processing run once the output is complete
+        for hook in &self.post_build {
+            crate::post_build::run_hook(hook, &self.output_dir)?;
+        }
+        if !self.post_build.is_empty() {
+            clock.lap("post_build");
+        }
+
+        // After the hooks, since links can point at files they write
+        let broken_links = match self.check_links {
+            true => {
+                let broken = self.broken_links()?;
+                for link in &broken {
+                    self.problem(link.diagnostic());
+                }
+                clock.lap("links");
+                broken
+            }
+            false => Vec::new(),
+        };
+
+        if let Some(cache) = &self.view_cache
+            && let Err(e) = cache.borrow().save()
+        {
+            self.warn(format!("Could not save view count cache: {}", e));
+        }
+        if let Err(e) = self.badge_cache.borrow().save() {
+            self.warn(format!("Could not save badge cache: {}", e));
+        }
+
+        let problems = self.problems.take();
+        if !problems.is_empty() {
+            return Err(RenderError::Strict(problems));
+        }
+
+        let ms = build_started.elapsed().as_millis();
+        let templates = self.renderer.template_timings();
+        let mut page_timings = self.page_timings.take();
+        page_timings.sort_by_key(|timing| std::cmp::Reverse(timing.us));
+        self.emit(BuildEvent::Finished {
+            stats: BuildStats {
+                pages: rendered,
+                unchanged,
+                collections: self.collections.len(),
+                warnings: self.warnings.borrow().len(),
+                ms,
+                templates: templates.clone(),
+            },
+        });
+
+        Ok(BuildReport {
+            pages: rendered,
+            unchanged,
+            files_written: self.files_written.get(),
+            bytes_written: self.bytes_written.get(),
+            warnings: self.warnings.borrow().clone(),
+            broken_links,
+            phases: clock.into_phases(),
+            page_timings,
+            templates,
+            ms,
+        })
     }
 }
 
@@ -467,9 +2293,119 @@ pub fn build_site(
     source_dir: &std::path::Path,
     output_dir: &std::path::Path,
     theme_dir: &std::path::Path,
-) -> Result<(), BuildError> {
-    let scanner = crate::scanner::SiteScanner::new(source_dir);
-    let (pages, collections) = scanner.scan().map_err(|e| BuildError::ScanError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+) -> Result<BuildReport, BuildError> {
+    build_site_with_events(config, source_dir, output_dir, theme_dir, noop_handler())
+}
+
+/// Same as [`build_site`], reporting progress through `on_event`
+pub fn build_site_with_events(
+    config: &crate::config::Config,
+    source_dir: &std::path::Path,
+    output_dir: &std::path::Path,
+    theme_dir: &std::path::Path,
+    on_event: EventHandler,
+) -> Result<BuildReport, BuildError> {
+    build_site_incremental(config, source_dir, output_dir, theme_dir, on_event, &mut BuildCache::new())
+}
+
+/// Same as [`build_site_with_events`], only rendering the pages affected by
+/// what changed since the build `cache` was last used for
+pub fn build_site_incremental(
+    config: &crate::config::Config,
+    source_dir: &std::path::Path,
+    output_dir: &std::path::Path,
+    theme_dir: &std::path::Path,
+    on_event: EventHandler,
+    cache: &mut BuildCache,
+) -> Result<BuildReport, BuildError> {
+    tracing::debug!("building {} into {}", source_dir.display(), output_dir.display());
+    let mut clock = PhaseClock::start();
+    on_event(&BuildEvent::ScanStarted {
+        source_dir: source_dir.to_path_buf(),
+    });
+
+    // Pulled from other repositories and CMSs before the source directory
+    // is scanned, so their pages are built like any other
+    let synced = crate::sources::sync_all(
+        &config.sources,
+        source_dir,
+        std::path::Path::new(crate::history::CACHE_DIR),
+        false,
+    );
+    if !config.sources.is_empty() {
+        clock.lap("sync");
+    }
+
+    cache.check_inputs(Fingerprint::new().with_json(config).with(output_dir).finish());
+
+    let timezone = config.timezone().map_err(BuildError::TimezoneError)?;
+    let scanner = crate::scanner::SiteScanner::new(source_dir)
+        .timezone(timezone)
+        .page_cache(std::mem::take(&mut cache.pages));
+    let scanned = scanner.scan();
+    cache.pages = scanner.into_page_cache();
+    let (mut pages, mut collections) = scanned.map_err(BuildError::ContentError)?;
+    clock.lap("scan");
+
+    let misencoded: Vec<PathBuf> = pages
+        .iter()
+        .chain(collections.iter().flat_map(|c| &c.pages))
+        .filter(|page| page.encoding != crate::encoding::Encoding::Utf8)
+        .map(|page| page.path.clone())
+        .collect();
+    if config.strict_encoding
+        && let Some(path) = misencoded.first()
+    {
+        return Err(BuildError::EncodingError(path.clone()));
+    }
+
+    // Static files are copied, not rendered
+    let static_dir = config.static_dir(source_dir);
+    let static_canonical = static_dir.canonicalize().ok();
+    collections.retain(|collection| {
+        let dir = source_dir.join(&collection.name);
+        dir != static_dir && (static_canonical.is_none() || dir.canonicalize().ok() != static_canonical)
+    });
+
+    let rules = crate::page_rules::PageRules::new(&config.page_rules).map_err(BuildError::PageRuleError)?;
+    if !rules.is_empty() {
+        for page in pages.iter_mut().chain(collections.iter_mut().flat_map(|c| &mut c.pages)) {
+            rules.apply(page, source_dir).map_err(BuildError::PageRuleError)?;
+        }
+    }
+    // Pages dated in the future wait for a build after their date, and
+    // expired pages are left out unless their collection keeps them with a
+    // banner. `zap serve` and `--future` keep both, so they can still be
+    // previewed and updated.
+    if !config.dev_mode && !config.future {
+        pages.retain(|page| !(page.is_scheduled() || page.is_expired()));
+        for collection in &mut collections {
+            let expired = config
+                .collections
+                .get(&collection.name)
+                .map(|c| c.expired)
+                .unwrap_or_default();
+            let keep_expired = expired == ExpiredPages::Banner;
+            collection
+                .pages
+                .retain(|page| !(page.is_scheduled() || !keep_expired && page.is_expired()));
+        }
+    }
+
+    crate::permalink::assign(&mut pages, &mut collections, config, source_dir)
+        .map_err(|(path, e)| BuildError::PermalinkError(path, e))?;
+
+    // Sorted once here so nav, prev/next and listings all agree
+    crate::ordering::sort_pages(&mut pages, source_dir, &[]);
+    collections.sort_by(|a, b| a.name.cmp(&b.name));
+    for collection in &mut collections {
+        let order = config
+            .collections
+            .get(&collection.name)
+            .map(|c| c.order.as_slice())
+            .unwrap_or_default();
+        crate::ordering::sort_pages(&mut collection.pages, &source_dir.join(&collection.name), order);
+    }
 
     let mut navigation: Vec<NavItem> = pages
         .iter()
@@ -487,15 +2423,94 @@ pub fn build_site(
         .iter()
         .map(|c| NavItem {
             text: title_case(&c.name),
-            link: format!("/{}", c.url()),
+            link: c.url(),
         })
         .collect();
 
     navigation.extend(collection_links);
 
+    let virtual_collections = crate::virtual_collections::build(&config.virtual_collections, &pages, &collections, source_dir)
+        .map_err(BuildError::VirtualCollectionError)?;
+    navigation.extend(virtual_collections.iter().filter(|c| c.nav).map(|c| NavItem {
+        text: c.title.clone(),
+        link: c.url(),
+    }));
+
+    // A page of the site's own at the same URL replaces a generated one
+    let privacy = config.privacy.clone().unwrap_or_default();
+    let counter = config.counter.as_ref().filter(|counter| !counter.endpoint.is_empty() && !privacy.enabled);
+    let inject = config.inject.clone().unwrap_or_default();
+    let analytics = [
+        counter.map(|counter| crate::privacy::host(&counter.endpoint).to_string()),
+        inject.plausible.as_ref().map(|_| "Plausible".to_string()),
+        inject.goatcounter.as_ref().map(|_| "GoatCounter".to_string()),
+        inject.google_analytics.as_ref().map(|_| "Google Analytics".to_string()),
+    ];
+    let facts = crate::legal::SiteFacts {
+        // Privacy mode removes analytics along with other third parties
+        analytics: analytics.into_iter().flatten().filter(|_| !privacy.enabled).collect(),
+        third_party_assets: !privacy.enabled,
+    };
+    let mut legal_pages = crate::legal::pages(&config.legal.clone().unwrap_or_default(), &facts, source_dir)?;
+    let urls: HashSet<String> = pages
+        .iter()
+        .chain(collections.iter().flat_map(|c| &c.pages))
+        .map(|page| page.url(source_dir))
+        .collect();
+    legal_pages.retain(|page| !urls.contains(&page.url));
+
+    let funding = config.funding.clone().unwrap_or_default();
+    let sponsors = crate::funding::sponsors(&funding, source_dir)?;
+    let support_page = crate::funding::support_page(&funding, &sponsors).filter(|page| !urls.contains(&page.url));
+
+    let roadmap_config = config.roadmap.clone().or_else(|| {
+        let default = crate::config::RoadmapConfig::default();
+        source_dir.join(&default.file).is_file().then_some(default)
+    });
+    let (roadmap, roadmap_warning) = match roadmap_config {
+        Some(roadmap_config) => {
+            let cache_dir = std::path::Path::new(crate::history::CACHE_DIR);
+            let (roadmap, warning) = crate::roadmap::load(&roadmap_config, source_dir, cache_dir)?;
+            (Some(roadmap).filter(|roadmap| !urls.contains(&roadmap.url)), warning)
+        }
+        None => (None, None),
+    };
+
+    let git = config.git.clone().unwrap_or_default();
+    let (git_history, git_warning) = match git.last_updated || git.contributors {
+        true => match GitHistory::read(source_dir, timezone) {
+            Ok(history) => {
+                let warning = history.shallow.then(|| {
+                    "The git repository is a shallow clone, so last_updated and contributors only cover its recent commits"
+                        .to_string()
+                });
+                (Some(history), warning)
+            }
+            Err(e) => (
+                None,
+                Some(format!("Could not read the git history, pages have no last_updated or contributors: {}", e)),
+            ),
+        },
+        false => (None, None),
+    };
+
+    let downloads_config = config.downloads.clone().or_else(|| {
+        let default = crate::config::DownloadsConfig::default();
+        source_dir.join(&default.file).is_file().then_some(default)
+    });
+    let (downloads, downloads_warning) = match &downloads_config {
+        Some(downloads_config) => {
+            let cache_dir = std::path::Path::new(crate::history::CACHE_DIR);
+            let (downloads, warning) = crate::downloads::load(downloads_config, source_dir, cache_dir)?;
+            (Some(downloads), warning)
+        }
+        None => (None, None),
+    };
+
     let home_config = config.home.clone().unwrap_or_default();
     let mut site_config = config.site.clone().unwrap_or_default();
     let home_page = pages.iter().find(|p| matches!(p.page_type, crate::PageType::Home));
+    let has_home = home_page.is_some();
 
     if site_config.title.is_none() {
         site_config.title = home_page
@@ -508,13 +2523,65 @@ pub fn build_site(
         site_config.tagline = home_page.and_then(|home| home.get_first_paragraph());
     }
 
+    // `{year}` in the copyright and the build date are where the site is
+    let now = chrono::Utc::now().with_timezone(&timezone);
+    let year = now.year();
+    let footer = config
+        .footer
+        .as_ref()
+        .map(|footer| Footer::new(footer, year))
+        .unwrap_or_default();
+
     let mut builder = SiteBuilder::new()
         .source_dir(source_dir)
         .output_dir(output_dir)
         .theme_dir(theme_dir)
+        .static_dir(static_dir)
+        .dev_mode(config.dev_mode)
+        .staging(config.staging)
+        .check_links(config.check_links)
+        .strict(config.strict)
         .site_config(site_config)
         .home_config(home_config)
-        .navigation(navigation);
+        .performance_config(config.performance.clone().unwrap_or_default())
+        .changelog_config(config.changelog.clone().unwrap_or_default())
+        .privacy_config(config.privacy.clone().unwrap_or_default())
+        .accessibility_config(config.accessibility.clone().unwrap_or_default())
+        .seo_config(config.seo.clone().unwrap_or_default())
+        .inject_config(config.inject.clone().unwrap_or_default())
+        .consent_config(config.consent.clone())
+        .anchors_config(config.anchors.clone().unwrap_or_default())
+        .counter_config(config.counter.clone().unwrap_or_default())
+        .badges_config(config.badges.clone().unwrap_or_default())
+        .git_config(git)
+        .post_build_hooks(config.post_build.clone())
+        .debug_templates(config.debug_templates)
+        .llms_config(config.llms.clone().unwrap_or_default())
+        .collection_configs(config.collections.clone())
+        .forms(config.forms.clone())
+        .navigation(navigation)
+        .footer(footer)
+        .build_date(now.format("%Y-%m-%d").to_string())
+        .virtual_collections(virtual_collections)
+        .legal_pages(legal_pages)
+        .sponsors(sponsors)
+        .git_history(git_history)
+        .downloads(downloads)
+        .download_pages(downloads_config.map(|config| config.pages).unwrap_or_default())
+        .support_page(support_page)
+        .roadmap(roadmap)
+        .theme_options(config.theme.clone())
+        .previous_outputs(std::mem::take(&mut cache.outputs))
+        .on_event(on_event);
+
+    // Theme options from `[extra]`, always set so themes can use `default()`
+    builder = builder.add_custom("extra", &config.extra)?;
+
+    // Minified pages are hard to debug, so `zap serve` never minifies
+    let performance = config.performance.clone().unwrap_or_default();
+    if performance.minify && !config.dev_mode {
+        builder = builder.post_processor(Minify);
+    }
 
     // Add development mode context if enabled
     if config.dev_mode {
@@ -535,13 +2602,79 @@ pub fn build_site(
     }
 
     let site = builder.build()?;
-    site.render_all()?;
+    // Reading the theme, git history and anything fetched for templates
+    clock.lap("setup");
+
+    for (source, result) in synced {
+        match result {
+            Ok(summary) => {
+                for path in summary.into_iter().flat_map(|summary| summary.skipped) {
+                    site.warn(format!("Kept {} rather than syncing {}, it wasn't written by the source", path.display(), source));
+                }
+            }
+            Err(e) => site.warn(format!("Could not sync {}, building with what was synced last: {}", source, e)),
+        }
+    }
+
+    for path in misencoded {
+        site.warn(format!(
+            "{} is not valid UTF-8, read it as Windows-1252. Re-save it as UTF-8 if characters look wrong.",
+            path.display()
+        ));
+    }
+
+    if let Some(warning) = roadmap_warning {
+        site.warn(warning);
+    }
+
+    if let Some(warning) = downloads_warning {
+        site.warn(warning);
+    }
 
-    Ok(())
+    if let Some(warning) = git_warning {
+        site.warn(warning);
+    }
+
+    if !has_home {
+        site.warn(format!(
+            "No README.md found in {}, the home page will not be generated",
+            source_dir.display()
+        ));
+    }
+
+    // A failed build leaves the outputs it was given out of the cache, so
+    // the next one renders everything
+    let mut report = site.render_all()?;
+    report.phases.splice(0..0, clock.into_phases());
+    cache.outputs = site.take_outputs();
+
+    Ok(report)
+}
+
+fn home_crumb() -> NavItem {
+    NavItem {
+        text: "Home".to_string(),
+        link: "/".to_string(),
+    }
+}
+
+/// Plain list markup, used as `page_content` for generated pages so themes
+/// without a dedicated template still get something usable from page.html
+fn links_html(title: &str, links: &[NavItem]) -> String {
+    let mut html = format!("<h1>{}</h1>\n<ul>\n", html_escape::encode_text(title));
+    for link in links {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            html_escape::encode_double_quoted_attribute(&link.link),
+            html_escape::encode_text(&link.text)
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
 }
 
 /// Convert snake_case to Title Case
-fn title_case(s: &str) -> String {
+pub(crate) fn title_case(s: &str) -> String {
     s.split('_')
         .map(|word| {
             let mut chars = word.chars();
@@ -553,3 +2686,85 @@ fn title_case(s: &str) -> String {
         .collect::<Vec<_>>()
         .join(" ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_theme_without_repository() {
+        let dir = std::env::temp_dir().join(format!("zap-builder-repository-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("site")).unwrap();
+        std::fs::write(dir.join("site/README.md"), "# Zap\n\nFast sites.\n").unwrap();
+        let theme = Path::new(env!("CARGO_MANIFEST_DIR")).join("../theme");
+        let home = || std::fs::read_to_string(dir.join("out/index.html")).unwrap();
+
+        let mut config = crate::config::Config::default();
+        build_site(&config, &dir.join("site"), &dir.join("out"), &theme).unwrap();
+        assert!(!home().contains("github.com"));
+
+        config.site = Some(crate::config::SiteConfig {
+            repository: Some("https://github.com/javif89/zap".to_string()),
+            ..Default::default()
+        });
+        build_site(&config, &dir.join("site"), &dir.join("out"), &theme).unwrap();
+        assert!(home().contains("href=\"https:&#x2F;&#x2F;github.com&#x2F;javif89&#x2F;zap\""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rendered_template() {
+        let dir = std::env::temp_dir().join(format!("zap-builder-templates-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("site/guides")).unwrap();
+        std::fs::create_dir_all(dir.join("theme")).unwrap();
+        std::fs::write(dir.join("site/README.md"), "# Zap\n").unwrap();
+        std::fs::write(dir.join("site/guides/install.md"), "# Install\n").unwrap();
+        // Neither home.html nor doc.html, so both fall back to page.html
+        std::fs::write(dir.join("theme/page.html"), "{{ page_content | safe }}").unwrap();
+
+        let templates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events = templates.clone();
+        let site = dir.join("site");
+        let on_event: EventHandler = std::sync::Arc::new(move |event| {
+            if let BuildEvent::PageRendered { path, template, .. } = event {
+                let path = path.strip_prefix(&site).unwrap_or(path);
+                events.lock().unwrap().push((path.to_string_lossy().to_string(), template.clone()));
+            }
+        });
+        build_site_with_events(&crate::config::Config::default(), &dir.join("site"), &dir.join("out"), &dir.join("theme"), on_event).unwrap();
+        let mut templates = templates.lock().unwrap().clone();
+        templates.sort();
+        assert_eq!(
+            templates,
+            [
+                ("README.md".to_string(), "page.html".to_string()),
+                ("guides/install.md".to_string(), "page.html".to_string()),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tag_pages() {
+        let dir = std::env::temp_dir().join(format!("zap-builder-tags-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("site/guides")).unwrap();
+        std::fs::write(dir.join("site/README.md"), "# Zap\n\nFast sites.\n").unwrap();
+        std::fs::write(dir.join("site/guides/install.md"), "+++\ntags = [\"Rust\", \"Setup\"]\n+++\n# Install\n").unwrap();
+        std::fs::write(dir.join("site/guides/usage.md"), "+++\ntags = [\"rust\"]\n+++\n# Usage\n").unwrap();
+        let theme = Path::new(env!("CARGO_MANIFEST_DIR")).join("../theme");
+        let read = |path: &str| std::fs::read_to_string(dir.join("out").join(path)).unwrap();
+
+        build_site(&crate::config::Config::default(), &dir.join("site"), &dir.join("out"), &theme).unwrap();
+        let index = read("tags/index.html");
+        assert!(index.contains("Rust") && index.contains("Setup"));
+        let rust = read("tags/rust/index.html");
+        assert!(rust.contains("Install") && rust.contains("Usage"));
+        let setup = read("tags/setup/index.html");
+        assert!(setup.contains("Install") && !setup.contains("Usage"));
+        assert!(read("guides/usage/index.html").contains("&#x2F;tags&#x2F;rust&#x2F;"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
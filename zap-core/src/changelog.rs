@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::markdown::{
+    PageElement, render_elements_to_html, render_inline_elements, render_inline_elements_text,
+    slugify,
+};
+
+/// A single release parsed from a Keep a Changelog style `CHANGELOG.md`
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub version: String,
+    pub date: Option<String>,
+    /// Full heading text, e.g. `[1.2.0] - 2024-05-01`
+    pub text: String,
+    pub slug: String,
+    pub link: String,
+    /// URL of the release's own page, when release pages are enabled
+    pub url: Option<String>,
+    /// List items grouped by lowercased `###` heading (`added`, `fixed`, ...)
+    pub sections: BTreeMap<String, Vec<String>>,
+    pub body_html: String,
+}
+
+/// Split a changelog into releases, one per H2
+pub fn parse_releases(elements: &[PageElement]) -> Vec<Release> {
+    let mut releases = Vec::new();
+    let mut current: Option<(String, Vec<PageElement>)> = None;
+
+    for element in elements {
+        match element {
+            PageElement::Heading { level: 2, content } => {
+                if let Some((text, body)) = current.take() {
+                    releases.push(build_release(text, &body));
+                }
+                current = Some((render_inline_elements_text(content), Vec::new()));
+            }
+            // Another H1 ends the releases
+            PageElement::Heading { level: 1, .. } => {
+                if let Some((text, body)) = current.take() {
+                    releases.push(build_release(text, &body));
+                }
+            }
+            _ => {
+                if let Some((_, body)) = current.as_mut() {
+                    body.push(element.clone());
+                }
+            }
+        }
+    }
+
+    if let Some((text, body)) = current {
+        releases.push(build_release(text, &body));
+    }
+
+    releases
+}
+
+fn build_release(text: String, body: &[PageElement]) -> Release {
+    let mut sections: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut section: Option<String> = None;
+
+    for element in body {
+        match element {
+            PageElement::Heading { content, .. } => {
+                section = Some(render_inline_elements_text(content).trim().to_lowercase());
+            }
+            PageElement::List { items, .. } => {
+                if let Some(name) = &section {
+                    sections
+                        .entry(name.clone())
+                        .or_default()
+                        .extend(items.iter().map(|item| render_inline_elements(&item.content)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let slug = slugify(&text);
+    Release {
+        version: parse_version(&text),
+        date: parse_date(&text),
+        link: format!("#{}", slug),
+        url: None,
+        slug,
+        text,
+        sections,
+        body_html: render_elements_to_html(body),
+    }
+}
+
+/// Directory name for a release's page. Dots are kept so `1.2.0` stays
+/// readable in the URL.
+pub fn release_dir(version: &str) -> String {
+    version
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '_') { c } else { '-' })
+        .collect::<String>()
+        .trim_matches(|c| c == '-' || c == '.')
+        .to_string()
+}
+
+// `[1.2.0] - 2024-05-01` and `v1.2.0 (2024-05-01)` both give `1.2.0`
+fn parse_version(text: &str) -> String {
+    let first = text
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_matches(|c| c == '[' || c == ']');
+
+    match first.strip_prefix('v') {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest.to_string(),
+        _ => first.to_string(),
+    }
+}
+
+// First YYYY-MM-DD in the heading
+fn parse_date(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(10).find_map(|w| {
+        let is_date = w.iter().enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == '-',
+            _ => c.is_ascii_digit(),
+        });
+        is_date.then(|| w.iter().collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+
+### Added
+
+- Dark mode
+
+## [1.2.0] - 2024-05-01
+
+### Added
+
+- Search
+- `zap check`
+
+### Fixed
+
+- Broken links in the sidebar
+
+## v1.1.0 (2024-03-15)
+
+### Removed
+
+- The old theme
+
+## [1.0.0]
+
+- First release
+
+[Unreleased]: https://github.com/owner/repo/compare/v1.2.0...HEAD
+[1.2.0]: https://github.com/owner/repo/compare/v1.1.0...v1.2.0
+";
+
+    #[test]
+    fn test_parse_releases() {
+        let releases = parse_releases(&crate::markdown::parse_structured(CHANGELOG));
+        let versions: Vec<&str> = releases.iter().map(|release| release.version.as_str()).collect();
+        assert_eq!(versions, ["Unreleased", "1.2.0", "1.1.0", "1.0.0"]);
+
+        let unreleased = &releases[0];
+        assert_eq!(unreleased.date, None);
+        assert_eq!(unreleased.sections["added"], ["Dark mode"]);
+
+        // Versions that are link references are read from the link's text
+        let release = &releases[1];
+        assert_eq!(release.text, "1.2.0 - 2024-05-01");
+        assert_eq!(release.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(release.link, format!("#{}", release.slug));
+        assert_eq!(release.sections["added"], ["Search", "<code>zap check</code>"]);
+        assert_eq!(release.sections["fixed"], ["Broken links in the sidebar"]);
+        assert!(release.body_html.contains("Broken links in the sidebar"));
+
+        assert_eq!(releases[2].date.as_deref(), Some("2024-03-15"));
+        assert_eq!(releases[2].sections.keys().collect::<Vec<_>>(), ["removed"]);
+        // Items outside a `###` section are only in the body
+        assert_eq!(releases[3].date, None);
+        assert!(releases[3].sections.is_empty());
+        assert!(releases[3].body_html.contains("First release"));
+    }
+
+    #[test]
+    fn test_parse_heading() {
+        assert_eq!(parse_version("[2.0.0-beta.1] - 2024-06-01"), "2.0.0-beta.1");
+        assert_eq!(parse_version("version-2"), "version-2");
+        assert_eq!(parse_date("1.0.0 - released 2024-1-5"), None);
+        assert_eq!(release_dir("2.0.0-Beta.1"), "2.0.0-beta.1");
+        assert_eq!(release_dir("[Unreleased]"), "unreleased");
+    }
+}
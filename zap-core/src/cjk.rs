@@ -0,0 +1,81 @@
+// Chinese and Japanese are written without spaces between words, so
+// counting and searching can't split their text on whitespace. Korean uses
+// spaces, so Hangul is treated like any other script.
+
+/// Whether `c` is a Han, Hiragana or Katakana character
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{30A0}'..='\u{30FF}' // Katakana
+        | '\u{31F0}'..='\u{31FF}' // Katakana phonetic extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth katakana
+        | '\u{20000}'..='\u{3134F}' // CJK extensions B to G
+    )
+}
+
+/// Words in `text`. Each Chinese or Japanese character counts as one, as
+/// reading speeds for those languages are given in characters. Tokens
+/// without letters or digits, like `#` and `-`, don't count.
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|token| {
+            segments(token)
+                .map(|(segment, cjk)| match cjk {
+                    true => segment.chars().count(),
+                    false => usize::from(segment.chars().any(char::is_alphanumeric)),
+                })
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Lowercase search terms in `text`. Runs of Chinese or Japanese are split
+/// into overlapping pairs of characters, so a query matches text with the
+/// same words without knowing where one word ends.
+pub fn search_terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for token in text.split_whitespace() {
+        for (segment, cjk) in segments(token) {
+            if !cjk {
+                terms.push(segment.to_lowercase());
+                continue;
+            }
+            let chars: Vec<char> = segment.chars().collect();
+            if chars.len() == 1 {
+                terms.push(segment.to_string());
+            }
+            terms.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+        }
+    }
+    terms
+}
+
+// Runs of CJK and other characters in a token, with whether they're CJK
+fn segments(token: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut rest = token;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let cjk = is_cjk(first);
+        let end = rest.find(|c: char| is_cjk(c) != cjk).unwrap_or(rest.len());
+        let (segment, tail) = rest.split_at(end);
+        rest = tail;
+        Some((segment, cjk))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cjk() {
+        assert_eq!(word_count("静的サイトを生成する"), 10);
+        assert_eq!(word_count("使用Rust编写 - fast"), 6);
+        assert_eq!(word_count("정적 사이트 생성기"), 3);
+        assert_eq!(search_terms("静态网站 Zap"), vec!["静态", "态网", "网站", "zap"]);
+        assert_eq!(search_terms("用 Rust"), vec!["用", "rust"]);
+    }
+}
@@ -1,11 +1,14 @@
-use std::{fmt, path::Path};
+use std::{collections::{BTreeMap, HashMap}, fmt, path::{Path, PathBuf}};
 
 use serde::{Deserialize, Serialize};
 
+use crate::direction::Direction;
+
 #[derive(Debug)]
 pub enum ConfigError {
     Io(std::io::Error),
     Parsing(toml::de::Error),
+    Invalid(String),
 }
 
 impl fmt::Display for ConfigError {
@@ -13,6 +16,7 @@ impl fmt::Display for ConfigError {
         match self {
             ConfigError::Io(e) => write!(f, "IO error: {}", e),
             ConfigError::Parsing(e) => write!(f, "TOML parse error: {}", e),
+            ConfigError::Invalid(message) => write!(f, "{}", message),
         }
     }
 }
@@ -35,9 +39,87 @@ impl From<toml::de::Error> for ConfigError {
 pub struct Config {
     pub site: Option<SiteConfig>,
     pub home: Option<HomeConfig>,
+    pub footer: Option<FooterConfig>,
+    pub legal: Option<LegalConfig>,
+    pub funding: Option<FundingConfig>,
+    pub roadmap: Option<RoadmapConfig>,
+    pub downloads: Option<DownloadsConfig>,
+    pub faq: Option<FaqConfig>,
+    pub performance: Option<PerformanceConfig>,
+    pub changelog: Option<ChangelogConfig>,
+    pub privacy: Option<PrivacyConfig>,
+    pub accessibility: Option<AccessibilityConfig>,
+    pub seo: Option<SeoConfig>,
+    pub inject: Option<InjectConfig>,
+    pub consent: Option<ConsentConfig>,
+    pub anchors: Option<AnchorsConfig>,
+    pub counter: Option<CounterConfig>,
+    pub badges: Option<BadgesConfig>,
+    pub git: Option<GitConfig>,
+    pub llms: Option<LlmsConfig>,
+    pub export: Option<ExportConfig>,
+    pub deploy: Option<DeployConfig>,
+    /// Per-collection settings, keyed by collection directory name
+    #[serde(default)]
+    pub collections: HashMap<String, CollectionConfig>,
+    /// Listings of pages picked by tag or path, keyed by name
+    #[serde(default)]
+    pub virtual_collections: BTreeMap<String, VirtualCollectionConfig>,
+    /// Forms pages embed with `[form NAME]`, keyed by name
+    #[serde(default)]
+    pub forms: BTreeMap<String, FormConfig>,
+    #[serde(default)]
+    pub post_build: Vec<PostBuildHook>,
+    /// Git repositories and CMSs whose content is pulled into the source
+    /// directory before every build
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+    /// Type, template and URL overrides for pages matching a pattern
+    #[serde(default)]
+    pub page_rules: Vec<PageRule>,
+    /// URL pattern for pages, e.g. `/:collection/:slug/`. Collections and
+    /// page rules can set their own.
+    #[serde(default)]
+    pub permalink: Option<String>,
+    /// Write pages to `page.html` instead of `page/index.html`
+    #[serde(default)]
+    pub ugly_urls: bool,
+    /// Directory copied verbatim into the output, for favicons, images and
+    /// extra stylesheets. Defaults to `static/` in the source directory.
+    #[serde(default)]
+    pub static_dir: Option<String>,
+    /// Fail the build on pages that aren't UTF-8, instead of reading them as
+    /// Windows-1252 with a warning
+    #[serde(default)]
+    pub strict_encoding: bool,
+    /// Build for a preview deployment: pages are marked `noindex` and
+    /// robots.txt disallows everything
+    #[serde(default)]
+    pub staging: bool,
+    /// Check internal links and anchors after every build, warning about
+    /// broken ones. `zap check` always does.
+    #[serde(default)]
+    pub check_links: bool,
+    /// Fail the build on problems that are otherwise warnings, e.g. in CI.
+    /// `zap build --strict` sets it.
+    #[serde(default)]
+    pub strict: bool,
+    /// Build pages dated in the future, and expired pages their collection
+    /// would leave out
+    #[serde(default)]
+    pub future: bool,
+    /// Options declared by the theme in its `theme.toml`, merged over their
+    /// defaults and given to templates as `theme_config`
+    #[serde(default, deserialize_with = "crate::front_matter::deserialize_extra")]
+    pub theme: serde_json::Map<String, serde_json::Value>,
+    /// Theme-defined options, given to templates as `extra`
+    #[serde(default, deserialize_with = "crate::front_matter::deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
     #[serde(default)]
     pub dev_mode: bool,
     #[serde(default)]
+    pub debug_templates: bool,
+    #[serde(default)]
     pub dev_server_host: String,
     #[serde(default = "default_dev_port")]
     pub dev_server_port: u16,
@@ -55,6 +137,20 @@ impl Config {
         Ok(config)
     }
 
+    pub fn static_dir(&self, source_dir: &Path) -> PathBuf {
+        match &self.static_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => source_dir.join("static"),
+        }
+    }
+
+    pub fn timezone(&self) -> Result<chrono_tz::Tz, crate::dates::DateError> {
+        match self.site.as_ref().and_then(|site| site.timezone.as_deref()) {
+            Some(name) => crate::dates::parse_timezone(name),
+            None => Ok(chrono_tz::Tz::UTC),
+        }
+    }
+
     pub fn dev(&mut self, host: String, port: u16) {
         self.dev_mode = true;
         self.dev_server_host = host;
@@ -69,6 +165,25 @@ pub struct SiteConfig {
     pub tagline: Option<String>,
     pub secondary_tagline: Option<String>,
     pub small_tag: Option<String>,
+    /// Link to the project's source repository
+    pub repository: Option<String>,
+    /// Name of a color scheme shipped by the theme, e.g. `rose` for the
+    /// default theme's `themes/rose.css`
+    pub color_scheme: Option<String>,
+    /// Timezone for front matter dates without an offset, e.g.
+    /// `Europe/Madrid`. Defaults to UTC.
+    pub timezone: Option<String>,
+    /// Where the site is published, e.g. `https://zap.dev`, for absolute
+    /// URLs in link previews
+    pub url: Option<String>,
+    /// Description for pages that don't have one of their own
+    pub description: Option<String>,
+    /// Link preview image for pages that don't have one of their own
+    pub image: Option<String>,
+    /// Language of the site's pages, e.g. `ar` or `pt-BR`. Defaults to `en`.
+    pub language: Option<String>,
+    /// Text direction, `ltr` or `rtl`. Defaults to the language's.
+    pub dir: Option<Direction>,
 }
 
 
@@ -93,12 +208,724 @@ impl Default for HomeConfig {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PerformanceConfig {
+    /// Hint browsers to prefetch likely next pages
+    pub prefetch: bool,
+    /// Inline above-the-fold CSS and load the rest asynchronously
+    pub critical_css: bool,
+    /// Minify pages in production builds
+    pub minify: bool,
+    /// Follow links within the site by fetching the page and swapping it
+    /// in, prefetching on hover
+    pub instant_navigation: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ChangelogConfig {
+    /// Also render each release at `/changelog/<version>/`
+    pub release_pages: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct AnchorsConfig {
+    /// Keep renamed headings' old anchors working, as hidden elements with
+    /// the old IDs
+    pub aliases: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct InjectConfig {
+    /// Raw HTML added at the end of every page's `<head>`
+    pub head: Option<String>,
+    /// Raw HTML added right after `<body>`
+    pub body_start: Option<String>,
+    /// Raw HTML added right before `</body>`
+    pub body_end: Option<String>,
+    /// Plausible site domain, e.g. `example.com`
+    pub plausible: Option<String>,
+    /// GoatCounter code, e.g. `example` for example.goatcounter.com, or the
+    /// count URL of a self-hosted instance
+    pub goatcounter: Option<String>,
+    /// Google Analytics measurement ID, e.g. `G-XXXXXXXXXX`
+    pub google_analytics: Option<String>,
+}
+
+/// A banner asking visitors before analytics from `[inject]` are loaded,
+/// and other snippets marked with `data-consent`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ConsentConfig {
+    pub text: String,
+    pub accept: String,
+    pub reject: String,
+    /// Button saving the boxes ticked, shown with more than one category
+    pub save: String,
+    /// Shown after the text, e.g. the privacy policy
+    pub links: Vec<Link>,
+    /// What visitors can allow. `[inject]` analytics are in `analytics`.
+    pub categories: Vec<ConsentCategory>,
+}
+
+impl Default for ConsentConfig {
+    fn default() -> Self {
+        Self {
+            text: "We'd like to count visits to improve this site. Is that OK?".to_string(),
+            accept: "Accept".to_string(),
+            reject: "Decline".to_string(),
+            save: "Save choices".to_string(),
+            links: Vec::new(),
+            categories: vec![ConsentCategory {
+                name: "analytics".to_string(),
+                label: "Analytics".to_string(),
+                description: None,
+            }],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ConsentCategory {
+    /// What snippets name in `data-consent`
+    pub name: String,
+    pub label: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct SeoConfig {
+    /// Add description, Open Graph and Twitter meta tags to pages whose
+    /// theme doesn't write them
+    pub inject: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Guarantee the built site makes no third-party requests: external
+    /// assets are self-hosted, analytics removed and embeds click-to-load
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Give every page a skip link, `main` and `nav` landmarks, and
+    /// `aria-current` on links to itself, where the theme doesn't
+    pub enabled: bool,
+    /// Text of the skip link
+    pub skip_link: String,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            skip_link: "Skip to content".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct CounterConfig {
+    /// GoatCounter-compatible count endpoint, e.g.
+    /// `https://example.goatcounter.com/count`
+    pub endpoint: String,
+    /// Fetch per-page view counts at build time and expose them as `views`
+    pub show_views: bool,
+    /// How long fetched view counts are reused between builds
+    pub cache_minutes: u64,
+}
+
+impl Default for CounterConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            show_views: false,
+            cache_minutes: 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct BadgesConfig {
+    /// How long fetched `badge:` images are reused between builds
+    pub cache_minutes: u64,
+}
+
+impl Default for BadgesConfig {
+    fn default() -> Self {
+        Self { cache_minutes: 60 }
+    }
+}
+
+/// Page details read from the git history
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Expose each page's last commit date as `last_updated`
+    pub last_updated: bool,
+    /// Expose the authors of each page's commits as `contributors`
+    pub contributors: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct CollectionConfig {
+    /// Pages listed per page of the collection's index
+    pub paginate: usize,
+    /// Pages to put first, by path within the collection without extension
+    pub order: Vec<String>,
+    /// URL pattern for the collection's pages, e.g. `/blog/:year/:slug/`
+    pub permalink: Option<String>,
+    /// What builds do with pages past their `expires` date
+    pub expired: ExpiredPages,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiredPages {
+    /// Leave them out of `zap build`. `zap serve` still shows them, with an
+    /// "out of date" banner.
+    #[default]
+    Exclude,
+    /// Render them with the banner, hidden from search engines
+    Banner,
+}
+
+impl Default for CollectionConfig {
+    fn default() -> Self {
+        Self {
+            paginate: 20,
+            order: Vec::new(),
+            permalink: None,
+            expired: ExpiredPages::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct VirtualCollectionConfig {
+    /// Defaults to the name, title-cased
+    pub title: Option<String>,
+    /// Only pages with all of these tags
+    pub tags: Vec<String>,
+    /// Only pages whose path in the source directory matches this glob
+    pub pattern: Option<String>,
+    /// Keep the site's order when unset
+    pub sort: Option<ListingSort>,
+    /// Only the first pages, after sorting
+    pub limit: Option<usize>,
+    /// Pages listed per page of the index
+    pub paginate: usize,
+    /// Link the index from the site navigation
+    pub nav: bool,
+}
+
+impl Default for VirtualCollectionConfig {
+    fn default() -> Self {
+        Self {
+            title: None,
+            tags: Vec::new(),
+            pattern: None,
+            sort: None,
+            limit: None,
+            paginate: 20,
+            nav: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingSort {
+    /// Newest first
+    Date,
+    Title,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct LlmsConfig {
+    /// Write `llms.txt` and `llms-full.txt` to the output directory
+    pub enabled: bool,
+}
+
+/// What `zap export` writes next to exported files
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Add each export to a `SHA256SUMS` in its directory
+    pub checksums: bool,
+    /// Sign exports and `SHA256SUMS` with the `minisign` command
+    pub sign: bool,
+    /// Secret key to sign with, minisign's default key when unset
+    pub minisign_key: Option<PathBuf>,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            checksums: true,
+            sign: false,
+            minisign_key: None,
+        }
+    }
+}
+
+/// A form posted to a form backend, since a static site can't take
+/// submissions itself
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct FormConfig {
+    pub backend: FormBackend,
+    /// The form's ID for Formspree, or the URL a custom backend takes
+    /// submissions at. Netlify needs neither.
+    pub action: Option<String>,
+    pub fields: Vec<FormField>,
+    /// Text of the submit button
+    pub submit: String,
+    /// Where visitors land after submitting. Defaults to a generated page at
+    /// `/forms/NAME/thanks/` showing `success_message`.
+    pub success_url: Option<String>,
+    pub success_title: String,
+    /// Markdown
+    pub success_message: String,
+}
+
+impl Default for FormConfig {
+    fn default() -> Self {
+        Self {
+            backend: FormBackend::Custom,
+            action: None,
+            fields: Vec::new(),
+            submit: "Send".to_string(),
+            success_url: None,
+            success_title: "Thank you".to_string(),
+            success_message: "Your message was sent.".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FormBackend {
+    /// Netlify Forms, which picks the form up from the deployed HTML
+    Netlify,
+    Formspree,
+    /// Any endpoint taking a urlencoded POST
+    Custom,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct FormField {
+    /// Name the value is submitted under
+    pub name: String,
+    /// Defaults to the name, title-cased
+    pub label: Option<String>,
+    /// `text`, `email`, `tel`, `url`, `number`, `date`, `textarea`, `select`
+    /// or `checkbox`. Defaults to `text`.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub required: bool,
+    pub placeholder: Option<String>,
+    /// Choices of a `select`
+    pub options: Vec<String>,
+}
+
+/// Where `zap deploy` publishes the site
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct DeployConfig {
+    pub ipfs: Option<IpfsConfig>,
+}
+
+/// Publishing to IPFS through a local node
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct IpfsConfig {
+    /// The node's API multiaddr, e.g. `/ip4/127.0.0.1/tcp/5001`, the `ipfs`
+    /// command's default when unset
+    pub api: Option<String>,
+    /// Base URL of an IPFS Pinning Service API to pin the site with, e.g.
+    /// `https://api.pinata.cloud/psa`
+    pub pinning_service: Option<String>,
+    /// Environment variable holding the pinning service's access token
+    pub pinning_token_env: String,
+    /// Domain whose DNSLink is pointed at the new CID
+    pub dnslink: Option<String>,
+    /// Cloudflare zone ID of the DNSLink domain. Without it the TXT record
+    /// to set is printed instead.
+    pub cloudflare_zone: Option<String>,
+    /// Environment variable holding a Cloudflare API token that can edit
+    /// the zone's DNS
+    pub cloudflare_token_env: String,
+}
+
+impl Default for IpfsConfig {
+    fn default() -> Self {
+        Self {
+            api: None,
+            pinning_service: None,
+            pinning_token_env: "IPFS_PINNING_TOKEN".to_string(),
+            dnslink: None,
+            cloudflare_zone: None,
+            cloudflare_token_env: "CLOUDFLARE_API_TOKEN".to_string(),
+        }
+    }
+}
+
+/// Overrides for pages whose path in the source directory matches
+/// `pattern`. The first matching rule applies.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct PageRule {
+    /// Glob such as `blog/*.md`
+    pub pattern: String,
+    /// `home`, `changelog`, `index`, `doc`, `page`, or a name of your own,
+    /// which renders with `<name>.html` unless `template` is set
+    #[serde(rename = "type")]
+    pub page_type: Option<String>,
+    pub template: Option<String>,
+    /// URL pattern such as `/blog/:year/:slug/`
+    pub url: Option<String>,
+}
+
+/// Content pulled into a directory of the site from elsewhere, either a
+/// git repository or a JSON endpoint
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SourceConfig {
+    /// Directory the content is written to, in the source directory
+    pub dir: String,
+    /// URL of the git repository to pull from
+    pub git: Option<String>,
+    /// Branch, tag or commit of `git`, its default branch when unset
+    #[serde(rename = "ref")]
+    pub reference: String,
+    /// Directory in the repository to take, all of it when unset
+    pub path: Option<String>,
+    /// JSON endpoint listing the pages, instead of `git`
+    pub url: Option<String>,
+    /// Environment variable with a token sent to `url`
+    pub token_env: Option<String>,
+    /// How long a sync is reused between builds. `zap sync` always pulls.
+    pub cache_minutes: u64,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            dir: String::new(),
+            git: None,
+            reference: "HEAD".to_string(),
+            path: None,
+            url: None,
+            token_env: None,
+            cache_minutes: 5,
+        }
+    }
+}
+
+/// A command run against the output directory after every build, e.g. a
+/// search indexer like Pagefind
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct PostBuildHook {
+    pub command: String,
+}
+
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct Link {
     pub text: String,
     pub link: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct FooterConfig {
+    /// Columns of links, each under a title
+    pub columns: Vec<FooterColumn>,
+    /// Accounts on other sites, e.g. `platform = "mastodon"`
+    pub social: Vec<SocialAccount>,
+    /// Copyright line, `{year}` is replaced with the year of the build
+    pub copyright: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct FooterColumn {
+    pub title: String,
+    #[serde(default)]
+    pub links: Vec<Link>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct SocialAccount {
+    pub platform: String,
+    pub url: String,
+    /// Defaults to the platform's name
+    pub label: Option<String>,
+}
+
+/// Generated legal pages
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct LegalConfig {
+    /// A privacy policy at `/privacy/`
+    pub privacy: Option<PrivacyPolicyConfig>,
+    /// An imprint (Impressum) at `/imprint/`
+    pub imprint: Option<ImprintConfig>,
+    /// The project's LICENSE file at `/license/`
+    pub license: bool,
+    /// The licenses of a Rust project's dependencies at
+    /// `/third-party-licenses/`
+    pub third_party: Option<ThirdPartyConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThirdPartyConfig {
+    pub title: String,
+    /// `Cargo.toml` of the project, relative to the source directory.
+    /// Looked for there and in the directory above when unset.
+    pub manifest: Option<PathBuf>,
+}
+
+impl Default for ThirdPartyConfig {
+    fn default() -> Self {
+        Self {
+            title: "Third-Party Licenses".to_string(),
+            manifest: None,
+        }
+    }
+}
+
+/// Sponsor links. Read from `.github/FUNDING.yml` when no accounts are set
+/// here.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct FundingConfig {
+    /// A "Support this project" page at `/support/`
+    pub page: bool,
+    pub title: String,
+    /// Markdown shown above the links on the support page
+    pub message: Option<String>,
+    /// Accounts by FUNDING.yml platform, e.g. `github = ["ana"]` or
+    /// `custom = "https://example.com/donate"`
+    #[serde(flatten)]
+    pub accounts: BTreeMap<String, FundingAccounts>,
+}
+
+impl Default for FundingConfig {
+    fn default() -> Self {
+        Self {
+            page: false,
+            title: "Support this project".to_string(),
+            message: None,
+            accounts: BTreeMap::new(),
+        }
+    }
+}
+
+/// One account or a list, as FUNDING.yml allows either
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum FundingAccounts {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl FundingAccounts {
+    pub fn names(&self) -> &[String] {
+        match self {
+            FundingAccounts::One(name) => std::slice::from_ref(name),
+            FundingAccounts::Many(names) => names,
+        }
+    }
+}
+
+/// A roadmap page, from `roadmap.toml` or a GitHub project. Generated
+/// whenever the source directory has a `roadmap.toml`, too.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct RoadmapConfig {
+    pub title: String,
+    pub url: String,
+    /// Items as `[[items]]` tables, relative to the source directory
+    pub file: PathBuf,
+    /// Read items from a GitHub project instead of `file`
+    pub github: Option<GithubProjectConfig>,
+    pub group_by: RoadmapGrouping,
+    /// Statuses that count as finished for progress bars
+    pub done: Vec<String>,
+    /// Groups listed first, in this order. The rest follow in the order
+    /// their first item appears.
+    pub order: Vec<String>,
+}
+
+impl Default for RoadmapConfig {
+    fn default() -> Self {
+        Self {
+            title: "Roadmap".to_string(),
+            url: "/roadmap/".to_string(),
+            file: PathBuf::from("roadmap.toml"),
+            github: None,
+            group_by: RoadmapGrouping::default(),
+            done: vec!["Done".to_string()],
+            order: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RoadmapGrouping {
+    #[default]
+    Status,
+    Milestone,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GithubProjectConfig {
+    /// User or organization the project belongs to
+    pub owner: String,
+    /// The number in the project's URL
+    pub number: u64,
+    /// Environment variable with a token that can read the project
+    #[serde(default = "default_token_env")]
+    pub token_env: String,
+    /// Single-select field items are grouped by with `group_by = "status"`
+    #[serde(default = "default_status_field")]
+    pub status_field: String,
+    /// How long fetched items are reused between builds
+    #[serde(default = "default_cache_minutes")]
+    pub cache_minutes: u64,
+}
+
+fn default_token_env() -> String {
+    "GITHUB_TOKEN".to_string()
+}
+
+fn default_status_field() -> String {
+    "Status".to_string()
+}
+
+fn default_cache_minutes() -> u64 {
+    60
+}
+
+/// A download matrix of a release's files by OS and architecture. Shown
+/// whenever the source directory has a `downloads.toml`, too.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct DownloadsConfig {
+    /// GitHub repository whose release is listed, `owner/name`
+    pub repo: Option<String>,
+    /// The release's tag, the latest release when unset
+    pub tag: Option<String>,
+    /// Files as `[[assets]]` tables instead of a GitHub release, relative to
+    /// the source directory
+    pub file: PathBuf,
+    /// URLs of the pages the matrix is shown on
+    pub pages: Vec<String>,
+    /// Environment variable with a GitHub token. Optional for public
+    /// repositories, but raises the API's rate limit.
+    pub token_env: String,
+    /// How long a fetched release is reused between builds
+    pub cache_minutes: u64,
+}
+
+impl Default for DownloadsConfig {
+    fn default() -> Self {
+        Self {
+            repo: None,
+            tag: None,
+            file: PathBuf::from("downloads.toml"),
+            pages: vec!["/".to_string()],
+            token_env: default_token_env(),
+            cache_minutes: default_cache_minutes(),
+        }
+    }
+}
+
+/// Where `zap faq` imports questions and answers from
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct FaqConfig {
+    /// `owner/name` of the GitHub repository
+    pub repo: Option<String>,
+    /// Discussions and issues with this label are imported
+    pub label: String,
+    /// Collection directory the pages are written to, in the source
+    /// directory
+    pub collection: String,
+    /// Import answered discussions, with the accepted answer
+    pub discussions: bool,
+    /// Import issues, with the first reply from a maintainer
+    pub issues: bool,
+    /// Environment variable with a token that can read the repository
+    pub token_env: String,
+}
+
+impl Default for FaqConfig {
+    fn default() -> Self {
+        Self {
+            repo: None,
+            label: "faq".to_string(),
+            collection: "faq".to_string(),
+            discussions: true,
+            issues: true,
+            token_env: default_token_env(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PrivacyPolicyConfig {
+    #[serde(default = "default_privacy_title")]
+    pub title: String,
+    /// The person or organisation responsible for the site's data
+    pub controller: String,
+    pub email: String,
+    pub address: Option<String>,
+    /// Who hosts the site, e.g. "GitHub Pages"
+    pub hosting: Option<String>,
+    #[serde(default, deserialize_with = "crate::front_matter::deserialize_date")]
+    pub updated: Option<String>,
+}
+
+fn default_privacy_title() -> String {
+    "Privacy Policy".to_string()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImprintConfig {
+    #[serde(default = "default_imprint_title")]
+    pub title: String,
+    pub name: String,
+    /// Postal address, one line per line
+    pub address: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub represented_by: Option<String>,
+    /// Register court and number, e.g. "Amtsgericht Berlin, HRB 12345"
+    pub register: Option<String>,
+    pub vat_id: Option<String>,
+    /// Person responsible for the content, where the law asks for one
+    pub responsible: Option<String>,
+}
+
+fn default_imprint_title() -> String {
+    "Imprint".to_string()
+}
+
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct Feature {
     pub title: String,
@@ -0,0 +1,179 @@
+// A consent banner from `[consent]` in zap.toml. Scripts that need consent
+// are written as `<script type="text/plain" data-consent="CATEGORY">`, which
+// browsers don't run, and a small script swaps them for real ones once the
+// visitor allows their category. Analytics from `[inject]` are marked this
+// way when the banner is on; snippets of the site's own can be too. Choices
+// are kept in localStorage, and asked for again when the categories change.
+
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+
+use crate::config::ConsentConfig;
+
+/// Written to the root of the output directory
+pub const SCRIPT_FILE: &str = "zap-consent.js";
+
+/// Mark the `<script>` tags in `snippet` as needing consent for `category`
+pub fn gate(snippet: &str, category: &str) -> String {
+    snippet.replace(
+        "<script",
+        &format!("<script type=\"text/plain\" data-consent=\"{}\"", attr(category)),
+    )
+}
+
+// Changes with the categories, so visitors are asked again about new ones
+fn version(config: &ConsentConfig) -> String {
+    let names: Vec<&str> = config.categories.iter().map(|category| category.name.as_str()).collect();
+    crate::anchors::stable_id(&names.join("\n"))
+}
+
+/// The banner and the script driving it, for the end of `<body>`. It stays
+/// hidden until the script finds no saved choice.
+pub fn banner_html(config: &ConsentConfig) -> String {
+    let mut html = format!(
+        "<div class=\"zap-consent\" data-zap-consent=\"{}\" data-zap-permanent=\"consent\" role=\"region\" aria-label=\"Cookie consent\" hidden>\n",
+        version(config)
+    );
+    html.push_str(STYLE);
+    html.push_str(&format!("<p>{}", text(&config.text)));
+    for link in &config.links {
+        html.push_str(&format!(" <a href=\"{}\">{}</a>", attr(&link.link), text(&link.text)));
+    }
+    html.push_str("</p>\n");
+
+    // A single category is what Accept and Decline are about, more get a
+    // box each
+    let choose = config.categories.len() > 1;
+    for category in &config.categories {
+        let input = format!(
+            "<input type=\"checkbox\" name=\"zap-consent\" value=\"{}\"",
+            attr(&category.name)
+        );
+        match choose {
+            true => {
+                html.push_str(&format!("<p><label>{}> {}</label>", input, text(&category.label)));
+                if let Some(description) = &category.description {
+                    html.push_str(&format!("<br><small>{}</small>", text(description)));
+                }
+                html.push_str("</p>\n");
+            }
+            false => html.push_str(&format!("{} hidden>\n", input)),
+        }
+    }
+
+    html.push_str(&format!(
+        "<p><button type=\"button\" data-zap-consent-reject>{}</button>",
+        text(&config.reject)
+    ));
+    if choose {
+        html.push_str(&format!(" <button type=\"button\" data-zap-consent-save>{}</button>", text(&config.save)));
+    }
+    html.push_str(&format!(
+        " <button type=\"button\" data-zap-consent-accept>{}</button></p>\n</div>\n",
+        text(&config.accept)
+    ));
+    html.push_str(&format!("<script src=\"/{}\" defer></script>\n", SCRIPT_FILE));
+    html
+}
+
+const STYLE: &str = "<style>.zap-consent{position:fixed;z-index:1000;inset:auto 1rem 1rem;max-width:32rem;margin-inline:auto;padding:1rem 1.25rem;background:#fff;color:#222;border:1px solid #ddd;border-radius:8px;box-shadow:0 4px 24px rgba(0,0,0,.15);font-size:.9rem}.zap-consent[hidden]{display:none}.zap-consent p{margin:0 0 .75rem}.zap-consent p:last-child{margin:0;display:flex;gap:.5rem;justify-content:flex-end}</style>\n";
+
+/// Any element with `data-zap-consent-open`, like a footer link, shows the
+/// banner again so visitors can change their mind
+pub const SCRIPT: &str = r#"(() => {
+  const KEY = 'zap-consent';
+  const banner = document.querySelector('[data-zap-consent]');
+  if (!banner) return;
+  const version = banner.dataset.zapConsent;
+  const boxes = () => [...banner.querySelectorAll('input[name="zap-consent"]')];
+
+  const saved = () => {
+    try {
+      const choice = JSON.parse(localStorage.getItem(KEY));
+      return choice && choice.version === version ? choice.granted : null;
+    } catch (e) {
+      return null;
+    }
+  };
+
+  const activate = (granted) => {
+    for (const placeholder of document.querySelectorAll('script[type="text/plain"][data-consent]')) {
+      if (!granted.includes(placeholder.dataset.consent)) continue;
+      const script = document.createElement('script');
+      // Keeps `data-consent`, telling instant navigation it isn't the page's
+      for (const { name, value } of placeholder.attributes) {
+        if (name !== 'type') script.setAttribute(name, value);
+      }
+      script.text = placeholder.text;
+      placeholder.replaceWith(script);
+    }
+  };
+
+  const save = (granted) => {
+    const before = saved() || [];
+    try {
+      localStorage.setItem(KEY, JSON.stringify({ version, granted }));
+    } catch (e) {}
+    banner.hidden = true;
+    // Scripts already running can't be stopped, a reload leaves them out
+    if (before.some((category) => !granted.includes(category))) location.reload();
+    else activate(granted);
+  };
+
+  banner.querySelector('[data-zap-consent-accept]').onclick = () => save(boxes().map((box) => box.value));
+  banner.querySelector('[data-zap-consent-reject]').onclick = () => save([]);
+  const choose = banner.querySelector('[data-zap-consent-save]');
+  if (choose) choose.onclick = () => save(boxes().filter((box) => box.checked).map((box) => box.value));
+
+  document.addEventListener('click', (event) => {
+    if (!event.target.closest('[data-zap-consent-open]')) return;
+    event.preventDefault();
+    const granted = saved() || [];
+    for (const box of boxes()) box.checked = granted.includes(box.value);
+    banner.hidden = false;
+  });
+
+  const granted = saved();
+  if (granted) activate(granted);
+  else banner.hidden = false;
+  // Pages swapped in by instant navigation bring their own placeholders
+  document.addEventListener('zap:navigate', () => activate(saved() || []));
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConsentCategory, Link};
+
+    #[test]
+    fn test_banner() {
+        assert_eq!(
+            gate("<script async src=\"https://example.com/a.js\"></script>\n<script>a()</script>", "analytics"),
+            "<script type=\"text/plain\" data-consent=\"analytics\" async src=\"https://example.com/a.js\"></script>\n\
+             <script type=\"text/plain\" data-consent=\"analytics\">a()</script>"
+        );
+
+        let mut config = ConsentConfig {
+            links: vec![Link {
+                text: "Privacy".to_string(),
+                link: "/privacy/".to_string(),
+            }],
+            ..Default::default()
+        };
+        let single = banner_html(&config);
+        assert!(single.contains("Is that OK? <a href=\"/privacy/\">Privacy</a></p>"));
+        assert!(single.contains("<input type=\"checkbox\" name=\"zap-consent\" value=\"analytics\" hidden>"));
+        assert!(!single.contains("data-zap-consent-save"));
+
+        config.categories.push(ConsentCategory {
+            name: "video".to_string(),
+            label: "Embedded videos".to_string(),
+            description: Some("YouTube sets cookies".to_string()),
+        });
+        let several = banner_html(&config);
+        assert!(several.contains("<label><input type=\"checkbox\" name=\"zap-consent\" value=\"video\"> Embedded videos</label><br><small>YouTube sets cookies</small>"));
+        assert!(several.contains("data-zap-consent-save"));
+        // Asked again when the categories change
+        assert_ne!(version(&config), version(&ConsentConfig::default()));
+    }
+}
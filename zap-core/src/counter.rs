@@ -0,0 +1,195 @@
+// GoatCounter-style visit counting. Pages embed the counting script and a
+// noscript pixel, and view counts are fetched from the public counter API at
+// build time so templates can show them without a request from the visitor.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// URL of the noscript tracking pixel for a page
+pub fn pixel_url(endpoint: &str, path: &str) -> String {
+    format!("{}?p={}", endpoint, encode(path))
+}
+
+/// Fetch the view count for a page. Requires "Allow adding visitor counts on
+/// your website" in the GoatCounter settings. Pages without any visits yet
+/// count as "0".
+pub fn fetch_views(endpoint: &str, path: &str) -> Result<String, String> {
+    let site = endpoint.trim_end_matches('/').trim_end_matches("/count");
+    let url = format!("{}/counter/{}.json", site, encode(path));
+
+    let mut response = match ureq::get(&url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(404)) => return Ok("0".to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut body = String::new();
+    response
+        .body_mut()
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| e.to_string())?;
+
+    #[derive(Deserialize)]
+    struct Count {
+        count: String,
+    }
+    let count: Count = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    Ok(count.count.trim().to_string())
+}
+
+// Percent-encode everything but unreserved characters, `/` included
+fn encode(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedViews {
+    count: String,
+    fetched_at: u64,
+}
+
+/// View counts from previous builds, so rebuilding doesn't hit the counter
+/// API for every page every time
+#[derive(Debug)]
+pub struct ViewCache {
+    path: PathBuf,
+    ttl_secs: u64,
+    entries: HashMap<String, CachedViews>,
+}
+
+impl ViewCache {
+    /// Load the cache for an endpoint from the project's cache directory
+    pub fn load(cache_dir: &Path, endpoint: &str, ttl_minutes: u64) -> Self {
+        let path = cache_dir.join(format!("views-{}.json", crate::anchors::stable_id(endpoint)));
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            ttl_secs: ttl_minutes * 60,
+            entries,
+        }
+    }
+
+    /// A cached count that hasn't expired yet
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.entries
+            .get(path)
+            .filter(|cached| now().saturating_sub(cached.fetched_at) < self.ttl_secs)
+            .map(|cached| cached.count.as_str())
+    }
+
+    /// A cached count regardless of age, used when fetching fails
+    pub fn get_stale(&self, path: &str) -> Option<&str> {
+        self.entries.get(path).map(|cached| cached.count.as_str())
+    }
+
+    pub fn insert(&mut self, path: &str, count: String) {
+        self.entries.insert(
+            path.to_string(),
+            CachedViews {
+                count,
+                fetched_at: now(),
+            },
+        );
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(&self.entries).map_err(std::io::Error::from)?;
+        std::fs::write(&self.path, json)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+
+    // Answers one request per response on a local port, returning each
+    // request's path
+    fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/count", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut paths = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                paths.push(line.split_whitespace().nth(1).unwrap_or_default().to_string());
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            paths
+        });
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn test_fetch_views() {
+        let (endpoint, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 18\r\nConnection: close\r\n\r\n{\"count\":\" 1 234\"}",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        assert_eq!(fetch_views(&endpoint, "/docs/intro/").unwrap(), "1 234");
+        // No visits yet
+        assert_eq!(fetch_views(&endpoint, "/new/").unwrap(), "0");
+        assert!(fetch_views(&endpoint, "/").is_err());
+        assert_eq!(server.join().unwrap(), ["/counter/%2Fdocs%2Fintro%2F.json", "/counter/%2Fnew%2F.json", "/counter/%2F.json"]);
+
+        assert_eq!(pixel_url("https://zap.goatcounter.com/count", "/a b/"), "https://zap.goatcounter.com/count?p=%2Fa%20b%2F");
+    }
+
+    #[test]
+    fn test_view_cache() {
+        let dir = std::env::temp_dir().join(format!("zap-views-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let endpoint = "https://zap.goatcounter.com/count";
+
+        let mut cache = ViewCache::load(&dir, endpoint, 60);
+        assert_eq!(cache.get("/"), None);
+        cache.insert("/", "12".to_string());
+        assert_eq!(cache.get("/"), Some("12"));
+        cache.save().unwrap();
+
+        // Kept per endpoint between builds
+        let cache = ViewCache::load(&dir, endpoint, 60);
+        assert_eq!(cache.get("/"), Some("12"));
+        assert_eq!(ViewCache::load(&dir, "https://other.goatcounter.com/count", 60).get_stale("/"), None);
+
+        // Expired counts are only used when fetching fails
+        let expired = ViewCache::load(&dir, endpoint, 0);
+        assert_eq!(expired.get("/"), None);
+        assert_eq!(expired.get_stale("/"), Some("12"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
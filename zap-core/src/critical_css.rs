@@ -0,0 +1,413 @@
+use std::collections::HashSet;
+
+// How much of the page body counts as "above the fold"
+const FOLD_BYTES: usize = 8 * 1024;
+
+// Selectors that always apply, regardless of the page markup
+const ALWAYS_KEEP: [&str; 6] = [":root", ":host", "*", "html", "body", "::backdrop"];
+
+/// A `<link rel="stylesheet">` tag found in a page
+#[derive(Debug, Clone)]
+pub struct StylesheetLink {
+    pub tag: String,
+    pub href: String,
+}
+
+/// Find stylesheet links served from the site itself
+pub fn local_stylesheets(html: &str) -> Vec<StylesheetLink> {
+    let mut links = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find("<link") {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start..start + len + 1];
+        rest = &rest[start + len + 1..];
+
+        if !tag.contains("rel=\"stylesheet\"") {
+            continue;
+        }
+        let Some(href) = attribute(tag, "href") else {
+            continue;
+        };
+        if href.starts_with('/') && !href.starts_with("//") {
+            links.push(StylesheetLink {
+                tag: tag.to_string(),
+                href: href.to_string(),
+            });
+        }
+    }
+
+    links
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{}=\"", name);
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+/// Replace a stylesheet link with inlined critical rules, loading the full
+/// stylesheet without blocking render
+pub fn inline_critical_css(html: &str, link: &StylesheetLink, critical: &str) -> String {
+    let deferred = format!(
+        "<style>{}</style>\n<link rel=\"preload\" href=\"{1}\" as=\"style\" onload=\"this.onload=null;this.rel='stylesheet'\">\n<noscript><link rel=\"stylesheet\" href=\"{1}\"></noscript>",
+        critical, link.href
+    );
+    html.replacen(&link.tag, &deferred, 1)
+}
+
+/// Extract the rules from `css` that style markup in the top of `html`
+pub fn extract_critical_css(css: &str, html: &str) -> String {
+    let markup = PageMarkup::above_the_fold(html);
+    let mut out = String::new();
+    for node in parse_css(css) {
+        node.write_critical(&markup, &mut out);
+    }
+    out
+}
+
+struct PageMarkup {
+    tags: HashSet<String>,
+    classes: HashSet<String>,
+    ids: HashSet<String>,
+}
+
+impl PageMarkup {
+    fn above_the_fold(html: &str) -> Self {
+        let body_start = html.find("<body").unwrap_or(0);
+        let mut end = (body_start + FOLD_BYTES).min(html.len());
+        while !html.is_char_boundary(end) {
+            end -= 1;
+        }
+        // The head is included so `html` and the root element's classes count
+        let fold = &html[..end];
+
+        let mut markup = PageMarkup {
+            tags: HashSet::new(),
+            classes: HashSet::new(),
+            ids: HashSet::new(),
+        };
+
+        for tag in fold.split('<').skip(1) {
+            let Some(tag) = tag.split('>').next() else {
+                continue;
+            };
+            if tag.starts_with('/') || tag.starts_with('!') {
+                continue;
+            }
+            let name: String = tag
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect();
+            markup.tags.insert(name.to_lowercase());
+
+            if let Some(classes) = attribute(tag, "class") {
+                markup.classes.extend(classes.split_whitespace().map(str::to_string));
+            }
+            if let Some(id) = attribute(tag, "id") {
+                markup.ids.insert(id.to_string());
+            }
+        }
+
+        markup
+    }
+
+    // A selector matches if every tag, class and id it names is on the page.
+    // Pseudo classes and combinators are ignored, which errs on the side of
+    // keeping rules.
+    fn matches(&self, selector: &str) -> bool {
+        let selector = selector.trim();
+        if ALWAYS_KEEP.contains(&selector) {
+            return true;
+        }
+
+        let mut chars = selector.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' | '#' => {
+                    let name = read_identifier(&mut chars);
+                    let present = if c == '.' {
+                        self.classes.contains(&name)
+                    } else {
+                        self.ids.contains(&name)
+                    };
+                    if !name.is_empty() && !present {
+                        return false;
+                    }
+                }
+                ':' => {
+                    // Skip pseudo classes, including arguments like :not(.x)
+                    read_identifier(&mut chars);
+                    if chars.peek() == Some(&'(') {
+                        let mut depth = 0;
+                        for c in chars.by_ref() {
+                            match c {
+                                '(' => depth += 1,
+                                ')' => {
+                                    depth -= 1;
+                                    if depth == 0 {
+                                        break;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                '[' => {
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let mut name = c.to_string();
+                    name.push_str(&read_identifier(&mut chars));
+                    if !self.tags.contains(&name.to_lowercase()) {
+                        return false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        true
+    }
+}
+
+// Reads a CSS identifier, unescaping `\:` style escapes used by utility classes
+fn read_identifier(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '\\' {
+            chars.next();
+            if let Some(escaped) = chars.next() {
+                name.push(escaped);
+            }
+        } else if c.is_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    name
+}
+
+#[derive(Debug)]
+enum CssNode {
+    // `@layer a, b;`, `@import ...;` and similar
+    Statement(String),
+    Rule { prelude: String, body: String },
+    Block { prelude: String, children: Vec<CssNode> },
+}
+
+impl CssNode {
+    fn write_critical(&self, markup: &PageMarkup, out: &mut String) {
+        match self {
+            // Layer order must be declared before any layered rules
+            CssNode::Statement(statement) => {
+                if statement.starts_with("@layer") {
+                    out.push_str(statement);
+                    out.push(';');
+                }
+            }
+            CssNode::Rule { prelude, body } => {
+                let keep = if prelude.starts_with('@') {
+                    // @property keeps custom property defaults working,
+                    // fonts and animations can wait for the full stylesheet
+                    prelude.starts_with("@property")
+                } else {
+                    prelude.split(',').any(|s| markup.matches(s))
+                };
+                if keep {
+                    out.push_str(&format!("{}{{{}}}", prelude, body));
+                }
+            }
+            CssNode::Block { prelude, children } => {
+                let mut inner = String::new();
+                for child in children {
+                    child.write_critical(markup, &mut inner);
+                }
+                if !inner.is_empty() {
+                    out.push_str(&format!("{}{{{}}}", prelude, inner));
+                }
+            }
+        }
+    }
+}
+
+// At-rules whose block contains further rules rather than declarations
+fn is_grouping_rule(prelude: &str) -> bool {
+    ["@media", "@layer", "@supports", "@container", "@scope"]
+        .iter()
+        .any(|rule| prelude.starts_with(rule))
+}
+
+fn parse_css(css: &str) -> Vec<CssNode> {
+    let css = strip_comments(css);
+    let mut pos = 0;
+    parse_nodes(&css, &mut pos)
+}
+
+fn parse_nodes(css: &str, pos: &mut usize) -> Vec<CssNode> {
+    let mut nodes = Vec::new();
+    let bytes = css.as_bytes();
+    let mut start = *pos;
+    let mut quote: Option<u8> = None;
+
+    while *pos < bytes.len() {
+        let b = bytes[*pos];
+        if let Some(q) = quote {
+            if b == b'\\' {
+                *pos += 1;
+            } else if b == q {
+                quote = None;
+            }
+            *pos += 1;
+            continue;
+        }
+
+        match b {
+            b'"' | b'\'' => quote = Some(b),
+            b';' => {
+                let statement = css[start..*pos].trim();
+                if !statement.is_empty() {
+                    nodes.push(CssNode::Statement(statement.to_string()));
+                }
+                start = *pos + 1;
+            }
+            b'{' => {
+                let prelude = css[start..*pos].trim().to_string();
+                *pos += 1;
+                if is_grouping_rule(&prelude) {
+                    let children = parse_nodes(css, pos);
+                    nodes.push(CssNode::Block { prelude, children });
+                } else {
+                    let body_start = *pos;
+                    skip_block(bytes, pos);
+                    let body = css[body_start..*pos].to_string();
+                    nodes.push(CssNode::Rule { prelude, body });
+                }
+                start = *pos + 1;
+            }
+            // End of the enclosing block, left for the caller to step over
+            b'}' => return nodes,
+            _ => {}
+        }
+        *pos += 1;
+    }
+
+    nodes
+}
+
+// Advance to the `}` closing the current block, leaving `pos` on it
+fn skip_block(bytes: &[u8], pos: &mut usize) {
+    let mut depth = 1;
+    let mut quote: Option<u8> = None;
+
+    while *pos < bytes.len() {
+        let b = bytes[*pos];
+        if let Some(q) = quote {
+            if b == b'\\' {
+                *pos += 1;
+            } else if b == q {
+                quote = None;
+            }
+        } else {
+            match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+        *pos += 1;
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<html class=\"dark\"><head><link rel=\"stylesheet\" href=\"/style.css\"></head><body><nav id=\"top\" class=\"nav md:flex\"><a href=\"/\">Home</a></nav>";
+
+    #[test]
+    fn test_selectors() {
+        let markup = PageMarkup::above_the_fold(PAGE);
+        for selector in ["nav", ".nav", "#top", "nav.nav > a", ".md\\:flex", "html.dark body", "a:hover", ".nav:not(.open)", "a[href]", "*"] {
+            assert!(markup.matches(selector), "{}", selector);
+        }
+        for selector in ["footer", ".sidebar", "#bottom", "nav .missing", ".md\\:grid"] {
+            assert!(!markup.matches(selector), "{}", selector);
+        }
+
+        // Only markup within the fold counts
+        let page = format!("<body><div class=\"{}\"></div><footer class=\"late\"></footer>", "x".repeat(FOLD_BYTES));
+        let markup = PageMarkup::above_the_fold(&page);
+        assert!(markup.matches("div") && !markup.matches("footer") && !markup.matches(".late"));
+    }
+
+    #[test]
+    fn test_extract_critical_css() {
+        let css = "@layer base, components;\n\
+            @import url(\"/fonts.css\");\n\
+            /* nav { color: blue } */\n\
+            @font-face { font-family: Inter; src: url(/inter.woff2); }\n\
+            @property --x { syntax: '<length>'; inherits: false; initial-value: 0px; }\n\
+            @keyframes spin { from { rotate: 0deg } to { rotate: 360deg } }\n\
+            nav a::after { content: \"}\"; }\n\
+            .sidebar { content: '{'; }\n\
+            @media (min-width: 768px) { .nav { display: flex } .sidebar { display: block } }\n\
+            @media print { footer { display: none } }\n\
+            @layer base { html { color: black } }";
+        assert_eq!(
+            extract_critical_css(css, PAGE),
+            "@layer base, components;\
+            @property --x{ syntax: '<length>'; inherits: false; initial-value: 0px; }\
+            nav a::after{ content: \"}\"; }\
+            @media (min-width: 768px){.nav{ display: flex }}\
+            @layer base{html{ color: black }}"
+        );
+
+        // An unterminated comment hides the rest of the stylesheet
+        assert_eq!(extract_critical_css("nav { color: red } /* footer { color: blue }", PAGE), "nav{ color: red }");
+    }
+
+    #[test]
+    fn test_inline_critical_css() {
+        let links = local_stylesheets("<link rel=\"stylesheet\" href=\"/style.css\"><link rel=\"stylesheet\" href=\"//cdn.example.com/x.css\"><link rel=\"icon\" href=\"/favicon.ico\">");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].href, "/style.css");
+
+        let html = inline_critical_css(PAGE, &links[0], "nav{color:red}");
+        assert!(html.contains("<style>nav{color:red}</style>\n<link rel=\"preload\" href=\"/style.css\" as=\"style\""));
+        assert!(html.contains("<noscript><link rel=\"stylesheet\" href=\"/style.css\"></noscript>"));
+    }
+}
@@ -0,0 +1,235 @@
+// Front matter dates. Pages are written by hand, so several formats are
+// accepted. Dates without an offset are in the site's timezone, and
+// everything is normalized to UTC.
+
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Serializer};
+
+// Local date and time formats, tried in order after RFC 3339
+const DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+const DATE_FORMATS: [&str; 7] = [
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%d %B %Y",
+    "%d %b %Y",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateError {
+    /// Day and month could be either way round, e.g. `03/04/2024`
+    Ambiguous(String),
+    Invalid(String),
+    UnknownTimezone(String),
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateError::Ambiguous(date) => write!(
+                f,
+                "\"{}\" is ambiguous, it could be day/month or month/day. Write it as YYYY-MM-DD",
+                date
+            ),
+            DateError::Invalid(date) => write!(
+                f,
+                "\"{}\" is not a date zap understands. Use YYYY-MM-DD or RFC 3339, e.g. 2024-05-01T10:00:00+02:00",
+                date
+            ),
+            DateError::UnknownTimezone(name) => write!(
+                f,
+                "Unknown timezone \"{}\", use a name from the tz database such as \"Europe/Madrid\"",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
+/// A page's date in UTC. Templates get it as `iso` (RFC 3339), `date`
+/// (`YYYY-MM-DD` in the site's timezone) and `timestamp` (Unix seconds,
+/// for sorting).
+#[derive(Debug, Clone, Copy)]
+pub struct PageDate {
+    pub utc: DateTime<Utc>,
+    tz: Tz,
+}
+
+impl PageDate {
+    pub fn timestamp(&self) -> i64 {
+        self.utc.timestamp()
+    }
+
+    /// `YYYY-MM-DD` in the site's timezone
+    pub fn date(&self) -> String {
+        self.utc.with_timezone(&self.tz).format("%Y-%m-%d").to_string()
+    }
+
+    pub fn iso(&self) -> String {
+        self.utc.to_rfc3339()
+    }
+}
+
+// Dates compare by instant, whatever timezone they were written in
+impl PartialEq for PageDate {
+    fn eq(&self, other: &Self) -> bool {
+        self.utc == other.utc
+    }
+}
+
+impl Eq for PageDate {}
+
+impl PartialOrd for PageDate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PageDate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.utc.cmp(&other.utc)
+    }
+}
+
+impl fmt::Display for PageDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.iso())
+    }
+}
+
+impl Serialize for PageDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Fields {
+            iso: String,
+            date: String,
+            timestamp: i64,
+        }
+
+        Fields {
+            iso: self.iso(),
+            date: self.date(),
+            timestamp: self.timestamp(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A timezone name from the tz database, e.g. `America/New_York` or `UTC`
+pub fn parse_timezone(name: &str) -> Result<Tz, DateError> {
+    name.parse()
+        .map_err(|_| DateError::UnknownTimezone(name.to_string()))
+}
+
+pub fn parse_date(text: &str, tz: Tz) -> Result<PageDate, DateError> {
+    let text = text.trim();
+    let page_date = |utc: DateTime<Utc>| PageDate { utc, tz };
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .or_else(|_| DateTime::parse_from_rfc2822(text))
+    {
+        return Ok(page_date(datetime.with_timezone(&Utc)));
+    }
+    if let Some(datetime) = text.strip_suffix('Z').and_then(|text| parse_local(text, Tz::UTC)) {
+        return Ok(page_date(datetime));
+    }
+    if let Some(datetime) = parse_local(text, tz) {
+        return Ok(page_date(datetime));
+    }
+
+    let date = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok());
+    let date = match date {
+        Some(date) => date,
+        None => day_month_date(text)?,
+    };
+    local_to_utc(date.and_hms_opt(0, 0, 0).expect("midnight exists"), tz)
+        .map(page_date)
+        .ok_or_else(|| DateError::Invalid(text.to_string()))
+}
+
+fn parse_local(text: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    let naive = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())?;
+    local_to_utc(naive, tz)
+}
+
+// Times skipped by a daylight saving change don't exist. Repeated ones take
+// the earlier of the two.
+fn local_to_utc(naive: NaiveDateTime, tz: Tz) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+// `25/12/2024` and `12/25/2024` can only be read one way. `03/04/2024`
+// could be either, so it's an error rather than a guess.
+fn day_month_date(text: &str) -> Result<NaiveDate, DateError> {
+    let invalid = || DateError::Invalid(text.to_string());
+    let parts: Vec<&str> = text.split(['/', '.', '-']).collect();
+    let [first, second, year] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    if year.len() != 4 {
+        return Err(invalid());
+    }
+
+    let number = |part: &str| part.parse::<u32>().map_err(|_| invalid());
+    let (first, second, year) = (number(first)?, number(second)?, number(year)? as i32);
+
+    let (day, month) = match (first > 12, second > 12) {
+        (true, false) => (first, second),
+        (false, true) => (second, first),
+        (false, false) if first == second => (first, second),
+        (false, false) => return Err(DateError::Ambiguous(text.to_string())),
+        (true, true) => return Err(invalid()),
+    };
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iso(text: &str, tz: &str) -> Result<String, DateError> {
+        parse_date(text, parse_timezone(tz)?).map(|date| date.iso())
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(iso("2024-05-01", "UTC").unwrap(), "2024-05-01T00:00:00+00:00");
+        assert_eq!(iso("2024-05-01", "Europe/Madrid").unwrap(), "2024-04-30T22:00:00+00:00");
+        assert_eq!(iso("2024-05-01T10:00:00+02:00", "America/New_York").unwrap(), "2024-05-01T08:00:00+00:00");
+        assert_eq!(iso("2024-05-01 10:00", "America/New_York").unwrap(), "2024-05-01T14:00:00+00:00");
+        assert_eq!(iso("2024-05-01T10:00:00Z", "Europe/Madrid").unwrap(), "2024-05-01T10:00:00+00:00");
+        assert_eq!(iso("May 1, 2024", "UTC").unwrap(), "2024-05-01T00:00:00+00:00");
+        assert_eq!(iso("25/12/2024", "UTC").unwrap(), "2024-12-25T00:00:00+00:00");
+
+        assert!(matches!(iso("03/04/2024", "UTC"), Err(DateError::Ambiguous(_))));
+        assert!(matches!(iso("next tuesday", "UTC"), Err(DateError::Invalid(_))));
+        assert!(matches!(iso("2024-05-01", "Mars/Olympus"), Err(DateError::UnknownTimezone(_))));
+    }
+
+    #[test]
+    fn test_local_date() {
+        let tz = parse_timezone("Asia/Tokyo").unwrap();
+        let date = parse_date("2024-05-01", tz).unwrap();
+        assert_eq!(date.iso(), "2024-04-30T15:00:00+00:00");
+        assert_eq!(date.date(), "2024-05-01");
+    }
+}
@@ -0,0 +1,257 @@
+// Problems found while building, and where they are. A `Diagnostic` names
+// the file, line and column when they're known and quotes the line, so
+// errors in a page's front matter, a theme template or zap.toml point at
+// what to fix. Also checks for problems with how the site fits together
+// that don't stop it building: pages that would overwrite each other's
+// output, like `guides.md` and `guides/index.md`, and pages nothing leads
+// to, which readers can only find by guessing their URL.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// 1-based
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based, in characters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The line of the file the problem is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_line: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            file: None,
+            line: None,
+            column: None,
+            source_line: None,
+            help: None,
+        }
+    }
+
+    pub fn in_file<P: Into<PathBuf>>(mut self, file: P) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// At byte `offset` of `source`, the file's content
+    pub fn at_offset(self, source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..offset].matches('\n').count() + 1;
+        self.at_line(source, line, Some(source[line_start..offset].chars().count() + 1))
+    }
+
+    /// At a 1-based `line` of `source`, and `column` in it when known
+    pub fn at_line(mut self, source: &str, line: usize, column: Option<usize>) -> Self {
+        self.line = Some(line);
+        self.column = column;
+        self.source_line = source.lines().nth(line.saturating_sub(1)).map(|text| text.trim_end().to_string());
+        self
+    }
+
+    /// At the first `needle` in `file`, or anywhere in it when it isn't
+    /// there or the file can't be read
+    pub fn find_in(self, file: &Path, needle: &str) -> Self {
+        let located = std::fs::read_to_string(file)
+            .ok()
+            .and_then(|source| source.find(needle).map(|offset| self.clone().at_offset(&source, offset)));
+        located.unwrap_or(self).in_file(file)
+    }
+
+    pub fn help<S: Into<String>>(mut self, help: S) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// `file:line:column: message`, for lists of problems on one line each
+    pub fn summary(&self) -> String {
+        match self.location() {
+            Some(location) => format!("{}: {}", location, self.message),
+            None => self.message.clone(),
+        }
+    }
+
+    fn location(&self) -> Option<String> {
+        let file = self.file.as_ref()?.display();
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file.to_string(),
+        })
+    }
+
+    /// The lines after the message: where it is, the line quoted and the
+    /// help, or nothing when none are known
+    pub fn context(&self) -> String {
+        let mut lines = Vec::new();
+        let gutter = " ".repeat(self.line.map_or(1, |line| line.to_string().len()));
+        if let Some(location) = self.location() {
+            lines.push(format!("{}--> {}", gutter, location));
+        }
+        if let (Some(line), Some(text)) = (self.line, &self.source_line) {
+            lines.push(format!("{} |", gutter));
+            lines.push(format!("{} | {}", line, text));
+            if let Some(column) = self.column {
+                lines.push(format!("{} | {}^", gutter, " ".repeat(column.saturating_sub(1))));
+            }
+        }
+        if let Some(help) = &self.help {
+            lines.push(format!("{} = help: {}", gutter, help));
+        }
+        lines.join("\n")
+    }
+}
+
+// Like rustc's, the quoted line with a caret under the column:
+//
+//     error: expected `=`
+//      --> site/guides/setup.md:3:7
+//       |
+//     3 | title "Setup"
+//       |       ^
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", severity, self.message)?;
+        match self.context().as_str() {
+            "" => Ok(()),
+            context => write!(f, "\n{}", context),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Output files written by more than one source, each with its sources in
+/// the order given. `outputs` pairs each output path with a description of
+/// what writes it, such as the page's source file.
+pub fn duplicate_outputs(outputs: &[(PathBuf, String)]) -> Vec<(PathBuf, Vec<String>)> {
+    let mut by_output: BTreeMap<&PathBuf, Vec<String>> = BTreeMap::new();
+    for (output, source) in outputs {
+        let sources = by_output.entry(output).or_default();
+        if !sources.contains(source) {
+            sources.push(source.clone());
+        }
+    }
+    by_output
+        .into_iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(output, sources)| (output.clone(), sources))
+        .collect()
+}
+
+/// The `pages` that can't be reached by following `links`, pairs of the
+/// URL linking and the URL linked to, from any of `roots`
+pub fn unreachable(pages: &[String], roots: &[String], links: &[(String, String)]) -> Vec<String> {
+    let mut leads_to: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, to) in links {
+        leads_to.entry(url_key(from)).or_default().push(url_key(to));
+    }
+
+    let mut reached: HashSet<String> = HashSet::new();
+    let mut queue: Vec<String> = roots.iter().map(|root| url_key(root)).collect();
+    while let Some(url) = queue.pop() {
+        if !reached.insert(url.clone()) {
+            continue;
+        }
+        queue.extend(leads_to.get(&url).into_iter().flatten().filter(|next| !reached.contains(*next)).cloned());
+    }
+
+    pages.iter().filter(|page| !reached.contains(&url_key(page))).cloned().collect()
+}
+
+// `/guides/`, `/guides` and `guides/index.html` are the same page
+fn url_key(url: &str) -> String {
+    url.split(['#', '?'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches("index.html")
+        .trim_matches('/')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic() {
+        let source = "+++\ntitle \"Setup\"\n+++\n";
+        let diagnostic = Diagnostic::error("expected `=`")
+            .in_file("site/setup.md")
+            .at_offset(source, source.find('"').unwrap())
+            .help("front matter is TOML");
+        assert_eq!(diagnostic.summary(), "site/setup.md:2:7: expected `=`");
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected `=`\n --> site/setup.md:2:7\n  |\n2 | title \"Setup\"\n  |       ^\n  = help: front matter is TOML"
+        );
+        assert_eq!(Diagnostic::warning("no theme").to_string(), "warning: no theme");
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let outputs = vec![
+            (PathBuf::from("guides/index.html"), "guides.md".to_string()),
+            (PathBuf::from("about/index.html"), "about.md".to_string()),
+            (PathBuf::from("guides/index.html"), "guides/index.md".to_string()),
+        ];
+        assert_eq!(
+            duplicate_outputs(&outputs),
+            [(PathBuf::from("guides/index.html"), vec!["guides.md".to_string(), "guides/index.md".to_string()])]
+        );
+
+        let urls = |urls: &[&str]| urls.iter().map(|url| url.to_string()).collect::<Vec<_>>();
+        let link = |from: &str, to: &str| (from.to_string(), to.to_string());
+        let links = vec![
+            link("/", "/guides/"),
+            link("/guides/", "/guides/install/"),
+            link("/guides/install/", "/faq/#linux"),
+            link("/old/", "/older/"),
+        ];
+        assert_eq!(
+            unreachable(
+                &urls(&["/", "/guides/", "/guides/install/", "/faq/", "/about/", "/old/", "/older/"]),
+                &urls(&["/", "/about"]),
+                &links,
+            ),
+            ["/old/", "/older/"]
+        );
+    }
+}
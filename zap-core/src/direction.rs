@@ -0,0 +1,57 @@
+// Text direction for the `dir` attribute, so sites in Arabic, Hebrew and
+// other right-to-left languages lay out correctly.
+
+use serde::{Deserialize, Serialize};
+
+// Languages written right to left, by primary subtag
+const RTL_LANGUAGES: [&str; 14] = [
+    "ar", "arc", "ckb", "dv", "fa", "he", "iw", "nqo", "ps", "sd", "syr", "ug", "ur", "yi",
+];
+
+// Scripts written right to left, for tags like `pa-Arab`
+const RTL_SCRIPTS: [&str; 6] = ["arab", "hebr", "nkoo", "syrc", "thaa", "adlm"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// The direction a language is written in, from its BCP 47 tag
+    pub fn of_language(language: &str) -> Self {
+        let mut subtags = language.split(['-', '_']).map(|subtag| subtag.to_lowercase());
+        let primary = subtags.next().unwrap_or_default();
+        // A script subtag is the only one with four letters
+        let script = subtags.find(|subtag| subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic()));
+
+        let rtl = match script {
+            Some(script) => RTL_SCRIPTS.contains(&script.as_str()),
+            None => RTL_LANGUAGES.contains(&primary.as_str()),
+        };
+        if rtl { Direction::Rtl } else { Direction::Ltr }
+    }
+
+    /// An explicit direction, or the language's
+    pub fn resolve(dir: Option<Direction>, language: Option<&str>) -> Self {
+        dir.or_else(|| language.map(Direction::of_language)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_language() {
+        assert_eq!(Direction::of_language("ar"), Direction::Rtl);
+        assert_eq!(Direction::of_language("he-IL"), Direction::Rtl);
+        assert_eq!(Direction::of_language("pa-Arab-PK"), Direction::Rtl);
+        assert_eq!(Direction::of_language("az-Latn"), Direction::Ltr);
+        assert_eq!(Direction::of_language("en"), Direction::Ltr);
+        assert_eq!(Direction::resolve(Some(Direction::Ltr), Some("ar")), Direction::Ltr);
+        assert_eq!(Direction::resolve(None, None), Direction::Ltr);
+    }
+}
@@ -0,0 +1,218 @@
+// Checks behind `zap doctor`. Each one looks for a single kind of problem
+// that would otherwise surface as a confusing build or serve failure, and
+// says how to fix it.
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::config::Config;
+use crate::renderer::Renderer;
+use crate::theme::ThemeManifest;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// Templates the builder renders pages with. The fallbacks still work, but
+// pages lose their layout.
+const PAGE_TEMPLATES: [&str; 3] = ["home.html", "doc.html", "page.html"];
+
+/// A problem found in the project or environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    fn new(problem: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            problem: problem.into(),
+            fix: fix.into(),
+        }
+    }
+}
+
+pub fn check_config(config_path: &Path) -> Vec<Finding> {
+    if !config_path.exists() {
+        return vec![Finding::new(
+            format!("{} does not exist, defaults are used", config_path.display()),
+            "Run `zap init` to create one",
+        )];
+    }
+
+    let config = match Config::read(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![Finding::new(
+                format!("{} is invalid: {}", config_path.display(), e),
+                "Fix the reported line, see example-zap.toml for every supported key",
+            )];
+        }
+    };
+
+    // The CLI's own sections aren't part of the site config
+    let source = std::fs::read_to_string(config_path).unwrap_or_default();
+    let known = serde_json::to_value(&config).unwrap_or_default();
+    let mut findings: Vec<Finding> = crate::validate::unknown_keys(&source, &known, &["build", "hooks", "serve"])
+        .into_iter()
+        .map(|issue| {
+            Finding::new(
+                format!("{}: {}", config_path.display(), issue),
+                "Fix or remove the key, see example-zap.toml for every supported key",
+            )
+        })
+        .collect();
+    findings.extend(crate::validate::check_values(&config).into_iter().map(|problem| {
+        Finding::new(format!("{}: {}", config_path.display(), problem), "Fix the value")
+    }));
+    findings
+}
+
+pub fn check_theme(theme_dir: &Path, source_dir: &Path) -> Vec<Finding> {
+    if !theme_dir.is_dir() {
+        return vec![Finding::new(
+            format!("Theme directory {} does not exist", theme_dir.display()),
+            "Pass --theme with the path to a theme, or copy the default theme there",
+        )];
+    }
+
+    let mut findings = Vec::new();
+    match ThemeManifest::read(theme_dir) {
+        Ok(manifest) => findings.extend(manifest.compatibility_warnings().into_iter().map(|warning| {
+            Finding::new(warning, "Run `zap upgrade` to update the theme")
+        })),
+        Err(e) => findings.push(Finding::new(
+            format!("{} is invalid: {}", theme_dir.join(crate::theme::MANIFEST_FILE).display(), e),
+            "Fix the reported line",
+        )),
+    }
+
+    let renderer = match Renderer::new(theme_dir) {
+        Ok(renderer) => renderer,
+        Err(e) => {
+            findings.push(Finding::new(
+                format!("Theme templates failed to load: {}", e),
+                "Fix the template syntax error",
+            ));
+            return findings;
+        }
+    };
+
+    let mut required = PAGE_TEMPLATES.to_vec();
+    if source_dir.join("CHANGELOG.md").exists() {
+        required.push("changelog.html");
+    }
+    for template in required {
+        if !renderer.has_template(template) {
+            findings.push(Finding::new(
+                format!("Theme has no {}", template),
+                format!("Add {}, pages using it fall back to a minimal layout", theme_dir.join(template).display()),
+            ));
+        }
+    }
+
+    findings
+}
+
+pub fn check_source(source_dir: &Path) -> Vec<Finding> {
+    if !source_dir.is_dir() {
+        return vec![Finding::new(
+            format!("Source directory {} does not exist", source_dir.display()),
+            "Pass --source with the directory holding your markdown, or run `zap init`",
+        )];
+    }
+
+    let mut findings = Vec::new();
+    if !source_dir.join("README.md").exists() {
+        findings.push(Finding::new(
+            format!("No README.md in {}, the site will have no home page", source_dir.display()),
+            format!("Create {}", source_dir.join("README.md").display()),
+        ));
+    }
+
+    for path in markdown_files(source_dir) {
+        let Ok(bytes) = std::fs::read(&path) else {
+            findings.push(Finding::new(
+                format!("{} can't be read", path.display()),
+                "Check the file's permissions",
+            ));
+            continue;
+        };
+        findings.extend(check_encoding(&path, &bytes));
+    }
+
+    findings
+}
+
+fn markdown_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn check_encoding(path: &Path, bytes: &[u8]) -> Option<Finding> {
+    if let Err(e) = std::str::from_utf8(bytes) {
+        return Some(Finding::new(
+            format!("{} is not valid UTF-8 (byte {})", path.display(), e.valid_up_to()),
+            "Re-save the file as UTF-8. zap reads it as Windows-1252, which may garble some characters",
+        ));
+    }
+    if bytes.starts_with(BOM) {
+        return Some(Finding::new(
+            format!("{} starts with a byte order mark", path.display()),
+            "Re-save the file as UTF-8 without BOM. zap skips it, but other tools may not",
+        ));
+    }
+    None
+}
+
+pub fn check_output(output_dir: &Path) -> Vec<Finding> {
+    // The output directory is created on build, so check the closest
+    // directory that already exists
+    let Some(existing) = output_dir.ancestors().find(|dir| dir.is_dir()) else {
+        return Vec::new();
+    };
+
+    let probe = existing.join(".zap-doctor");
+    let writable = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+
+    if writable {
+        return Vec::new();
+    }
+    vec![Finding::new(
+        format!("{} is not writable", existing.display()),
+        "Fix the directory's permissions or pass --output with a writable directory",
+    )]
+}
+
+pub fn check_port(host: &str, port: u16) -> Vec<Finding> {
+    match TcpListener::bind((host, port)) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![Finding::new(
+            format!("Can't listen on {}:{}: {}", host, port, e),
+            "Stop whatever is using the port, or run `zap serve --port <PORT>`",
+        )],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_encoding() {
+        let path = Path::new("page.md");
+
+        assert_eq!(check_encoding(path, "# Café".as_bytes()), None);
+        assert!(check_encoding(path, b"\xEF\xBB\xBF# Page").unwrap().problem.contains("byte order mark"));
+        assert!(check_encoding(path, b"# Caf\xE9").unwrap().problem.contains("byte 5"));
+    }
+}
@@ -0,0 +1,355 @@
+// A download matrix for the latest release, from a GitHub release's assets or
+// a `downloads.toml` in the source directory. Assets are sorted into a table
+// of operating systems by architecture, going by their file names, with
+// their size and SHA-256 checksum.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::DownloadsConfig;
+
+// Columns and rows in this order, whatever order the assets come in
+const ARCHS: [&str; 5] = ["x86_64", "arm64", "x86", "armv7", "universal"];
+const OSES: [&str; 4] = ["Linux", "macOS", "Windows", "FreeBSD"];
+
+// Files published alongside the downloads to verify them
+const VERIFICATION_EXTENSIONS: [&str; 8] =
+    [".sha256", ".sha256sum", ".sha512", ".sig", ".asc", ".minisig", ".pem", ".intoto.jsonl"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
+    /// In bytes
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+    /// Detected from the name when not given
+    pub os: Option<String>,
+    pub arch: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String,
+    /// The release's page
+    pub url: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Download {
+    pub name: String,
+    pub url: String,
+    /// Human readable, e.g. `4.2 MB`
+    pub size: Option<String>,
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadRow {
+    pub os: String,
+    /// One per architecture in `archs`, empty where there's no build
+    pub cells: Vec<Vec<Download>>,
+}
+
+/// The matrix templates get as `downloads`
+#[derive(Debug, Clone, Serialize)]
+pub struct Downloads {
+    pub version: String,
+    pub url: Option<String>,
+    /// Only architectures some asset is built for
+    pub archs: Vec<String>,
+    pub rows: Vec<DownloadRow>,
+    /// Assets for no particular system, like source archives
+    pub other: Vec<Download>,
+}
+
+/// Read the release from wherever `config` says. A GitHub release that can't
+/// be fetched falls back to the last fetched one, kept in `cache_dir`, with a
+/// warning.
+pub fn load(
+    config: &DownloadsConfig,
+    source_dir: &Path,
+    cache_dir: &Path,
+) -> std::io::Result<(Downloads, Option<String>)> {
+    let (release, warning) = match &config.repo {
+        Some(repo) => github_release(config, repo, cache_dir)?,
+        None => {
+            let path = source_dir.join(&config.file);
+            let text = crate::encoding::read_to_string(&path)?;
+            let release: Release = toml::from_str(&text).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+            })?;
+            (release, None)
+        }
+    };
+    Ok((matrix(release), warning))
+}
+
+/// Sort a release's assets into the matrix
+pub fn matrix(release: Release) -> Downloads {
+    let mut systems: HashMap<(String, String), Vec<Download>> = HashMap::new();
+    let mut other = Vec::new();
+    for asset in release.assets.into_iter().filter(|asset| !is_verification(&asset.name)) {
+        let os = asset.os.clone().or_else(|| detect_os(&asset.name).map(str::to_string));
+        let arch = asset.arch.clone().or_else(|| detect_arch(&asset.name).map(str::to_string));
+        let download = Download {
+            size: asset.size.map(size),
+            name: asset.name,
+            url: asset.url,
+            sha256: asset.sha256,
+        };
+        match os {
+            Some(os) => {
+                // A build that doesn't say is almost always x86_64
+                let arch = arch.unwrap_or_else(|| ARCHS[0].to_string());
+                systems.entry((os, arch)).or_default().push(download);
+            }
+            None => other.push(download),
+        }
+    }
+
+    let mut archs: Vec<String> = systems.keys().map(|(_, arch)| arch.clone()).collect();
+    archs.sort_by_key(|arch| (rank(&ARCHS, arch), arch.clone()));
+    archs.dedup();
+    let mut oses: Vec<String> = systems.keys().map(|(os, _)| os.clone()).collect();
+    oses.sort_by_key(|os| (rank(&OSES, os), os.clone()));
+    oses.dedup();
+
+    let rows = oses
+        .into_iter()
+        .map(|os| DownloadRow {
+            cells: archs
+                .iter()
+                .map(|arch| systems.remove(&(os.clone(), arch.clone())).unwrap_or_default())
+                .collect(),
+            os,
+        })
+        .collect();
+
+    Downloads {
+        version: release.version,
+        url: release.url,
+        archs,
+        rows,
+        other,
+    }
+}
+
+fn rank(order: &[&str], name: &str) -> usize {
+    order.iter().position(|known| known.eq_ignore_ascii_case(name)).unwrap_or(order.len())
+}
+
+fn is_verification(name: &str) -> bool {
+    let name = name.to_lowercase();
+    VERIFICATION_EXTENSIONS.iter().any(|extension| name.ends_with(extension)) || is_checksums_file(&name)
+}
+
+fn is_checksums_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("sha256sums") || name.contains("checksums")
+}
+
+fn detect_os(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+    // Before Windows, since "darwin" contains "win"
+    if has(&["darwin", "macos", "apple", "osx", ".dmg", ".pkg"]) {
+        Some("macOS")
+    } else if has(&["windows", "win64", "win32", ".exe", ".msi"]) {
+        Some("Windows")
+    } else if has(&["linux", ".deb", ".rpm", ".appimage"]) {
+        Some("Linux")
+    } else if has(&["freebsd"]) {
+        Some("FreeBSD")
+    } else {
+        None
+    }
+}
+
+fn detect_arch(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+    // Before x86, which they contain
+    if has(&["x86_64", "x86-64", "amd64", "x64"]) {
+        Some("x86_64")
+    } else if has(&["aarch64", "arm64"]) {
+        Some("arm64")
+    } else if has(&["i686", "i386", "x86", "_386", "-386"]) {
+        Some("x86")
+    } else if has(&["armv7", "armhf"]) {
+        Some("armv7")
+    } else if has(&["universal"]) {
+        Some("universal")
+    } else {
+        None
+    }
+}
+
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRelease {
+    release: Release,
+    fetched_at: u64,
+}
+
+fn cache_path(cache_dir: &Path, repo: &str, tag: Option<&str>) -> PathBuf {
+    let key = format!("{}/{}", repo, tag.unwrap_or_default());
+    cache_dir.join(format!("downloads-{}.json", crate::anchors::stable_id(&key)))
+}
+
+// The release from the cache while it's fresh, then from the API
+fn github_release(config: &DownloadsConfig, repo: &str, cache_dir: &Path) -> std::io::Result<(Release, Option<String>)> {
+    let path = cache_path(cache_dir, repo, config.tag.as_deref());
+    let cached: Option<CachedRelease> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok());
+    if let Some(cached) = &cached
+        && now().saturating_sub(cached.fetched_at) < config.cache_minutes * 60
+    {
+        return Ok((cached.release.clone(), None));
+    }
+
+    match fetch_release(config, repo) {
+        Ok(release) => {
+            let fresh = CachedRelease {
+                release,
+                fetched_at: now(),
+            };
+            // An unwritable cache only costs a fetch next build
+            if let Ok(json) = serde_json::to_string(&fresh) {
+                let _ = std::fs::create_dir_all(cache_dir).and_then(|_| std::fs::write(&path, json));
+            }
+            Ok((fresh.release, None))
+        }
+        Err(e) => match cached {
+            Some(cached) => Ok((
+                cached.release,
+                Some(format!("Could not fetch the release from GitHub, using the last fetched one: {}", e)),
+            )),
+            None => Err(std::io::Error::other(format!("Could not fetch the release from GitHub: {}", e))),
+        },
+    }
+}
+
+fn fetch_release(config: &DownloadsConfig, repo: &str) -> Result<Release, String> {
+    let (owner, name) = crate::github::parse_repo(repo)
+        .ok_or_else(|| format!("`{}` is not a GitHub repository, use `owner/name`", repo))?;
+    let token = crate::github::token(&config.token_env).ok();
+    let path = match &config.tag {
+        Some(tag) => format!("/repos/{}/{}/releases/tags/{}", owner, name, tag),
+        None => format!("/repos/{}/{}/releases/latest", owner, name),
+    };
+    let json = crate::github::rest(token.as_deref(), &path)?;
+    let mut release = parse_release(&json)?;
+
+    // Older releases have no digests, but often a checksums file
+    if release.assets.iter().any(|asset| asset.sha256.is_none())
+        && let Some(sums) = json["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|asset| asset["name"].as_str().is_some_and(is_checksums_file))
+            .and_then(|asset| asset["browser_download_url"].as_str())
+    {
+        let download = crate::privacy::download(sums)?;
+        let sums = parse_checksums(&String::from_utf8_lossy(&download.bytes));
+        for asset in release.assets.iter_mut().filter(|asset| asset.sha256.is_none()) {
+            asset.sha256 = sums.get(&asset.name).cloned();
+        }
+    }
+    Ok(release)
+}
+
+fn parse_release(json: &serde_json::Value) -> Result<Release, String> {
+    let version = json["tag_name"]
+        .as_str()
+        .ok_or_else(|| json["message"].as_str().unwrap_or("release not found").to_string())?;
+    let assets = json["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|asset| {
+            Some(Asset {
+                name: asset["name"].as_str()?.to_string(),
+                url: asset["browser_download_url"].as_str()?.to_string(),
+                size: asset["size"].as_u64(),
+                sha256: asset["digest"].as_str().and_then(|digest| digest.strip_prefix("sha256:")).map(str::to_string),
+                os: None,
+                arch: None,
+            })
+        })
+        .collect();
+
+    Ok(Release {
+        version: version.to_string(),
+        url: json["html_url"].as_str().map(str::to_string),
+        assets,
+    })
+}
+
+// `sha256sum` output: a hash, then the file name, optionally marked binary
+fn parse_checksums(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+            let name = name.trim_start().trim_start_matches('*');
+            (hash.len() == 64).then(|| (name.to_string(), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let json = serde_json::json!({
+            "tag_name": "v1.2.0",
+            "html_url": "https://github.com/o/r/releases/tag/v1.2.0",
+            "assets": [
+                { "name": "zap-x86_64-unknown-linux-gnu.tar.gz", "browser_download_url": "https://example.com/1", "size": 4404019,
+                  "digest": "sha256:abc" },
+                { "name": "zap-aarch64-apple-darwin.tar.gz", "browser_download_url": "https://example.com/2", "size": 2048 },
+                { "name": "zap-x86_64-pc-windows-msvc.zip", "browser_download_url": "https://example.com/3", "size": 10 },
+                { "name": "zap-x86_64-apple-darwin.tar.gz", "browser_download_url": "https://example.com/4", "size": 10 },
+                { "name": "SHA256SUMS", "browser_download_url": "https://example.com/5", "size": 10 },
+                { "name": "source.tar.gz", "browser_download_url": "https://example.com/6", "size": 10 },
+            ],
+        });
+        let downloads = matrix(parse_release(&json).unwrap());
+
+        assert_eq!(downloads.version, "v1.2.0");
+        assert_eq!(downloads.archs, ["x86_64", "arm64"]);
+        let oses: Vec<&str> = downloads.rows.iter().map(|row| row.os.as_str()).collect();
+        assert_eq!(oses, ["Linux", "macOS", "Windows"]);
+        let linux = &downloads.rows[0].cells[0][0];
+        assert_eq!(linux.size.as_deref(), Some("4.2 MB"));
+        assert_eq!(linux.sha256.as_deref(), Some("abc"));
+        assert!(downloads.rows[0].cells[1].is_empty());
+        assert_eq!(downloads.rows[1].cells[1][0].size.as_deref(), Some("2.0 KB"));
+        assert_eq!(downloads.other[0].name, "source.tar.gz");
+
+        let sums = parse_checksums(&format!("{} *zap.zip\nnot a checksum\n", "a".repeat(64)));
+        assert_eq!(sums.get("zap.zip").map(String::len), Some(64));
+    }
+}
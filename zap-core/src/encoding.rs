@@ -0,0 +1,80 @@
+// Markdown written in Windows editors is often saved with a byte order mark,
+// or as Windows-1252 rather than UTF-8. Both are decoded here so those pages
+// build like any other.
+
+use std::fmt;
+use std::path::Path;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// Windows-1252 characters for bytes 0x80 to 0x9F. The rest of the range
+// matches Latin-1, and so the first 256 code points. The five undefined
+// bytes map to the control characters with the same value.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// How a source file was decoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// Not valid UTF-8, so read as Windows-1252, which every byte is valid in
+    Windows1252,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Windows1252 => write!(f, "Windows-1252"),
+        }
+    }
+}
+
+/// Decode a file's contents, dropping a leading byte order mark
+pub fn decode(bytes: &[u8]) -> (String, Encoding) {
+    let bytes = bytes.strip_prefix(BOM).unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), Encoding::Utf8),
+        Err(_) => (
+            bytes.iter().map(|&byte| windows_1252_char(byte)).collect(),
+            Encoding::Windows1252,
+        ),
+    }
+}
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+pub fn read_text(path: &Path) -> std::io::Result<(String, Encoding)> {
+    Ok(decode(&std::fs::read(path)?))
+}
+
+/// Like `std::fs::read_to_string`, but for files in any encoding `decode`
+/// understands
+pub fn read_to_string(path: impl AsRef<Path>) -> std::io::Result<String> {
+    Ok(read_text(path.as_ref())?.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"\xEF\xBB\xBF+++\ndraft = true\n+++"), ("+++\ndraft = true\n+++".to_string(), Encoding::Utf8));
+        assert_eq!(decode("Café".as_bytes()), ("Café".to_string(), Encoding::Utf8));
+        assert_eq!(
+            decode(b"Caf\xE9 \x93quoted\x94 \x80"),
+            ("Café \u{201C}quoted\u{201D} €".to_string(), Encoding::Windows1252)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::diagnostics::Diagnostic;
+
+/// Progress events emitted while a site is being built.
+///
+/// Consumers (CLI output, JSON reporters, the dev server) subscribe through
+/// an [`EventHandler`] instead of core printing to stdout directly.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BuildEvent {
+    ScanStarted { source_dir: PathBuf },
+    PageRendered {
+        path: PathBuf,
+        template: String,
+        ms: u128,
+        us: u128,
+        timings: PageTimings,
+    },
+    Warning {
+        #[serde(flatten)]
+        diagnostic: Diagnostic,
+    },
+    Finished { stats: BuildStats },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildStats {
+    pub pages: usize,
+    /// Pages skipped by an incremental rebuild because nothing they depend
+    /// on changed
+    pub unchanged: usize,
+    pub collections: usize,
+    pub warnings: usize,
+    pub ms: u128,
+    /// Tera rendering cost per template, slowest first
+    pub templates: Vec<TemplateTiming>,
+}
+
+/// Where a page's render time went. What's left is building the
+/// template's context.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageTimings {
+    /// Parsing markdown and rendering it to HTML, without highlighting
+    pub markdown_us: u128,
+    /// Highlighting code blocks
+    pub highlight_us: u128,
+    pub template_us: u128,
+    /// Post-processing the rendered page and writing it out
+    pub write_us: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateTiming {
+    pub template: String,
+    pub renders: usize,
+    pub us: u128,
+}
+
+/// Callback invoked for every [`BuildEvent`]
+pub type EventHandler = Arc<dyn Fn(&BuildEvent) + Send + Sync>;
+
+/// Handler that discards every event
+pub fn noop_handler() -> EventHandler {
+    Arc::new(|_| {})
+}
@@ -0,0 +1,296 @@
+// Inventory of the external URLs a built site references, for `zap report
+// externals`: what the site loads from third parties, and every outbound
+// link, so a maintainer can audit them and spot misspelled domains.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::privacy::{attribute, external_url, host, tag_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExternalKind {
+    Script,
+    Stylesheet,
+    Font,
+    Image,
+    Media,
+    Frame,
+    /// A page linked to, not loaded by the browser
+    Link,
+}
+
+impl std::fmt::Display for ExternalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternalKind::Script => write!(f, "script"),
+            ExternalKind::Stylesheet => write!(f, "stylesheet"),
+            ExternalKind::Font => write!(f, "font"),
+            ExternalKind::Image => write!(f, "image"),
+            ExternalKind::Media => write!(f, "media"),
+            ExternalKind::Frame => write!(f, "frame"),
+            ExternalKind::Link => write!(f, "link"),
+        }
+    }
+}
+
+/// An external URL and where it's used
+#[derive(Debug, Clone, Default)]
+pub struct ExternalUse {
+    pub kinds: BTreeSet<ExternalKind>,
+    /// Files referencing it, relative to the output directory
+    pub files: BTreeSet<PathBuf>,
+}
+
+/// External URLs by domain
+#[derive(Debug, Default)]
+pub struct Externals {
+    pub domains: BTreeMap<String, BTreeMap<String, ExternalUse>>,
+}
+
+impl Externals {
+    fn add(&mut self, url: String, kind: ExternalKind, file: &Path) {
+        let domain = host(&url).to_lowercase();
+        let entry = self.domains.entry(domain).or_default().entry(url).or_default();
+        entry.kinds.insert(kind);
+        entry.files.insert(file.to_path_buf());
+    }
+
+    /// Pairs of domains one typo apart, such as `github.com` and `githb.com`
+    /// or `gihtub.com`, which are more often a mistake than two different
+    /// sites. Subdomains of the same domain aren't compared.
+    pub fn lookalikes(&self) -> Vec<(&str, &str)> {
+        let domains: Vec<&str> = self.domains.keys().map(|domain| domain.as_str()).collect();
+        let mut pairs = Vec::new();
+        for (i, a) in domains.iter().enumerate() {
+            for b in &domains[i + 1..] {
+                let (a_base, b_base) = (base_domain(a), base_domain(b));
+                if a_base != b_base && typo_distance(a_base, b_base) == 1 {
+                    pairs.push((*a, *b));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Every external URL referenced by the HTML and CSS files in `output_dir`.
+/// Copies self-hosted by privacy mode are local, so they aren't listed.
+pub fn scan(output_dir: &Path) -> std::io::Result<Externals> {
+    let mut externals = Externals::default();
+
+    let mut files: Vec<PathBuf> = WalkDir::new(output_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+
+    for path in files {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        let found = match extension {
+            "html" | "htm" => page_externals(&std::fs::read_to_string(&path)?),
+            "css" => css_externals(&std::fs::read_to_string(&path)?),
+            _ => continue,
+        };
+        let relative = path.strip_prefix(output_dir).unwrap_or(&path);
+        for (url, kind) in found {
+            externals.add(url, kind, relative);
+        }
+    }
+
+    Ok(externals)
+}
+
+/// External URLs in a page, with what references them
+pub fn page_externals(html: &str) -> Vec<(String, ExternalKind)> {
+    let mut found = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            rest = &rest[end..];
+            continue;
+        }
+        let Some(len) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..len + 1];
+        rest = &rest[len + 1..];
+
+        let name = tag_name(tag);
+        if name == "style"
+            && let Some(end) = rest.find("</style>")
+        {
+            found.extend(css_externals(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        let mut add = |attr: &str, kind: ExternalKind| {
+            if let Some(url) = attribute(tag, attr).and_then(|url| external_url(url, None)) {
+                found.push((url, kind));
+            }
+        };
+        match name.as_str() {
+            "script" => {
+                add("src", ExternalKind::Script);
+                // Inline code isn't markup
+                if let Some(end) = rest.find("</script>") {
+                    rest = &rest[end..];
+                }
+            }
+            "link" => add("href", link_kind(tag)),
+            "a" | "area" => add("href", ExternalKind::Link),
+            "form" => add("action", ExternalKind::Link),
+            "img" | "image" => add("src", ExternalKind::Image),
+            "iframe" | "embed" => add("src", ExternalKind::Frame),
+            "object" => add("data", ExternalKind::Frame),
+            "video" => {
+                add("src", ExternalKind::Media);
+                add("poster", ExternalKind::Image);
+            }
+            "audio" | "track" => add("src", ExternalKind::Media),
+            "source" => add("src", ExternalKind::Media),
+            _ => {}
+        }
+        if let Some(srcset) = attribute(tag, "srcset") {
+            for candidate in srcset.split(',') {
+                let url = candidate.split_whitespace().next().unwrap_or_default();
+                if let Some(url) = external_url(url, None) {
+                    found.push((url, ExternalKind::Image));
+                }
+            }
+        }
+        if let Some(style) = attribute(tag, "style") {
+            found.extend(css_externals(style));
+        }
+    }
+
+    found
+}
+
+/// External URLs in a stylesheet's `url(...)`s and `@import`s
+pub fn css_externals(css: &str) -> Vec<(String, ExternalKind)> {
+    let mut found = Vec::new();
+
+    for (start, _) in css.match_indices("url(") {
+        let open = start + "url(".len();
+        let Some(len) = css[open..].find(')') else {
+            break;
+        };
+        let url = css[open..open + len].trim().trim_matches(|c| c == '"' || c == '\'');
+        let in_import = css[..start].trim_end().ends_with("@import");
+        if let Some(url) = external_url(url, None) {
+            let kind = if in_import { ExternalKind::Stylesheet } else { asset_kind(&url) };
+            found.push((url, kind));
+        }
+    }
+    for (start, _) in css.match_indices("@import") {
+        let after = css[start + "@import".len()..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let url = after[1..].split(quote).next().unwrap_or_default();
+        if let Some(url) = external_url(url, None) {
+            found.push((url, ExternalKind::Stylesheet));
+        }
+    }
+
+    found
+}
+
+fn link_kind(tag: &str) -> ExternalKind {
+    let rel = attribute(tag, "rel").unwrap_or_default().to_lowercase();
+    let kind = attribute(tag, "as").unwrap_or_default().to_lowercase();
+    if rel.contains("stylesheet") || kind == "style" {
+        ExternalKind::Stylesheet
+    } else if rel.contains("icon") || kind == "image" {
+        ExternalKind::Image
+    } else if kind == "font" {
+        ExternalKind::Font
+    } else if kind == "script" || rel.contains("modulepreload") {
+        ExternalKind::Script
+    } else {
+        ExternalKind::Link
+    }
+}
+
+fn asset_kind(url: &str) -> ExternalKind {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    let is_font = [".woff2", ".woff", ".ttf", ".otf", ".eot"]
+        .iter()
+        .any(|extension| path.ends_with(extension));
+    if is_font { ExternalKind::Font } else { ExternalKind::Image }
+}
+
+// The last two labels, `example.com` for `cdn.example.com`
+fn base_domain(domain: &str) -> &str {
+    match domain.rmatch_indices('.').nth(1) {
+        Some((dot, _)) => &domain[dot + 1..],
+        None => domain,
+    }
+}
+
+// Edits between two strings, counting swapped neighbours as one
+pub(crate) fn typo_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_externals() {
+        let html = r#"<link rel="stylesheet" href="https://cdn.example.com/app.css">
+<script src="//cdn.example.com/app.js"></script>
+<!-- <img src="https://hidden.example.com/a.png"> -->
+<a href="https://githb.com/zap">Source</a> <a href="/docs/">Docs</a>
+<style>@font-face { src: url("https://fonts.example.com/a.woff2"); }</style>
+<img src="/logo.png" srcset="https://img.example.com/a.png 1x, /b.png 2x">"#;
+        assert_eq!(
+            page_externals(html),
+            vec![
+                ("https://cdn.example.com/app.css".to_string(), ExternalKind::Stylesheet),
+                ("https://cdn.example.com/app.js".to_string(), ExternalKind::Script),
+                ("https://githb.com/zap".to_string(), ExternalKind::Link),
+                ("https://fonts.example.com/a.woff2".to_string(), ExternalKind::Font),
+                ("https://img.example.com/a.png".to_string(), ExternalKind::Image),
+            ]
+        );
+
+        let mut externals = Externals::default();
+        for (url, kind) in page_externals(html) {
+            externals.add(url, kind, Path::new("index.html"));
+        }
+        externals.add("https://www.github.com/".to_string(), ExternalKind::Link, Path::new("index.html"));
+        externals.add("https://gihtub.com/".to_string(), ExternalKind::Link, Path::new("index.html"));
+        assert_eq!(
+            externals.lookalikes(),
+            vec![("gihtub.com", "www.github.com"), ("githb.com", "www.github.com")]
+        );
+    }
+}
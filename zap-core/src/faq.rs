@@ -0,0 +1,309 @@
+// Questions and answers from a GitHub repository, imported by `zap faq` into
+// a collection so the docs keep up with what the community has answered.
+// Answered discussions bring their accepted answer, and issues the first
+// reply from a maintainer. Fetched entries are cached in `.zap-cache/`, and
+// only fetched again with `zap faq --refresh`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::FaqConfig;
+
+const CACHE_FILE: &str = "faq.json";
+
+// First line of the front matter of every imported page, so re-importing
+// knows which pages it may overwrite or remove
+const MARKER: &str = "# Imported from GitHub by `zap faq`. Edits are overwritten on the next import.";
+
+// Replies from these count as answers to an issue
+const MAINTAINERS: [&str; 3] = ["OWNER", "MEMBER", "COLLABORATOR"];
+
+const DISCUSSIONS_QUERY: &str = r#"
+query($owner: String!, $name: String!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    discussions(first: 100, after: $cursor, answered: true) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title url updatedAt
+        labels(first: 20) { nodes { name } }
+        answer { body }
+      }
+    }
+  }
+}
+"#;
+
+const ISSUES_QUERY: &str = r#"
+query($owner: String!, $name: String!, $label: String!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    issues(first: 50, after: $cursor, labels: [$label]) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title url updatedAt
+        comments(first: 100) { nodes { body authorAssociation } }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaqEntry {
+    pub question: String,
+    /// Markdown, as written on GitHub
+    pub answer: String,
+    pub url: String,
+    /// When the question or its answer last changed, as `YYYY-MM-DD`
+    pub updated: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaqCache {
+    pub repo: String,
+    pub label: String,
+    /// Seconds since the Unix epoch
+    pub fetched_at: u64,
+    pub entries: Vec<FaqEntry>,
+}
+
+impl FaqCache {
+    /// The cache from the last fetch, if it was for the same repository and
+    /// label
+    pub fn load(cache_dir: &Path, repo: &str, label: &str) -> Option<Self> {
+        let data = std::fs::read_to_string(cache_dir.join(CACHE_FILE)).ok()?;
+        let cache: Self = serde_json::from_str(&data).ok()?;
+        (cache.repo == repo && cache.label == label).then_some(cache)
+    }
+
+    pub fn save(&self, cache_dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(cache_dir)?;
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        std::fs::write(cache_dir.join(CACHE_FILE), json)
+    }
+}
+
+/// Fetch the entries `config` asks for, discussions first
+pub fn fetch(config: &FaqConfig, repo: &str) -> Result<FaqCache, String> {
+    let (owner, name) = crate::github::parse_repo(repo)
+        .ok_or_else(|| format!("`{}` is not a GitHub repository, use `owner/name`", repo))?;
+    let token = crate::github::token(&config.token_env)?;
+
+    let repository = serde_json::json!({ "owner": owner, "name": name });
+    let mut entries = Vec::new();
+    if config.discussions {
+        entries.extend(fetch_all(&token, DISCUSSIONS_QUERY, repository.clone(), |data| {
+            discussions(data, &config.label)
+        })?);
+    }
+    if config.issues {
+        let mut variables = repository;
+        variables["label"] = config.label.clone().into();
+        entries.extend(fetch_all(&token, ISSUES_QUERY, variables, issues)?);
+    }
+
+    Ok(FaqCache {
+        repo: repo.to_string(),
+        label: config.label.clone(),
+        fetched_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        entries,
+    })
+}
+
+type Page = (Vec<FaqEntry>, Option<String>);
+
+// Every page of a query, following its cursor
+fn fetch_all(
+    token: &str,
+    query: &str,
+    mut variables: serde_json::Value,
+    parse: impl Fn(&serde_json::Value) -> Page,
+) -> Result<Vec<FaqEntry>, String> {
+    let mut entries = Vec::new();
+    loop {
+        let data = crate::github::graphql(token, query, variables.clone())?;
+        if data["repository"].is_null() {
+            return Err(format!("repository {}/{} not found", variables["owner"], variables["name"]));
+        }
+
+        let (page, next) = parse(&data);
+        entries.extend(page);
+        match next {
+            Some(next) => variables["cursor"] = next.into(),
+            None => return Ok(entries),
+        }
+    }
+}
+
+fn next_cursor(connection: &serde_json::Value) -> Option<String> {
+    match connection["pageInfo"]["hasNextPage"].as_bool() {
+        Some(true) => connection["pageInfo"]["endCursor"].as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn entry(node: &serde_json::Value, answer: &str) -> Option<FaqEntry> {
+    Some(FaqEntry {
+        question: node["title"].as_str()?.trim().to_string(),
+        answer: answer.trim().to_string(),
+        url: node["url"].as_str()?.to_string(),
+        updated: node["updatedAt"].as_str().unwrap_or_default().chars().take(10).collect(),
+    })
+}
+
+// Answered discussions with the label. Discussions can't be filtered by
+// label in the query, so they're filtered here.
+fn discussions(data: &serde_json::Value, label: &str) -> Page {
+    let connection = &data["repository"]["discussions"];
+    let entries = connection["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|node| {
+            node["labels"]["nodes"]
+                .as_array()
+                .is_some_and(|labels| labels.iter().any(|l| l["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(label))))
+        })
+        .filter_map(|node| entry(node, node["answer"]["body"].as_str()?))
+        .collect();
+    (entries, next_cursor(connection))
+}
+
+// Issues with a reply from a maintainer
+fn issues(data: &serde_json::Value) -> Page {
+    let connection = &data["repository"]["issues"];
+    let entries = connection["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            let answer = node["comments"]["nodes"].as_array()?.iter().find(|comment| {
+                comment["authorAssociation"]
+                    .as_str()
+                    .is_some_and(|association| MAINTAINERS.contains(&association))
+            })?;
+            entry(node, answer["body"].as_str()?)
+        })
+        .collect();
+    (entries, next_cursor(connection))
+}
+
+/// What an import changed in the collection directory
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub written: Vec<PathBuf>,
+    /// Pages from an earlier import whose question is gone or unlabeled
+    pub removed: Vec<PathBuf>,
+}
+
+/// Write a page per entry to `dir`, and remove pages an earlier import wrote
+/// that no longer have an entry. Pages written by hand are left alone, even
+/// at the same path.
+pub fn write_collection(dir: &Path, entries: &[FaqEntry]) -> std::io::Result<ImportSummary> {
+    std::fs::create_dir_all(dir)?;
+    let mut summary = ImportSummary::default();
+    let mut names: HashSet<String> = HashSet::new();
+
+    for entry in entries {
+        let slug = crate::markdown::slugify(&entry.question);
+        let mut name = format!("{}.md", slug);
+        let mut n = 2;
+        while !names.insert(name.clone()) {
+            name = format!("{}-{}.md", slug, n);
+            n += 1;
+        }
+
+        let path = dir.join(&name);
+        if path.exists() && !is_imported(&path) {
+            continue;
+        }
+        std::fs::write(&path, page(entry))?;
+        summary.written.push(path);
+    }
+
+    for file in std::fs::read_dir(dir)? {
+        let path = file?.path();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+        if name.ends_with(".md") && !names.contains(&name) && is_imported(&path) {
+            std::fs::remove_file(&path)?;
+            summary.removed.push(path);
+        }
+    }
+    Ok(summary)
+}
+
+fn is_imported(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| content.lines().nth(1) == Some(MARKER))
+}
+
+fn page(entry: &FaqEntry) -> String {
+    let mut front_matter = toml::Table::new();
+    front_matter.insert("tags".to_string(), toml::Value::Array(vec!["faq".into()]));
+    if !entry.updated.is_empty() {
+        front_matter.insert("date".to_string(), entry.updated.clone().into());
+    }
+    let mut extra = toml::Table::new();
+    extra.insert("source".to_string(), entry.url.clone().into());
+    front_matter.insert("extra".to_string(), toml::Value::Table(extra));
+
+    format!(
+        "+++\n{}\n{}+++\n# {}\n\n{}\n\n[Originally answered on GitHub]({})\n",
+        MARKER,
+        toml::to_string(&front_matter).unwrap_or_default(),
+        entry.question,
+        entry.answer,
+        entry.url
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let data = serde_json::json!({ "repository": { "discussions": {
+            "pageInfo": { "hasNextPage": false, "endCursor": null },
+            "nodes": [
+                { "number": 1, "title": "How do I deploy?", "url": "https://github.com/o/r/discussions/1",
+                  "updatedAt": "2025-03-04T10:00:00Z", "labels": { "nodes": [{ "name": "FAQ" }] }, "answer": { "body": "Run `zap build`." } },
+                { "number": 2, "title": "Unlabeled", "url": "https://github.com/o/r/discussions/2",
+                  "updatedAt": "2025-03-04T10:00:00Z", "labels": { "nodes": [] }, "answer": { "body": "No" } },
+            ],
+        } } });
+        let (entries, next) = discussions(&data, "faq");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].updated, "2025-03-04");
+        assert_eq!(next, None);
+
+        let data = serde_json::json!({ "repository": { "issues": {
+            "pageInfo": { "hasNextPage": false },
+            "nodes": [{ "number": 3, "title": "Themes?", "url": "https://github.com/o/r/issues/3", "updatedAt": "2025-01-01T00:00:00Z",
+                "comments": { "nodes": [{ "body": "Me too", "authorAssociation": "NONE" }, { "body": "See the docs.", "authorAssociation": "OWNER" }] } }],
+        } } });
+        let (issues, _) = issues(&data);
+        assert_eq!(issues[0].answer, "See the docs.");
+
+        let dir = std::env::temp_dir().join(format!("zap-faq-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("themes.md"), "# Written by hand\n").unwrap();
+
+        let summary = write_collection(&dir, &[entries[0].clone(), issues[0].clone()]).unwrap();
+        assert_eq!(summary.written, [dir.join("how-do-i-deploy.md")]);
+        let page = std::fs::read_to_string(dir.join("how-do-i-deploy.md")).unwrap();
+        let front_matter = crate::front_matter::parse_front_matter(&page).unwrap();
+        assert_eq!(front_matter.tags, ["faq"]);
+        assert_eq!(front_matter.date.as_deref(), Some("2025-03-04"));
+
+        let summary = write_collection(&dir, &[]).unwrap();
+        assert_eq!(summary.removed, [dir.join("how-do-i-deploy.md")]);
+        assert!(dir.join("themes.md").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
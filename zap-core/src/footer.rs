@@ -0,0 +1,95 @@
+// The site footer from `[footer]`, given to templates as `footer` so every
+// theme renders the same links, accounts and copyright line.
+
+use serde::Serialize;
+
+use crate::config::{FooterColumn, FooterConfig};
+
+/// What templates get as `footer`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Footer {
+    pub columns: Vec<FooterColumn>,
+    pub social: Vec<SocialLink>,
+    /// With `{year}` replaced
+    pub copyright: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SocialLink {
+    /// Lowercase platform name, for themes to pick an icon
+    pub platform: String,
+    pub url: String,
+    /// The configured label, or the platform's name
+    pub label: String,
+}
+
+impl Footer {
+    pub fn new(config: &FooterConfig, year: i32) -> Self {
+        Self {
+            columns: config.columns.clone(),
+            social: config
+                .social
+                .iter()
+                .map(|account| {
+                    let platform = account.platform.trim().to_lowercase();
+                    SocialLink {
+                        label: account.label.clone().unwrap_or_else(|| platform_name(&platform)),
+                        platform,
+                        url: account.url.clone(),
+                    }
+                })
+                .collect(),
+            copyright: config
+                .copyright
+                .as_ref()
+                .map(|copyright| copyright.replace("{year}", &year.to_string())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty() && self.social.is_empty() && self.copyright.is_none()
+    }
+}
+
+fn platform_name(platform: &str) -> String {
+    let name = match platform {
+        "github" => "GitHub",
+        "gitlab" => "GitLab",
+        "linkedin" => "LinkedIn",
+        "youtube" => "YouTube",
+        "rss" => "RSS",
+        "x" => "X",
+        _ => {
+            let mut chars = platform.chars();
+            return match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            };
+        }
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SocialAccount;
+
+    #[test]
+    fn test_footer() {
+        let config = FooterConfig {
+            columns: Vec::new(),
+            social: vec![
+                SocialAccount { platform: "GitHub".to_string(), url: "https://github.com/zap".to_string(), label: None },
+                SocialAccount { platform: "mastodon".to_string(), url: "https://example.social/@zap".to_string(), label: None },
+            ],
+            copyright: Some("© 2020–{year} Zap contributors".to_string()),
+        };
+        let footer = Footer::new(&config, 2025);
+
+        assert_eq!(footer.copyright.as_deref(), Some("© 2020–2025 Zap contributors"));
+        assert_eq!(footer.social[0].platform, "github");
+        assert_eq!(footer.social[0].label, "GitHub");
+        assert_eq!(footer.social[1].label, "Mastodon");
+    }
+}
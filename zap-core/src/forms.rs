@@ -0,0 +1,212 @@
+// Forms from `[forms.NAME]` in zap.toml, placed in a page with `[form NAME]`
+// on a line of its own. A static site can't take submissions itself, so
+// forms post to a backend: Netlify Forms, Formspree or an endpoint of the
+// site's own. Every form carries a honeypot field that people never see and
+// spam bots fill in, and lands visitors on a thank-you page afterwards,
+// generated unless the form names its own.
+
+use std::collections::BTreeMap;
+
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+
+use crate::builder::GeneratedPage;
+use crate::config::{FormBackend, FormConfig, FormField};
+
+pub const FIELD_TYPES: [&str; 9] = ["text", "email", "tel", "url", "number", "date", "textarea", "select", "checkbox"];
+
+// Formspree's name for it. Netlify is told the name, and custom backends
+// should drop submissions that fill it in.
+const HONEYPOT: &str = "_gotcha";
+
+const MARKER: &str = "<p>[form ";
+const MARKER_END: &str = "]</p>";
+
+/// Replace every `[form NAME]` paragraph in `html` with the form, calling
+/// `missing` with names that have no `[forms.NAME]`, which are left as-is
+pub fn expand(
+    html: &str,
+    forms: &BTreeMap<String, FormConfig>,
+    site_url: Option<&str>,
+    mut missing: impl FnMut(&str),
+) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(MARKER) {
+        let Some(length) = rest[start..].find(MARKER_END) else {
+            break;
+        };
+        let name = rest[start + MARKER.len()..start + length].trim();
+        out.push_str(&rest[..start]);
+        match forms.get(name) {
+            Some(form) => out.push_str(&form_html(name, form, site_url)),
+            None => {
+                missing(name);
+                out.push_str(&rest[start..start + length + MARKER_END.len()]);
+            }
+        }
+        rest = &rest[start + length + MARKER_END.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Where visitors land after submitting the form
+pub fn success_url(name: &str, form: &FormConfig) -> String {
+    match &form.success_url {
+        Some(url) => url.clone(),
+        None => format!("/forms/{}/thanks/", crate::markdown::slugify(name)),
+    }
+}
+
+/// Thank-you pages for the forms that don't name their own
+pub fn success_pages(forms: &BTreeMap<String, FormConfig>) -> Vec<GeneratedPage> {
+    forms
+        .iter()
+        .filter(|(_, form)| form.success_url.is_none())
+        .map(|(name, form)| {
+            let markdown = format!("# {}\n\n{}\n", form.success_title, form.success_message);
+            GeneratedPage {
+                title: form.success_title.clone(),
+                url: success_url(name, form),
+                html: crate::markdown::render_elements_to_html(&crate::markdown::parse_structured(&markdown)),
+            }
+        })
+        .collect()
+}
+
+pub fn form_html(name: &str, form: &FormConfig, site_url: Option<&str>) -> String {
+    let success = success_url(name, form);
+    let action = match form.backend {
+        // Netlify redirects to the form's action after taking a submission
+        FormBackend::Netlify => success.clone(),
+        FormBackend::Formspree => {
+            let id = form.action.as_deref().unwrap_or_default();
+            match id.starts_with("http") {
+                true => id.to_string(),
+                false => format!("https://formspree.io/f/{}", id),
+            }
+        }
+        FormBackend::Custom => form.action.clone().unwrap_or_default(),
+    };
+
+    let mut html = format!(
+        "<form class=\"zap-form\" name=\"{}\" method=\"post\" action=\"{}\"",
+        attr(name),
+        attr(&action)
+    );
+    match form.backend {
+        FormBackend::Netlify => {
+            html.push_str(&format!(" data-netlify=\"true\" netlify-honeypot=\"{}\">\n", HONEYPOT));
+            html.push_str(&format!("<input type=\"hidden\" name=\"form-name\" value=\"{}\">\n", attr(name)));
+        }
+        _ => {
+            // Other backends redirect to `_next`, which has to be absolute
+            let next = match (success.starts_with('/'), site_url) {
+                (true, Some(site_url)) => format!("{}{}", site_url.trim_end_matches('/'), success),
+                _ => success,
+            };
+            html.push_str(">\n");
+            html.push_str(&format!("<input type=\"hidden\" name=\"_next\" value=\"{}\">\n", attr(&next)));
+        }
+    }
+
+    for field in &form.fields {
+        html.push_str(&field_html(name, field));
+    }
+
+    // Off screen rather than `display: none`, which some bots skip
+    html.push_str(&format!(
+        "<p class=\"zap-form-honeypot\" aria-hidden=\"true\" style=\"position: absolute; left: -10000px;\"><label>Leave this empty <input type=\"text\" name=\"{}\" tabindex=\"-1\" autocomplete=\"off\"></label></p>\n",
+        HONEYPOT
+    ));
+    html.push_str(&format!("<p><button type=\"submit\">{}</button></p>\n</form>\n", text(&form.submit)));
+    html
+}
+
+fn field_html(form: &str, field: &FormField) -> String {
+    let id = format!("zap-form-{}-{}", crate::markdown::slugify(form), crate::markdown::slugify(&field.name));
+    let label = field.label.clone().unwrap_or_else(|| crate::builder::title_case(&field.name));
+    // The `required` attribute is what assistive technology announces, the
+    // star is for sighted visitors
+    let label = match field.required {
+        true => format!("<label for=\"{}\">{}<span aria-hidden=\"true\"> *</span></label>", id, text(&label)),
+        false => format!("<label for=\"{}\">{}</label>", id, text(&label)),
+    };
+    let mut attributes = format!("id=\"{}\" name=\"{}\"", id, attr(&field.name));
+    if field.required {
+        attributes.push_str(" required");
+    }
+    if let Some(placeholder) = &field.placeholder {
+        attributes.push_str(&format!(" placeholder=\"{}\"", attr(placeholder)));
+    }
+
+    let kind = field.kind.as_deref().unwrap_or("text");
+    let input = match kind {
+        "textarea" => format!("<textarea {} rows=\"6\"></textarea>", attributes),
+        "select" => {
+            let options: String = field
+                .options
+                .iter()
+                .map(|option| format!("<option>{}</option>", text(option)))
+                .collect();
+            format!("<select {}><option value=\"\">Choose…</option>{}</select>", attributes, options)
+        }
+        "checkbox" => {
+            return format!(
+                "<p class=\"zap-form-field zap-form-checkbox\"><input type=\"checkbox\" {} value=\"yes\"> {}</p>\n",
+                attributes, label
+            );
+        }
+        _ => {
+            let autocomplete = match (kind, field.name.as_str()) {
+                ("email", _) => " autocomplete=\"email\"",
+                ("tel", _) => " autocomplete=\"tel\"",
+                ("text", "name") => " autocomplete=\"name\"",
+                _ => "",
+            };
+            format!("<input type=\"{}\" {}{}>", attr(kind), attributes, autocomplete)
+        }
+    };
+    format!("<p class=\"zap-form-field\">{}\n{}</p>\n", label, input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let field = |name: &str, kind: &str, required: bool| FormField {
+            name: name.to_string(),
+            kind: Some(kind.to_string()),
+            required,
+            ..Default::default()
+        };
+        let form = FormConfig {
+            backend: FormBackend::Formspree,
+            action: Some("xyzzy".to_string()),
+            fields: vec![field("email", "email", true), field("message", "textarea", false)],
+            ..Default::default()
+        };
+        let forms = BTreeMap::from([("contact".to_string(), form)]);
+
+        let mut missing = Vec::new();
+        let html = expand(
+            "<p>Write to us.</p>\n<p>[form contact]</p>\n<p>[form other]</p>\n",
+            &forms,
+            Some("https://zap.dev/"),
+            |name| missing.push(name.to_string()),
+        );
+        assert_eq!(missing, ["other"]);
+        assert!(html.starts_with("<p>Write to us.</p>\n<form class=\"zap-form\" name=\"contact\" method=\"post\" action=\"https://formspree.io/f/xyzzy\">"));
+        assert!(html.contains("<input type=\"hidden\" name=\"_next\" value=\"https://zap.dev/forms/contact/thanks/\">"));
+        assert!(html.contains("<label for=\"zap-form-contact-email\">Email<span aria-hidden=\"true\"> *</span></label>\n<input type=\"email\" id=\"zap-form-contact-email\" name=\"email\" required autocomplete=\"email\">"));
+        assert!(html.contains("<textarea id=\"zap-form-contact-message\" name=\"message\" rows=\"6\"></textarea>"));
+        assert!(html.contains("name=\"_gotcha\" tabindex=\"-1\""));
+        assert!(html.ends_with("</form>\n\n<p>[form other]</p>\n"));
+
+        let pages = success_pages(&forms);
+        assert_eq!(pages[0].url, "/forms/contact/thanks/");
+        assert!(pages[0].html.contains("Your message was sent."));
+    }
+}
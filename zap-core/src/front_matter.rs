@@ -0,0 +1,118 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::direction::Direction;
+
+const DELIMITER: &str = "+++";
+
+/// TOML front matter between `+++` lines at the top of a page
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+    pub tags: Vec<String>,
+    /// Position among its siblings, lower first
+    pub weight: Option<i64>,
+    /// Publish date, as a TOML date (`2024-05-01`) or a string
+    #[serde(deserialize_with = "deserialize_date")]
+    pub date: Option<String>,
+    /// When the page goes out of date, in the same formats as `date`
+    #[serde(deserialize_with = "deserialize_date")]
+    pub expires: Option<String>,
+    pub draft: bool,
+    /// Summary for search results and link previews
+    pub description: Option<String>,
+    /// Image shown in link previews
+    pub image: Option<String>,
+    /// Names of the page's content variants
+    pub variants: Vec<String>,
+    /// Variant published by builds, the first one when unset
+    pub variant: Option<String>,
+    /// Language of the page, when it isn't the site's
+    pub language: Option<String>,
+    /// Text direction, when it isn't the language's
+    pub dir: Option<Direction>,
+    /// Anything else for the theme, under `[extra]`
+    #[serde(deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+pub(crate) fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Date {
+        Datetime(toml::value::Datetime),
+        Text(String),
+    }
+
+    Ok(Option::<Date>::deserialize(deserializer)?.map(|date| match date {
+        Date::Datetime(datetime) => datetime.to_string(),
+        Date::Text(text) => text,
+    }))
+}
+
+/// A free-form `[extra]` table, as JSON for templates. TOML dates become
+/// strings, since JSON has no date type.
+pub fn deserialize_extra<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<serde_json::Map<String, serde_json::Value>, D::Error> {
+    let table = toml::Table::deserialize(deserializer)?;
+    Ok(table.into_iter().map(|(key, value)| (key, toml_to_json(value))).collect())
+}
+
+pub(crate) fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(datetime) => serde_json::Value::String(datetime.to_string()),
+        toml::Value::Array(values) => values.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table.into_iter().map(|(key, value)| (key, toml_to_json(value))).collect(),
+        ),
+    }
+}
+
+/// Split a page into its front matter source, if any, and the markdown body
+pub fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content.strip_prefix(DELIMITER) else {
+        return (None, content);
+    };
+    let Some(rest) = rest.strip_prefix('\n').or_else(|| rest.strip_prefix("\r\n")) else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    // Unterminated, so it's not front matter
+    (None, content)
+}
+
+pub fn parse_front_matter(content: &str) -> Result<FrontMatter, toml::de::Error> {
+    match split_front_matter(content).0 {
+        Some(source) => toml::from_str(source),
+        None => Ok(FrontMatter::default()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra() {
+        let content = "+++\ntitle = \"ignored\"\n[extra]\nhero = \"wide\"\nreviewed = 2024-05-01\nauthors = [\"Ana\", \"Bo\"]\n+++\n# Hello\n";
+        let front_matter = parse_front_matter(content).unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(front_matter.extra),
+            serde_json::json!({ "hero": "wide", "reviewed": "2024-05-01", "authors": ["Ana", "Bo"] })
+        );
+    }
+}
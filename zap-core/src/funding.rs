@@ -0,0 +1,185 @@
+// Sponsor links from GitHub's `.github/FUNDING.yml`, or `[funding]` in
+// zap.toml, so the site asks for support in the same places the repository
+// does.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::builder::GeneratedPage;
+use crate::config::FundingConfig;
+
+/// Read from the source directory's `.github`, then the one above it
+pub const FUNDING_FILE: &str = ".github/FUNDING.yml";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SponsorLink {
+    /// The FUNDING.yml key, e.g. `ko_fi`
+    pub platform: String,
+    pub label: String,
+    pub url: String,
+}
+
+/// Accounts by platform from a FUNDING.yml. It only uses `key: value`,
+/// `key: [a, b]` and `key:` followed by `- item` lines, so that's all this
+/// reads.
+pub fn parse_funding_yml(source: &str) -> BTreeMap<String, Vec<String>> {
+    let mut accounts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+
+    for line in source.lines() {
+        let line = strip_comment(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            if let Some(key) = &current {
+                accounts.entry(key.clone()).or_default().push(unquote(item));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim();
+        let values: Vec<String> = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(list) => list.split(',').map(unquote).filter(|v| !v.is_empty()).collect(),
+            None => Some(unquote(value)).filter(|v| !v.is_empty() && v != "null").into_iter().collect(),
+        };
+        accounts.entry(key.clone()).or_default().extend(values);
+        current = Some(key);
+    }
+
+    accounts.retain(|_, values| !values.is_empty());
+    accounts
+}
+
+// `#` starts a comment unless it's part of a URL, like `page#section`
+fn strip_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(i) => &line[..i],
+        None if line.trim_start().starts_with('#') => "",
+        None => line,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// The FUNDING.yml for a site, if there is one
+pub fn find_file(source_dir: &Path) -> Option<PathBuf> {
+    std::iter::once(source_dir)
+        .chain(source_dir.parent())
+        .map(|dir| dir.join(FUNDING_FILE))
+        .find(|path| path.is_file())
+}
+
+/// The site's sponsor links: `[funding]`'s accounts, or FUNDING.yml's when
+/// it has none
+pub fn sponsors(config: &FundingConfig, source_dir: &Path) -> std::io::Result<Vec<SponsorLink>> {
+    if !config.accounts.is_empty() {
+        let accounts = config
+            .accounts
+            .iter()
+            .map(|(platform, accounts)| (platform.clone(), accounts.names().to_vec()))
+            .collect();
+        return Ok(links(&accounts));
+    }
+    match find_file(source_dir) {
+        Some(path) => Ok(links(&parse_funding_yml(&crate::encoding::read_to_string(&path)?))),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The support page `[funding] page` asks for, when there's anything to link
+pub fn support_page(config: &FundingConfig, sponsors: &[SponsorLink]) -> Option<GeneratedPage> {
+    (config.page && !sponsors.is_empty()).then(|| GeneratedPage {
+        title: config.title.clone(),
+        url: "/support/".to_string(),
+        html: page_html(&config.title, config.message.as_deref(), sponsors),
+    })
+}
+
+/// A link per account, by platform. Accounts on platforms zap doesn't know
+/// are left out, unless they're URLs like `custom`'s.
+pub fn links(accounts: &BTreeMap<String, Vec<String>>) -> Vec<SponsorLink> {
+    let mut links = Vec::new();
+    for (platform, names) in accounts {
+        for name in names {
+            let (label, url) = match platform.as_str() {
+                "github" => ("GitHub Sponsors".to_string(), format!("https://github.com/sponsors/{}", name)),
+                "patreon" => ("Patreon".to_string(), format!("https://www.patreon.com/{}", name)),
+                "open_collective" => ("Open Collective".to_string(), format!("https://opencollective.com/{}", name)),
+                "ko_fi" => ("Ko-fi".to_string(), format!("https://ko-fi.com/{}", name)),
+                "tidelift" => ("Tidelift".to_string(), format!("https://tidelift.com/funding/github/{}", name)),
+                "community_bridge" | "lfx_crowdfunding" => (
+                    "LFX Crowdfunding".to_string(),
+                    format!("https://crowdfunding.lfx.linuxfoundation.org/projects/{}", name),
+                ),
+                "liberapay" => ("Liberapay".to_string(), format!("https://liberapay.com/{}", name)),
+                "issuehunt" => ("IssueHunt".to_string(), format!("https://issuehunt.io/r/{}", name)),
+                "polar" => ("Polar".to_string(), format!("https://polar.sh/{}", name)),
+                "buy_me_a_coffee" => ("Buy Me a Coffee".to_string(), format!("https://buymeacoffee.com/{}", name)),
+                "thanks_dev" => ("thanks.dev".to_string(), format!("https://thanks.dev/{}", name)),
+                _ if name.starts_with("http://") || name.starts_with("https://") => {
+                    (crate::privacy::host(name).trim_start_matches("www.").to_string(), name.clone())
+                }
+                _ => continue,
+            };
+            links.push(SponsorLink {
+                platform: platform.clone(),
+                label,
+                url,
+            });
+        }
+    }
+    links
+}
+
+/// The "Support this project" page: the message, then a link per account
+pub fn page_html(title: &str, message: Option<&str>, links: &[SponsorLink]) -> String {
+    let mut html = format!("<h1>{}</h1>\n", html_escape::encode_text(title));
+    if let Some(message) = message {
+        html.push_str(&crate::markdown::render_elements_to_html(&crate::markdown::parse_structured(message)));
+    }
+    html.push_str("<ul>\n");
+    for link in links {
+        html.push_str(&format!(
+            "<li><a href=\"{}\" data-platform=\"{}\">{}</a></li>\n",
+            html_escape::encode_double_quoted_attribute(&link.url),
+            html_escape::encode_double_quoted_attribute(&link.platform),
+            html_escape::encode_text(&link.label)
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_funding_yml() {
+        let source = "# These are supported funding model platforms\n\ngithub: [ana, 'bo']\npatreon: # Replace with a single Patreon username\nko_fi: ana\ncustom:\n  - https://www.paypal.me/ana\n  - \"https://example.com/donate#now\"\n";
+        let accounts = parse_funding_yml(source);
+        assert_eq!(accounts.keys().collect::<Vec<_>>(), ["custom", "github", "ko_fi"]);
+
+        let links: Vec<(String, String)> = links(&accounts).into_iter().map(|link| (link.label, link.url)).collect();
+        assert_eq!(
+            links,
+            [
+                ("paypal.me".to_string(), "https://www.paypal.me/ana".to_string()),
+                ("example.com".to_string(), "https://example.com/donate#now".to_string()),
+                ("GitHub Sponsors".to_string(), "https://github.com/sponsors/ana".to_string()),
+                ("GitHub Sponsors".to_string(), "https://github.com/sponsors/bo".to_string()),
+                ("Ko-fi".to_string(), "https://ko-fi.com/ana".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// When each page was last committed and who committed to it, read from the
+// git history in one `git log` over the source directory. Sites built
+// outside a git repository, or without git installed, just don't get them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::dates::PageDate;
+
+// Separate commits and a commit's date from its author in the log
+const COMMIT: char = '\x1e';
+const FIELD: char = '\x1f';
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileHistory {
+    /// The last commit that changed the file
+    pub last_updated: PageDate,
+    /// Authors by number of commits to the file, most first
+    pub contributors: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct GitHistory {
+    files: HashMap<PathBuf, FileHistory>,
+    /// A shallow clone, as CI often checks out, only has recent commits, so
+    /// older pages get the date of the oldest commit it has
+    pub shallow: bool,
+}
+
+impl GitHistory {
+    /// Read the history of every file under `dir`. Errors when `dir` isn't
+    /// in a git repository or git can't be run.
+    pub fn read(dir: &Path, tz: Tz) -> Result<Self, String> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let shallow = git(dir, &["rev-parse", "--is-shallow-repository"])? == "true";
+        let log = git(
+            dir,
+            &[
+                "-c",
+                "core.quotePath=false",
+                "log",
+                "--no-renames",
+                "--name-only",
+                &format!("--format={}%aI{}%aN", COMMIT, FIELD),
+                "--",
+                ".",
+            ],
+        )?;
+
+        Ok(Self {
+            files: parse_log(&log, Path::new(&root), tz),
+            shallow,
+        })
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&FileHistory> {
+        let path = path.canonicalize().ok()?;
+        self.files.get(&path)
+    }
+}
+
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Commits come newest first, so a file's first commit is its last update
+fn parse_log(log: &str, root: &Path, tz: Tz) -> HashMap<PathBuf, FileHistory> {
+    let mut files: HashMap<PathBuf, (PageDate, Vec<(String, usize)>)> = HashMap::new();
+    for commit in log.split(COMMIT).filter(|commit| !commit.trim().is_empty()) {
+        let mut lines = commit.lines();
+        let Some((date, author)) = lines.next().and_then(|line| line.split_once(FIELD)) else {
+            continue;
+        };
+        let Ok(date) = crate::dates::parse_date(date, tz) else {
+            continue;
+        };
+
+        for file in lines.filter(|line| !line.is_empty()) {
+            let (_, authors) = files.entry(root.join(file)).or_insert_with(|| (date, Vec::new()));
+            match authors.iter_mut().find(|(name, _)| name == author) {
+                Some((_, commits)) => *commits += 1,
+                None => authors.push((author.to_string(), 1)),
+            }
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(path, (last_updated, mut authors))| {
+            // Stable, so authors with as many commits stay most recent first
+            authors.sort_by_key(|(_, commits)| std::cmp::Reverse(*commits));
+            let contributors = authors.into_iter().map(|(name, _)| name).collect();
+            (
+                path,
+                FileHistory {
+                    last_updated,
+                    contributors,
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let log = format!(
+            "{c}2025-03-04T10:00:00+01:00{f}Ana\n\nsite/a.md\n\n\
+             {c}2025-02-01T09:00:00+00:00{f}Bo\n\nsite/a.md\nsite/b.md\n\n\
+             {c}2025-01-01T09:00:00+00:00{f}Bo\n\nsite/a.md\n",
+            c = COMMIT,
+            f = FIELD
+        );
+        let files = parse_log(&log, Path::new("/repo"), chrono_tz::UTC);
+
+        let a = &files[Path::new("/repo/site/a.md")];
+        assert_eq!(a.last_updated.iso(), "2025-03-04T09:00:00+00:00");
+        assert_eq!(a.contributors, ["Bo", "Ana"]);
+        let b = &files[Path::new("/repo/site/b.md")];
+        assert_eq!(b.last_updated.date(), "2025-02-01");
+        assert_eq!(b.contributors, ["Bo"]);
+    }
+}
@@ -0,0 +1,65 @@
+// GitHub's API, for content zap reads from a repository or project rather
+// than the source directory.
+
+use std::io::Read;
+
+const API_URL: &str = "https://api.github.com";
+const GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+/// The token in `env`. GitHub's GraphQL API needs one even for public data.
+pub fn token(env: &str) -> Result<String, String> {
+    std::env::var(env)
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .ok_or_else(|| format!("set {} to a GitHub token", env))
+}
+
+/// Run a query, returning its `data`. Errors GitHub reports in the response
+/// fail the query, as they usually mean a name or permission is wrong.
+pub fn graphql(token: &str, query: &str, variables: serde_json::Value) -> Result<serde_json::Value, String> {
+    let body = serde_json::json!({ "query": query, "variables": variables });
+    let mut response = ureq::post(GRAPHQL_URL)
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("User-Agent", "zap")
+        .header("Content-Type", "application/json")
+        .send(body.to_string())
+        .map_err(|e| e.to_string())?;
+
+    let mut json = read_json(&mut response)?;
+    if let Some(message) = json["errors"][0]["message"].as_str() {
+        return Err(message.to_string());
+    }
+    Ok(json["data"].take())
+}
+
+/// GET a REST endpoint, e.g. `/repos/owner/name/releases/latest`. Public
+/// data doesn't need a token, but without one GitHub allows 60 requests an
+/// hour.
+pub fn rest(token: Option<&str>, path: &str) -> Result<serde_json::Value, String> {
+    let mut request = ureq::get(&format!("{}{}", API_URL, path))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "zap");
+    if let Some(token) = token {
+        request = request.header("Authorization", &format!("Bearer {}", token));
+    }
+    let mut response = request.call().map_err(|e| e.to_string())?;
+    read_json(&mut response)
+}
+
+pub(crate) fn read_json(response: &mut ureq::http::Response<ureq::Body>) -> Result<serde_json::Value, String> {
+    let mut text = String::new();
+    response
+        .body_mut()
+        .as_reader()
+        .read_to_string(&mut text)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+/// `owner` and `name` from `owner/name` or a repository URL
+pub fn parse_repo(repo: &str) -> Option<(&str, &str)> {
+    let repo = repo.trim().trim_end_matches('/').trim_end_matches(".git");
+    let repo = repo.strip_prefix("https://github.com/").unwrap_or(repo);
+    let (owner, name) = repo.split_once('/')?;
+    (!owner.is_empty() && !name.is_empty() && !name.contains('/')).then_some((owner, name))
+}
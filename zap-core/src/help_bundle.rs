@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::anchors::Anchor;
+use crate::markdown::{PageElement, render_inline_elements_text};
+use crate::site::Page;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+// Bumped whenever the bundle layout changes in a way readers must know about
+pub const BUNDLE_FORMAT: u32 = 1;
+
+#[derive(Debug)]
+pub enum HelpBundleError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    MissingManifest,
+    UnsupportedFormat(u32),
+}
+
+impl From<std::io::Error> for HelpBundleError {
+    fn from(err: std::io::Error) -> Self {
+        HelpBundleError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for HelpBundleError {
+    fn from(err: serde_json::Error) -> Self {
+        HelpBundleError::Json(err)
+    }
+}
+
+impl std::fmt::Display for HelpBundleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HelpBundleError::Io(e) => write!(f, "IO error: {}", e),
+            HelpBundleError::Json(e) => write!(f, "JSON error: {}", e),
+            HelpBundleError::MissingManifest => write!(f, "Help bundle has no {}", MANIFEST_FILE),
+            HelpBundleError::UnsupportedFormat(v) => {
+                write!(f, "Unsupported help bundle format version: {}", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HelpBundleError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelpManifest {
+    pub format: u32,
+    pub title: String,
+    pub pages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub url: String,
+    pub title: String,
+    pub text: String,
+}
+
+/// Build a plain text search index for the given pages
+pub fn search_index(pages: &[Page], source_dir: &Path) -> Vec<SearchEntry> {
+    pages
+        .iter()
+        .map(|page| SearchEntry {
+            url: page.url(source_dir),
+            title: page.title.clone(),
+            text: elements_text(page.elements()),
+        })
+        .collect()
+}
+
+fn elements_text(elements: &[PageElement]) -> String {
+    let mut parts = Vec::new();
+
+    for element in elements {
+        match element {
+            PageElement::Heading { content, .. } | PageElement::Paragraph { content } => {
+                parts.push(render_inline_elements_text(content));
+            }
+            PageElement::List { items, .. } => {
+                for item in items {
+                    parts.push(render_inline_elements_text(&item.content));
+                }
+            }
+            PageElement::BlockQuote { content } => parts.push(elements_text(content)),
+            _ => {}
+        }
+    }
+
+    parts.join("\n")
+}
+
+/// Package a rendered site directory into a tar help bundle
+pub fn write_help_bundle(
+    site_dir: &Path,
+    bundle_path: &Path,
+    title: &str,
+    index: &[SearchEntry],
+) -> Result<(), HelpBundleError> {
+    let manifest = HelpManifest {
+        format: BUNDLE_FORMAT,
+        title: title.to_string(),
+        pages: index.iter().map(|entry| entry.url.clone()).collect(),
+    };
+
+    if let Some(parent) = bundle_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file = std::fs::File::create(bundle_path)?;
+    let mut archive = tar::Builder::new(file);
+    archive.append_dir_all(".", site_dir)?;
+    append_json(&mut archive, MANIFEST_FILE, &manifest)?;
+    append_json(&mut archive, SEARCH_INDEX_FILE, &index)?;
+    archive.finish()?;
+
+    Ok(())
+}
+
+fn append_json<W: std::io::Write, T: Serialize>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+) -> Result<(), HelpBundleError> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data.as_slice())?;
+
+    Ok(())
+}
+
+/// Read-only view of a help bundle, for applications embedding docs as
+/// offline help
+pub struct HelpBundle {
+    manifest: HelpManifest,
+    search_index: Vec<SearchEntry>,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl HelpBundle {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, HelpBundleError> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    /// Load a bundle from any reader, e.g. `include_bytes!` data
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, HelpBundleError> {
+        let mut files = HashMap::new();
+        let mut archive = tar::Archive::new(reader);
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            files.insert(path, data);
+        }
+
+        let manifest: HelpManifest = match files.get(MANIFEST_FILE) {
+            Some(data) => serde_json::from_slice(data)?,
+            None => return Err(HelpBundleError::MissingManifest),
+        };
+        if manifest.format > BUNDLE_FORMAT {
+            return Err(HelpBundleError::UnsupportedFormat(manifest.format));
+        }
+
+        let search_index = match files.get(SEARCH_INDEX_FILE) {
+            Some(data) => serde_json::from_slice(data)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            manifest,
+            search_index,
+            files,
+        })
+    }
+
+    pub fn manifest(&self) -> &HelpManifest {
+        &self.manifest
+    }
+
+    /// Raw file contents by bundle path, e.g. `style.css`
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path.trim_start_matches('/')).map(Vec::as_slice)
+    }
+
+    /// Rendered HTML for a page URL such as `/installation/`
+    pub fn page(&self, url: &str) -> Option<&str> {
+        let data = self.file(&page_file(url))?;
+        std::str::from_utf8(data).ok()
+    }
+
+    /// Heading anchors for a page URL, in outline order, or none when the
+    /// page has no anchors file
+    pub fn anchors(&self, url: &str) -> Result<Vec<Anchor>, HelpBundleError> {
+        let anchors = crate::anchors::anchors_path(Path::new(&page_file(url)));
+        let Some(data) = self.file(&anchors.to_string_lossy().replace('\\', "/")) else {
+            return Ok(Vec::new());
+        };
+        let map: HashMap<String, Anchor> = serde_json::from_slice(data)?;
+        let mut anchors: Vec<Anchor> = map.into_values().collect();
+        anchors.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(anchors)
+    }
+
+    /// Pages containing every word of `query`, case-insensitively. Chinese
+    /// and Japanese are matched by pairs of characters, since they don't
+    /// separate words with spaces.
+    pub fn search(&self, query: &str) -> Vec<&SearchEntry> {
+        let terms = crate::cjk::search_terms(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        self.search_index
+            .iter()
+            .filter(|entry| {
+                let haystack = format!("{}\n{}", entry.title, entry.text).to_lowercase();
+                terms.iter().all(|term| haystack.contains(term))
+            })
+            .collect()
+    }
+}
+
+// Bundle path of the page at `url`, `guides/index.html` for `/guides/` or
+// `guides/install.html` with `ugly_urls`
+fn page_file(url: &str) -> String {
+    crate::permalink::out_path(url).to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let root = std::env::temp_dir().join(format!("zap-help-bundle-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let site = root.join("out");
+        std::fs::create_dir_all(site.join("proxy")).unwrap();
+        std::fs::write(site.join("index.html"), "<h1>Help</h1>").unwrap();
+        std::fs::write(site.join("proxy/index.html"), "<h1>Proxy settings</h1>").unwrap();
+        std::fs::write(site.join("style.css"), "body{}").unwrap();
+        let elements = crate::markdown::parse_structured("# Proxy settings\n\n## Manual\n\n### Ports\n");
+        let anchors = crate::anchors::page_anchors(&elements);
+        std::fs::write(site.join("proxy/anchors.json"), crate::anchors::anchors_json(&anchors).unwrap()).unwrap();
+        let index = vec![
+            SearchEntry { url: "/".to_string(), title: "Help".to_string(), text: "Start here".to_string() },
+            SearchEntry { url: "/proxy/".to_string(), title: "Proxy settings".to_string(), text: "Set the proxy port".to_string() },
+        ];
+
+        let path = root.join("help.tar");
+        write_help_bundle(&site, &path, "App Help", &index).unwrap();
+        let bundle = HelpBundle::open(&path).unwrap();
+        assert_eq!(bundle.manifest().format, BUNDLE_FORMAT);
+        assert_eq!(bundle.manifest().title, "App Help");
+        assert_eq!(bundle.manifest().pages, ["/", "/proxy/"]);
+        assert_eq!(bundle.page("/proxy/"), Some("<h1>Proxy settings</h1>"));
+        assert_eq!(bundle.file("/style.css"), Some(b"body{}".as_slice()));
+        assert_eq!(bundle.page("/missing/"), None);
+
+        let read = bundle.anchors("/proxy/").unwrap();
+        let slugs: Vec<&str> = read.iter().map(|anchor| anchor.slug.as_str()).collect();
+        assert_eq!(slugs, ["proxy-settings", "manual", "ports"]);
+        assert_eq!(read[2].id, anchors[2].id);
+        assert!(bundle.anchors("/").unwrap().is_empty());
+
+        let hits: Vec<&str> = bundle.search("PROXY port").iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(hits, ["/proxy/"]);
+
+        // A damaged anchors file is an error, not a page without anchors
+        std::fs::write(site.join("proxy/anchors.json"), "{\"h1\": ").unwrap();
+        write_help_bundle(&site, &path, "App Help", &index).unwrap();
+        let bundle = HelpBundle::open(&path).unwrap();
+        assert!(matches!(bundle.anchors("/proxy/"), Err(HelpBundleError::Json(_))));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+// A record of every `zap build`, appended to `.zap-cache/history.jsonl` so
+// warning counts and build times can be compared across commits.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Project-local cache directory, relative to where zap is run
+pub const CACHE_DIR: &str = ".zap-cache";
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildRecord {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Short hash of the checked out commit, when building a git repository
+    pub commit: Option<String>,
+    pub pages: usize,
+    pub ms: u128,
+    pub warnings: Vec<String>,
+    /// Why the build failed, if it did
+    pub error: Option<String>,
+}
+
+impl BuildRecord {
+    pub fn new() -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            commit: current_commit(),
+            ..Self::default()
+        }
+    }
+}
+
+fn current_commit() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+pub fn append_record(cache_dir: &Path, record: &BuildRecord) -> std::io::Result<()> {
+    std::fs::create_dir_all(cache_dir)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(cache_dir.join(HISTORY_FILE))?;
+
+    let line = serde_json::to_string(record).map_err(std::io::Error::from)?;
+    writeln!(file, "{}", line)
+}
+
+/// Every recorded build, oldest first. Lines that can't be parsed, e.g. from
+/// an interrupted write, are skipped.
+pub fn read_history(cache_dir: &Path) -> std::io::Result<Vec<BuildRecord>> {
+    let path = cache_dir.join(HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Warnings in `latest` that `previous` didn't have
+pub fn new_warnings<'a>(previous: &BuildRecord, latest: &'a BuildRecord) -> Vec<&'a String> {
+    latest
+        .warnings
+        .iter()
+        .filter(|warning| !previous.warnings.contains(warning))
+        .collect()
+}
+
+/// Warnings in `previous` that `latest` no longer has
+pub fn resolved_warnings<'a>(previous: &'a BuildRecord, latest: &BuildRecord) -> Vec<&'a String> {
+    new_warnings(latest, previous)
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let minutes = (timestamp % 86400) / 60;
+
+    // Civil date from days since the epoch, per Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951782400 + 3600 + 120), "2000-02-29 01:02");
+        assert_eq!(format_timestamp(1735689599), "2024-12-31 23:59");
+    }
+
+    #[test]
+    fn test_warning_changes() {
+        let record = |warnings: &[&str]| BuildRecord {
+            warnings: warnings.iter().map(|w| w.to_string()).collect(),
+            ..BuildRecord::default()
+        };
+        let previous = record(&["a", "b"]);
+        let latest = record(&["b", "c"]);
+
+        assert_eq!(new_warnings(&previous, &latest), ["c"]);
+        assert_eq!(resolved_warnings(&previous, &latest), ["a"]);
+    }
+}
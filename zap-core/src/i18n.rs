@@ -0,0 +1,166 @@
+// String catalogs for a theme's own text, like "On this page" or "Next",
+// from `i18n/<language>.toml` in the theme. Templates look strings up with
+// `trans(key="on_this_page", lang=lang)`, so the theme's chrome follows the
+// page's language independently of what the content is written in.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::ConfigError;
+
+pub const I18N_DIR: &str = "i18n";
+
+// Used for keys a language's catalog doesn't have
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Strings by language, then key. Nested tables are flattened to dotted
+/// keys, so `[nav] next = "Next"` is `nav.next`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Catalogs(BTreeMap<String, BTreeMap<String, String>>);
+
+impl Catalogs {
+    /// Read every catalog in the theme, none when it has no `i18n/`
+    pub fn load(theme_dir: &Path) -> Result<Self, ConfigError> {
+        let dir = theme_dir.join(I18N_DIR);
+        let mut catalogs = BTreeMap::new();
+        if !dir.is_dir() {
+            return Ok(Self(catalogs));
+        }
+
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "toml") {
+                continue;
+            }
+            let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let data = std::fs::read_to_string(&path)?;
+            let table: toml::Table = toml::from_str(&data)
+                .map_err(|e| ConfigError::Invalid(format!("{}: {}", path.display(), e)))?;
+
+            let mut strings = BTreeMap::new();
+            flatten(&table, "", &mut strings)
+                .map_err(|key| ConfigError::Invalid(format!("{}: `{}` is not a string", path.display(), key)))?;
+            catalogs.insert(language.to_lowercase(), strings);
+        }
+        Ok(Self(catalogs))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// A string in `language`, then the language without its region
+    /// (`pt-BR`, then `pt`), then English
+    pub fn lookup(&self, language: &str, key: &str) -> Option<&str> {
+        candidates(language)
+            .iter()
+            .find_map(|language| self.0.get(language).and_then(|strings| strings.get(key)))
+            .map(String::as_str)
+    }
+
+    /// Whether the theme has a catalog for `language` or its base language
+    pub fn has_language(&self, language: &str) -> bool {
+        candidates(language)[..2].iter().any(|language| self.0.contains_key(language))
+    }
+}
+
+// The catalogs a lookup tries, in order
+fn candidates(language: &str) -> [String; 3] {
+    let language = language.to_lowercase();
+    let base = language.split(['-', '_']).next().unwrap_or_default().to_string();
+    [language, base, FALLBACK_LANGUAGE.to_string()]
+}
+
+// Errors with the key of a value that isn't a string or table
+fn flatten(table: &toml::Table, prefix: &str, strings: &mut BTreeMap<String, String>) -> Result<(), String> {
+    for (key, value) in table {
+        let key = match prefix {
+            "" => key.clone(),
+            prefix => format!("{}.{}", prefix, key),
+        };
+        match value {
+            toml::Value::String(text) => {
+                strings.insert(key, text.clone());
+            }
+            toml::Value::Table(table) => flatten(table, &key, strings)?,
+            _ => return Err(key),
+        }
+    }
+    Ok(())
+}
+
+/// `trans(key="...", lang=lang)`, with `lang` defaulting to the site's
+/// language. Other arguments fill `{name}` placeholders, e.g.
+/// `trans(key="page_of", n=2, total=5)` for `"Page {n} of {total}"`.
+pub struct Trans {
+    pub catalogs: Catalogs,
+    pub language: String,
+}
+
+impl tera::Function for Trans {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let key = args
+            .get("key")
+            .and_then(tera::Value::as_str)
+            .ok_or_else(|| tera::Error::msg("trans() requires a `key` argument"))?;
+        let language = args.get("lang").and_then(tera::Value::as_str).unwrap_or(&self.language);
+
+        let text = self.catalogs.lookup(language, key).ok_or_else(|| {
+            tera::Error::msg(format!(
+                "trans(): no `{}` in the theme's {}/{}.toml or {}/{}.toml",
+                key, I18N_DIR, language, I18N_DIR, FALLBACK_LANGUAGE
+            ))
+        })?;
+
+        let mut text = text.to_string();
+        for (name, value) in args.iter().filter(|(name, _)| !matches!(name.as_str(), "key" | "lang")) {
+            let value = match value {
+                tera::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            text = text.replace(&format!("{{{}}}", name), &value);
+        }
+        Ok(tera::Value::String(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Function;
+
+    #[test]
+    fn test_trans() {
+        let catalogs = |source: &str| {
+            let table: toml::Table = toml::from_str(source).unwrap();
+            let mut strings = BTreeMap::new();
+            flatten(&table, "", &mut strings).unwrap();
+            strings
+        };
+        let trans = Trans {
+            catalogs: Catalogs(BTreeMap::from([
+                ("en".to_string(), catalogs("next = \"Next\"\n[pages]\nof = \"Page {n} of {total}\"")),
+                ("pt".to_string(), catalogs("next = \"Próxima\"")),
+            ])),
+            language: "en".to_string(),
+        };
+        let call = |args: serde_json::Value| {
+            let args: HashMap<String, tera::Value> = serde_json::from_value(args).unwrap();
+            trans.call(&args).map(|value| value.as_str().unwrap().to_string())
+        };
+
+        assert_eq!(call(serde_json::json!({ "key": "next" })).unwrap(), "Next");
+        assert_eq!(call(serde_json::json!({ "key": "next", "lang": "pt-BR" })).unwrap(), "Próxima");
+        assert_eq!(
+            call(serde_json::json!({ "key": "pages.of", "lang": "pt", "n": 2, "total": 5 })).unwrap(),
+            "Page 2 of 5"
+        );
+        assert!(call(serde_json::json!({ "key": "missing" })).is_err());
+        assert!(trans.catalogs.has_language("PT-br"));
+        assert!(!trans.catalogs.has_language("de"));
+    }
+}
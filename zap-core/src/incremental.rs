@@ -0,0 +1,174 @@
+// Rebuilds for `zap serve`. Each output file is recorded with a fingerprint
+// of everything it was rendered from: its source file, the pages it lists,
+// and the site-wide inputs (config, theme, navigation). On the next build,
+// outputs whose fingerprint is unchanged and that are still on disk are
+// skipped, and unchanged source files aren't parsed again.
+//
+// Templates can include and extend each other, so any theme change rebuilds
+// everything rather than tracking which pages use which template.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::site::Page;
+
+/// State kept between builds of the same site
+#[derive(Default)]
+pub struct BuildCache {
+    // Config the cache was filled with. When it changes nothing in the
+    // cache can be trusted.
+    inputs: Option<u64>,
+    pub(crate) pages: PageCache,
+    pub(crate) outputs: HashMap<PathBuf, u64>,
+}
+
+impl BuildCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start over if the site-wide inputs changed since the last build
+    pub(crate) fn check_inputs(&mut self, inputs: u64) {
+        if self.inputs != Some(inputs) {
+            *self = Self {
+                inputs: Some(inputs),
+                ..Self::default()
+            };
+        }
+    }
+}
+
+/// Scanned pages, reused while their file's size and modification time
+/// stay the same
+#[derive(Default)]
+pub struct PageCache {
+    entries: HashMap<PathBuf, CachedPage>,
+}
+
+struct CachedPage {
+    modified: SystemTime,
+    len: u64,
+    page: Page,
+}
+
+impl PageCache {
+    pub fn get(&self, path: &Path) -> Option<Page> {
+        let cached = self.entries.get(path)?;
+        let (modified, len) = file_stamp(path)?;
+        (cached.modified == modified && cached.len == len).then(|| cached.page.clone())
+    }
+
+    pub fn insert(&mut self, page: &Page) {
+        if let Some((modified, len)) = file_stamp(&page.path) {
+            self.entries.insert(
+                page.path.clone(),
+                CachedPage {
+                    modified,
+                    len,
+                    page: page.clone(),
+                },
+            );
+        }
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Hash of the inputs an output is rendered from. Only compared within one
+/// process, so the hasher doesn't need to be stable across versions.
+#[derive(Clone, Default)]
+pub struct Fingerprint(DefaultHasher);
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, value: impl Hash) -> Self {
+        value.hash(&mut self.0);
+        self
+    }
+
+    /// For template context, which is serializable but rarely `Hash`. Goes
+    /// through a `Value` so map keys are sorted, whatever a `HashMap`'s
+    /// iteration order.
+    pub fn with_json(self, value: &impl Serialize) -> Self {
+        let json = serde_json::to_value(value).map(|value| value.to_string());
+        self.with(json.unwrap_or_default())
+    }
+
+    /// A file's contents. Missing files hash the same as empty ones.
+    pub fn with_file(self, path: &Path) -> Self {
+        self.with(std::fs::read(path).unwrap_or_default())
+    }
+
+    /// Every file under `dir`, with its path
+    pub fn with_dir(mut self, dir: &Path) -> Self {
+        let mut files: Vec<PathBuf> = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect();
+        files.sort();
+        for file in files {
+            self = self.with(&file).with_file(&file);
+        }
+        self
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_cache() {
+        let dir = std::env::temp_dir().join(format!("zap-page-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("page.md");
+        std::fs::write(&path, "# Page").unwrap();
+
+        let page = Page {
+            title: "Page".to_string(),
+            path: path.clone(),
+            page_type: crate::PageType::Regular,
+            tags: Vec::new(),
+            weight: None,
+            date: None,
+            expires: None,
+            draft: false,
+            description: None,
+            image: None,
+            language: None,
+            dir: None,
+            extra: Default::default(),
+            encoding: crate::encoding::Encoding::Utf8,
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: Default::default(),
+        };
+        let mut cache = PageCache::default();
+        cache.insert(&page);
+        assert_eq!(cache.get(&path).map(|page| page.title), Some("Page".to_string()));
+
+        std::fs::write(&path, "# Renamed page").unwrap();
+        assert!(cache.get(&path).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+// Snippets from `[inject]` added to every page, so analytics or a chat
+// widget don't need a fork of the theme.
+
+use crate::config::{ConsentConfig, InjectConfig};
+
+/// Add the configured snippets to a page. Analytics are left out of
+/// `zap serve`, so local visits aren't counted. With a consent banner they
+/// only run once visitors allow `analytics`.
+pub fn apply(html: String, config: &InjectConfig, analytics: bool, consent: Option<&ConsentConfig>) -> String {
+    let mut head = String::new();
+    if analytics {
+        let snippets = analytics_snippets(config);
+        match consent {
+            Some(_) => head.push_str(&crate::consent::gate(&snippets, "analytics")),
+            None => head.push_str(&snippets),
+        }
+    }
+    if let Some(snippet) = &config.head {
+        head.push_str(snippet);
+    }
+
+    let mut html = html;
+    if !head.is_empty() {
+        html = insert_before(html, "</head>", &head);
+    }
+    if let Some(snippet) = &config.body_start {
+        html = insert_after_body(html, snippet);
+    }
+    if let Some(snippet) = &config.body_end {
+        html = insert_before(html, "</body>", snippet);
+    }
+    if let Some(consent) = consent {
+        html = insert_before(html, "</body>", &crate::consent::banner_html(consent));
+    }
+    html
+}
+
+fn analytics_snippets(config: &InjectConfig) -> String {
+    let attr = |value: &str| html_escape::encode_double_quoted_attribute(value).to_string();
+    let mut snippets = String::new();
+
+    if let Some(domain) = &config.plausible {
+        snippets.push_str(&format!(
+            "<script defer data-domain=\"{}\" src=\"https://plausible.io/js/script.js\"></script>\n",
+            attr(domain)
+        ));
+    }
+    if let Some(code) = &config.goatcounter {
+        // A code, or the full count URL for self-hosted instances
+        let endpoint = match code.contains("://") {
+            true => code.clone(),
+            false => format!("https://{}.goatcounter.com/count", code),
+        };
+        snippets.push_str(&format!(
+            "<script data-goatcounter=\"{}\" async src=\"//gc.zgo.at/count.js\"></script>\n",
+            attr(&endpoint)
+        ));
+    }
+    if let Some(id) = &config.google_analytics {
+        let js_id = serde_json::to_string(id).unwrap_or_default().replace('<', "\\u003c");
+        snippets.push_str(&format!(
+            "<script async src=\"https://www.googletagmanager.com/gtag/js?id={}\"></script>\n\
+             <script>window.dataLayer = window.dataLayer || []; function gtag(){{dataLayer.push(arguments);}} gtag('js', new Date()); gtag('config', {});</script>\n",
+            attr(id),
+            js_id
+        ));
+    }
+
+    snippets
+}
+
+fn insert_before(html: String, tag: &str, snippet: &str) -> String {
+    match html.rfind(tag) {
+        Some(pos) => format!("{}{}{}", &html[..pos], snippet, &html[pos..]),
+        None => html,
+    }
+}
+
+// After the opening `<body ...>` tag
+fn insert_after_body(html: String, snippet: &str) -> String {
+    let body = html
+        .match_indices("<body")
+        .map(|(pos, _)| pos)
+        .find(|&pos| html[pos + 5..].starts_with(|c: char| c == '>' || c.is_whitespace()));
+    match body.and_then(|pos| html[pos..].find('>').map(|end| pos + end + 1)) {
+        Some(pos) => format!("{}{}{}", &html[..pos], snippet, &html[pos..]),
+        None => html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let config = InjectConfig {
+            head: Some("<link rel=\"me\" href=\"https://example.social/@zap\">".to_string()),
+            body_start: Some("<div id=\"top\"></div>".to_string()),
+            body_end: Some("<script src=\"/chat.js\"></script>".to_string()),
+            plausible: Some("zap.dev".to_string()),
+            ..Default::default()
+        };
+        let html = "<html><head><title>Zap</title></head><body class=\"page\"><header></header></body></html>";
+
+        assert_eq!(
+            apply(html.to_string(), &config, true, None),
+            "<html><head><title>Zap</title><script defer data-domain=\"zap.dev\" src=\"https://plausible.io/js/script.js\"></script>\n\
+             <link rel=\"me\" href=\"https://example.social/@zap\"></head><body class=\"page\"><div id=\"top\"></div><header></header><script src=\"/chat.js\"></script></body></html>"
+        );
+        assert!(!apply(html.to_string(), &config, false, None).contains("plausible"));
+
+        let consent = ConsentConfig::default();
+        let gated = apply(html.to_string(), &config, true, Some(&consent));
+        assert!(gated.contains("<script type=\"text/plain\" data-consent=\"analytics\" defer data-domain=\"zap.dev\""));
+        assert!(gated.contains("<script src=\"/chat.js\"></script><div class=\"zap-consent\""));
+        assert!(gated.ends_with("<script src=\"/zap-consent.js\" defer></script>\n</body></html>"));
+    }
+}
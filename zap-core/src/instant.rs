@@ -0,0 +1,173 @@
+// Instant navigation, from `instant_navigation` under `[performance]`. A
+// small script follows links within the site by fetching the next page and
+// swapping in its body, prefetching pages when links are hovered, so moving
+// around feels instant. Without JavaScript links work as usual, and pages
+// whose head loads different scripts or stylesheets are loaded normally.
+//
+// Theme scripts in `<head>` run once; ones needing to set up each page can
+// listen for `zap:navigate` on `document`. Scripts in the body run again
+// when they're inline, but external ones only once. Elements marked
+// `data-zap-permanent="NAME"` are kept across pages, and links inside
+// `data-no-instant` are followed normally. `zap serve`'s live reload
+// listens for `zap:navigate` too, to reload the page now showing.
+
+/// Written to the root of the output directory
+pub const SCRIPT_FILE: &str = "zap-instant.js";
+
+const SCRIPT_TAG: &str = "<script src=\"/zap-instant.js\" defer></script>\n";
+
+/// Load the script at the end of the page's head, so swapping bodies
+/// doesn't run it again
+pub fn insert_script(html: String) -> String {
+    match html.find("</head>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], SCRIPT_TAG, &html[pos..]),
+        None => html,
+    }
+}
+
+pub const SCRIPT: &str = r#"(() => {
+  if (!window.fetch || !window.DOMParser || !history.pushState) return;
+
+  // Prefetched pages are used for 30s, then fetched again
+  const cache = new Map();
+  const fetchPage = (url) => {
+    const cached = cache.get(url);
+    if (cached && Date.now() - cached.time < 30000) return cached.page;
+    const page = fetch(url, { credentials: 'same-origin' }).then((response) => {
+      const type = response.headers.get('Content-Type') || '';
+      if (!response.ok || !type.includes('text/html')) throw new Error(response.status);
+      return response.text().then((html) => ({ url: response.url, html }));
+    });
+    page.catch(() => cache.delete(url));
+    cache.set(url, { time: Date.now(), page });
+    return page;
+  };
+
+  // Pages of the site rather than files, and not this page
+  const target = (link) => {
+    if (!link || link.closest('[data-no-instant]') || link.hasAttribute('download')) return null;
+    if (link.target && link.target !== '_self') return null;
+    const url = new URL(link.getAttribute('href'), location.href);
+    if (url.origin !== location.origin) return null;
+    const name = url.pathname.split('/').pop();
+    if (name.includes('.') && !name.endsWith('.html')) return null;
+    if (url.pathname === location.pathname && url.search === location.search) return null;
+    return url;
+  };
+
+  const runs = (script) => !script.type || /javascript|module/.test(script.type);
+  // Scripts and styles in the head stay, they can't be undone without a
+  // full page load. Scripts waiting for consent are activated in place.
+  const kept = (el) => el.matches('script[data-consent], link[rel~="stylesheet"], style')
+    || (el.tagName === 'SCRIPT' && runs(el));
+  const loads = (doc) => [...doc.head.children]
+    .filter((el) => kept(el) && !el.matches('[data-consent]'))
+    .map((el) => el.outerHTML)
+    .sort()
+    .join('\n');
+
+  const ran = new Set([...document.querySelectorAll('script[src]')].map((script) => script.src));
+  const runScripts = (body) => {
+    for (const inert of body.querySelectorAll('script')) {
+      if (!runs(inert)) continue;
+      if (inert.src && ran.has(inert.src)) continue;
+      if (inert.src) ran.add(inert.src);
+      const script = document.createElement('script');
+      for (const { name, value } of inert.attributes) script.setAttribute(name, value);
+      // In page order, like the first page's
+      if (!inert.hasAttribute('async')) script.async = false;
+      script.text = inert.text;
+      inert.replaceWith(script);
+    }
+  };
+
+  const swap = (doc) => {
+    for (const el of [...document.head.children]) if (!kept(el)) el.remove();
+    for (const el of doc.head.children) if (!kept(el)) document.head.append(document.importNode(el, true));
+    for (const el of doc.body.querySelectorAll('[data-zap-permanent]')) {
+      const current = document.querySelector(`[data-zap-permanent="${CSS.escape(el.dataset.zapPermanent)}"]`);
+      if (current) el.replaceWith(current);
+    }
+    document.body.replaceWith(document.adoptNode(doc.body));
+    runScripts(document.body);
+  };
+
+  const scrollTo = (url, scroll) => {
+    const anchor = url.hash && document.getElementById(decodeURIComponent(url.hash.slice(1)));
+    if (scroll != null) window.scrollTo(0, scroll);
+    else if (anchor) anchor.scrollIntoView();
+    else window.scrollTo(0, 0);
+  };
+
+  let visits = 0;
+  let shown = location.pathname + location.search;
+  const visit = (url, push, scroll) => {
+    const visitId = ++visits;
+    const href = url.href.split('#')[0];
+    return fetchPage(href).then(({ url: fetched, html }) => {
+      if (visitId !== visits) return;
+      const doc = new DOMParser().parseFromString(html, 'text/html');
+      const root = document.documentElement;
+      if (loads(doc) !== loads(document) || doc.documentElement.lang !== root.lang || doc.documentElement.dir !== root.dir) {
+        location.href = url.href;
+        return;
+      }
+      const next = new URL(fetched);
+      next.hash = url.hash;
+      if (push) {
+        history.replaceState({ scroll: window.scrollY }, '');
+        history.pushState({}, '', next.href);
+      }
+      swap(doc);
+      shown = location.pathname + location.search;
+      scrollTo(next, scroll);
+      document.dispatchEvent(new CustomEvent('zap:navigate', { detail: { url: next.href } }));
+    }).catch(() => {
+      if (visitId === visits) location.href = url.href;
+    });
+  };
+
+  document.addEventListener('click', (event) => {
+    if (event.defaultPrevented || event.button !== 0) return;
+    if (event.metaKey || event.ctrlKey || event.shiftKey || event.altKey) return;
+    const url = target(event.target.closest && event.target.closest('a[href]'));
+    if (!url) return;
+    event.preventDefault();
+    visit(url, true);
+  });
+
+  // Entries for anchors within a page are left to the browser
+  window.addEventListener('popstate', (event) => {
+    if (location.pathname + location.search === shown) return;
+    visit(new URL(location.href), false, event.state && event.state.scroll);
+  });
+
+  // Hovering a link for a moment, or touching it, fetches its page
+  const saveData = navigator.connection && navigator.connection.saveData;
+  let hover = null;
+  const prefetch = (event) => {
+    const url = target(event.target.closest && event.target.closest('a[href]'));
+    if (url && !saveData) fetchPage(url.href.split('#')[0]).catch(() => {});
+  };
+  document.addEventListener('mouseover', (event) => {
+    clearTimeout(hover);
+    hover = setTimeout(() => prefetch(event), 65);
+  });
+  document.addEventListener('mouseout', () => clearTimeout(hover));
+  document.addEventListener('touchstart', prefetch, { passive: true });
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_script() {
+        assert_eq!(
+            insert_script("<html><head><title>Zap</title></head><body></body></html>".to_string()),
+            "<html><head><title>Zap</title><script src=\"/zap-instant.js\" defer></script>\n</head><body></body></html>"
+        );
+        assert_eq!(insert_script("<p>Zap</p>".to_string()), "<p>Zap</p>");
+    }
+}
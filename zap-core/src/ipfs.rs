@@ -0,0 +1,127 @@
+// Publishing the built site to IPFS. The output directory is added to a
+// local node with the `ipfs` command, which prints the directory's CID, and
+// can then be pinned by a service speaking the IPFS Pinning Service API so
+// it stays available while the local node is offline. A DNSLink TXT record
+// points a domain at the new CID.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Add `dir` and everything in it to the local node, returning the CID of
+/// the directory. `api` is the node's API multiaddr, the `ipfs` command's
+/// default when unset.
+pub fn add(dir: &Path, api: Option<&str>) -> Result<String, String> {
+    let output = ipfs(api)
+        .args(["add", "--recursive", "--quieter", "--cid-version=1"])
+        .arg(dir)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "the ipfs command is not installed, see https://docs.ipfs.tech/install/command-line/".to_string()
+            }
+            _ => format!("could not run ipfs: {}", e),
+        })?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let cid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    match cid.is_empty() {
+        true => Err("ipfs didn't print a CID".to_string()),
+        false => Ok(cid),
+    }
+}
+
+fn ipfs(api: Option<&str>) -> Command {
+    let mut command = Command::new("ipfs");
+    if let Some(api) = api {
+        command.arg("--api").arg(api);
+    }
+    command
+}
+
+/// Ask a pinning service to pin `cid`, returning the status it reports,
+/// usually `queued`. The service fetches the content from the network, so
+/// the local node is connected to the addresses it asks for.
+pub fn pin_remote(service: &str, token: &str, cid: &str, name: &str, api: Option<&str>) -> Result<String, String> {
+    let body = serde_json::json!({ "cid": cid, "name": name });
+    let mut response = ureq::post(&format!("{}/pins", service.trim_end_matches('/')))
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("User-Agent", "zap")
+        .header("Content-Type", "application/json")
+        .send(body.to_string())
+        .map_err(|e| e.to_string())?;
+    let json = crate::github::read_json(&mut response)?;
+
+    for delegate in json["delegates"].as_array().into_iter().flatten().filter_map(|d| d.as_str()) {
+        // Best effort, the service finds the content through the DHT
+        // otherwise, only slower
+        let _ = ipfs(api).args(["swarm", "connect", delegate]).output();
+    }
+    Ok(json["status"].as_str().unwrap_or("queued").to_string())
+}
+
+/// The name of the TXT record holding a domain's DNSLink
+pub fn dnslink_name(domain: &str) -> String {
+    format!("_dnslink.{}", domain.trim_end_matches('.'))
+}
+
+/// The DNSLink TXT record's value for `cid`
+pub fn dnslink_value(cid: &str) -> String {
+    format!("dnslink=/ipfs/{}", cid)
+}
+
+/// Point `domain`'s DNSLink at `cid` through Cloudflare's API, creating the
+/// TXT record or replacing the one there
+pub fn update_dnslink_cloudflare(zone: &str, token: &str, domain: &str, cid: &str) -> Result<(), String> {
+    let records = format!("https://api.cloudflare.com/client/v4/zones/{}/dns_records", zone);
+    let name = dnslink_name(domain);
+    let auth = format!("Bearer {}", token);
+
+    let mut response = ureq::get(&records)
+        .query("type", "TXT")
+        .query("name", &name)
+        .header("Authorization", &auth)
+        .call()
+        .map_err(|e| e.to_string())?;
+    let existing = cloudflare_result(crate::github::read_json(&mut response)?)?;
+    let id = existing[0]["id"].as_str().map(str::to_string);
+
+    let body = serde_json::json!({ "type": "TXT", "name": name, "content": dnslink_value(cid), "ttl": 1 });
+    let request = match &id {
+        Some(id) => ureq::put(&format!("{}/{}", records, id)),
+        None => ureq::post(&records),
+    };
+    let mut response = request
+        .header("Authorization", &auth)
+        .header("Content-Type", "application/json")
+        .send(body.to_string())
+        .map_err(|e| e.to_string())?;
+    cloudflare_result(crate::github::read_json(&mut response)?)?;
+    Ok(())
+}
+
+// Cloudflare wraps every response, reporting failures in `errors`
+fn cloudflare_result(mut json: serde_json::Value) -> Result<serde_json::Value, String> {
+    if json["success"].as_bool() != Some(true) {
+        let message = json["errors"][0]["message"].as_str().unwrap_or("the request failed");
+        return Err(message.to_string());
+    }
+    Ok(json["result"].take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dnslink() {
+        assert_eq!(dnslink_name("docs.example.com."), "_dnslink.docs.example.com");
+        assert_eq!(dnslink_value("bafy123"), "dnslink=/ipfs/bafy123");
+
+        let failed = serde_json::json!({ "success": false, "errors": [{ "code": 9109, "message": "Invalid access token" }] });
+        assert_eq!(cloudflare_result(failed).unwrap_err(), "Invalid access token");
+        let ok = serde_json::json!({ "success": true, "errors": [], "result": [{ "id": "abc" }] });
+        assert_eq!(cloudflare_result(ok).unwrap()[0]["id"], "abc");
+    }
+}
@@ -0,0 +1,210 @@
+// Boilerplate legal pages generated from `[legal]` in zap.toml: a privacy
+// policy, an imprint (Impressum), the project's license and its
+// dependencies' licenses, so small projects can publish them without writing
+// them by hand.
+
+use std::path::Path;
+
+use crate::builder::GeneratedPage;
+use crate::config::{ImprintConfig, LegalConfig, PrivacyPolicyConfig};
+
+const LICENSE_FILES: [&str; 5] = ["LICENSE", "LICENSE.md", "LICENSE.txt", "LICENCE", "COPYING"];
+
+/// What the privacy policy has to mention about how the site is built
+#[derive(Debug, Clone, Default)]
+pub struct SiteFacts {
+    /// Services visits are counted with
+    pub analytics: Vec<String>,
+    /// Pages may load assets from other servers, as privacy mode is off
+    pub third_party_assets: bool,
+}
+
+/// The pages `[legal]` asks for
+pub fn pages(config: &LegalConfig, facts: &SiteFacts, source_dir: &Path) -> std::io::Result<Vec<GeneratedPage>> {
+    let mut pages = Vec::new();
+
+    if let Some(privacy) = &config.privacy {
+        pages.push(GeneratedPage {
+            title: privacy.title.clone(),
+            url: "/privacy/".to_string(),
+            html: markdown_html(&privacy_policy(privacy, facts)),
+        });
+    }
+    if let Some(imprint) = &config.imprint {
+        pages.push(GeneratedPage {
+            title: imprint.title.clone(),
+            url: "/imprint/".to_string(),
+            html: markdown_html(&imprint_markdown(imprint)),
+        });
+    }
+    if config.license {
+        let path = find(source_dir, &LICENSE_FILES).ok_or_else(|| {
+            not_found(format!("[legal] license is set, but there's no LICENSE file in {}", source_dir.display()))
+        })?;
+        let text = crate::encoding::read_to_string(&path)?;
+        let html = match path.extension().is_some_and(|extension| extension == "md") {
+            true => markdown_html(&text),
+            false => format!("<h1>License</h1>\n<pre>{}</pre>\n", html_escape::encode_text(text.trim_end())),
+        };
+        pages.push(GeneratedPage {
+            title: "License".to_string(),
+            url: "/license/".to_string(),
+            html,
+        });
+    }
+    if let Some(third_party) = &config.third_party {
+        let manifest = match &third_party.manifest {
+            Some(manifest) => source_dir.join(manifest),
+            None => find(source_dir, &["Cargo.toml"]).ok_or_else(|| {
+                not_found(format!("[legal.third_party] is set, but there's no Cargo.toml in {}", source_dir.display()))
+            })?,
+        };
+        let dependencies = crate::licenses::dependencies(&manifest)?;
+        pages.push(GeneratedPage {
+            title: third_party.title.clone(),
+            url: "/third-party-licenses/".to_string(),
+            html: crate::licenses::page_html(&third_party.title, &dependencies),
+        });
+    }
+
+    Ok(pages)
+}
+
+// The first of `names` in the source directory, then the directory above
+fn find(source_dir: &Path, names: &[&str]) -> Option<std::path::PathBuf> {
+    let dirs = std::iter::once(source_dir).chain(source_dir.parent());
+    dirs.flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+fn not_found(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message)
+}
+
+fn markdown_html(markdown: &str) -> String {
+    crate::markdown::render_elements_to_html(&crate::markdown::parse_structured(markdown))
+}
+
+// Lines kept apart, as markdown would join them into one paragraph
+fn lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> String {
+    lines.into_iter().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("  \n")
+}
+
+fn email_link(email: &str) -> String {
+    format!("[{}](mailto:{})", email, email)
+}
+
+fn privacy_policy(config: &PrivacyPolicyConfig, facts: &SiteFacts) -> String {
+    let mut markdown = format!("# {}\n\n", config.title);
+    if let Some(updated) = &config.updated {
+        markdown.push_str(&format!("Last updated: {}\n\n", updated));
+    }
+
+    let address = config.address.as_deref().unwrap_or_default();
+    let contact = format!("Email: {}", email_link(&config.email));
+    markdown.push_str(&format!(
+        "## Who is responsible\n\n{}\n\n",
+        lines([config.controller.as_str()].into_iter().chain(address.lines()).chain([contact.as_str()]))
+    ));
+
+    markdown.push_str(
+        "## What this site collects\n\nThis is a static site. It has no accounts and sets no cookies of its own.\n\n",
+    );
+    let host = config.hosting.as_deref().unwrap_or("The server hosting this site");
+    markdown.push_str(&format!(
+        "## Server logs\n\n{} may log each request, including your IP address, your browser and the page requested, \
+         to keep the site running and secure.\n\n",
+        host
+    ));
+
+    if !facts.analytics.is_empty() {
+        markdown.push_str(&format!(
+            "## Visit counting\n\nVisits are counted with {}, which receives the address of the page, the page you \
+             came from and your browser's user agent.\n\n",
+            facts.analytics.join(" and ")
+        ));
+    }
+    if facts.third_party_assets {
+        markdown.push_str(
+            "## Resources from other servers\n\nPages may load scripts, stylesheets, fonts or images from other \
+             servers, which receive your IP address when they do.\n\n",
+        );
+    }
+
+    markdown.push_str(&format!(
+        "## Your rights\n\nYou can ask what data about you is held, and have it corrected or deleted, by writing \
+         to {}.\n",
+        email_link(&config.email)
+    ));
+    markdown
+}
+
+fn imprint_markdown(config: &ImprintConfig) -> String {
+    let mut markdown = format!(
+        "# {}\n\n{}\n\n",
+        config.title,
+        lines([config.name.as_str()].into_iter().chain(config.address.lines()))
+    );
+
+    if let Some(representative) = &config.represented_by {
+        markdown.push_str(&format!("Represented by: {}\n\n", representative));
+    }
+
+    let email = format!("Email: {}", email_link(&config.email));
+    let phone = config.phone.as_ref().map(|phone| format!("Phone: {}", phone));
+    let contact = lines([Some(email.as_str()), phone.as_deref()].into_iter().flatten());
+    markdown.push_str(&format!("## Contact\n\n{}\n\n", contact));
+
+    if let Some(register) = &config.register {
+        markdown.push_str(&format!("## Register entry\n\n{}\n\n", register));
+    }
+    if let Some(vat_id) = &config.vat_id {
+        markdown.push_str(&format!("## VAT ID\n\n{}\n\n", vat_id));
+    }
+    if let Some(responsible) = &config.responsible {
+        markdown.push_str(&format!("## Responsible for content\n\n{}\n\n", responsible));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        let config: LegalConfig = toml::from_str(
+            r#"
+[privacy]
+controller = "Ana Lima"
+email = "privacy@example.com"
+
+[imprint]
+title = "Impressum"
+name = "Ana Lima"
+address = "Hauptstr. 1\n10115 Berlin"
+email = "hello@example.com"
+vat_id = "DE123456789"
+"#,
+        )
+        .unwrap();
+        let facts = SiteFacts {
+            analytics: vec!["example.goatcounter.com".to_string()],
+            third_party_assets: false,
+        };
+
+        let pages = pages(&config, &facts, Path::new("/nonexistent")).unwrap();
+        let urls: Vec<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(urls, ["/privacy/", "/imprint/"]);
+        assert!(pages[0].html.contains("example.goatcounter.com"));
+        assert!(!pages[0].html.contains("other servers"));
+        assert!(pages[1].html.contains("Impressum"));
+        assert!(pages[1].html.contains("DE123456789"));
+
+        let license = LegalConfig {
+            license: true,
+            ..Default::default()
+        };
+        assert!(super::pages(&license, &facts, Path::new("/nonexistent")).is_err());
+    }
+}
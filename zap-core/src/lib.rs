@@ -1,18 +1,91 @@
+pub mod accessibility;
+pub mod anchors;
+pub mod archive;
+pub mod artifacts;
+pub mod asset_usage;
+pub mod badges;
+pub mod ansi;
 pub mod builder;
+pub mod changelog;
+pub mod cjk;
 pub mod config;
+pub mod consent;
+pub mod counter;
+pub mod critical_css;
+pub mod dates;
+pub mod diagnostics;
+pub mod direction;
+pub mod doctor;
+pub mod downloads;
+pub mod encoding;
+pub mod events;
+pub mod externals;
+pub mod faq;
+pub mod footer;
+pub mod forms;
+pub mod git;
+pub mod front_matter;
+pub mod funding;
+pub mod github;
+pub mod help_bundle;
+pub mod history;
+pub mod i18n;
+pub mod incremental;
+pub mod inject;
+pub mod instant;
+pub mod ipfs;
+pub mod legal;
+pub mod licenses;
+pub mod link_check;
+pub mod links;
+pub mod llms;
 pub mod markdown;
+pub mod nav;
+pub mod ordering;
+pub mod page_rules;
+pub mod pagination;
+pub mod permalink;
+pub mod post_build;
+pub mod post_process;
+pub mod posts;
+pub mod privacy;
 pub mod renderer;
+pub mod report;
+pub mod roadmap;
+pub mod sampling;
 pub mod scanner;
+pub mod schema;
+pub mod seo;
 pub mod site;
+pub mod sources;
+pub mod staging;
+pub mod taxonomy;
 pub mod template;
+pub mod theme;
+pub mod toc;
+pub mod upgrade;
+pub mod validate;
+pub mod variants;
+pub mod virtual_collections;
 
 // Re-export main types
-pub use builder::{BuildError, NavItem, RenderError, Site, SiteBuilder, build_site};
+pub use anchors::{Anchor, page_anchors};
+pub use builder::{
+    BuildError, NavItem, RenderError, Site, SiteBuilder, build_site, build_site_incremental,
+    build_site_with_events,
+};
+pub use changelog::{Release, parse_releases};
+pub use diagnostics::{Diagnostic, Severity};
+pub use events::{BuildEvent, BuildStats, EventHandler, PageTimings, TemplateTiming};
+pub use help_bundle::{HelpBundle, HelpBundleError, SearchEntry};
+pub use incremental::BuildCache;
 pub use markdown::{
-    InlineElement, ListItem, PageElement, get_page_structured, parse_page, render_elements_to_html,
+    CodeTab, InlineElement, ListItem, PageElement, get_page_structured, parse_page, render_elements_to_html,
     render_inline_elements_text, slugify,
 };
 pub use renderer::{Renderer, RenderContext};
+pub use report::{BuildReport, PageTiming, PhaseTiming};
 pub use scanner::{ScanError, SiteScanner};
 pub use site::{Collection, Page, PageType, Zap};
 pub use template::{TemplateError, TemplateRenderer};
+pub use toc::{TocEntry, build_toc};
@@ -0,0 +1,176 @@
+// The crates a Rust project ships with and their licenses, from `cargo
+// metadata`, for a third-party licenses page that stays current with
+// Cargo.lock.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    /// SPDX expression, e.g. `MIT OR Apache-2.0`
+    pub license: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Dependencies of the workspace at `manifest` that end up in its builds.
+/// Dev and build dependencies aren't shipped, so they're left out.
+pub fn dependencies(manifest: &Path) -> std::io::Result<Vec<Dependency>> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--locked", "--manifest-path"])
+        .arg(manifest)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "cargo metadata failed for {}: {}",
+            manifest.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    Ok(shipped(&metadata))
+}
+
+// Packages reachable from the workspace members through normal dependencies
+fn shipped(metadata: &serde_json::Value) -> Vec<Dependency> {
+    let ids = |value: &serde_json::Value| -> Vec<String> {
+        value.as_array().into_iter().flatten().filter_map(|id| id.as_str().map(str::to_string)).collect()
+    };
+    let members: HashSet<String> = ids(&metadata["workspace_members"]).into_iter().collect();
+
+    let mut graph: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in metadata["resolve"]["nodes"].as_array().into_iter().flatten() {
+        let deps = node["deps"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|dep| {
+                dep["dep_kinds"]
+                    .as_array()
+                    .is_some_and(|kinds| kinds.iter().any(|kind| kind["kind"].is_null()))
+            })
+            .filter_map(|dep| dep["pkg"].as_str())
+            .collect();
+        if let Some(id) = node["id"].as_str() {
+            graph.insert(id, deps);
+        }
+    }
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = members.iter().map(String::as_str).collect();
+    while let Some(id) = stack.pop() {
+        if seen.insert(id) {
+            stack.extend(graph.get(id).into_iter().flatten());
+        }
+    }
+
+    let mut dependencies: Vec<Dependency> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|package| {
+            package["id"]
+                .as_str()
+                .is_some_and(|id| seen.contains(id) && !members.contains(id))
+        })
+        .map(|package| {
+            let text = |key: &str| package[key].as_str().map(str::to_string);
+            let name = text("name").unwrap_or_default();
+            let version = text("version").unwrap_or_default();
+            let from_registry = text("source").is_some_and(|source| source.starts_with("registry+"));
+            Dependency {
+                url: match from_registry {
+                    true => Some(format!("https://crates.io/crates/{}/{}", name, version)),
+                    false => text("repository").or_else(|| text("homepage")),
+                },
+                license: text("license"),
+                name,
+                version,
+            }
+        })
+        .collect();
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    dependencies
+}
+
+/// The page's content: how many crates use each license, then every crate
+pub fn page_html(title: &str, dependencies: &[Dependency]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for dependency in dependencies {
+        *counts.entry(dependency.license.as_deref().unwrap_or("Unknown")).or_default() += 1;
+    }
+
+    let mut html = format!(
+        "<h1>{}</h1>\n<p>This project is built with {} third-party crates, under these licenses:</p>\n<ul>\n",
+        html_escape::encode_text(title),
+        dependencies.len()
+    );
+    for (license, count) in counts {
+        html.push_str(&format!("<li>{}: {}</li>\n", html_escape::encode_text(license), count));
+    }
+    html.push_str("</ul>\n<table>\n<thead><tr><th>Crate</th><th>Version</th><th>License</th></tr></thead>\n<tbody>\n");
+    for dependency in dependencies {
+        let name = html_escape::encode_text(&dependency.name);
+        let name = match &dependency.url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", html_escape::encode_double_quoted_attribute(url), name),
+            None => name.to_string(),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            name,
+            html_escape::encode_text(&dependency.version),
+            html_escape::encode_text(dependency.license.as_deref().unwrap_or("Unknown"))
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped() {
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        let normal = serde_json::json!([{ "kind": null, "target": null }]);
+        let dev = serde_json::json!([{ "kind": "dev", "target": null }]);
+        let metadata = serde_json::json!({
+            "workspace_members": ["path+file:///app#0.1.0"],
+            "packages": [
+                { "id": "path+file:///app#0.1.0", "name": "app", "version": "0.1.0", "source": null },
+                { "id": "serde", "name": "serde", "version": "1.0.0", "license": "MIT OR Apache-2.0", "source": registry },
+                { "id": "itoa", "name": "itoa", "version": "1.0.1", "license": "MIT", "source": registry },
+                { "id": "tempfile", "name": "tempfile", "version": "3.0.0", "license": "MIT", "source": registry },
+                { "id": "local", "name": "local", "version": "0.2.0", "repository": "https://example.com/local", "source": null },
+            ],
+            "resolve": { "nodes": [
+                { "id": "path+file:///app#0.1.0", "deps": [
+                    { "pkg": "serde", "dep_kinds": normal },
+                    { "pkg": "tempfile", "dep_kinds": dev },
+                    { "pkg": "local", "dep_kinds": normal },
+                ] },
+                { "id": "serde", "deps": [{ "pkg": "itoa", "dep_kinds": normal }] },
+                { "id": "itoa", "deps": [] },
+                { "id": "tempfile", "deps": [] },
+                { "id": "local", "deps": [] },
+            ] },
+        });
+
+        let dependencies = shipped(&metadata);
+        let names: Vec<&str> = dependencies.iter().map(|dependency| dependency.name.as_str()).collect();
+        assert_eq!(names, ["itoa", "local", "serde"]);
+        assert_eq!(dependencies[2].url.as_deref(), Some("https://crates.io/crates/serde/1.0.0"));
+        assert_eq!(dependencies[1].url.as_deref(), Some("https://example.com/local"));
+
+        let html = page_html("Third-Party Licenses", &dependencies);
+        assert!(html.contains("<li>MIT: 1</li>"));
+        assert!(html.contains("<li>Unknown: 1</li>"));
+    }
+}
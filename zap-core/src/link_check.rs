@@ -0,0 +1,234 @@
+// Internal links checked against the built site. Every link in a page's
+// markdown to another page, a file or a `#heading` has to lead to a file in
+// the output directory, and to an element with that id when it has a
+// fragment. Images have to be in the output directory too. Links are read
+// from the markdown rather than the output so broken ones are reported at
+// the line they were written on.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::diagnostics::Diagnostic;
+use crate::links::PageLinks;
+
+/// Every file in the output directory, and the ids in each HTML file
+#[derive(Debug, Default)]
+pub struct OutputMap {
+    files: HashSet<String>,
+    ids: HashMap<String, HashSet<String>>,
+}
+
+impl OutputMap {
+    pub fn read(output_dir: &Path) -> std::io::Result<Self> {
+        let mut map = Self::default();
+        for entry in WalkDir::new(output_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(output_dir).unwrap_or(entry.path());
+            let path: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+            let path = path.join("/");
+            if path.ends_with(".html") {
+                let html = std::fs::read_to_string(entry.path())?;
+                map.ids.insert(path.clone(), ids(&html));
+            }
+            map.files.insert(path);
+        }
+        Ok(map)
+    }
+
+    // The file a URL path is served from: `/guides/` and `/guides` are
+    // `guides/index.html`
+    fn file(&self, url_path: &str) -> Option<&str> {
+        let path = percent_decode(url_path.trim_start_matches('/'));
+        let candidates = match path.is_empty() || path.ends_with('/') {
+            true => vec![format!("{}index.html", path)],
+            false => vec![path.clone(), format!("{}/index.html", path)],
+        };
+        candidates
+            .into_iter()
+            .find_map(|candidate| self.files.get(&candidate).map(String::as_str))
+    }
+}
+
+// Every `id` in a page, and the `name` of old-style `<a name>` anchors
+fn ids(html: &str) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for attribute in [" id=\"", "<a name=\""] {
+        let mut rest = html;
+        while let Some(start) = rest.find(attribute) {
+            rest = &rest[start + attribute.len()..];
+            let Some(end) = rest.find('"') else {
+                break;
+            };
+            ids.insert(html_escape::decode_html_entities(&rest[..end]).into_owned());
+        }
+    }
+    ids
+}
+
+/// A link that leads nowhere in the built site
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    /// The page's markdown file
+    pub source: PathBuf,
+    /// 1-based
+    pub line: usize,
+    pub href: String,
+    pub reason: String,
+    /// An image rather than a link
+    pub image: bool,
+}
+
+impl BrokenLink {
+    fn what(&self) -> &'static str {
+        if self.image { "missing image" } else { "broken link" }
+    }
+
+    /// The link's line of the page, quoted with the link marked
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::warning(format!("{} `{}`, {}", self.what(), self.href, self.reason)).in_file(&self.source);
+        let Ok(source) = std::fs::read_to_string(&self.source) else {
+            return diagnostic;
+        };
+        let column = source
+            .lines()
+            .nth(self.line.saturating_sub(1))
+            .and_then(|line| line.find(&self.href).map(|offset| line[..offset].chars().count() + 1));
+        diagnostic.at_line(&source, self.line, column)
+    }
+}
+
+impl std::fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {} `{}`, {}", self.source.display(), self.line, self.what(), self.href, self.reason)
+    }
+}
+
+/// Check the links in `content`, the source of the page at `page_url`.
+/// Links to other sites, and links in raw HTML, aren't checked.
+pub fn check_page(
+    map: &OutputMap,
+    source: &Path,
+    content: &str,
+    page_url: &str,
+    links: &PageLinks,
+    source_dir: &Path,
+) -> Vec<BrokenLink> {
+    let (_, body) = crate::front_matter::split_front_matter(content);
+    let body_start = content.len() - body.len();
+    let page_dir = source.parent().unwrap_or(source_dir);
+
+    let mut broken = Vec::new();
+    for (event, range) in Parser::new(body).into_offset_iter() {
+        let (dest_url, image) = match event {
+            Event::Start(Tag::Link { dest_url, .. }) => (dest_url, false),
+            Event::Start(Tag::Image { dest_url, .. }) => (dest_url, true),
+            _ => continue,
+        };
+        let Some(reason) = check_link(map, &dest_url, page_url, page_dir, links, source_dir) else {
+            continue;
+        };
+        broken.push(BrokenLink {
+            source: source.to_path_buf(),
+            line: content[..body_start + range.start].matches('\n').count() + 1,
+            href: dest_url.to_string(),
+            reason,
+            image,
+        });
+    }
+    broken
+}
+
+// Why a link is broken, or `None` when it isn't or isn't checked
+fn check_link(
+    map: &OutputMap,
+    href: &str,
+    page_url: &str,
+    page_dir: &Path,
+    links: &PageLinks,
+    source_dir: &Path,
+) -> Option<String> {
+    if href.contains("://") || href.starts_with("//") || (href.contains(':') && !href.contains('/')) {
+        // Other sites, and `mailto:` or `tel:`
+        return None;
+    }
+    let (path, fragment) = match href.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (href, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+
+    let target = match path.is_empty() {
+        true => page_url.to_string(),
+        false if path.to_lowercase().ends_with(".md") => match links.resolve(path, page_dir, source_dir) {
+            Some(url) => url,
+            None => return Some("no page is built from that file".to_string()),
+        },
+        false => crate::links::target_url(path, page_url, page_dir, links, source_dir)?,
+    };
+    let target = target.split('#').next().unwrap_or_default();
+    let Some(file) = map.file(target) else {
+        return Some(format!("nothing is built at {}", target));
+    };
+
+    let fragment = fragment.map(percent_decode).filter(|fragment| !fragment.is_empty())?;
+    let ids = map.ids.get(file)?;
+    match ids.contains(&fragment) {
+        true => None,
+        false => Some(format!("{} has no heading or element with id `{}`", target, fragment)),
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_page() {
+        let mut map = OutputMap::default();
+        for (file, ids) in [("index.html", vec!["top"]), ("guides/install/index.html", vec!["linux", "mac-os"])] {
+            map.files.insert(file.to_string());
+            map.ids.insert(file.to_string(), ids.into_iter().map(str::to_string).collect());
+        }
+        map.files.insert("logo%.png".to_string());
+
+        let mut links = PageLinks::default();
+        links.insert(Path::new("site/guides/install.md"), "/guides/install/".to_string());
+        links.insert(Path::new("site/README.md"), "/".to_string());
+
+        let content = "+++\ntitle = \"Home\"\n+++\n\n[ok](guides/install.md#linux) [ok](/guides/install#mac-os)\n\
+                       [anchor](#nope) [file](/logo%25.png) [remote](https://example.com/#x) [mail](mailto:a@b.c)\n\n\
+                       [missing](guides/usage.md)\n[page](guides/other/)\n![logo](/logo%25.png) ![gone](img/gone.png)\n";
+        let broken = check_page(&map, Path::new("site/README.md"), content, "/", &links, Path::new("site"));
+        let found: Vec<(usize, &str)> = broken.iter().map(|link| (link.line, link.href.as_str())).collect();
+        assert_eq!(found, [(6, "#nope"), (8, "guides/usage.md"), (9, "guides/other/"), (10, "img/gone.png")]);
+        assert_eq!(broken[2].reason, "nothing is built at /guides/other");
+        assert_eq!(broken[3].to_string(), "site/README.md:10: missing image `img/gone.png`, nothing is built at /img/gone.png");
+    }
+}
@@ -0,0 +1,225 @@
+// Links between pages written as their markdown files, `[Install](install.md)`,
+// so they work when browsing the source on GitHub too. Rewritten to wherever
+// the linked page is served, which depends on permalinks and `ugly_urls`.
+// Links in either form also make up the backlinks each page gets.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::builder::NavItem;
+
+/// Page URLs by source file
+#[derive(Default)]
+pub struct PageLinks {
+    urls: BTreeMap<PathBuf, String>,
+}
+
+impl PageLinks {
+    pub fn insert(&mut self, source: &Path, url: String) {
+        self.urls.insert(normalize(source), url);
+    }
+
+    pub fn urls(&self) -> &BTreeMap<PathBuf, String> {
+        &self.urls
+    }
+
+    /// Rewrite `href`s in `html` that point at a markdown file, relative to
+    /// `page_dir` or, starting with `/`, to `source_dir`. Links to files that
+    /// aren't pages are left alone.
+    pub fn rewrite(&self, html: &str, page_dir: &Path, source_dir: &Path) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find("href=\"") {
+            let value_start = start + "href=\"".len();
+            let Some(len) = rest[value_start..].find('"') else {
+                break;
+            };
+            out.push_str(&rest[..value_start]);
+            let href = &rest[value_start..value_start + len];
+            out.push_str(&self.resolve(href, page_dir, source_dir).unwrap_or_else(|| href.to_string()));
+            rest = &rest[value_start + len..];
+        }
+        out.push_str(rest);
+
+        out
+    }
+
+    pub(crate) fn resolve(&self, href: &str, page_dir: &Path, source_dir: &Path) -> Option<String> {
+        if href.contains("://") || href.starts_with("mailto:") || href.starts_with("//") {
+            return None;
+        }
+        let (path, fragment) = match href.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (href, None),
+        };
+        if !path.to_lowercase().ends_with(".md") {
+            return None;
+        }
+
+        let source = match path.strip_prefix('/') {
+            Some(path) => source_dir.join(path),
+            None => page_dir.join(path),
+        };
+        let url = self.urls.get(&normalize(&source))?;
+        Some(match fragment {
+            Some(fragment) => format!("{}#{}", url, fragment),
+            None => url.clone(),
+        })
+    }
+}
+
+/// Pages linking to each page, by the linked page's URL
+#[derive(Debug, Default)]
+pub struct Backlinks {
+    pages: HashSet<String>,
+    linked_from: HashMap<String, Vec<NavItem>>,
+}
+
+impl Backlinks {
+    /// Backlinks between the pages in `links`
+    pub fn new(links: &PageLinks) -> Self {
+        Self {
+            pages: links.urls.values().map(|url| url_key(url)).collect(),
+            linked_from: HashMap::new(),
+        }
+    }
+
+    /// Record the links in `markdown`, the source of the page `from` in
+    /// `page_dir`. Links are to pages as markdown files or as URLs, relative
+    /// or from the root. Links to anything that isn't a page are ignored.
+    pub fn add_page(&mut self, from: &NavItem, markdown: &str, page_dir: &Path, links: &PageLinks, source_dir: &Path) {
+        let from_key = url_key(&from.link);
+        for event in Parser::new(markdown) {
+            let Event::Start(Tag::Link { dest_url, .. }) = event else {
+                continue;
+            };
+            let Some(target) = target_url(&dest_url, &from.link, page_dir, links, source_dir) else {
+                continue;
+            };
+            let key = url_key(&target);
+            if key == from_key || !self.pages.contains(&key) {
+                continue;
+            }
+
+            let pages = self.linked_from.entry(key).or_default();
+            if !pages.iter().any(|page| page.link == from.link) {
+                let at = pages.partition_point(|page| page.text.to_lowercase() <= from.text.to_lowercase());
+                pages.insert(at, from.clone());
+            }
+        }
+    }
+
+    /// Pages linking to `url`, by title
+    pub fn get(&self, url: &str) -> &[NavItem] {
+        self.linked_from.get(&url_key(url)).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+// Where a link in a page at `page_url` goes, without its fragment
+pub(crate) fn target_url(href: &str, page_url: &str, page_dir: &Path, links: &PageLinks, source_dir: &Path) -> Option<String> {
+    if href.contains("://") || href.starts_with("mailto:") || href.starts_with("//") {
+        return None;
+    }
+    let path = href.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    if path.to_lowercase().ends_with(".md") {
+        return links.resolve(path, page_dir, source_dir);
+    }
+    if path.starts_with('/') {
+        return Some(path.to_string());
+    }
+
+    // Relative to the page's directory, which is the page itself for
+    // `/guides/install/`
+    let base = &page_url[..page_url.rfind('/').map_or(0, |i| i + 1)];
+    let joined = format!("{}{}", base, path);
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+// `/guides/install/`, `/guides/install` and `/guides/install/index.html`
+// are the same page
+fn url_key(url: &str) -> String {
+    url.trim_end_matches("index.html").trim_matches('/').to_string()
+}
+
+// Resolves `.` and `..` without touching the filesystem, so paths compare
+// equal however they were written
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let mut links = PageLinks::default();
+        links.insert(Path::new("./site/guides/01-install.md"), "/guides/install.html".to_string());
+        links.insert(Path::new("./site/README.md"), "/".to_string());
+
+        let html = r#"<a href="01-install.md#linux">Install</a> <a href="../README.md">Home</a> <a href="/guides/01-install.md">Again</a> <a href="missing.md">Missing</a> <a href="https://example.com/a.md">Remote</a>"#;
+        assert_eq!(
+            links.rewrite(html, Path::new("./site/guides"), Path::new("./site")),
+            r#"<a href="/guides/install.html#linux">Install</a> <a href="/">Home</a> <a href="/guides/install.html">Again</a> <a href="missing.md">Missing</a> <a href="https://example.com/a.md">Remote</a>"#
+        );
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let mut links = PageLinks::default();
+        links.insert(Path::new("site/guides/install.md"), "/guides/install/".to_string());
+        links.insert(Path::new("site/guides/usage.md"), "/guides/usage/".to_string());
+        links.insert(Path::new("site/faq.md"), "/faq/".to_string());
+
+        let mut backlinks = Backlinks::new(&links);
+        let page = |text: &str, link: &str| NavItem {
+            text: text.to_string(),
+            link: link.to_string(),
+        };
+        backlinks.add_page(
+            &page("Usage", "/guides/usage/"),
+            "[Install](install.md#linux), [again](../install/) and [FAQ](/faq/). [Self](#top) [Out](https://example.com/faq/)",
+            Path::new("site/guides"),
+            &links,
+            Path::new("site"),
+        );
+        backlinks.add_page(
+            &page("FAQ", "/faq/"),
+            "See [install](../guides/install/index.html).",
+            Path::new("site"),
+            &links,
+            Path::new("site"),
+        );
+
+        let titles = |url: &str| backlinks.get(url).iter().map(|page| page.text.clone()).collect::<Vec<_>>();
+        assert_eq!(titles("/guides/install/"), ["FAQ", "Usage"]);
+        assert_eq!(titles("/faq"), ["Usage"]);
+        assert!(titles("/guides/usage/").is_empty());
+    }
+}
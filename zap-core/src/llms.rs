@@ -0,0 +1,110 @@
+// `llms.txt` and `llms-full.txt` output, following https://llmstxt.org: an
+// index of the site's pages for LLM tools, and every page's markdown in one
+// file for tools that want the whole thing.
+
+use std::path::PathBuf;
+
+/// A page listed in `llms.txt`
+#[derive(Debug, Clone)]
+pub struct LlmsEntry {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+    /// Markdown source, for `llms-full.txt`
+    pub source: PathBuf,
+}
+
+/// A `##` section of `llms.txt`
+#[derive(Debug, Clone)]
+pub struct LlmsSection {
+    pub title: String,
+    pub entries: Vec<LlmsEntry>,
+}
+
+pub fn llms_txt(title: &str, summary: Option<&str>, sections: &[LlmsSection]) -> String {
+    let mut out = format!("# {}\n", title);
+    if let Some(summary) = summary {
+        out.push_str(&format!("\n> {}\n", single_line(summary)));
+    }
+
+    for section in sections.iter().filter(|s| !s.entries.is_empty()) {
+        out.push_str(&format!("\n## {}\n\n", section.title));
+        for entry in &section.entries {
+            out.push_str(&format!("- [{}]({})", entry.title, entry.url));
+            if let Some(description) = &entry.description {
+                out.push_str(&format!(": {}", single_line(description)));
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+pub fn llms_full_txt(title: &str, sections: &[LlmsSection]) -> std::io::Result<String> {
+    let mut out = format!("# {}\n", title);
+
+    for entry in sections.iter().flat_map(|s| &s.entries) {
+        let markdown = crate::encoding::read_to_string(&entry.source)?;
+        let (_, body) = crate::front_matter::split_front_matter(&markdown);
+        out.push_str(&format!("\n---\nurl: {}\n---\n\n{}\n", entry.url, body.trim()));
+    }
+
+    Ok(out)
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llms_txt() {
+        let root = std::env::temp_dir().join(format!("zap-llms-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("install.md"), "+++\ntitle = \"Install\"\n+++\n\n# Install\n\nRun the installer.\n\n").unwrap();
+        std::fs::write(root.join("CHANGELOG.md"), "# Changelog\n\n## 1.0.0\n").unwrap();
+
+        let entry = |title: &str, url: &str, description: Option<&str>, source: &str| LlmsEntry {
+            title: title.to_string(),
+            url: url.to_string(),
+            description: description.map(str::to_string),
+            source: root.join(source),
+        };
+        let sections = vec![
+            LlmsSection {
+                title: "Docs".to_string(),
+                entries: vec![entry("Install", "/install/", Some("Run the\n  installer."), "install.md")],
+            },
+            LlmsSection {
+                title: "Guides".to_string(),
+                entries: vec![],
+            },
+            LlmsSection {
+                title: "Optional".to_string(),
+                entries: vec![entry("Changelog", "/changelog/", None, "CHANGELOG.md")],
+            },
+        ];
+
+        // Empty sections are left out, and descriptions kept to one line
+        assert_eq!(
+            llms_txt("Zap", Some("Static sites\nfrom markdown."), &sections),
+            "# Zap\n\n> Static sites from markdown.\n\n## Docs\n\n- [Install](/install/): Run the installer.\n\n## Optional\n\n- [Changelog](/changelog/)\n"
+        );
+        assert_eq!(llms_txt("Zap", None, &[]), "# Zap\n");
+
+        // Front matter is left out of the full text
+        assert_eq!(
+            llms_full_txt("Zap", &sections).unwrap(),
+            "# Zap\n\n---\nurl: /install/\n---\n\n# Install\n\nRun the installer.\n\n---\nurl: /changelog/\n---\n\n# Changelog\n\n## 1.0.0\n"
+        );
+
+        std::fs::remove_file(root.join("CHANGELOG.md")).unwrap();
+        assert!(llms_full_txt("Zap", &sections).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,4 +1,6 @@
+use std::cell::Cell;
 use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd, html, CodeBlockKind};
 use syntect::highlighting::ThemeSet;
 use syntect::html::highlighted_html_for_string;
@@ -8,8 +10,17 @@ use syntect::parsing::SyntaxSet;
 static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
 static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
 
+thread_local! {
+    static HIGHLIGHT_TIME: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Time this thread has spent highlighting code blocks, for build timings
+pub fn highlight_time() -> Duration {
+    HIGHLIGHT_TIME.get()
+}
+
 pub fn parse_page(path: &str) -> Result<String, std::io::Error> {
-    let content = std::fs::read_to_string(path)?;
+    let content = crate::encoding::read_to_string(path)?;
     let options = Options::all();
     let parser = Parser::new_ext(&content, options);
 
@@ -77,6 +88,34 @@ pub enum PageElement {
     Table { headers: Vec<Vec<InlineElement>>, rows: Vec<Vec<Vec<InlineElement>>> },
     HorizontalRule,
     Html { content: String },
+    Tabs { tabs: Vec<CodeTab> },
+    TableOfContents,
+}
+
+/// A single code sample inside a `::: tabs` container
+#[derive(Debug, Clone)]
+pub struct CodeTab {
+    pub label: String,
+    pub language: Option<String>,
+    pub content: String,
+}
+
+impl CodeTab {
+    /// Build a tab from a fenced block. The first word of the info string is
+    /// the language, anything after it is used as the tab label.
+    fn from_code_block(info: Option<&str>, content: &str) -> Self {
+        let info = info.unwrap_or_default().trim();
+        let (language, label) = match info.split_once(char::is_whitespace) {
+            Some((lang, label)) => (lang, label.trim()),
+            None => (info, info),
+        };
+
+        Self {
+            label: if label.is_empty() { "Code".to_string() } else { label.to_string() },
+            language: if language.is_empty() { None } else { Some(language.to_string()) },
+            content: content.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -103,8 +142,11 @@ struct Heading {
     text: String,
 }
 
+// Pages that can't be read have no headings. The scanner reports them.
 fn get_page_headings(path: &std::path::PathBuf) -> Vec<Heading> {
-    let content = std::fs::read_to_string(path).expect("Faild to rd some page sry");
+    let Ok(content) = crate::encoding::read_to_string(path) else {
+        return Vec::new();
+    };
     let options = Options::all();
     let parser = Parser::new_ext(&content, options);
 
@@ -140,10 +182,26 @@ fn get_page_headings(path: &std::path::PathBuf) -> Vec<Heading> {
     headings
 }
 
+// A definition list's `:` lines collide with `:::` container markers, so
+// pages with containers are parsed without them
+fn structured_options(content: &str) -> Options {
+    let mut options = Options::all();
+    if content.lines().any(|line| line.trim_start().starts_with(CONTAINER_CLOSE)) {
+        options.remove(Options::ENABLE_DEFINITION_LIST);
+    }
+    options
+}
+
+/// A page that can't be read, like one removed since it was scanned, has no
+/// elements
 pub fn get_page_structured(path: &std::path::PathBuf) -> Vec<PageElement> {
-    let content = std::fs::read_to_string(path).expect("Failed to read page");
-    let options = Options::all();
-    let parser = Parser::new_ext(&content, options);
+    let content = crate::encoding::read_to_string(path).unwrap_or_default();
+    parse_structured(&content)
+}
+
+pub fn parse_structured(content: &str) -> Vec<PageElement> {
+    let options = structured_options(content);
+    let parser = Parser::new_ext(content, options);
 
     let mut elements = Vec::new();
     let mut stack: Vec<ElementBuilder> = Vec::new();
@@ -268,7 +326,89 @@ pub fn get_page_structured(path: &std::path::PathBuf) -> Vec<PageElement> {
         }
     }
     
+    mark_toc(group_tabs(elements))
+}
+
+const TOC_MARKER: &str = "[TOC]";
+
+/// Replace a paragraph containing only `[TOC]` with an inline table of
+/// contents. `[TOC]` as inline code is left as it is.
+fn mark_toc(elements: Vec<PageElement>) -> Vec<PageElement> {
     elements
+        .into_iter()
+        .map(|element| match &element {
+            PageElement::Paragraph { content }
+                if content.iter().all(|inline| matches!(inline, InlineElement::Text(_)))
+                    && render_inline_elements_text(content).trim() == TOC_MARKER =>
+            {
+                PageElement::TableOfContents
+            }
+            _ => element,
+        })
+        .collect()
+}
+
+const TABS_OPEN: &str = "::: tabs";
+const CONTAINER_CLOSE: &str = ":::";
+
+fn is_container_marker(element: &PageElement, marker: &str) -> bool {
+    match element {
+        PageElement::Paragraph { content } => render_inline_elements_text(content).trim() == marker,
+        _ => false,
+    }
+}
+
+/// Collapse code blocks wrapped in `::: tabs` / `:::` markers into a single
+/// tabbed element. Anything else in the container is kept where it was,
+/// before or after the tabs. Unterminated containers are left untouched.
+fn group_tabs(elements: Vec<PageElement>) -> Vec<PageElement> {
+    let mut grouped = Vec::with_capacity(elements.len());
+    let mut iter = elements.into_iter();
+
+    while let Some(element) = iter.next() {
+        if !is_container_marker(&element, TABS_OPEN) {
+            grouped.push(element);
+            continue;
+        }
+
+        let mut inside = Vec::new();
+        let mut closed = false;
+        for inner in iter.by_ref() {
+            if is_container_marker(&inner, CONTAINER_CLOSE) {
+                closed = true;
+                break;
+            }
+            inside.push(inner);
+        }
+        if !closed {
+            grouped.push(element);
+            grouped.extend(inside);
+            continue;
+        }
+
+        let before = inside
+            .iter()
+            .position(|inner| matches!(inner, PageElement::CodeBlock { .. }))
+            .unwrap_or(inside.len());
+        let mut tabs = Vec::new();
+        let mut rest = Vec::new();
+        for inner in inside {
+            match &inner {
+                PageElement::CodeBlock { language, content } => {
+                    tabs.push(CodeTab::from_code_block(language.as_deref(), content))
+                }
+                _ => rest.push(inner),
+            }
+        }
+        let after = rest.split_off(before);
+        grouped.extend(rest);
+        if !tabs.is_empty() {
+            grouped.push(PageElement::Tabs { tabs });
+        }
+        grouped.extend(after);
+    }
+
+    grouped
 }
 
 #[derive(Debug)]
@@ -296,6 +436,7 @@ enum BuilderKind {
     Strikethrough,
     Link(String, Option<String>), // url, title
     Image(String, Option<String>), // url, title
+    Metadata, // front matter, not rendered
 }
 
 #[derive(Debug, Default)]
@@ -332,6 +473,7 @@ impl ElementBuilder {
                 let title_str = if title.is_empty() { None } else { Some(title.to_string()) };
                 BuilderKind::Image(dest_url.to_string(), title_str)
             }
+            Tag::MetadataBlock(_) => BuilderKind::Metadata,
             _ => BuilderKind::Paragraph, // Fallback
         };
         
@@ -430,47 +572,50 @@ pub fn get_page_title(path: &std::path::PathBuf) -> String {
 // HTML Rendering functions
 pub fn render_elements_to_html(elements: &[PageElement]) -> String {
     let mut html = String::new();
+    let mut anchor_ids = crate::anchors::page_anchors(elements).into_iter().map(|a| a.id);
     
     for element in elements {
-        html.push_str(&render_element(element));
+        let anchor_id = match element {
+            PageElement::Heading { .. } => anchor_ids.next(),
+            _ => None,
+        };
+
+        // The inline TOC needs the whole page, not just its own element
+        if matches!(element, PageElement::TableOfContents) {
+            let toc = crate::toc::build_toc(elements);
+            html.push_str(&format!(
+                "<nav class=\"toc\">\n{}</nav>\n",
+                crate::toc::render_toc_html(&toc)
+            ));
+            continue;
+        }
+
+        html.push_str(&render_element(element, anchor_id.as_deref()));
+    }
+
+    if elements.iter().any(|e| matches!(e, PageElement::Tabs { .. })) {
+        html.push_str(TABS_SCRIPT);
     }
     
     html
 }
 
-fn render_element(element: &PageElement) -> String {
+fn render_element(element: &PageElement, anchor_id: Option<&str>) -> String {
     match element {
         PageElement::Heading { level, content } => {
             let text = render_inline_elements_text(content);
             let slug = slugify(&text);
             let rendered_content = render_inline_elements(content);
-            format!("<h{0} id=\"{1}\">{2}</h{0}>\n", level, slug, rendered_content)
+            let anchor_attr = anchor_id
+                .map(|id| format!(" data-anchor-id=\"{}\"", id))
+                .unwrap_or_default();
+            format!("<h{0} id=\"{1}\"{2}>{3}</h{0}>\n", level, slug, anchor_attr, rendered_content)
         }
         PageElement::Paragraph { content } => {
             format!("<p>{}</p>\n", render_inline_elements(content))
         }
         PageElement::CodeBlock { language, content } => {
-            if let Some(lang) = language {
-                // Use syntect for highlighting
-                let syntax = SYNTAX_SET.find_syntax_by_token(lang)
-                    .or_else(|| {
-                        match lang.as_str() {
-                            "nix" => SYNTAX_SET.find_syntax_by_name("JavaScript"),
-                            "toml" => SYNTAX_SET.find_syntax_by_name("YAML"),
-                            _ => None
-                        }
-                    });
-                
-                if let Some(syntax) = syntax {
-                    let theme = &THEME_SET.themes["base16-ocean.dark"];
-                    highlighted_html_for_string(content, &SYNTAX_SET, syntax, theme)
-                        .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>\n", html_escape::encode_text(content)))
-                } else {
-                    format!("<pre><code>{}</code></pre>\n", html_escape::encode_text(content))
-                }
-            } else {
-                format!("<pre><code>{}</code></pre>\n", html_escape::encode_text(content))
-            }
+            highlight_code(language.as_deref(), content)
         }
         PageElement::List { items, ordered } => {
             let tag = if *ordered { "ol" } else { "ul" };
@@ -488,9 +633,106 @@ fn render_element(element: &PageElement) -> String {
         }
         PageElement::HorizontalRule => "<hr />\n".to_string(),
         PageElement::Html { content } => format!("{}\n", content),
+        PageElement::Tabs { tabs } => render_tabs(tabs),
+        // Rendered by render_elements_to_html, which has the full page
+        PageElement::TableOfContents => String::new(),
+    }
+}
+
+fn highlight_code(language: Option<&str>, content: &str) -> String {
+    let started = Instant::now();
+    let html = highlight(language, content);
+    HIGHLIGHT_TIME.set(HIGHLIGHT_TIME.get() + started.elapsed());
+    html
+}
+
+fn highlight(language: Option<&str>, content: &str) -> String {
+    let plain = || format!("<pre><code>{}</code></pre>\n", html_escape::encode_text(content));
+
+    let Some(lang) = language else {
+        return plain();
+    };
+
+    // Terminal output keeps its own colors instead of being highlighted
+    if matches!(lang, "console" | "ansi") {
+        return crate::ansi::render_ansi(content);
+    }
+
+    // Use syntect for highlighting
+    let syntax = SYNTAX_SET.find_syntax_by_token(lang)
+        .or_else(|| {
+            match lang {
+                "nix" => SYNTAX_SET.find_syntax_by_name("JavaScript"),
+                "toml" => SYNTAX_SET.find_syntax_by_name("YAML"),
+                _ => None
+            }
+        });
+
+    if let Some(syntax) = syntax {
+        let theme = &THEME_SET.themes["base16-ocean.dark"];
+        highlighted_html_for_string(content, &SYNTAX_SET, syntax, theme)
+            .unwrap_or_else(|_| plain())
+    } else {
+        plain()
     }
 }
 
+fn render_tabs(tabs: &[CodeTab]) -> String {
+    let mut html = String::from("<div class=\"zap-tabs\" data-zap-tabs>\n<div class=\"zap-tabs-list\" role=\"tablist\">\n");
+
+    for (i, tab) in tabs.iter().enumerate() {
+        html.push_str(&format!(
+            "<button type=\"button\" role=\"tab\" aria-selected=\"{}\" tabindex=\"{}\">{}</button>\n",
+            i == 0,
+            if i == 0 { 0 } else { -1 },
+            html_escape::encode_text(&tab.label)
+        ));
+    }
+    html.push_str("</div>\n");
+
+    for (i, tab) in tabs.iter().enumerate() {
+        html.push_str(&format!(
+            "<div class=\"zap-tabs-panel\" role=\"tabpanel\"{}>\n{}</div>\n",
+            if i == 0 { "" } else { " hidden" },
+            highlight_code(tab.language.as_deref(), &tab.content)
+        ));
+    }
+
+    html.push_str("</div>\n");
+    html
+}
+
+// Wires up tab buttons to their panels. Guarded so pages with several tab
+// groups only install the handlers once.
+const TABS_SCRIPT: &str = r#"<script>
+(function() {
+    if (window.__zapTabs) return;
+    window.__zapTabs = true;
+    document.addEventListener('DOMContentLoaded', function() {
+        document.querySelectorAll('[data-zap-tabs]').forEach(function(group) {
+            var tabs = Array.from(group.querySelectorAll('[role="tab"]'));
+            var panels = Array.from(group.querySelectorAll('[role="tabpanel"]'));
+            function select(index) {
+                tabs.forEach(function(tab, i) {
+                    tab.setAttribute('aria-selected', i === index);
+                    tab.tabIndex = i === index ? 0 : -1;
+                    panels[i].hidden = i !== index;
+                });
+                tabs[index].focus();
+            }
+            tabs.forEach(function(tab, i) {
+                tab.addEventListener('click', function() { select(i); });
+                tab.addEventListener('keydown', function(e) {
+                    if (e.key === 'ArrowRight') select((i + 1) % tabs.length);
+                    if (e.key === 'ArrowLeft') select((i - 1 + tabs.length) % tabs.length);
+                });
+            });
+        });
+    });
+})();
+</script>
+"#;
+
 pub fn render_inline_elements_text(elements: &[InlineElement]) -> String {
     let mut text = String::new();
     
@@ -533,7 +775,7 @@ pub fn slugify(text: &str) -> String {
         .to_string()
 }
 
-fn render_inline_elements(elements: &[InlineElement]) -> String {
+pub fn render_inline_elements(elements: &[InlineElement]) -> String {
     let mut html = String::new();
     
     for element in elements {
@@ -634,4 +876,65 @@ fn render_table(headers: &[Vec<InlineElement>], rows: &[Vec<Vec<InlineElement>>]
     
     html.push_str("</table>\n");
     html
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(element: &PageElement) -> Vec<&str> {
+        match element {
+            PageElement::Tabs { tabs } => tabs.iter().map(|tab| tab.label.as_str()).collect(),
+            _ => panic!("not tabs: {:?}", element),
+        }
+    }
+
+    fn text(element: &PageElement) -> String {
+        match element {
+            PageElement::Paragraph { content } => render_inline_elements_text(content),
+            _ => panic!("not a paragraph: {:?}", element),
+        }
+    }
+
+    #[test]
+    fn test_group_tabs() {
+        let elements = parse_structured("Intro\n\n::: tabs\n```bash Linux\nls\n```\n```powershell\ndir\n```\n:::\n\nOutro\n");
+        assert_eq!(elements.len(), 3);
+        assert_eq!(text(&elements[0]), "Intro");
+        assert_eq!(labels(&elements[1]), ["Linux", "powershell"]);
+        match &elements[1] {
+            PageElement::Tabs { tabs } => {
+                assert_eq!(tabs[0].language.as_deref(), Some("bash"));
+                assert_eq!(tabs[1].content, "dir\n");
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(text(&elements[2]), "Outro");
+
+        // Without a closing marker nothing is grouped
+        let elements = parse_structured("::: tabs\n\n```bash\nls\n```\n");
+        assert_eq!(text(&elements[0]), "::: tabs");
+        assert!(matches!(elements[1], PageElement::CodeBlock { .. }));
+
+        // Text in the container isn't dropped
+        let elements = parse_structured("::: tabs\n\nPick one:\n\n```bash\nls\n```\n\nEither works.\n\n```sh\nls\n```\n\n:::\n");
+        assert_eq!(elements.len(), 3);
+        assert_eq!(text(&elements[0]), "Pick one:");
+        assert_eq!(labels(&elements[1]), ["bash", "sh"]);
+        assert_eq!(text(&elements[2]), "Either works.");
+
+        let elements = parse_structured("::: tabs\n\nNo code yet.\n\n:::\n");
+        assert_eq!(elements.len(), 1);
+        assert_eq!(text(&elements[0]), "No code yet.");
+    }
+
+    #[test]
+    fn test_definition_lists() {
+        let has_definition_list = |content: &str| {
+            Parser::new_ext(content, structured_options(content))
+                .any(|event| matches!(event, Event::Start(Tag::DefinitionList)))
+        };
+        assert!(has_definition_list("Term\n: Definition\n"));
+        assert!(!has_definition_list("Term\n: Definition\n\n::: tabs\n```bash\nls\n```\n:::\n"));
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+
+use crate::builder::NavItem;
+
+/// An entry in a collection's nested sidebar. Subdirectories become entries
+/// with children, linked to the directory's `index.md` when it has one.
+#[derive(Debug, Clone, Serialize)]
+pub struct NavNode {
+    pub text: String,
+    pub link: Option<String>,
+    /// This entry is the current page
+    pub current: bool,
+    /// The current page is this entry or one of its descendants
+    pub active: bool,
+    pub children: Vec<NavNode>,
+    // Source directory name, for directory entries
+    #[serde(skip)]
+    dir: Option<String>,
+}
+
+/// A page's place in the tree: the directories between the collection and
+/// the page, and whether it's the `index.md` of the innermost one
+pub struct TreeEntry {
+    pub dirs: Vec<String>,
+    pub is_index: bool,
+    pub item: NavItem,
+}
+
+/// Build a tree from pages in sidebar order. Directories appear where their
+/// first page does.
+pub fn build_tree(entries: impl IntoIterator<Item = TreeEntry>) -> Vec<NavNode> {
+    let mut root = Vec::new();
+    for entry in entries {
+        insert(&mut root, &entry.dirs, entry.is_index, entry.item);
+    }
+    root
+}
+
+fn insert(nodes: &mut Vec<NavNode>, dirs: &[String], is_index: bool, item: NavItem) {
+    let Some((dir, rest)) = dirs.split_first() else {
+        nodes.push(NavNode {
+            text: item.text,
+            link: Some(item.link),
+            current: false,
+            active: false,
+            children: Vec::new(),
+            dir: None,
+        });
+        return;
+    };
+
+    let position = match nodes.iter().position(|node| node.dir.as_ref() == Some(dir)) {
+        Some(position) => position,
+        None => {
+            nodes.push(NavNode {
+                text: dir_title(dir),
+                link: None,
+                current: false,
+                active: false,
+                children: Vec::new(),
+                dir: Some(dir.clone()),
+            });
+            nodes.len() - 1
+        }
+    };
+
+    let node = &mut nodes[position];
+    if rest.is_empty() && is_index {
+        node.text = item.text;
+        node.link = Some(item.link);
+    } else {
+        insert(&mut node.children, rest, is_index, item);
+    }
+}
+
+/// Mark the entry for `link` as current, and it and its parents as active
+pub fn mark_active(nodes: &mut [NavNode], link: &str) -> bool {
+    let mut found = false;
+    for node in nodes {
+        let child_active = mark_active(&mut node.children, link);
+        node.current = node.link.as_deref() == Some(link);
+        node.active = child_active || node.current;
+        found |= node.active;
+    }
+    found
+}
+
+// `02-advanced_topics` reads as "Advanced Topics"
+fn dir_title(name: &str) -> String {
+    crate::ordering::split_order_prefix(name)
+        .1
+        .split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dirs: &[&str], is_index: bool, text: &str) -> TreeEntry {
+        TreeEntry {
+            dirs: dirs.iter().map(|d| d.to_string()).collect(),
+            is_index,
+            item: NavItem {
+                text: text.to_string(),
+                link: format!("/{}/", text),
+            },
+        }
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let mut tree = build_tree([
+            entry(&[], true, "overview"),
+            entry(&["02-advanced_topics"], false, "plugins"),
+            entry(&["02-advanced_topics", "deep"], false, "internals"),
+            entry(&["basics"], true, "basics"),
+            entry(&[], false, "faq"),
+            entry(&["basics"], false, "install"),
+        ]);
+
+        let texts: Vec<&str> = tree.iter().map(|node| node.text.as_str()).collect();
+        assert_eq!(texts, ["overview", "Advanced Topics", "basics", "faq"]);
+        assert_eq!(tree[1].link, None);
+        assert_eq!(tree[1].children[1].text, "Deep");
+        assert_eq!(tree[1].children[1].children[0].text, "internals");
+        assert_eq!(tree[2].link.as_deref(), Some("/basics/"));
+        assert_eq!(tree[2].children[0].text, "install");
+
+        assert!(mark_active(&mut tree, "/internals/"));
+        assert!(tree[1].active && tree[1].children[1].active);
+        assert!(tree[1].children[1].children[0].current && !tree[1].current);
+        assert!(!tree[0].active && !tree[2].active);
+    }
+}
@@ -0,0 +1,121 @@
+// Page order within a collection. Pages listed in the collection's `order`
+// config come first, then pages by front matter `weight`, then by numeric
+// filename prefix (`01-install.md`), then by path. The prefix only orders
+// the page and is left out of its URL.
+
+use std::path::{Path, PathBuf};
+
+use crate::site::{Page, PageType};
+
+/// Split a numeric ordering prefix off a file stem: `01-install` gives
+/// `(Some(1), "install")`. Stems like `2024-01-01-release` aren't split, so
+/// date-prefixed names keep their full URL.
+pub fn split_order_prefix(stem: &str) -> (Option<u32>, &str) {
+    let digits = stem.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return (None, stem);
+    }
+
+    let rest = &stem[digits..];
+    let Some(name) = rest.strip_prefix('-').or_else(|| rest.strip_prefix('_')) else {
+        return (None, stem);
+    };
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return (None, stem);
+    }
+
+    match stem[..digits].parse() {
+        Ok(position) => (Some(position), name),
+        Err(_) => (None, stem),
+    }
+}
+
+/// A page's path without its extension or ordering prefix, as used in URLs
+pub fn url_path(relative_path: &Path) -> PathBuf {
+    let without_extension = relative_path.with_extension("");
+    let Some(stem) = without_extension.file_name().map(|s| s.to_string_lossy().to_string()) else {
+        return without_extension;
+    };
+
+    without_extension.with_file_name(split_order_prefix(&stem).1)
+}
+
+/// Sort pages found under `dir`. `order` lists page paths relative to `dir`,
+/// as they appear in URLs (`install`, `advanced/custom-themes`).
+pub fn sort_pages(pages: &mut [Page], dir: &Path, order: &[String]) {
+    pages.sort_by_cached_key(|page| {
+        let relative = page.path.strip_prefix(dir).unwrap_or(&page.path);
+        let slug = url_path(relative).to_string_lossy().replace('\\', "/");
+        let listed = order.iter().position(|entry| entry.trim_matches('/') == slug);
+        let prefix = page
+            .path
+            .file_stem()
+            .and_then(|stem| split_order_prefix(&stem.to_string_lossy()).0);
+
+        (
+            // A collection's landing page leads its navigation
+            !(matches!(page.page_type, PageType::Index) && page.path.parent() == Some(dir)),
+            listed.unwrap_or(usize::MAX),
+            page.weight.is_none(),
+            page.weight,
+            prefix.is_none(),
+            prefix,
+            page.path.clone(),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_order_prefix() {
+        assert_eq!(split_order_prefix("01-install"), (Some(1), "install"));
+        assert_eq!(split_order_prefix("10_advanced"), (Some(10), "advanced"));
+        assert_eq!(split_order_prefix("install"), (None, "install"));
+        assert_eq!(split_order_prefix("404"), (None, "404"));
+        assert_eq!(split_order_prefix("2024-01-01-release"), (None, "2024-01-01-release"));
+    }
+
+    #[test]
+    fn test_sort_pages() {
+        let page = |name: &str, weight: Option<i64>| Page {
+            title: name.to_string(),
+            path: PathBuf::from("guides").join(name),
+            page_type: PageType::Regular,
+            tags: Vec::new(),
+            weight,
+            date: None,
+            expires: None,
+            draft: false,
+            description: None,
+            image: None,
+            language: None,
+            dir: None,
+            extra: Default::default(),
+            encoding: Default::default(),
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: Default::default(),
+        };
+        let mut pages = vec![
+            page("zebra.md", None),
+            page("02-second.md", None),
+            page("01-first.md", None),
+            page("heavy.md", Some(10)),
+            page("light.md", Some(1)),
+            page("pinned.md", None),
+        ];
+
+        sort_pages(&mut pages, Path::new("guides"), &["pinned".to_string()]);
+
+        let titles: Vec<&str> = pages.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["pinned.md", "light.md", "heavy.md", "01-first.md", "02-second.md", "zebra.md"]
+        );
+    }
+}
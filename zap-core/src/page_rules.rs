@@ -0,0 +1,160 @@
+// `[[page_rules]]` in zap.toml: give pages matching a glob a type, template
+// or URL, instead of relying on file names like README.md and index.md.
+
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::config::PageRule;
+use crate::site::{Page, PageType};
+
+#[derive(Debug)]
+pub struct PageRuleError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for PageRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "page rule \"{}\": {}", self.pattern, self.message)
+    }
+}
+
+impl std::error::Error for PageRuleError {}
+
+pub struct PageRules {
+    rules: Vec<(GlobMatcher, PageRule)>,
+}
+
+impl PageRules {
+    pub fn new(rules: &[PageRule]) -> Result<Self, PageRuleError> {
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let error = |message: String| PageRuleError {
+                pattern: rule.pattern.clone(),
+                message,
+            };
+            // `*` stays within a directory, `**` crosses them
+            let glob = GlobBuilder::new(&rule.pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| error(e.kind().to_string()))?;
+            if let Some(url) = &rule.url {
+                crate::permalink::validate(url).map_err(|e| error(e.to_string()))?;
+            }
+            compiled.push((glob.compile_matcher(), rule.clone()));
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the first rule matching the page's path relative to
+    /// `source_dir`
+    pub fn apply(&self, page: &mut Page, source_dir: &Path) -> Result<(), PageRuleError> {
+        let relative = page.path.strip_prefix(source_dir).unwrap_or(&page.path);
+        let matched = self.rules.iter().find(|(matcher, _)| matcher.is_match(relative));
+        let Some((_, rule)) = matched else {
+            return Ok(());
+        };
+
+        if let Some(name) = &rule.page_type {
+            page.page_type = page_type(name);
+            if let PageType::Custom(name) = &page.page_type {
+                page.template = Some(format!("{}.html", name));
+            }
+        }
+        if let Some(template) = &rule.template {
+            page.template = Some(template.clone());
+        }
+        if let Some(url) = &rule.url {
+            let permalink = crate::permalink::expand(url, relative, page.date.as_ref()).map_err(|e| PageRuleError {
+                pattern: rule.pattern.clone(),
+                message: format!("{}: {}", relative.display(), e),
+            })?;
+            page.permalink = Some(permalink);
+        }
+        Ok(())
+    }
+}
+
+/// The page type a rule names. Names that aren't built in are custom types.
+pub fn page_type(name: &str) -> PageType {
+    match name.to_lowercase().as_str() {
+        "home" => PageType::Home,
+        "changelog" => PageType::Changelog,
+        "index" => PageType::Index,
+        "doc" => PageType::Doc,
+        "page" | "regular" => PageType::Regular,
+        _ => PageType::Custom(name.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn page(path: &str) -> Page {
+        Page {
+            title: "Hello".to_string(),
+            path: PathBuf::from("/site").join(path),
+            page_type: PageType::Regular,
+            tags: Vec::new(),
+            weight: None,
+            date: crate::dates::parse_date("2024-03-09", chrono_tz::Tz::UTC).ok(),
+            expires: None,
+            draft: false,
+            description: None,
+            image: None,
+            language: None,
+            dir: None,
+            extra: Default::default(),
+            encoding: crate::encoding::Encoding::Utf8,
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_page_rules() {
+        let rules = PageRules::new(&[
+            PageRule {
+                pattern: "blog/*.md".to_string(),
+                page_type: Some("post".to_string()),
+                url: Some("/blog/:year/:slug/".to_string()),
+                ..Default::default()
+            },
+            PageRule {
+                pattern: "blog/**".to_string(),
+                template: Some("archive.html".to_string()),
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+        let source_dir = Path::new("/site");
+
+        let mut post = page("blog/02-hello.md");
+        rules.apply(&mut post, source_dir).unwrap();
+        assert!(matches!(&post.page_type, PageType::Custom(name) if name == "post"));
+        assert_eq!(post.template_name(), "post.html");
+        assert_eq!(post.url(source_dir), "/blog/2024/hello/");
+
+        // `*` doesn't cross directories, so the second rule applies
+        let mut archived = page("blog/2019/old.md");
+        rules.apply(&mut archived, source_dir).unwrap();
+        assert_eq!(archived.template_name(), "archive.html");
+        assert_eq!(archived.url(source_dir), "/blog/2019/old/");
+
+        assert!(PageRules::new(&[PageRule {
+            pattern: "blog/[.md".to_string(),
+            ..Default::default()
+        }])
+        .is_err());
+    }
+}
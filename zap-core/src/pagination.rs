@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::builder::NavItem;
+
+/// One page of a collection's index, exposed to templates as `paginator`
+#[derive(Debug, Clone, Serialize)]
+pub struct Paginator {
+    pub items: Vec<NavItem>,
+    /// 1-based
+    pub current: usize,
+    pub total_pages: usize,
+    pub total_items: usize,
+    pub page_size: usize,
+    pub url: String,
+    pub first_url: String,
+    pub last_url: String,
+    pub previous_url: Option<String>,
+    pub next_url: Option<String>,
+}
+
+/// URL of page `n` of an index at `base_url`; the first page is the index
+/// itself, later ones live under `page/<n>/`
+pub fn page_url(base_url: &str, n: usize) -> String {
+    if n <= 1 {
+        base_url.to_string()
+    } else {
+        format!("{}page/{}/", base_url, n)
+    }
+}
+
+/// Split `items` into pages of `page_size`. There is always at least one
+/// page, even with no items.
+pub fn paginate(items: &[NavItem], page_size: usize, base_url: &str) -> Vec<Paginator> {
+    let page_size = page_size.max(1);
+    let total_pages = items.len().div_ceil(page_size).max(1);
+
+    (1..=total_pages)
+        .map(|current| {
+            let start = (current - 1) * page_size;
+            let end = (start + page_size).min(items.len());
+            Paginator {
+                items: items[start..end].to_vec(),
+                current,
+                total_pages,
+                total_items: items.len(),
+                page_size,
+                url: page_url(base_url, current),
+                first_url: page_url(base_url, 1),
+                last_url: page_url(base_url, total_pages),
+                previous_url: (current > 1).then(|| page_url(base_url, current - 1)),
+                next_url: (current < total_pages).then(|| page_url(base_url, current + 1)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(count: usize) -> Vec<NavItem> {
+        (1..=count)
+            .map(|n| NavItem {
+                text: format!("Post {}", n),
+                link: format!("/blog/post-{}/", n),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_paginate() {
+        // An empty collection still has its index page
+        let pages = paginate(&[], 10, "/blog/");
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].items.is_empty());
+        assert_eq!((pages[0].current, pages[0].total_pages, pages[0].total_items), (1, 1, 0));
+        assert_eq!((pages[0].previous_url.as_deref(), pages[0].next_url.as_deref()), (None, None));
+
+        for (count, total_pages) in [(1, 1), (9, 1), (10, 1), (11, 2), (20, 2), (21, 3)] {
+            let pages = paginate(&items(count), 10, "/blog/");
+            assert_eq!(pages.len(), total_pages, "{} items", count);
+            assert_eq!(pages.iter().map(|page| page.items.len()).sum::<usize>(), count);
+            assert!(pages.iter().all(|page| page.total_pages == total_pages && page.page_size == 10));
+        }
+
+        let pages = paginate(&items(21), 10, "/blog/");
+        assert_eq!(pages[2].items[0].text, "Post 21");
+        let urls: Vec<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(urls, ["/blog/", "/blog/page/2/", "/blog/page/3/"]);
+        assert_eq!(pages[0].previous_url, None);
+        assert_eq!(pages[0].next_url.as_deref(), Some("/blog/page/2/"));
+        assert_eq!(pages[1].previous_url.as_deref(), Some("/blog/"));
+        assert_eq!(pages[1].next_url.as_deref(), Some("/blog/page/3/"));
+        assert_eq!(pages[2].previous_url.as_deref(), Some("/blog/page/2/"));
+        assert_eq!(pages[2].next_url, None);
+        assert!(pages.iter().all(|page| page.first_url == "/blog/" && page.last_url == "/blog/page/3/"));
+
+        // A page size of zero is treated as one
+        assert_eq!(paginate(&items(3), 0, "/blog/").len(), 3);
+    }
+}
@@ -0,0 +1,222 @@
+// URL patterns such as `/blog/:year/:slug/`, expanded for each page.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::dates::PageDate;
+use crate::site::{Collection, Page, PageType};
+
+pub const PLACEHOLDERS: [&str; 6] = ["year", "month", "day", "slug", "path", "collection"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PermalinkError {
+    UnknownPlaceholder(String),
+    /// The pattern uses a date placeholder and the page has no date
+    MissingDate(String),
+}
+
+impl std::fmt::Display for PermalinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermalinkError::UnknownPlaceholder(name) => write!(
+                f,
+                "unknown placeholder :{}, expected one of :{}",
+                name,
+                PLACEHOLDERS.join(", :")
+            ),
+            PermalinkError::MissingDate(name) => {
+                write!(f, ":{} needs a date in the page's front matter", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PermalinkError {}
+
+/// Check a pattern only uses known placeholders
+pub fn validate(pattern: &str) -> Result<(), PermalinkError> {
+    for (name, _) in placeholders(pattern) {
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(PermalinkError::UnknownPlaceholder(name.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Expand `pattern` for the page at `relative`, its path in the source
+/// directory. `:slug` drops the file's order prefix, `:path` is the
+/// directories it's in, and `:collection` the first of those.
+pub fn expand(pattern: &str, relative: &Path, date: Option<&PageDate>) -> Result<String, PermalinkError> {
+    let url_path = crate::ordering::url_path(relative);
+    let slug = url_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir: Vec<String> = url_path
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let ymd = date.map(|date| date.date());
+
+    let mut url = String::new();
+    let mut rest = pattern;
+    for (name, start) in placeholders(pattern) {
+        let offset = pattern.len() - rest.len();
+        url.push_str(&rest[..start - offset]);
+        rest = &pattern[start + name.len() + 1..];
+
+        let value = match name {
+            "year" | "month" | "day" => {
+                let ymd = ymd
+                    .as_deref()
+                    .ok_or_else(|| PermalinkError::MissingDate(name.to_string()))?;
+                let part = match name {
+                    "year" => 0,
+                    "month" => 1,
+                    _ => 2,
+                };
+                ymd.split('-').nth(part).unwrap_or_default().to_string()
+            }
+            "slug" => slug.clone(),
+            "path" => dir.join("/"),
+            "collection" => dir.first().cloned().unwrap_or_default(),
+            _ => return Err(PermalinkError::UnknownPlaceholder(name.to_string())),
+        };
+        url.push_str(&value);
+    }
+    url.push_str(rest);
+
+    Ok(normalize(&url))
+}
+
+/// Decide the URL of every page that doesn't get the default one: pages
+/// under a site or collection `permalink` pattern, and with `ugly_urls`,
+/// every page that isn't a directory index. Pages given a URL by a page rule
+/// keep it. Fails with the path of the page that couldn't be given a URL.
+pub fn assign(
+    pages: &mut [Page],
+    collections: &mut [Collection],
+    config: &Config,
+    source_dir: &Path,
+) -> Result<(), (PathBuf, PermalinkError)> {
+    for page in pages.iter_mut() {
+        assign_page(page, config.permalink.as_deref(), config.ugly_urls, source_dir)?;
+    }
+    for collection in collections.iter_mut() {
+        let pattern = config
+            .collections
+            .get(&collection.name)
+            .and_then(|c| c.permalink.as_deref())
+            .or(config.permalink.as_deref());
+        for page in &mut collection.pages {
+            assign_page(page, pattern, config.ugly_urls, source_dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn assign_page(
+    page: &mut Page,
+    pattern: Option<&str>,
+    ugly_urls: bool,
+    source_dir: &Path,
+) -> Result<(), (PathBuf, PermalinkError)> {
+    // Home and index pages are their directory's index either way
+    if matches!(page.page_type, PageType::Home | PageType::Index) {
+        return Ok(());
+    }
+
+    let patterned = !matches!(page.page_type, PageType::Changelog);
+    if page.permalink.is_none()
+        && patterned
+        && let Some(pattern) = pattern
+    {
+        let relative = page.path.strip_prefix(source_dir).unwrap_or(&page.path);
+        let permalink = expand(pattern, relative, page.date.as_ref()).map_err(|e| (page.path.clone(), e))?;
+        page.permalink = Some(permalink);
+    }
+    if ugly_urls {
+        page.permalink = Some(ugly(&page.url(source_dir)));
+    }
+    Ok(())
+}
+
+/// `url` as a `.html` file, e.g. `/docs/install.html` for `/docs/install/`
+pub fn ugly(url: &str) -> String {
+    match url.trim_end_matches('/') {
+        "" => "/".to_string(),
+        path if url.ends_with('/') => format!("{}.html", path),
+        _ => url.to_string(),
+    }
+}
+
+/// Where a page with this URL is written, relative to the output directory
+pub fn out_path(url: &str) -> PathBuf {
+    let path = url.trim_matches('/');
+    if path.rsplit('/').next().is_some_and(|name| name.contains('.')) {
+        PathBuf::from(path)
+    } else {
+        PathBuf::from(path).join("index.html")
+    }
+}
+
+// Collapses the empty segments an empty placeholder leaves. URLs end in a
+// slash unless the last segment is a file name.
+fn normalize(url: &str) -> String {
+    let segments: Vec<&str> = url.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.last() {
+        None => "/".to_string(),
+        Some(last) if last.contains('.') => format!("/{}", segments.join("/")),
+        Some(_) => format!("/{}/", segments.join("/")),
+    }
+}
+
+// Each `:name` in the pattern with the byte offset of its colon
+fn placeholders(pattern: &str) -> Vec<(&str, usize)> {
+    let mut found = Vec::new();
+    for (start, _) in pattern.match_indices(':') {
+        let name = &pattern[start + 1..];
+        let len = name
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(name.len());
+        if len > 0 {
+            found.push((&name[..len], start));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let date = crate::dates::parse_date("2024-03-09", chrono_tz::Tz::UTC).unwrap();
+        let relative = Path::new("blog/2024/01-hello.md");
+
+        assert_eq!(
+            expand("/blog/:year/:month/:slug/", relative, Some(&date)),
+            Ok("/blog/2024/03/hello/".to_string())
+        );
+        assert_eq!(
+            expand("/:collection/:slug.html", relative, None),
+            Ok("/blog/hello.html".to_string())
+        );
+        assert_eq!(expand("/:path/:slug", relative, None), Ok("/blog/2024/hello/".to_string()));
+        assert_eq!(
+            expand("/:year/:slug/", relative, None),
+            Err(PermalinkError::MissingDate("year".to_string()))
+        );
+        assert!(validate("/:slugs/").is_err());
+        assert_eq!(out_path("/blog/hello/"), PathBuf::from("blog/hello/index.html"));
+        assert_eq!(out_path("/blog/hello.html"), PathBuf::from("blog/hello.html"));
+        assert_eq!(ugly("/blog/hello/"), "/blog/hello.html");
+        assert_eq!(ugly("/"), "/");
+    }
+}
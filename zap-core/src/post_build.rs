@@ -0,0 +1,121 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::PostBuildHook;
+
+/// A post-build command that could not be run or exited with an error
+#[derive(Debug)]
+pub struct HookError {
+    pub command: String,
+    pub reason: String,
+    pub output: String,
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` failed ({})", self.command, self.reason)?;
+        if !self.output.is_empty() {
+            write!(f, "\n\n{}", self.output)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Run a post-build command, such as a search indexer, against the output
+/// directory. `{output}` in the command is replaced with the output
+/// directory, quoted for the shell, which is also available as
+/// `ZAP_OUTPUT_DIR`.
+pub fn run_hook(hook: &PostBuildHook, output_dir: &Path) -> Result<(), HookError> {
+    let command = hook
+        .command
+        .replace("{output}", &shell_quote(&output_dir.to_string_lossy()));
+    run_command(&command, output_dir)
+}
+
+/// Run a shell command, such as a `zap serve` hook, with `ZAP_OUTPUT_DIR`
+/// set, failing with its output if it exits with an error
+pub fn run_command(command: &str, output_dir: &Path) -> Result<(), HookError> {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+
+    let output = process
+        .env("ZAP_OUTPUT_DIR", output_dir)
+        .output()
+        .map_err(|e| HookError {
+            command: command.to_string(),
+            reason: e.to_string(),
+            output: String::new(),
+        })?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let mut details = String::from_utf8_lossy(&output.stdout).into_owned();
+    details.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Err(HookError {
+        command: command.to_string(),
+        reason: output.status.to_string(),
+        output: details.trim_end().to_string(),
+    })
+}
+
+// A single argument to `sh -c` or `cmd /C`, whatever the path has in it.
+// Windows paths can't contain `"`.
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook() {
+        let root = std::env::temp_dir().join(format!("zap-post-build-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        // Nothing in the name is run by the shell
+        let output_dir = root.join("out dir 'quoted' $(echo injected); echo `x`");
+        std::fs::create_dir_all(&output_dir).unwrap();
+
+        let hook = PostBuildHook {
+            command: "printf '%s' {output} > \"$ZAP_OUTPUT_DIR/../seen\"".to_string(),
+        };
+        run_hook(&hook, &output_dir).unwrap();
+        let seen = std::fs::read_to_string(root.join("seen")).unwrap();
+        assert_eq!(seen, output_dir.to_string_lossy());
+
+        let hook = PostBuildHook {
+            command: "echo indexing; echo broken >&2; exit 3".to_string(),
+        };
+        let error = run_hook(&hook, &output_dir).unwrap_err();
+        assert_eq!(error.output, "indexing\nbroken");
+        assert!(error.to_string().starts_with("`echo indexing; echo broken >&2; exit 3` failed"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        if cfg!(windows) {
+            assert_eq!(shell_quote("C:\\My Site\\out"), "\"C:\\My Site\\out\"");
+        } else {
+            assert_eq!(shell_quote("/tmp/it's here"), "'/tmp/it'\\''s here'");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Steps run on each rendered page, in order, before it's written to the
+// output. Privacy mode and critical CSS run before these, since they need
+// the site's state.
+
+/// A step that rewrites a page's HTML
+pub trait PostProcessor {
+    fn process(&self, html: String) -> String;
+}
+
+/// Strips comments and collapses whitespace. Only changes that can't affect
+/// rendering are made, so content in `pre`, `textarea`, `script` and `style`
+/// is left alone.
+pub struct Minify;
+
+impl PostProcessor for Minify {
+    fn process(&self, html: String) -> String {
+        minify_html(&html)
+    }
+}
+
+// Elements whose content is whitespace sensitive or isn't HTML
+const RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        // Comments, except conditional comments, which IE-era markup relies on
+        if rest.starts_with("<!--") && !rest.starts_with("<!--[if") {
+            match rest.find("-->") {
+                Some(end) => {
+                    rest = &rest[end + 3..];
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        if let Some(element) = raw_element(rest) {
+            let close = format!("</{}", element);
+            let end = find_ignore_case(rest, &close).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            if !rest.is_empty() {
+                // Past the closing tag's name, so it isn't matched again
+                out.push_str(&rest[..close.len()]);
+                rest = &rest[close.len()..];
+            }
+            continue;
+        }
+
+        // Tags are copied as they are, so attribute values keep their spacing
+        if let Some(end) = tag_end(rest) {
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let c = rest.chars().next().expect("rest is not empty");
+        if c.is_ascii_whitespace() {
+            let trimmed = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            // A run including a line break is indentation, so it collapses
+            // to a newline to keep the output readable in view-source
+            let newline = rest[..rest.len() - trimmed.len()].contains('\n');
+            match out.chars().next_back() {
+                // Whitespace either side of a removed comment
+                Some(' ') if newline => {
+                    out.pop();
+                    out.push('\n');
+                }
+                Some(' ' | '\n') => {}
+                _ => out.push(if newline { '\n' } else { ' ' }),
+            }
+            rest = trimmed;
+            continue;
+        }
+
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+// Length of the tag `html` starts with, if it does
+fn tag_end(html: &str) -> Option<usize> {
+    let name = html.strip_prefix('<')?;
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') {
+        return None;
+    }
+
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+// The element name if `html` starts with the opening tag of a raw element
+fn raw_element(html: &str) -> Option<&'static str> {
+    let name = html.strip_prefix('<')?;
+    RAW_ELEMENTS.into_iter().find(|element| {
+        name.get(..element.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(element))
+            && name[element.len()..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+    })
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| {
+            haystack
+                .get(i..i + needle.len())
+                .is_some_and(|candidate| candidate.eq_ignore_ascii_case(needle))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minify_html() {
+        let html = "<div>\n    <!-- nav -->\n    <p title=\"a  b\">Some   text</p>\n</div>\n<pre>  keep\n    this</pre>\n<SCRIPT>if (a  <  b) {}</SCRIPT>";
+        assert_eq!(
+            minify_html(html),
+            "<div>\n<p title=\"a  b\">Some text</p>\n</div>\n<pre>  keep\n    this</pre>\n<SCRIPT>if (a  <  b) {}</SCRIPT>"
+        );
+    }
+}
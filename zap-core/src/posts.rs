@@ -0,0 +1,112 @@
+// Publishing status of dated pages, for `zap report posts`
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use crate::dates::PageDate;
+use crate::front_matter::split_front_matter;
+use crate::site::Page;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PostStatus {
+    Draft,
+    Scheduled,
+    Published,
+}
+
+impl std::fmt::Display for PostStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostStatus::Draft => write!(f, "draft"),
+            PostStatus::Scheduled => write!(f, "scheduled"),
+            PostStatus::Published => write!(f, "published"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostSummary {
+    pub title: String,
+    pub path: PathBuf,
+    pub date: Option<PageDate>,
+    pub status: PostStatus,
+    pub words: usize,
+}
+
+/// Summarize a page. Pages dated after `now` are scheduled, undated pages
+/// count as published.
+pub fn summarize(page: &Page, now: DateTime<Utc>) -> std::io::Result<PostSummary> {
+    let content = crate::encoding::read_to_string(&page.path)?;
+
+    let status = if page.draft {
+        PostStatus::Draft
+    } else if page.date.is_some_and(|date| date.utc > now) {
+        PostStatus::Scheduled
+    } else {
+        PostStatus::Published
+    };
+
+    Ok(PostSummary {
+        title: page.title.clone(),
+        path: page.path.clone(),
+        date: page.date,
+        status,
+        words: word_count(split_front_matter(&content).1),
+    })
+}
+
+/// Summaries grouped by status: drafts, then scheduled posts soonest first,
+/// then published posts newest first
+pub fn sort_summaries(summaries: &mut [PostSummary]) {
+    summaries.sort_by(|a, b| {
+        a.status.cmp(&b.status).then_with(|| match a.status {
+            PostStatus::Published => b.date.cmp(&a.date),
+            _ => a.date.cmp(&b.date),
+        })
+    });
+}
+
+/// Words in a markdown body, skipping markup-only tokens like `#` and `-`.
+/// Chinese and Japanese characters count one each.
+pub fn word_count(markdown: &str) -> usize {
+    crate::cjk::word_count(markdown)
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`
+pub fn today() -> String {
+    Utc::now().format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(word_count("# Title\n\n- one two\n- three\n\n---\n"), 4);
+    }
+
+    #[test]
+    fn test_sort_summaries() {
+        let summary = |title: &str, date: Option<&str>, status| PostSummary {
+            title: title.to_string(),
+            path: PathBuf::new(),
+            date: date.map(|date| crate::dates::parse_date(date, chrono_tz::Tz::UTC).unwrap()),
+            status,
+            words: 0,
+        };
+        let mut summaries = vec![
+            summary("old", Some("2024-01-01"), PostStatus::Published),
+            summary("later", Some("2099-06-01"), PostStatus::Scheduled),
+            summary("new", Some("2024-06-01"), PostStatus::Published),
+            summary("draft", None, PostStatus::Draft),
+            summary("soon", Some("2099-01-01"), PostStatus::Scheduled),
+        ];
+
+        sort_summaries(&mut summaries);
+
+        let titles: Vec<&str> = summaries.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["draft", "soon", "later", "new", "old"]);
+    }
+}
@@ -0,0 +1,473 @@
+// Privacy mode: rewrites built pages so they make no third-party requests.
+// External scripts, stylesheets, fonts and images are downloaded at build
+// time and served from the site, analytics are removed, and embeds such as
+// YouTube videos only load after the visitor clicks them.
+
+use std::io::Read;
+
+/// Where self-hosted copies of external assets are written, relative to the
+/// output directory
+pub const EXTERNAL_DIR: &str = "_external";
+
+// Hosts whose scripts are dropped rather than self-hosted
+const ANALYTICS_HOSTS: [&str; 14] = [
+    "google-analytics.com",
+    "googletagmanager.com",
+    "plausible.io",
+    "cloudflareinsights.com",
+    "usefathom.com",
+    "gc.zgo.at",
+    "goatcounter.com",
+    "umami.is",
+    "simpleanalyticscdn.com",
+    "hotjar.com",
+    "clarity.ms",
+    "connect.facebook.net",
+    "matomo.cloud",
+    "segment.com",
+];
+
+// Inline snippets that only exist to talk to an analytics service
+const ANALYTICS_SNIPPETS: [&str; 4] = ["gtag(", "_paq.push", "fbq(", "dataLayer.push"];
+
+// Attributes that make the browser fetch a URL, on any element
+const FETCH_ATTRIBUTES: [&str; 3] = ["src", "poster", "data"];
+
+// Some CDNs, Google Fonts in particular, pick the font format by user agent
+const USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36";
+
+/// A downloaded asset
+pub struct Download {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+}
+
+pub fn download(url: &str) -> Result<Download, String> {
+    let mut response = ureq::get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .map_err(|e| e.to_string())?;
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let mut bytes = Vec::new();
+    response
+        .body_mut()
+        .as_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Download {
+        bytes,
+        content_type,
+    })
+}
+
+/// File extension for a downloaded asset, from its URL or content type
+pub fn asset_extension(url: &str, content_type: Option<&str>) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file = path.rsplit('/').next().unwrap_or_default();
+    if let Some((_, ext)) = file.rsplit_once('.')
+        && !ext.is_empty()
+        && ext.len() <= 5
+        && ext.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return ext.to_lowercase();
+    }
+
+    let mime = content_type
+        .unwrap_or_default()
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim();
+    match mime {
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" => "js",
+        "font/woff2" => "woff2",
+        "font/woff" => "woff",
+        "font/ttf" => "ttf",
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/gif" => "gif",
+        _ => "bin",
+    }
+    .to_string()
+}
+
+/// Resolve a URL found in a page or stylesheet to an absolute external URL.
+/// Returns `None` for URLs served by the site itself.
+pub fn external_url(url: &str, base: Option<&str>) -> Option<String> {
+    let url = url.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Some(url.to_string());
+    }
+    if let Some(rest) = url.strip_prefix("//") {
+        return Some(format!("https://{}", rest));
+    }
+    if url.is_empty() || url.starts_with("data:") || url.starts_with('#') {
+        return None;
+    }
+
+    // Relative URLs are only external inside a downloaded stylesheet
+    let (scheme, rest) = base?.split_once("://")?;
+    let host_end = rest.find('/').unwrap_or(rest.len());
+    let origin = format!("{}://{}", scheme, &rest[..host_end]);
+    if url.starts_with('/') {
+        return Some(format!("{}{}", origin, url));
+    }
+
+    let base_path = rest[host_end..].split(['?', '#']).next().unwrap_or_default();
+    let mut segments: Vec<&str> = base_path.split('/').filter(|s| !s.is_empty()).collect();
+    if !base_path.ends_with('/') {
+        segments.pop();
+    }
+    for segment in url.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    Some(format!("{}/{}", origin, segments.join("/")))
+}
+
+pub(crate) fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+fn is_analytics(url: &str) -> bool {
+    let host = host(url);
+    ANALYTICS_HOSTS
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
+/// Rewrite a page so it makes no third-party requests. `self_host` is given
+/// an external URL and returns the local URL of a downloaded copy, or `None`
+/// when it couldn't be downloaded, in which case the reference is removed.
+pub fn make_private(html: &str, self_host: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // Commented out markup is never fetched
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let Some(len) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..len + 1];
+
+        let name = tag_name(tag);
+        let close = format!("</{}>", name);
+        let body_end = rest[tag.len()..].find(&close).map(|i| tag.len() + i);
+
+        match (name.as_str(), body_end) {
+            ("script" | "style" | "iframe", Some(body_end)) => {
+                let end = body_end + close.len();
+                let element = &rest[..end];
+                let body = &rest[tag.len()..body_end];
+
+                match name.as_str() {
+                    "script" => out.push_str(&private_script(element, tag, body, self_host)),
+                    "style" => {
+                        out.push_str(tag);
+                        out.push_str(&private_css(body, None, self_host));
+                        out.push_str(&close);
+                    }
+                    _ => out.push_str(&embed_placeholder(element, tag, self_host)),
+                }
+                rest = &rest[end..];
+            }
+            _ => {
+                out.push_str(&private_tag(tag, self_host));
+                rest = &rest[len + 1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn private_script(
+    element: &str,
+    tag: &str,
+    body: &str,
+    self_host: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    match attribute(tag, "src").and_then(|src| external_url(src, None)) {
+        Some(src) if is_analytics(&src) => String::new(),
+        Some(src) => match self_host(&src) {
+            Some(local) => element.replacen(tag, &set_attribute(tag, "src", Some(&local)), 1),
+            None => String::new(),
+        },
+        None => {
+            let tracking = ANALYTICS_SNIPPETS.iter().any(|s| body.contains(s))
+                || ANALYTICS_HOSTS.iter().any(|h| body.contains(h));
+            if tracking {
+                String::new()
+            } else {
+                element.to_string()
+            }
+        }
+    }
+}
+
+fn private_tag(tag: &str, self_host: &dyn Fn(&str) -> Option<String>) -> String {
+    let name = tag_name(tag);
+
+    if name == "link"
+        && let Some(href) = attribute(tag, "href").and_then(|href| external_url(href, None))
+    {
+        let rel = attribute(tag, "rel").unwrap_or_default();
+        // Connection hints and pages being linked to are not needed
+        if rel.contains("preconnect") || rel.contains("dns-prefetch") || is_analytics(&href) {
+            return String::new();
+        }
+        return match self_host(&href) {
+            Some(local) => set_attribute(tag, "href", Some(&local)),
+            None => String::new(),
+        };
+    }
+
+    let mut tag = tag.to_string();
+    for name in FETCH_ATTRIBUTES {
+        if let Some(url) = attribute(&tag, name).and_then(|url| external_url(url, None)) {
+            let local = self_host(&url);
+            tag = set_attribute(&tag, name, local.as_deref());
+        }
+    }
+    // Responsive candidates would each need downloading, `src` is enough
+    if attribute(&tag, "srcset").is_some_and(|set| set.split(',').any(|c| external_url(c.split_whitespace().next().unwrap_or_default(), None).is_some())) {
+        tag = set_attribute(&tag, "srcset", None);
+    }
+    tag
+}
+
+/// Rewrite `url(...)` and `@import` references in a stylesheet. `base` is
+/// the stylesheet's own URL when it was downloaded, for resolving relative
+/// references.
+pub fn private_css(
+    css: &str,
+    base: Option<&str>,
+    self_host: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    // Assets that couldn't be downloaded become empty data URLs
+    let localize = |url: &str| -> Option<String> {
+        external_url(url, base)
+            .map(|external| self_host(&external).unwrap_or_else(|| "data:,".to_string()))
+    };
+
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+
+    loop {
+        let url_at = rest.find("url(");
+        let import_at = rest.find("@import");
+        let Some(start) = [url_at, import_at].into_iter().flatten().min() else {
+            break;
+        };
+
+        if Some(start) == url_at {
+            let open = start + "url(".len();
+            let Some(len) = rest[open..].find(')') else {
+                break;
+            };
+            let raw = &rest[open..open + len];
+            let url = raw.trim().trim_matches(|c| c == '"' || c == '\'');
+            out.push_str(&rest[..open]);
+            match localize(url) {
+                Some(local) => out.push_str(&format!("\"{}\"", local)),
+                None => out.push_str(raw),
+            }
+            rest = &rest[open + len..];
+        } else {
+            // `@import "..."` without url()
+            let after = start + "@import".len();
+            let trimmed = rest[after..].trim_start();
+            let quote = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'');
+            let Some(quote) = quote else {
+                out.push_str(&rest[..after]);
+                rest = &rest[after..];
+                continue;
+            };
+            let open = rest.len() - trimmed.len() + 1;
+            let Some(len) = rest[open..].find(quote) else {
+                break;
+            };
+            let url = &rest[open..open + len];
+            out.push_str(&rest[..open]);
+            out.push_str(&localize(url).unwrap_or_else(|| url.to_string()));
+            rest = &rest[open + len..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+// Replace an external iframe with a button that loads it on click. YouTube
+// embeds show a self-hosted thumbnail and load from youtube-nocookie.com.
+fn embed_placeholder(
+    element: &str,
+    tag: &str,
+    self_host: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let Some(src) = attribute(tag, "src").and_then(|src| external_url(src, None)) else {
+        return element.to_string();
+    };
+
+    let youtube_id = youtube_id(&src);
+    let mut element = element.to_string();
+    let mut thumbnail = String::new();
+    let mut label = format!("Load content from {}", host(&src));
+
+    if let Some(id) = &youtube_id {
+        let nocookie = format!("https://www.youtube-nocookie.com/embed/{}", id);
+        let query = src.split_once('?').map(|(_, q)| format!("?{}", q)).unwrap_or_default();
+        element = element.replacen(tag, &set_attribute(tag, "src", Some(&format!("{}{}", nocookie, query))), 1);
+        label = "Load video from YouTube".to_string();
+
+        if let Some(local) = self_host(&format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", id)) {
+            thumbnail = format!("<img src=\"{}\" alt=\"\" loading=\"lazy\">", local);
+        }
+    }
+
+    format!(
+        "<div class=\"zap-embed\" data-embed=\"{}\"><button type=\"button\" onclick=\"var e=this.parentNode;e.outerHTML=e.dataset.embed\">{}<span>{}</span></button></div>",
+        html_escape::encode_double_quoted_attribute(&element),
+        thumbnail,
+        html_escape::encode_text(&label)
+    )
+}
+
+fn youtube_id(src: &str) -> Option<String> {
+    let host = host(src);
+    if !(host.ends_with("youtube.com") || host.ends_with("youtube-nocookie.com")) {
+        return None;
+    }
+    let (_, rest) = src.split_once("/embed/")?;
+    let id: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    (!id.is_empty()).then_some(id)
+}
+
+pub(crate) fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// Byte range of ` name="value"` in a tag, and the range of the value
+fn find_attribute(tag: &str, name: &str) -> Option<(usize, usize, usize, usize)> {
+    let lower = tag.to_lowercase();
+    let mut from = 0;
+    while let Some(i) = lower[from..].find(name) {
+        let start = from + i;
+        from = start + name.len();
+
+        let preceded = lower[..start].ends_with(char::is_whitespace);
+        let after = lower[from..].trim_start();
+        if !preceded || !after.starts_with('=') {
+            continue;
+        }
+
+        let value = after[1..].trim_start();
+        let value_start = tag.len() - value.len();
+        let (value_start, value_end, end) = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => {
+                let len = value[1..].find(q)?;
+                (value_start + 1, value_start + 1 + len, value_start + len + 2)
+            }
+            _ => {
+                let len = value
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(value.len());
+                (value_start, value_start + len, value_start + len)
+            }
+        };
+        let attr_start = lower[..start].trim_end().len();
+        return Some((attr_start, end, value_start, value_end));
+    }
+    None
+}
+
+pub(crate) fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    find_attribute(tag, name).map(|(_, _, start, end)| &tag[start..end])
+}
+
+// Set an attribute's value, or remove it with `None`
+fn set_attribute(tag: &str, name: &str, value: Option<&str>) -> String {
+    let Some((start, end, _, _)) = find_attribute(tag, name) else {
+        return tag.to_string();
+    };
+    let replacement = value
+        .map(|v| format!(" {}=\"{}\"", name, html_escape::encode_double_quoted_attribute(v)))
+        .unwrap_or_default();
+    format!("{}{}{}", &tag[..start], replacement, &tag[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(url: &str) -> Option<String> {
+        Some(format!("/_external/{}", url.rsplit('/').next().unwrap()))
+    }
+
+    #[test]
+    fn test_rewrites_external_requests() {
+        let html = r#"<link rel="preconnect" href="https://fonts.gstatic.com"><link rel="stylesheet" href="https://cdn.example.com/app.css"><script async src="https://www.googletagmanager.com/gtag/js?id=G-1"></script><script>gtag('config', 'G-1');</script><img data-src="x.png" src='//img.example.com/a.png'>"#;
+        let out = make_private(html, &local);
+        assert_eq!(
+            out,
+            r#"<link rel="stylesheet" href="/_external/app.css"><img data-src="x.png" src="/_external/a.png">"#
+        );
+    }
+
+    #[test]
+    fn test_youtube_embed_becomes_click_to_load() {
+        let html = r#"<iframe src="https://www.youtube.com/embed/abc_123?start=5"></iframe>"#;
+        let out = make_private(html, &local);
+        assert!(out.starts_with("<div class=\"zap-embed\""));
+        assert!(out.contains("<img src=\"/_external/hqdefault.jpg\""));
+        assert!(out.contains("youtube-nocookie.com/embed/abc_123?start=5"));
+        assert!(!out.contains("<iframe"));
+    }
+
+    #[test]
+    fn test_css_references_resolve_against_stylesheet() {
+        let css = "@import 'https://x.com/a.css';@font-face{src:url(../fonts/f.woff2) format('woff2')}";
+        let out = private_css(css, Some("https://cdn.com/lib/css/s.css"), &|url| {
+            Some(format!("<{}>", url))
+        });
+        assert_eq!(
+            out,
+            "@import '<https://x.com/a.css>';@font-face{src:url(\"<https://cdn.com/lib/fonts/f.woff2>\") format('woff2')}"
+        );
+    }
+}
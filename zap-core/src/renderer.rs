@@ -1,10 +1,18 @@
 use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::events::TemplateTiming;
 use crate::template::{TemplateRenderer, TemplateError};
 
 pub struct Renderer {
     templates: TemplateRenderer,
     global_context: RenderContext,  // Global context set once
+    debug: bool,
+    // Time spent in Tera, in total and per template
+    render_time: Cell<Duration>,
+    template_times: RefCell<HashMap<String, (usize, Duration)>>,
 }
 
 impl Renderer {
@@ -13,21 +21,101 @@ impl Renderer {
         Ok(Self {
             templates: TemplateRenderer::new(&theme_glob)?,
             global_context: RenderContext::new(),
+            debug: false,
+            render_time: Cell::new(Duration::ZERO),
+            template_times: RefCell::new(HashMap::new()),
         })
     }
+
+    // Append a panel with the template name and its context to every render
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
     
     // Set global context that applies to all renders
     pub fn set_global_context<T: Serialize>(&mut self, key: &str, value: &T) {
         self.global_context.add_to_context(key, value);
     }
     
+    pub fn has_template(&self, template: &str) -> bool {
+        self.templates.has_template(template)
+    }
+
+    // Theme asset URLs for the `asset()` template function
+    pub fn register_assets(&mut self, urls: HashMap<String, String>) {
+        self.templates.register_assets(urls);
+    }
+
+    // Default seed for the `sample()` and `shuffle()` template functions
+    pub fn register_sampling(&mut self, seed: u64) {
+        self.templates.register_sampling(seed);
+    }
+
+    // Theme strings for the `trans()` template function
+    pub fn register_translations(&mut self, catalogs: crate::i18n::Catalogs, language: &str) {
+        self.templates.register_translations(catalogs, language);
+    }
+
     // Render template to string with merged global + page context
     pub fn render(&self, template: &str, page_context: &RenderContext) -> Result<String, TemplateError> {
         // Merge global and page contexts
         let mut merged = self.global_context.clone();
         merged.merge(page_context);
         
-        self.templates.render_with_context(template, &merged.inner)
+        let started = Instant::now();
+        let html = self.templates.render_with_context(template, &merged.inner);
+        self.record_time(template, started.elapsed());
+        let html = html?;
+        if self.debug {
+            return Ok(inject_debug_panel(html, template, merged));
+        }
+
+        Ok(html)
+    }
+
+    fn record_time(&self, template: &str, elapsed: Duration) {
+        self.render_time.set(self.render_time.get() + elapsed);
+        let mut times = self.template_times.borrow_mut();
+        let entry = times.entry(template.to_string()).or_insert((0, Duration::ZERO));
+        entry.0 += 1;
+        entry.1 += elapsed;
+    }
+
+    /// Total time spent rendering templates so far
+    pub fn render_time(&self) -> Duration {
+        self.render_time.get()
+    }
+
+    /// Time spent per template, slowest first
+    pub fn template_timings(&self) -> Vec<TemplateTiming> {
+        let mut timings: Vec<TemplateTiming> = self
+            .template_times
+            .borrow()
+            .iter()
+            .map(|(template, (renders, time))| TemplateTiming {
+                template: template.clone(),
+                renders: *renders,
+                us: time.as_micros(),
+            })
+            .collect();
+        timings.sort_by_key(|timing| std::cmp::Reverse(timing.us));
+        timings
+    }
+}
+
+// A collapsed panel fixed to the corner of the page, plus a comment for
+// anyone reading the source
+fn inject_debug_panel(html: String, template: &str, context: RenderContext) -> String {
+    let json = serde_json::to_string_pretty(&context.inner.into_json()).unwrap_or_default();
+    let panel = format!(
+        "<!-- zap: rendered with {0} -->\n<details id=\"zap-template-debug\" style=\"position:fixed;bottom:1rem;right:1rem;z-index:9998;max-width:min(40rem,90vw);max-height:80vh;overflow:auto;padding:0.5rem 0.75rem;border-radius:0.5rem;background:#111827;color:#e5e7eb;font:12px/1.5 monospace;box-shadow:0 4px 12px rgba(0,0,0,0.3)\"><summary style=\"cursor:pointer\">{0}</summary><pre style=\"margin:0.5rem 0 0;white-space:pre-wrap\">{1}</pre></details>\n",
+        html_escape::encode_text(template),
+        html_escape::encode_text(&json)
+    );
+
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], panel, &html[pos..]),
+        None => format!("{}{}", html, panel),
     }
 }
 
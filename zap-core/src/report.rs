@@ -0,0 +1,76 @@
+// What a build did, returned by `Site::render_all`. Progress events are for
+// showing a build as it runs, this is for callers that want the totals.
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::events::{PageTimings, TemplateTiming};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildReport {
+    /// Pages rendered from markdown. Listings and tag pages aren't counted.
+    pub pages: usize,
+    /// Pages an incremental rebuild skipped because nothing they depend on
+    /// changed
+    pub unchanged: usize,
+    /// Files written to the output directory, rendered or copied
+    pub files_written: usize,
+    pub bytes_written: u64,
+    pub warnings: Vec<String>,
+    /// Found when the build checked links, also among the warnings
+    pub broken_links: Vec<crate::link_check::BrokenLink>,
+    /// Time spent in each phase, in the order they ran
+    pub phases: Vec<PhaseTiming>,
+    /// Each page rendered and where its time went, slowest first
+    pub page_timings: Vec<PageTiming>,
+    /// Tera rendering cost per template, slowest first
+    pub templates: Vec<TemplateTiming>,
+    pub ms: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub us: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageTiming {
+    pub path: PathBuf,
+    pub template: String,
+    pub us: u128,
+    #[serde(flatten)]
+    pub timings: PageTimings,
+}
+
+// Times consecutive phases of a build
+pub(crate) struct PhaseClock {
+    started: Instant,
+    phases: Vec<PhaseTiming>,
+}
+
+impl PhaseClock {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// End the current phase, starting the next
+    pub fn lap(&mut self, phase: &str) {
+        let us = self.started.elapsed().as_micros();
+        tracing::debug!(phase, us = us as u64, "phase finished");
+        self.phases.push(PhaseTiming {
+            phase: phase.to_string(),
+            us,
+        });
+        self.started = Instant::now();
+    }
+
+    pub fn into_phases(self) -> Vec<PhaseTiming> {
+        self.phases
+    }
+}
@@ -0,0 +1,368 @@
+// A roadmap page, from a `roadmap.toml` in the source directory or a GitHub
+// project. Items are grouped by status or milestone, and each group gets a
+// count of finished items for progress bars.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{GithubProjectConfig, RoadmapConfig, RoadmapGrouping};
+
+const PROJECT_QUERY: &str = r#"
+query($owner: String!, $number: Int!, $status: String!, $cursor: String) {
+  repositoryOwner(login: $owner) {
+    ... on ProjectV2Owner {
+      projectV2(number: $number) {
+        items(first: 100, after: $cursor) {
+          pageInfo { hasNextPage endCursor }
+          nodes {
+            status: fieldValueByName(name: $status) {
+              ... on ProjectV2ItemFieldSingleSelectValue { name }
+            }
+            content {
+              ... on Issue { title url milestone { title } }
+              ... on PullRequest { title url milestone { title } }
+              ... on DraftIssue { title }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoadmapItem {
+    pub title: String,
+    pub status: Option<String>,
+    pub milestone: Option<String>,
+    /// Markdown in `roadmap.toml`, HTML once loaded. GitHub items don't
+    /// have one.
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoadmapGroup {
+    pub name: String,
+    pub items: Vec<RoadmapItem>,
+    pub done: usize,
+    pub total: usize,
+    /// Finished items out of 100, rounded down
+    pub percent: usize,
+}
+
+/// The roadmap page's context
+#[derive(Debug, Clone, Serialize)]
+pub struct Roadmap {
+    pub title: String,
+    pub url: String,
+    pub group_by: RoadmapGrouping,
+    pub groups: Vec<RoadmapGroup>,
+    pub done: usize,
+    pub total: usize,
+    pub percent: usize,
+}
+
+#[derive(Deserialize)]
+struct RoadmapFile {
+    #[serde(default)]
+    items: Vec<RoadmapItem>,
+}
+
+/// Read the roadmap's items from wherever `config` says. A GitHub project
+/// that can't be fetched falls back to the items from the last build that
+/// could, kept in `cache_dir`, with a warning.
+pub fn load(
+    config: &RoadmapConfig,
+    source_dir: &Path,
+    cache_dir: &Path,
+) -> std::io::Result<(Roadmap, Option<String>)> {
+    let (items, warning) = match &config.github {
+        Some(github) => github_items(github, cache_dir)?,
+        None => {
+            let path = source_dir.join(&config.file);
+            let text = crate::encoding::read_to_string(&path)?;
+            let file: RoadmapFile = toml::from_str(&text).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+            })?;
+            let items = file
+                .items
+                .into_iter()
+                .map(|item| RoadmapItem {
+                    description: item.description.map(|markdown| {
+                        crate::markdown::render_elements_to_html(&crate::markdown::parse_structured(&markdown))
+                    }),
+                    ..item
+                })
+                .collect();
+            (items, None)
+        }
+    };
+    Ok((group(config, items), warning))
+}
+
+/// Group items in the configured order, counting the finished ones
+pub fn group(config: &RoadmapConfig, items: Vec<RoadmapItem>) -> Roadmap {
+    let is_done = |item: &RoadmapItem| {
+        item.status
+            .as_deref()
+            .is_some_and(|status| config.done.iter().any(|done| done.eq_ignore_ascii_case(status)))
+    };
+
+    let mut groups: Vec<RoadmapGroup> = config
+        .order
+        .iter()
+        .map(|name| RoadmapGroup {
+            name: name.clone(),
+            items: Vec::new(),
+            done: 0,
+            total: 0,
+            percent: 0,
+        })
+        .collect();
+    for item in items {
+        let name = match config.group_by {
+            RoadmapGrouping::Status => item.status.clone().unwrap_or_else(|| "No status".to_string()),
+            RoadmapGrouping::Milestone => item.milestone.clone().unwrap_or_else(|| "No milestone".to_string()),
+        };
+        let index = match groups.iter().position(|group| group.name.eq_ignore_ascii_case(&name)) {
+            Some(index) => index,
+            None => {
+                groups.push(RoadmapGroup {
+                    name,
+                    items: Vec::new(),
+                    done: 0,
+                    total: 0,
+                    percent: 0,
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.done += usize::from(is_done(&item));
+        group.total += 1;
+        group.items.push(item);
+    }
+    groups.retain(|group| group.total > 0);
+    for group in &mut groups {
+        group.percent = percent(group.done, group.total);
+    }
+
+    let done = groups.iter().map(|group| group.done).sum();
+    let total = groups.iter().map(|group| group.total).sum();
+    Roadmap {
+        title: config.title.clone(),
+        url: config.url.clone(),
+        group_by: config.group_by,
+        groups,
+        done,
+        total,
+        percent: percent(done, total),
+    }
+}
+
+fn percent(done: usize, total: usize) -> usize {
+    match total {
+        0 => 0,
+        total => done * 100 / total,
+    }
+}
+
+/// The page's content for themes without a `roadmap.html`
+pub fn page_html(roadmap: &Roadmap) -> String {
+    let mut html = format!(
+        "<h1>{}</h1>\n<p>{} of {} done</p>\n",
+        html_escape::encode_text(&roadmap.title),
+        roadmap.done,
+        roadmap.total
+    );
+    for group in &roadmap.groups {
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<progress value=\"{}\" max=\"{}\">{}%</progress>\n<ul>\n",
+            html_escape::encode_text(&group.name),
+            group.done,
+            group.total,
+            group.percent
+        ));
+        for item in &group.items {
+            let title = html_escape::encode_text(&item.title);
+            match &item.url {
+                Some(url) => html.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    html_escape::encode_double_quoted_attribute(url),
+                    title
+                )),
+                None => html.push_str(&format!("<li>{}</li>\n", title)),
+            }
+        }
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedItems {
+    items: Vec<RoadmapItem>,
+    fetched_at: u64,
+}
+
+fn cache_path(cache_dir: &Path, github: &GithubProjectConfig) -> PathBuf {
+    let key = format!("{}/{}/{}", github.owner, github.number, github.status_field);
+    cache_dir.join(format!("roadmap-{}.json", crate::anchors::stable_id(&key)))
+}
+
+// Items from the cache while it's fresh, then from the API
+fn github_items(github: &GithubProjectConfig, cache_dir: &Path) -> std::io::Result<(Vec<RoadmapItem>, Option<String>)> {
+    let path = cache_path(cache_dir, github);
+    let cached: Option<CachedItems> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok());
+    if let Some(cached) = &cached
+        && now().saturating_sub(cached.fetched_at) < github.cache_minutes * 60
+    {
+        return Ok((cached.items.clone(), None));
+    }
+
+    match fetch_project(github) {
+        Ok(items) => {
+            let fresh = CachedItems {
+                items,
+                fetched_at: now(),
+            };
+            // An unwritable cache only costs a fetch next build
+            if let Ok(json) = serde_json::to_string(&fresh) {
+                let _ = std::fs::create_dir_all(cache_dir).and_then(|_| std::fs::write(&path, json));
+            }
+            Ok((fresh.items, None))
+        }
+        Err(e) => match cached {
+            Some(cached) => Ok((
+                cached.items,
+                Some(format!("Could not fetch the roadmap from GitHub, using the last fetched items: {}", e)),
+            )),
+            None => Err(std::io::Error::other(format!("Could not fetch the roadmap from GitHub: {}", e))),
+        },
+    }
+}
+
+fn fetch_project(github: &GithubProjectConfig) -> Result<Vec<RoadmapItem>, String> {
+    let token = crate::github::token(&github.token_env)?;
+
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let variables = serde_json::json!({
+            "owner": github.owner,
+            "number": github.number,
+            "status": github.status_field,
+            "cursor": cursor,
+        });
+        let data = crate::github::graphql(&token, PROJECT_QUERY, variables)?;
+
+        let (page, next) = parse_project(&data)?;
+        items.extend(page);
+        match next {
+            Some(next) => cursor = Some(next),
+            None => return Ok(items),
+        }
+    }
+}
+
+// A page of items from the query's data, and the cursor for the next
+fn parse_project(data: &serde_json::Value) -> Result<(Vec<RoadmapItem>, Option<String>), String> {
+    let items = &data["repositoryOwner"]["projectV2"]["items"];
+    if items.is_null() {
+        return Err("project not found".to_string());
+    }
+
+    let text = |value: &serde_json::Value| value.as_str().map(str::to_string);
+    let page = items["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            let content = &node["content"];
+            Some(RoadmapItem {
+                // Items without content are redacted, from repositories the
+                // token can't see
+                title: text(&content["title"])?,
+                status: text(&node["status"]["name"]),
+                milestone: text(&content["milestone"]["title"]),
+                description: None,
+                url: text(&content["url"]),
+            })
+        })
+        .collect();
+
+    let next = match items["pageInfo"]["hasNextPage"].as_bool() {
+        Some(true) => text(&items["pageInfo"]["endCursor"]),
+        _ => None,
+    };
+    Ok((page, next))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roadmap() {
+        let file: RoadmapFile = toml::from_str(
+            r#"
+[[items]]
+title = "Plugins"
+status = "In progress"
+milestone = "1.0"
+
+[[items]]
+title = "Themes"
+status = "done"
+milestone = "1.0"
+
+[[items]]
+title = "Search"
+"#,
+        )
+        .unwrap();
+        let config = RoadmapConfig {
+            order: vec!["Done".to_string()],
+            ..Default::default()
+        };
+
+        let roadmap = group(&config, file.items.clone());
+        let groups: Vec<(&str, usize, usize)> =
+            roadmap.groups.iter().map(|group| (group.name.as_str(), group.done, group.total)).collect();
+        assert_eq!(groups, [("Done", 1, 1), ("In progress", 0, 1), ("No status", 0, 1)]);
+        assert_eq!(roadmap.percent, 33);
+
+        let config = RoadmapConfig {
+            group_by: RoadmapGrouping::Milestone,
+            ..Default::default()
+        };
+        let roadmap = group(&config, file.items);
+        assert_eq!(roadmap.groups[0].name, "1.0");
+        assert_eq!(roadmap.groups[0].percent, 50);
+
+        let data = serde_json::json!({ "repositoryOwner": { "projectV2": { "items": {
+            "pageInfo": { "hasNextPage": true, "endCursor": "abc" },
+            "nodes": [
+                { "status": { "name": "Todo" }, "content": { "title": "Docs", "url": "https://github.com/o/r/issues/1", "milestone": null } },
+                { "status": null, "content": {} },
+            ],
+        } } } });
+        let (items, next) = parse_project(&data).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].status.as_deref(), Some("Todo"));
+        assert_eq!(next.as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,109 @@
+// `sample()` and `shuffle()` for templates, so a home page can feature a
+// rotating selection of pages. The picks are random but repeatable: the
+// seed defaults to the build date, so every build on the same day picks the
+// same pages and the selection changes from one day to the next.
+
+use std::collections::HashMap;
+
+use tera::Value;
+
+/// A seed from any text. FNV-1a, as `DefaultHasher` can change between Rust
+/// versions and the same seed has to pick the same pages.
+pub fn seed(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Shuffle `items` in place, the same way every time for the same seed
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix(seed);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+// SplitMix64, small and good enough for picking pages
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+// The `pages` array and seed shared by both functions. A `seed` argument
+// replaces the build's, e.g. to pick differently in two places on a page.
+fn shuffled(function: &str, args: &HashMap<String, Value>, default_seed: u64) -> tera::Result<Vec<Value>> {
+    let mut pages = args
+        .get("pages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| tera::Error::msg(format!("{}() requires a `pages` array", function)))?
+        .clone();
+    let seed = match args.get("seed") {
+        Some(Value::String(text)) => seed(text),
+        Some(value) => seed(&value.to_string()),
+        None => default_seed,
+    };
+    shuffle(&mut pages, seed);
+    Ok(pages)
+}
+
+/// `sample(pages=..., n=3)`: `n` of the pages, in random order
+pub struct Sample(pub u64);
+
+impl tera::Function for Sample {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let n = args
+            .get("n")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| tera::Error::msg("sample() requires `n`, the number of pages to pick"))?;
+        let mut pages = shuffled("sample", args, self.0)?;
+        pages.truncate(n as usize);
+        Ok(Value::Array(pages))
+    }
+}
+
+/// `shuffle(pages=...)`: all of the pages, in random order
+pub struct Shuffle(pub u64);
+
+impl tera::Function for Shuffle {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        Ok(Value::Array(shuffled("shuffle", args, self.0)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Function;
+
+    #[test]
+    fn test_sample() {
+        let pages: Vec<Value> = (0..10).map(Value::from).collect();
+        let args = |extra: &[(&str, Value)]| {
+            let mut args = HashMap::from([("pages".to_string(), Value::from(pages.clone()))]);
+            args.extend(extra.iter().map(|(key, value)| (key.to_string(), value.clone())));
+            args
+        };
+
+        let picked = Sample(seed("2024-05-01")).call(&args(&[("n", Value::from(3))])).unwrap();
+        assert_eq!(picked.as_array().unwrap().len(), 3);
+        assert_eq!(picked, Sample(seed("2024-05-01")).call(&args(&[("n", Value::from(3))])).unwrap());
+        assert_ne!(picked, Sample(seed("2024-05-02")).call(&args(&[("n", Value::from(3))])).unwrap());
+
+        // An explicit seed overrides the build's
+        let seeded = args(&[("seed", Value::from("hero"))]);
+        assert_eq!(Shuffle(1).call(&seeded).unwrap(), Shuffle(2).call(&seeded).unwrap());
+
+        let mut all = Shuffle(1).call(&args(&[])).unwrap().as_array().unwrap().clone();
+        all.sort_by_key(|value| value.as_u64());
+        assert_eq!(all, pages);
+        assert!(Sample(1).call(&args(&[])).is_err());
+    }
+}
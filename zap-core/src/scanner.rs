@@ -1,5 +1,13 @@
+use crate::config::ConfigError;
+use crate::dates::{DateError, parse_date};
+use crate::diagnostics::Diagnostic;
+use crate::front_matter::{parse_front_matter, split_front_matter};
+use crate::incremental::PageCache;
+use chrono_tz::Tz;
 use crate::markdown::get_page_title;
+use crate::schema::{CollectionSchema, SCHEMA_FILE, SchemaViolation};
 use crate::site::{Collection, Page, PageType};
+use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -7,6 +15,11 @@ use walkdir::WalkDir;
 pub enum ScanError {
     IoError(std::io::Error),
     InvalidPath(PathBuf),
+    FrontMatter(PathBuf, toml::de::Error),
+    Date(PathBuf, DateError),
+    Variant(PathBuf, String),
+    Schema(PathBuf, ConfigError),
+    SchemaViolations(Vec<SchemaViolation>),
 }
 
 impl From<std::io::Error> for ScanError {
@@ -20,23 +33,93 @@ impl std::fmt::Display for ScanError {
         match self {
             ScanError::IoError(e) => write!(f, "IO error: {}", e),
             ScanError::InvalidPath(p) => write!(f, "Invalid path: {}", p.display()),
+            ScanError::FrontMatter(p, e) => {
+                write!(f, "Invalid front matter in {}: {}", p.display(), e)
+            }
+            ScanError::Date(p, e) => write!(f, "Invalid date in {}: {}", p.display(), e),
+            ScanError::Variant(p, message) => write!(f, "Invalid variants in {}: {}", p.display(), message),
+            ScanError::Schema(p, e) => write!(f, "Invalid schema in {}: {}", p.display(), e),
+            ScanError::SchemaViolations(violations) => {
+                write!(f, "Front matter doesn't match the collection's schema:")?;
+                for violation in violations {
+                    write!(f, "\n  {}: {}", violation.path.display(), violation.message)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 impl std::error::Error for ScanError {}
 
+impl ScanError {
+    /// The problems, each pointed at the page or the line in it
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            ScanError::FrontMatter(p, e) => {
+                let diagnostic = Diagnostic::error(format!("invalid front matter: {}", e.message().trim_end()))
+                    .help("front matter is TOML between `+++` lines");
+                let content = crate::encoding::read_text(p).map(|(content, _)| content).unwrap_or_default();
+                // The span is in the front matter, which starts after `+++`
+                let located = match (split_front_matter(&content).0, e.span()) {
+                    (Some(source), Some(span)) => {
+                        let start = source.as_ptr() as usize - content.as_ptr() as usize;
+                        diagnostic.at_offset(&content, start + span.start)
+                    }
+                    _ => diagnostic,
+                };
+                vec![located.in_file(p)]
+            }
+            ScanError::Date(p, e) => vec![Diagnostic::error(format!("invalid date: {}", e)).in_file(p)],
+            ScanError::Variant(p, message) => vec![Diagnostic::error(format!("invalid variants: {}", message)).in_file(p)],
+            ScanError::Schema(p, e) => vec![Diagnostic::error(format!("invalid schema: {}", e)).in_file(p)],
+            ScanError::SchemaViolations(violations) => violations
+                .iter()
+                .map(|violation| {
+                    let diagnostic = Diagnostic::error(format!("front matter doesn't match the schema: {}", violation.message));
+                    match violation.message.split('`').nth(1) {
+                        Some(field) => diagnostic.find_in(&violation.path, &format!("{} =", field)),
+                        None => diagnostic.in_file(&violation.path),
+                    }
+                })
+                .collect(),
+            _ => vec![Diagnostic::error(self.to_string())],
+        }
+    }
+}
+
 pub struct SiteScanner {
     source_dir: PathBuf,
+    timezone: Tz,
+    cache: RefCell<PageCache>,
 }
 
 impl SiteScanner {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             source_dir: path.as_ref().to_path_buf(),
+            timezone: Tz::UTC,
+            cache: RefCell::new(PageCache::default()),
         }
     }
 
+    /// Timezone for front matter dates that don't have an offset
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Pages from an earlier scan, reused when their file hasn't changed
+    pub fn page_cache(mut self, cache: PageCache) -> Self {
+        self.cache = RefCell::new(cache);
+        self
+    }
+
+    /// The page cache, updated with this scan's pages
+    pub fn into_page_cache(self) -> PageCache {
+        self.cache.into_inner()
+    }
+
     pub fn scan(&self) -> Result<(Vec<Page>, Vec<Collection>), ScanError> {
         let pages = self.scan_pages()?;
         let collections = self.scan_collections()?;
@@ -83,6 +166,10 @@ impl SiteScanner {
             return Ok(None);
         };
 
+        if let Some(page) = self.cache.borrow().get(&path) {
+            return Ok(Some(page));
+        }
+
         let page_type = match file_name.to_string_lossy().to_lowercase().as_str() {
             "readme.md" => PageType::Home,
             "changelog.md" => PageType::Changelog,
@@ -91,12 +178,47 @@ impl SiteScanner {
         };
 
         let title = get_page_title(&path);
+        let (content, encoding) = crate::encoding::read_text(&path)?;
+        let front_matter = parse_front_matter(&content)
+            .map_err(|e| ScanError::FrontMatter(path.clone(), e))?;
+        let date = front_matter
+            .date
+            .map(|date| parse_date(&date, self.timezone))
+            .transpose()
+            .map_err(|e| ScanError::Date(path.clone(), e))?;
+        let expires = front_matter
+            .expires
+            .map(|date| parse_date(&date, self.timezone))
+            .transpose()
+            .map_err(|e| ScanError::Date(path.clone(), e))?;
+
+        let variants = page_variants(front_matter.variants, front_matter.variant)
+            .map_err(|message| ScanError::Variant(path.clone(), message))?;
 
-        Ok(Some(Page {
+        let page = Page {
             title,
             path: path.clone(),
             page_type,
-        }))
+            tags: front_matter.tags,
+            weight: front_matter.weight,
+            date,
+            expires,
+            draft: front_matter.draft,
+            description: front_matter.description,
+            image: front_matter.image,
+            language: front_matter.language,
+            dir: front_matter.dir,
+            extra: front_matter.extra,
+            encoding,
+            template: None,
+            permalink: None,
+            variants,
+            variant: None,
+            elements: Default::default(),
+        };
+        self.cache.borrow_mut().insert(&page);
+
+        Ok(Some(page))
     }
 
     fn scan_collection(&self, path: PathBuf) -> Result<Collection, ScanError> {
@@ -119,8 +241,52 @@ impl SiteScanner {
             }
         }
 
+        let schema = CollectionSchema::read(&path).map_err(|e| ScanError::Schema(path.join(SCHEMA_FILE), e))?;
+        if let Some(schema) = schema {
+            let violations = self.check_schema(&schema, &collection.pages)?;
+            if !violations.is_empty() {
+                return Err(ScanError::SchemaViolations(violations));
+            }
+        }
+
         Ok(collection)
     }
+
+    // Drafts and the collection's index pages don't have to match. Pages are
+    // read again, since cached ones don't keep their front matter source.
+    fn check_schema(&self, schema: &CollectionSchema, pages: &[Page]) -> Result<Vec<SchemaViolation>, ScanError> {
+        let mut violations = Vec::new();
+        for page in pages.iter().filter(|page| !page.draft && !matches!(page.page_type, PageType::Index)) {
+            let (content, _) = crate::encoding::read_text(&page.path)?;
+            let front_matter = match split_front_matter(&content).0 {
+                Some(source) => toml::from_str(source).map_err(|e| ScanError::FrontMatter(page.path.clone(), e))?,
+                None => toml::Table::new(),
+            };
+            violations.extend(schema.check(&front_matter, self.timezone).into_iter().map(|message| SchemaViolation {
+                path: page.path.clone(),
+                message,
+            }));
+        }
+        violations.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(violations)
+    }
+}
+
+// Variant names with the published one first
+fn page_variants(mut variants: Vec<String>, published: Option<String>) -> Result<Vec<String>, String> {
+    if let Some(name) = variants.iter().find(|name| !crate::variants::is_valid_name(name)) {
+        return Err(format!(
+            "\"{}\" can only use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    if let Some(published) = published {
+        let Some(position) = variants.iter().position(|name| *name == published) else {
+            return Err(format!("variant \"{}\" isn't in variants", published));
+        };
+        variants[..=position].rotate_right(1);
+    }
+    Ok(variants)
 }
 
 fn get_all_markdown_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
@@ -0,0 +1,195 @@
+// Front matter schemas, declared per collection in `_collection.toml`, so
+// a blog post missing its date or author fails the build instead of
+// rendering without them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::config::ConfigError;
+
+/// Read from the collection's directory
+pub const SCHEMA_FILE: &str = "_collection.toml";
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct CollectionSchema {
+    /// Front matter fields by name. Fields under `[extra]` are named like
+    /// `extra.author`.
+    pub fields: BTreeMap<String, FieldSchema>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FieldSchema {
+    #[serde(rename = "type")]
+    pub kind: Option<FieldType>,
+    pub required: bool,
+    /// Allowed values, for a string or each string in an array
+    #[serde(rename = "enum")]
+    pub values: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    /// A TOML date or a string zap can read as one
+    Date,
+    Array,
+    Table,
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::String => write!(f, "a string"),
+            FieldType::Integer => write!(f, "an integer"),
+            FieldType::Float => write!(f, "a number"),
+            FieldType::Boolean => write!(f, "true or false"),
+            FieldType::Date => write!(f, "a date"),
+            FieldType::Array => write!(f, "an array"),
+            FieldType::Table => write!(f, "a table"),
+        }
+    }
+}
+
+/// A page whose front matter doesn't match its collection's schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl CollectionSchema {
+    /// The schema in `dir`, if it has one
+    pub fn read(dir: &Path) -> Result<Option<Self>, ConfigError> {
+        let path = dir.join(SCHEMA_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read_to_string(path)?;
+        Ok(Some(toml::from_str(&data)?))
+    }
+
+    /// What's wrong with a page's front matter, one message per field
+    pub fn check(&self, front_matter: &toml::Table, timezone: Tz) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (name, field) in &self.fields {
+            let Some(value) = lookup(front_matter, name) else {
+                if field.required {
+                    problems.push(format!("`{}` is required", name));
+                }
+                continue;
+            };
+
+            if let Some(kind) = field.kind
+                && !matches_type(value, kind, timezone)
+            {
+                problems.push(format!("`{}` should be {}, not {}", name, kind, describe(value)));
+                continue;
+            }
+
+            if !field.values.is_empty() {
+                let strings: Vec<&str> = match value {
+                    toml::Value::Array(items) => items.iter().filter_map(toml::Value::as_str).collect(),
+                    value => value.as_str().into_iter().collect(),
+                };
+                for string in strings.into_iter().filter(|s| !field.values.iter().any(|v| v == s)) {
+                    problems.push(format!(
+                        "`{}` is \"{}\", which isn't one of: {}",
+                        name,
+                        string,
+                        field.values.join(", ")
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+// A field by dotted name, `extra.author` for `author` under `[extra]`
+fn lookup<'a>(table: &'a toml::Table, name: &str) -> Option<&'a toml::Value> {
+    let mut keys = name.split('.');
+    let mut value = table.get(keys.next()?)?;
+    for key in keys {
+        value = value.as_table()?.get(key)?;
+    }
+    Some(value)
+}
+
+fn matches_type(value: &toml::Value, kind: FieldType, timezone: Tz) -> bool {
+    match (kind, value) {
+        (FieldType::String, toml::Value::String(_))
+        | (FieldType::Integer, toml::Value::Integer(_))
+        | (FieldType::Float, toml::Value::Float(_) | toml::Value::Integer(_))
+        | (FieldType::Boolean, toml::Value::Boolean(_))
+        | (FieldType::Date, toml::Value::Datetime(_))
+        | (FieldType::Array, toml::Value::Array(_))
+        | (FieldType::Table, toml::Value::Table(_)) => true,
+        (FieldType::Date, toml::Value::String(text)) => crate::dates::parse_date(text, timezone).is_ok(),
+        _ => false,
+    }
+}
+
+fn describe(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => format!("\"{}\"", text),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        toml::Value::Array(_) => "an array".to_string(),
+        toml::Value::Table(_) => "a table".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let schema: CollectionSchema = toml::from_str(
+            r#"
+[fields.date]
+type = "date"
+required = true
+
+[fields."extra.author"]
+type = "string"
+required = true
+
+[fields.tags]
+type = "array"
+enum = ["news", "release"]
+
+[fields.weight]
+type = "integer"
+"#,
+        )
+        .unwrap();
+        let check = |source: &str| schema.check(&toml::from_str(source).unwrap(), Tz::UTC);
+
+        assert!(check("date = 2024-05-01\ntags = [\"news\"]\n[extra]\nauthor = \"Ana\"").is_empty());
+        assert!(check("date = \"2024-05-01 10:00\"\n[extra]\nauthor = \"Ana\"").is_empty());
+        assert_eq!(
+            check("date = \"soon\"\ntags = [\"news\", \"blog\"]\nweight = \"1\""),
+            vec![
+                "`date` should be a date, not \"soon\"",
+                "`extra.author` is required",
+                "`tags` is \"blog\", which isn't one of: news, release",
+                "`weight` should be an integer, not \"1\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// Search and social sharing metadata for each page, exposed to templates as
+// `seo`. Themes that don't write the meta tags themselves can have zap add
+// them with `[seo] inject = true`.
+
+use serde::Serialize;
+
+use crate::config::SiteConfig;
+use crate::markdown::{InlineElement, PageElement};
+use crate::site::{Page, PageType};
+
+/// Descriptions taken from the page's text are cut to about this many
+/// characters, what search engines show
+pub const DESCRIPTION_LENGTH: usize = 160;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Seo {
+    pub title: String,
+    pub site_name: Option<String>,
+    pub description: Option<String>,
+    /// Absolute when the site's `url` is set
+    pub image: Option<String>,
+    /// Absolute when the site's `url` is set
+    pub url: String,
+    /// `website` for the home page, `article` for the rest
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+/// Metadata for `page`, served at `url`. The description is the front
+/// matter's, then the first paragraph, then the site's. The image is the
+/// front matter's, then the first image in the page, then the site's.
+pub fn page_seo(page: &Page, url: &str, site: &SiteConfig) -> Seo {
+    let is_home = matches!(page.page_type, PageType::Home);
+    let title = match (is_home, &site.title) {
+        (true, Some(title)) => title.clone(),
+        _ => page.title.clone(),
+    };
+
+    let description = page
+        .description
+        .clone()
+        .or_else(|| page.get_first_paragraph())
+        .or_else(|| site.description.clone())
+        .map(|text| truncate(&text, DESCRIPTION_LENGTH))
+        .filter(|text| !text.is_empty());
+
+    let base = site.url.as_deref();
+    let image = page
+        .image
+        .clone()
+        .or_else(|| first_image(page.elements()))
+        .map(|image| absolute(base, url, &image))
+        .or_else(|| site.image.as_ref().map(|image| absolute(base, "/", image)));
+
+    Seo {
+        title,
+        site_name: site.title.clone(),
+        description,
+        image,
+        url: absolute(base, "/", url),
+        kind: if is_home { "website" } else { "article" },
+    }
+}
+
+/// The `<meta>` tags for `seo`
+pub fn meta_tags(seo: &Seo) -> String {
+    let mut tags = Vec::new();
+    let mut meta = |attribute: &str, name: &str, content: &str| {
+        tags.push(format!(
+            "<meta {}=\"{}\" content=\"{}\">",
+            attribute,
+            name,
+            html_escape::encode_double_quoted_attribute(content)
+        ));
+    };
+
+    if let Some(description) = &seo.description {
+        meta("name", "description", description);
+    }
+    meta("property", "og:title", &seo.title);
+    meta("property", "og:type", seo.kind);
+    meta("property", "og:url", &seo.url);
+    if let Some(site_name) = &seo.site_name {
+        meta("property", "og:site_name", site_name);
+    }
+    if let Some(description) = &seo.description {
+        meta("property", "og:description", description);
+    }
+    if let Some(image) = &seo.image {
+        meta("property", "og:image", image);
+    }
+    let card = if seo.image.is_some() { "summary_large_image" } else { "summary" };
+    meta("name", "twitter:card", card);
+
+    tags.join("\n")
+}
+
+/// Add the meta tags to the end of the page's head, unless the theme already
+/// writes them
+pub fn inject(html: String, seo: &Seo) -> String {
+    if html.contains("property=\"og:title\"") {
+        return html;
+    }
+    match html.find("</head>") {
+        Some(pos) => format!("{}{}\n{}", &html[..pos], meta_tags(seo), &html[pos..]),
+        None => html,
+    }
+}
+
+fn first_image(elements: &[PageElement]) -> Option<String> {
+    elements.iter().find_map(|element| match element {
+        PageElement::Paragraph { content } => content.iter().find_map(|inline| match inline {
+            InlineElement::Image { url, .. } => Some(url.clone()),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+// Links relative to the page are resolved against its URL, and root-relative
+// ones against the site's `url` when there is one
+fn absolute(base: Option<&str>, page_url: &str, link: &str) -> String {
+    if link.contains("://") || link.starts_with("//") {
+        return link.to_string();
+    }
+    let path = match link.strip_prefix('/') {
+        Some(_) => link.to_string(),
+        None => {
+            let dir = &page_url[..page_url.rfind('/').map(|i| i + 1).unwrap_or(0)];
+            format!("{}{}", if dir.is_empty() { "/" } else { dir }, link)
+        }
+    };
+    match base {
+        Some(base) => format!("{}{}", base.trim_end_matches('/'), path),
+        None => path,
+    }
+}
+
+// Cut at a word boundary, collapsing whitespace
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max {
+        return text;
+    }
+
+    let mut cut = String::new();
+    for word in text.split(' ') {
+        if cut.chars().count() + word.chars().count() + 1 > max - 1 {
+            break;
+        }
+        if !cut.is_empty() {
+            cut.push(' ');
+        }
+        cut.push_str(word);
+    }
+    if cut.is_empty() {
+        cut = text.chars().take(max - 1).collect();
+    }
+    let cut = cut.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    format!("{}…", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_tags() {
+        assert_eq!(absolute(Some("https://zap.dev/"), "/docs/install/", "shot.png"), "https://zap.dev/docs/install/shot.png");
+        assert_eq!(absolute(None, "/docs/install/", "/og.png"), "/og.png");
+        assert_eq!(absolute(Some("https://zap.dev"), "/", "https://cdn.dev/a.png"), "https://cdn.dev/a.png");
+
+        let long = "word ".repeat(50);
+        let cut = truncate(&long, DESCRIPTION_LENGTH);
+        assert!(cut.chars().count() <= DESCRIPTION_LENGTH);
+        assert!(cut.ends_with("word…"));
+        assert_eq!(truncate("Short  and\nsweet.", DESCRIPTION_LENGTH), "Short and sweet.");
+
+        let seo = Seo {
+            title: "Install \"zap\"".to_string(),
+            site_name: Some("Zap".to_string()),
+            description: None,
+            image: None,
+            url: "/docs/install/".to_string(),
+            kind: "article",
+        };
+        let html = inject("<html><head><title>Zap</title></head></html>".to_string(), &seo);
+        assert!(html.contains("<meta property=\"og:title\" content=\"Install &quot;zap&quot;\">"));
+        assert!(html.contains("<meta name=\"twitter:card\" content=\"summary\">\n</head>"));
+        assert_eq!(inject(html.clone(), &seo), html);
+    }
+}
@@ -1,4 +1,9 @@
+use crate::dates::PageDate;
+use crate::direction::Direction;
+use crate::encoding::Encoding;
 use crate::markdown::{PageElement, get_page_structured, get_page_title};
+use crate::scanner::ScanError;
+use std::cell::OnceCell;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -7,11 +12,65 @@ pub struct Page {
     pub title: String,
     pub path: PathBuf,
     pub page_type: PageType,
+    /// Tags from the page's front matter
+    pub tags: Vec<String>,
+    /// Ordering weight from the page's front matter
+    pub weight: Option<i64>,
+    /// Publish date from the page's front matter
+    pub date: Option<PageDate>,
+    /// When the page goes out of date, from its front matter
+    pub expires: Option<PageDate>,
+    pub draft: bool,
+    /// Description from the page's front matter
+    pub description: Option<String>,
+    /// Share image from the page's front matter
+    pub image: Option<String>,
+    /// Language from the page's front matter, when it isn't the site's
+    pub language: Option<String>,
+    /// Text direction from the page's front matter
+    pub dir: Option<Direction>,
+    /// Theme-defined values from the page's `[extra]` front matter
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// How the source file was decoded
+    pub encoding: Encoding,
+    /// Template set by a page rule, instead of the page type's
+    pub template: Option<String>,
+    /// URL set by a page rule, e.g. `/blog/2024/hello/`
+    pub permalink: Option<String>,
+    /// Content variants, the published one first
+    pub variants: Vec<String>,
+    /// Variant this copy of the page renders, when it's a preview of one
+    /// that isn't published
+    pub variant: Option<String>,
+    /// Parsed on the first call to `elements`
+    pub(crate) elements: OnceCell<Vec<PageElement>>,
 }
 
 impl Page {
+    /// Whether the page's `date` is still to come
+    pub fn is_scheduled(&self) -> bool {
+        self.is_scheduled_at(chrono::Utc::now())
+    }
+
+    /// Whether the page's `expires` date has passed
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(chrono::Utc::now())
+    }
+
+    fn is_scheduled_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.date.is_some_and(|date| date.utc > now)
+    }
+
+    fn is_expired_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires.utc <= now)
+    }
+
     pub fn url(&self, source_dir: &Path) -> String {
         // Convert absolute path to relative path for URL
+        if let Some(permalink) = &self.permalink {
+            return permalink.clone();
+        }
+
         let relative_path = self.path.strip_prefix(source_dir).unwrap_or(&self.path);
 
         match &self.page_type {
@@ -22,17 +81,28 @@ impl Page {
                 format!("/{}/", dir_path.to_string_lossy())
             }
             _ => {
-                let url_path = relative_path.with_extension("");
+                let url_path = crate::ordering::url_path(relative_path);
                 format!("/{}/", url_path.to_string_lossy())
             }
         }
     }
 
-    pub fn elements(&self) -> Vec<PageElement> {
-        get_page_structured(&self.path)
+    /// The page's markdown, parsed once and kept for later calls
+    pub fn elements(&self) -> &[PageElement] {
+        self.elements.get_or_init(|| {
+            let Some(variant) = self.variant.as_ref().or(self.variants.first()) else {
+                return get_page_structured(&self.path);
+            };
+            let markdown = crate::encoding::read_to_string(&self.path).unwrap_or_default();
+            crate::markdown::parse_structured(&crate::variants::select(&markdown, variant))
+        })
     }
 
-    pub fn template_name(&self) -> &'static str {
+    pub fn template_name(&self) -> &str {
+        if let Some(template) = &self.template {
+            return template;
+        }
+
         match self.page_type {
             PageType::Home => "home.html",
             PageType::Changelog => "changelog.html",
@@ -50,10 +120,10 @@ impl Page {
 
     pub fn get_first_heading(&self) -> Option<String> {
         self.elements()
-            .into_iter()
+            .iter()
             .find_map(|element| match element {
                 PageElement::Heading { content, .. } => {
-                    Some(crate::markdown::render_inline_elements_text(&content))
+                    Some(crate::markdown::render_inline_elements_text(content))
                 },
                 _ => None,
             })
@@ -61,10 +131,10 @@ impl Page {
 
     pub fn get_first_paragraph(&self) -> Option<String> {
         self.elements()
-            .into_iter()
+            .iter()
             .find_map(|element| match element {
                 PageElement::Paragraph { content } => {
-                    Some(crate::markdown::render_inline_elements_text(&content))
+                    Some(crate::markdown::render_inline_elements_text(content))
                 },
                 _ => None,
             })
@@ -78,6 +148,8 @@ pub enum PageType {
     Index,
     Doc,
     Regular,
+    /// A type named by a page rule
+    Custom(String),
     Unknown,
 }
 
@@ -88,8 +160,10 @@ pub struct Collection {
 }
 
 impl Collection {
+    /// URL of the collection's landing page. Keeps the directory's case, like
+    /// the pages inside it.
     pub fn url(&self) -> String {
-        self.name.to_lowercase()
+        format!("/{}/", self.name)
     }
 }
 
@@ -114,34 +188,39 @@ impl Zap {
         self.out_path = path;
     }
 
-    pub fn scan(&mut self) {
-        for path in std::fs::read_dir(&self.scan_path)
-            .expect("Failed to read scan path")
-            .filter_map(|e| e.ok())
-        {
+    pub fn scan(&mut self) -> Result<(), ScanError> {
+        let Ok(entries) = std::fs::read_dir(&self.scan_path) else {
+            return Ok(());
+        };
+        for path in entries.filter_map(|e| e.ok()) {
             if path.path().is_dir() {
-                self.collections.push(self.scan_collection(path.path()));
+                let collection = self.scan_collection(path.path())?;
+                self.collections.push(collection);
             } else if get_extension(path.path().to_path_buf()) == "md" {
-                self.pages.push(self.scan_page(path.path()).unwrap());
+                let page = self.scan_page(path.path())?;
+                self.pages.push(page);
             }
         }
+        Ok(())
     }
 
-    fn scan_collection(&self, path: PathBuf) -> Collection {
+    fn scan_collection(&self, path: PathBuf) -> Result<Collection, ScanError> {
         let mut collection = Collection {
             name: path.file_name().unwrap().to_string_lossy().to_string(),
             pages: Vec::new(),
         };
 
         for f in get_all_markdown_files(path) {
-            collection.pages.push(self.scan_page(f).unwrap());
+            collection.pages.push(self.scan_page(f)?);
         }
 
-        collection
+        Ok(collection)
     }
 
-    fn scan_page(&self, path: PathBuf) -> Option<Page> {
-        path.file_name()?;
+    fn scan_page(&self, path: PathBuf) -> Result<Page, ScanError> {
+        if path.file_name().is_none() {
+            return Err(ScanError::InvalidPath(path));
+        }
 
         let page_type = match path
             .file_name()
@@ -157,12 +236,35 @@ impl Zap {
         };
 
         let title = get_page_title(&path);
+        let (content, encoding) = crate::encoding::read_text(&path).unwrap_or_default();
+        let front_matter = crate::front_matter::parse_front_matter(&content)
+            .map_err(|e| ScanError::FrontMatter(path.clone(), e))?;
         let relative_path = path.strip_prefix(&self.scan_path).unwrap();
 
-        Some(Page {
+        Ok(Page {
             title,
             path: relative_path.to_path_buf(),
             page_type,
+            tags: front_matter.tags,
+            weight: front_matter.weight,
+            date: front_matter
+                .date
+                .and_then(|date| crate::dates::parse_date(&date, chrono_tz::Tz::UTC).ok()),
+            expires: front_matter
+                .expires
+                .and_then(|date| crate::dates::parse_date(&date, chrono_tz::Tz::UTC).ok()),
+            draft: front_matter.draft,
+            description: front_matter.description,
+            image: front_matter.image,
+            language: front_matter.language,
+            dir: front_matter.dir,
+            extra: front_matter.extra,
+            encoding,
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: OnceCell::new(),
         })
     }
 
@@ -175,8 +277,7 @@ impl Zap {
     }
 
     pub fn render_page(&self, page: &Page) -> String {
-        let elements = page.elements();
-        crate::markdown::render_elements_to_html(&elements)
+        crate::markdown::render_elements_to_html(page.elements())
     }
 }
 
@@ -201,3 +302,66 @@ fn get_extension(path: PathBuf) -> String {
         None => "Uknown".into(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::parse_date;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_scheduled_and_expired() {
+        let date = |text: &str| parse_date(text, Tz::Europe__Madrid).unwrap();
+        let page = Page {
+            title: "Launch party".to_string(),
+            path: PathBuf::from("events/launch.md"),
+            page_type: PageType::Regular,
+            tags: Vec::new(),
+            weight: None,
+            date: Some(date("2025-06-01")),
+            expires: Some(date("2025-06-30 18:00")),
+            draft: false,
+            description: None,
+            image: None,
+            language: None,
+            dir: None,
+            extra: Default::default(),
+            encoding: Default::default(),
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: Default::default(),
+        };
+
+        // Midnight in Madrid is 22:00 UTC the day before
+        assert!(page.is_scheduled_at(date("2025-05-31T21:59:59Z").utc));
+        assert!(!page.is_scheduled_at(date("2025-05-31T22:00:00Z").utc));
+        assert!(!page.is_expired_at(date("2025-06-30 17:59").utc));
+        assert!(page.is_expired_at(date("2025-06-30 18:00").utc));
+
+        let undated = Page { date: None, expires: None, ..page };
+        assert!(!undated.is_scheduled_at(date("2000-01-01").utc));
+        assert!(!undated.is_expired_at(date("2100-01-01").utc));
+    }
+
+    #[test]
+    fn test_scan_front_matter_error() {
+        let dir = std::env::temp_dir().join(format!("zap-site-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("guides")).unwrap();
+        std::fs::write(dir.join("guides/install.md"), "+++\ntags = [\"setup\"]\n+++\n# Install\n").unwrap();
+
+        let mut zap = Zap::new(dir.clone());
+        zap.scan().unwrap();
+        assert_eq!(zap.collections()[0].pages[0].tags, ["setup"]);
+
+        std::fs::write(dir.join("guides/broken.md"), "+++\ntags = [\n+++\n# Broken\n").unwrap();
+        let mut zap = Zap::new(dir.clone());
+        match zap.scan() {
+            Err(ScanError::FrontMatter(path, _)) => assert_eq!(path, dir.join("guides/broken.md")),
+            other => panic!("expected a front matter error, got {:?}", other),
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
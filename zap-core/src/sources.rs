@@ -0,0 +1,447 @@
+// Content kept outside the site's repository, pulled into the source
+// directory before it's scanned, so pages can be edited by people who
+// don't work in the repository: another git repository at a branch, tag or
+// commit, or a CMS serving its pages as JSON. Each source writes into its
+// own directory and remembers what it wrote in `.zap-cache/`, so syncing
+// again only rewrites files that changed, removes the ones the source no
+// longer has, and leaves files written by hand alone.
+
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::config::SourceConfig;
+
+/// A file from a source, at a path relative to the source's directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFile {
+    pub path: String,
+    pub content: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum Fetched {
+    /// Still at the version the last sync got
+    Unchanged,
+    Files { version: String, files: Vec<RemoteFile> },
+}
+
+/// Where a source's files come from. Implement it to pull content from a
+/// CMS zap has no adapter for.
+pub trait ContentSource {
+    /// Every file the source has, or `Unchanged` when it's still at
+    /// `version`, what the last sync's fetch returned
+    fn fetch(&self, version: Option<&str>) -> Result<Fetched, String>;
+}
+
+/// Files from a directory of a git repository at a branch, tag or commit
+pub struct GitSource {
+    pub repo: String,
+    pub reference: String,
+    /// Directory in the repository, the whole repository when empty
+    pub path: String,
+    /// Where the repository is checked out between syncs
+    pub checkout: PathBuf,
+}
+
+impl ContentSource for GitSource {
+    fn fetch(&self, version: Option<&str>) -> Result<Fetched, String> {
+        if !self.checkout.join(".git").is_dir() {
+            std::fs::create_dir_all(&self.checkout).map_err(|e| e.to_string())?;
+            crate::git::git(&self.checkout, &["init", "--quiet"])?;
+        }
+        // Only the commit itself, the history isn't needed
+        crate::git::git(&self.checkout, &["fetch", "--quiet", "--depth", "1", &self.repo, &self.reference])?;
+        let commit = crate::git::git(&self.checkout, &["rev-parse", "FETCH_HEAD"])?;
+        if version == Some(commit.as_str()) {
+            return Ok(Fetched::Unchanged);
+        }
+        crate::git::git(&self.checkout, &["checkout", "--quiet", "--force", "--detach", "FETCH_HEAD"])?;
+
+        let root = self.checkout.join(&self.path);
+        if !root.is_dir() {
+            return Err(format!("{} has no directory {}", self.reference, self.path));
+        }
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&root).into_iter().filter_entry(|entry| entry.file_name() != ".git") {
+            let entry = entry.map_err(|e| e.to_string())?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            let path: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+            files.push(RemoteFile {
+                path: path.join("/"),
+                content: std::fs::read(entry.path()).map_err(|e| e.to_string())?,
+            });
+        }
+        Ok(Fetched::Files { version: commit, files })
+    }
+}
+
+/// Pages from a JSON endpoint, a list of entries each with a `path` or
+/// `slug`, and either the whole file as `content` or a markdown `body` with
+/// `title`, `date`, `tags` and other `front_matter`. A CMS without such an
+/// endpoint can usually get one from a small function or webhook.
+pub struct ApiSource {
+    pub url: String,
+    /// Sent as `Authorization: Bearer`
+    pub token: Option<String>,
+}
+
+impl ContentSource for ApiSource {
+    fn fetch(&self, version: Option<&str>) -> Result<Fetched, String> {
+        let mut request = ureq::get(&self.url)
+            .header("Accept", "application/json")
+            .header("User-Agent", "zap");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {}", token));
+        }
+        if let Some(etag) = version.and_then(|version| version.strip_prefix("etag:")) {
+            request = request.header("If-None-Match", etag);
+        }
+        let mut response = request.call().map_err(|e| e.to_string())?;
+        if response.status() == 304 {
+            return Ok(Fetched::Unchanged);
+        }
+
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let body = response.body_mut().read_to_string().map_err(|e| e.to_string())?;
+        // Servers without ETags are compared by what they sent
+        let current = match etag {
+            Some(etag) => format!("etag:{}", etag),
+            None => format!("sha256:{:x}", Sha256::digest(body.as_bytes())),
+        };
+        if version == Some(current.as_str()) {
+            return Ok(Fetched::Unchanged);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+        Ok(Fetched::Files {
+            version: current,
+            files: api_files(&json)?,
+        })
+    }
+}
+
+// The entries as files, from a list or a list under `entries`, `items` or
+// `pages`
+fn api_files(json: &serde_json::Value) -> Result<Vec<RemoteFile>, String> {
+    let entries = json
+        .as_array()
+        .or_else(|| ["entries", "items", "pages"].iter().find_map(|key| json[key].as_array()))
+        .ok_or("the response isn't a list of entries")?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = match (entry["path"].as_str(), entry["slug"].as_str()) {
+            (Some(path), _) => path.to_string(),
+            (None, Some(slug)) => format!("{}.md", slug.trim_matches('/')),
+            (None, None) => return Err("an entry has neither `path` nor `slug`".to_string()),
+        };
+        let content = match entry["content"].as_str() {
+            Some(content) => content.to_string(),
+            None => api_page(entry)?,
+        };
+        files.push(RemoteFile {
+            path,
+            content: content.into_bytes(),
+        });
+    }
+    Ok(files)
+}
+
+fn api_page(entry: &serde_json::Value) -> Result<String, String> {
+    let mut front_matter = toml::Table::new();
+    for key in ["title", "date", "tags", "description"] {
+        if !entry[key].is_null() {
+            front_matter.insert(key.to_string(), toml::Value::try_from(&entry[key]).map_err(|e| e.to_string())?);
+        }
+    }
+    if let Some(extra) = entry["front_matter"].as_object() {
+        for (key, value) in extra.iter().filter(|(_, value)| !value.is_null()) {
+            front_matter.insert(key.clone(), toml::Value::try_from(value).map_err(|e| e.to_string())?);
+        }
+    }
+    let body = entry["body"].as_str().or_else(|| entry["markdown"].as_str()).unwrap_or_default();
+    Ok(format!(
+        "+++\n{}+++\n{}\n",
+        toml::to_string(&front_matter).map_err(|e| e.to_string())?,
+        body.trim_end()
+    ))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    version: Option<String>,
+    synced_at: u64,
+    /// Written by the source, relative to its directory
+    files: BTreeSet<String>,
+}
+
+/// What a sync changed in the source's directory
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub written: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// Files the source has that were written by hand, which are kept
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Fetch `source` and bring `dir` up to date with it. `state_path` keeps
+/// what was synced between runs.
+pub fn sync(source: &dyn ContentSource, dir: &Path, state_path: &Path) -> Result<SyncSummary, String> {
+    let mut state: SyncState = std::fs::read_to_string(state_path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    // Files deleted since the last sync are written again
+    let version = state
+        .version
+        .as_deref()
+        .filter(|_| state.files.iter().all(|file| dir.join(file).is_file()));
+
+    let mut summary = SyncSummary::default();
+    match source.fetch(version)? {
+        Fetched::Unchanged => {}
+        Fetched::Files { version, files } => {
+            // Checked before anything is written, so a bad path doesn't
+            // leave the directory half synced
+            if let Some(file) = files.iter().find(|file| !is_relative(&file.path)) {
+                return Err(format!("{} is outside the source's directory", file.path));
+            }
+            let mut synced = BTreeSet::new();
+            if let Err(e) = write_files(files, dir, &state.files, &mut synced, &mut summary) {
+                // The files written so far are still the source's, not
+                // hand written, and the next sync fetches everything again
+                state.files.extend(synced);
+                state.version = None;
+                save_state(&state, state_path)?;
+                return Err(e);
+            }
+            for file in state.files.difference(&synced) {
+                let path = dir.join(file);
+                if std::fs::remove_file(&path).is_ok() {
+                    summary.removed.push(path);
+                }
+            }
+            state.version = Some(version);
+            state.files = synced;
+        }
+    }
+
+    state.synced_at = now();
+    save_state(&state, state_path)?;
+    Ok(summary)
+}
+
+// Write the files that changed, adding every file the source owns to
+// `synced`. Files that exist and weren't `owned` before are skipped.
+fn write_files(
+    files: Vec<RemoteFile>,
+    dir: &Path,
+    owned: &BTreeSet<String>,
+    synced: &mut BTreeSet<String>,
+    summary: &mut SyncSummary,
+) -> Result<(), String> {
+    for file in files {
+        let path = dir.join(&file.path);
+        if path.exists() && !owned.contains(&file.path) {
+            summary.skipped.push(path);
+            continue;
+        }
+        synced.insert(file.path);
+        if std::fs::read(&path).is_ok_and(|content| content == file.content) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, &file.content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        summary.written.push(path);
+    }
+    Ok(())
+}
+
+fn save_state(state: &SyncState, state_path: &Path) -> Result<(), String> {
+    if let Some(parent) = state_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    std::fs::write(state_path, json).map_err(|e| e.to_string())
+}
+
+// Paths from a source can't climb out of its directory
+fn is_relative(path: &str) -> bool {
+    !path.is_empty() && Path::new(path).components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// The adapter for a `[[sources]]` entry
+pub fn content_source(config: &SourceConfig, cache_dir: &Path) -> Result<Box<dyn ContentSource>, String> {
+    match (&config.git, &config.url) {
+        (Some(repo), None) => Ok(Box::new(GitSource {
+            repo: repo.clone(),
+            reference: config.reference.clone(),
+            path: config.path.clone().unwrap_or_default(),
+            checkout: cache_dir.join(state_name(config)),
+        })),
+        (None, Some(url)) => {
+            let token = match &config.token_env {
+                Some(env) => Some(std::env::var(env).map_err(|_| format!("set {} to the source's token", env))?),
+                None => None,
+            };
+            Ok(Box::new(ApiSource { url: url.clone(), token }))
+        }
+        _ => Err("set either `git` or `url`".to_string()),
+    }
+}
+
+// Named after the directory and where it's pulled from, so changing either
+// starts over
+fn state_name(config: &SourceConfig) -> String {
+    let from = config.git.as_deref().or(config.url.as_deref()).unwrap_or_default();
+    format!(
+        "{}-{}",
+        crate::markdown::slugify(&config.dir),
+        crate::anchors::stable_id(&format!("{}\n{}\n{}", from, config.reference, config.path.as_deref().unwrap_or_default()))
+    )
+}
+
+/// Sync every source into `source_dir`, returning each one's description
+/// and summary. Sources synced within their `cache_minutes` are skipped,
+/// with `None`, unless `force` is set.
+pub fn sync_all(
+    configs: &[SourceConfig],
+    source_dir: &Path,
+    cache_dir: &Path,
+    force: bool,
+) -> Vec<(String, Result<Option<SyncSummary>, String>)> {
+    let cache_dir = cache_dir.join("sources");
+    configs
+        .iter()
+        .map(|config| {
+            let from = config.git.as_deref().or(config.url.as_deref()).unwrap_or("nowhere");
+            let description = format!("{} from {}", config.dir, from);
+            let state_path = cache_dir.join(format!("{}.json", state_name(config)));
+
+            let synced_at = std::fs::read_to_string(&state_path)
+                .ok()
+                .and_then(|data| serde_json::from_str::<SyncState>(&data).ok())
+                .map(|state| state.synced_at);
+            let fresh = synced_at.is_some_and(|at| now().saturating_sub(at) < config.cache_minutes * 60);
+            if fresh && !force && source_dir.join(&config.dir).is_dir() {
+                return (description, Ok(None));
+            }
+
+            let result = match is_relative(&config.dir) {
+                true => content_source(config, &cache_dir)
+                    .and_then(|source| sync(source.as_ref(), &source_dir.join(&config.dir), &state_path))
+                    .map(Some),
+                false => Err(format!("`dir` \"{}\" has to be inside the source directory", config.dir)),
+            };
+            (description, result)
+        })
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct Files(RefCell<Vec<(&'static str, Vec<RemoteFile>)>>);
+
+    impl ContentSource for Files {
+        fn fetch(&self, version: Option<&str>) -> Result<Fetched, String> {
+            let (next, files) = self.0.borrow_mut().remove(0);
+            match version == Some(next) {
+                true => Ok(Fetched::Unchanged),
+                false => Ok(Fetched::Files { version: next.to_string(), files }),
+            }
+        }
+    }
+
+    fn file(path: &str, content: &str) -> RemoteFile {
+        RemoteFile {
+            path: path.to_string(),
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_sync() {
+        let root = std::env::temp_dir().join(format!("zap-sources-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dir = root.join("site/handbook");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("local.md"), "by hand").unwrap();
+        let state = root.join("state.json");
+
+        let source = Files(RefCell::new(vec![
+            ("v1", vec![file("a.md", "a"), file("guides/b.md", "b"), file("local.md", "remote")]),
+            ("v1", vec![]),
+            ("v2", vec![file("a.md", "a"), file("guides/b.md", "b2")]),
+        ]));
+        let first = sync(&source, &dir, &state).unwrap();
+        assert_eq!(first.written, [dir.join("a.md"), dir.join("guides/b.md")]);
+        assert_eq!(first.skipped, [dir.join("local.md")]);
+        assert_eq!(std::fs::read_to_string(dir.join("local.md")).unwrap(), "by hand");
+
+        let unchanged = sync(&source, &dir, &state).unwrap();
+        assert!(unchanged.written.is_empty() && unchanged.removed.is_empty());
+
+        std::fs::remove_file(dir.join("a.md")).unwrap();
+        let second = sync(&source, &dir, &state).unwrap();
+        assert_eq!(second.written, [dir.join("a.md"), dir.join("guides/b.md")]);
+        assert!(second.removed.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // A bad path fails the sync before anything is written
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = Files(RefCell::new(vec![
+            ("v1", vec![file("a.md", "a"), file("../escape.md", "x")]),
+            ("v1", vec![file("a.md", "a"), file("blocked/c.md", "c")]),
+            ("v2", vec![file("a.md", "a2")]),
+        ]));
+        assert!(sync(&source, &dir, &state).is_err());
+        assert!(!dir.join("a.md").exists() && !root.join("site/escape.md").exists());
+        assert!(!state.exists());
+
+        // A failed write keeps what was written as the source's
+        std::fs::write(dir.join("blocked"), "by hand").unwrap();
+        assert!(sync(&source, &dir, &state).is_err());
+        assert_eq!(std::fs::read_to_string(dir.join("a.md")).unwrap(), "a");
+        let retried = sync(&source, &dir, &state).unwrap();
+        assert_eq!(retried.written, [dir.join("a.md")]);
+        assert!(retried.skipped.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let json = serde_json::json!({ "entries": [
+            { "slug": "intro", "title": "Intro", "tags": ["start"], "body": "Hello", "front_matter": { "weight": 2 } },
+            { "path": "raw.md", "content": "+++\ntitle = \"Raw\"\n+++\nRaw" },
+        ]});
+        let files = api_files(&json).unwrap();
+        assert_eq!(files[0].path, "intro.md");
+        assert_eq!(
+            String::from_utf8_lossy(&files[0].content),
+            "+++\ntags = [\"start\"]\ntitle = \"Intro\"\nweight = 2\n+++\nHello\n"
+        );
+        assert_eq!(files[1], file("raw.md", "+++\ntitle = \"Raw\"\n+++\nRaw"));
+        assert!(!is_relative("../escape.md") && !is_relative("/etc/passwd"));
+    }
+}
@@ -0,0 +1,41 @@
+// Staging builds, for preview deployments that search engines shouldn't
+// index. Every page gets a `noindex` robots tag and the site a robots.txt
+// disallowing everything, replacing any in the static directory, so a
+// preview can't be indexed even if it's linked from somewhere.
+
+pub const ROBOTS_TXT: &str = "User-agent: *\nDisallow: /\n";
+
+const NOINDEX: &str = "<meta name=\"robots\" content=\"noindex, nofollow\">";
+
+/// Add the `noindex` tag to the start of the page's head
+pub fn insert_noindex(html: &str) -> String {
+    // Not `<header>`
+    let head = html
+        .match_indices("<head")
+        .map(|(pos, _)| pos)
+        .find(|&pos| html[pos + 5..].starts_with(|c: char| c == '>' || c.is_whitespace()));
+    let Some(head) = head else {
+        return format!("{}{}", NOINDEX, html);
+    };
+    match html[head..].find('>') {
+        Some(end) => {
+            let pos = head + end + 1;
+            format!("{}\n{}{}", &html[..pos], NOINDEX, &html[pos..])
+        }
+        None => html.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_noindex() {
+        assert_eq!(
+            insert_noindex("<html><head lang=\"en\"><title>Zap</title></head></html>"),
+            "<html><head lang=\"en\">\n<meta name=\"robots\" content=\"noindex, nofollow\"><title>Zap</title></head></html>"
+        );
+        assert_eq!(insert_noindex("<p>Zap</p>"), format!("{}<p>Zap</p>", NOINDEX));
+    }
+}
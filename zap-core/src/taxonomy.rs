@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::builder::NavItem;
+use crate::markdown::slugify;
+
+/// A tag and every page carrying it, for the generated `/tags/` pages
+#[derive(Debug, Clone, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+    pub pages: Vec<NavItem>,
+}
+
+pub fn tag_url(name: &str) -> String {
+    format!("/tags/{}/", slugify(name))
+}
+
+/// Links to a page's tags, as exposed to its template
+pub fn tag_links(tags: &[String]) -> Vec<NavItem> {
+    tags.iter()
+        .filter(|name| !slugify(name).is_empty())
+        .map(|name| NavItem {
+            text: name.clone(),
+            link: tag_url(name),
+        })
+        .collect()
+}
+
+/// Group pages by tag. Tags that only differ in case or punctuation share a
+/// slug and are merged under the first spelling seen.
+pub fn collect_tags<'a>(pages: impl Iterator<Item = (&'a [String], NavItem)>) -> Vec<Tag> {
+    let mut tags: BTreeMap<String, Tag> = BTreeMap::new();
+
+    for (names, page) in pages {
+        for name in names {
+            let slug = slugify(name);
+            if slug.is_empty() {
+                continue;
+            }
+            let tag = tags.entry(slug.clone()).or_insert_with(|| Tag {
+                name: name.clone(),
+                url: tag_url(name),
+                slug,
+                pages: Vec::new(),
+            });
+            tag.pages.push(page.clone());
+        }
+    }
+
+    tags.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(title: &str) -> NavItem {
+        NavItem {
+            text: title.to_string(),
+            link: format!("/{}/", slugify(title)),
+        }
+    }
+
+    #[test]
+    fn test_collect_tags() {
+        let first = vec!["Rust".to_string(), "Command Line".to_string()];
+        let second = vec!["rust".to_string(), "command-line!".to_string(), "???".to_string()];
+        let third = vec!["Async".to_string()];
+        let tags = collect_tags(
+            [
+                (first.as_slice(), page("Install")),
+                (second.as_slice(), page("Usage")),
+                (third.as_slice(), page("Tasks")),
+            ]
+            .into_iter(),
+        );
+
+        // Sorted by slug, spelled as first seen, and tags without a slug are left out
+        let slugs: Vec<&str> = tags.iter().map(|tag| tag.slug.as_str()).collect();
+        assert_eq!(slugs, ["async", "command-line", "rust"]);
+        assert_eq!(tags[1].name, "Command Line");
+        assert_eq!(tags[1].url, "/tags/command-line/");
+        assert_eq!(tags[2].name, "Rust");
+        let pages: Vec<&str> = tags[2].pages.iter().map(|page| page.text.as_str()).collect();
+        assert_eq!(pages, ["Install", "Usage"]);
+
+        let links = tag_links(&second);
+        assert_eq!(links.len(), 2);
+        assert_eq!((links[0].text.as_str(), links[0].link.as_str()), ("rust", "/tags/rust/"));
+        assert_eq!(links[1].link, "/tags/command-line/");
+    }
+}
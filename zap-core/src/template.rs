@@ -1,10 +1,14 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use serde::Serialize;
 use tera::{Context, Tera};
+use crate::diagnostics::Diagnostic;
 
 #[derive(Debug)]
 pub enum TemplateError {
     TeraError(tera::Error),
+    /// Theme templates that don't parse, or failed to render, and where
+    Template(Vec<Diagnostic>),
     IoError(std::io::Error),
 }
 
@@ -24,6 +28,10 @@ impl std::fmt::Display for TemplateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TemplateError::TeraError(e) => write!(f, "Template error: {}", e),
+            TemplateError::Template(diagnostics) => {
+                let summaries: Vec<String> = diagnostics.iter().map(Diagnostic::summary).collect();
+                write!(f, "{}", summaries.join("\n"))
+            }
             TemplateError::IoError(e) => write!(f, "IO error: {}", e),
         }
     }
@@ -31,6 +39,121 @@ impl std::fmt::Display for TemplateError {
 
 impl std::error::Error for TemplateError {}
 
+impl TemplateError {
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            TemplateError::Template(diagnostics) => diagnostics.clone(),
+            TemplateError::TeraError(e) => tera_diagnostics(e, |_| None),
+            TemplateError::IoError(e) => vec![Diagnostic::error(e.to_string())],
+        }
+    }
+}
+
+// Tera puts what went wrong in the error's causes, and for parse errors
+// the line and column in pest's ` --> 3:8` and `= expected ...` lines. Each
+// template that failed to load is a `* ` item of its own. Render errors
+// have no position, so they point at the first use of the name they quote,
+// like the variable that's missing. `path_of` finds the file of a template
+// by its name.
+fn tera_diagnostics(error: &tera::Error, path_of: impl Fn(&str) -> Option<PathBuf>) -> Vec<Diagnostic> {
+    let mut text = error.to_string();
+    let mut cause = std::error::Error::source(error);
+    while let Some(e) = cause {
+        text.push('\n');
+        text.push_str(&e.to_string());
+        cause = e.source();
+    }
+
+    text.split("\n* ")
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let lines: Vec<&str> = entry.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+            let header = lines.first().copied().unwrap_or_default();
+            let position = lines.iter().find_map(|line| {
+                let (line, column) = line.strip_prefix("--> ")?.split_once(':')?;
+                Some((line.parse::<usize>().ok()?, column.parse::<usize>().ok()?))
+            });
+            let message = lines
+                .iter()
+                .find_map(|line| line.strip_prefix("= "))
+                .or(lines.last().copied())
+                .unwrap_or_default();
+
+            // The template the problem is in, a parent when it's there
+            let quoted = |text: &str, prefix: &str| {
+                let rest = &text[text.find(prefix)? + prefix.len()..];
+                rest.find(['\'', '"']).map(|end| rest[..end].to_string())
+            };
+            let name = quoted(message, "while rendering '")
+                .or_else(|| quoted(header, "(error happened in '"))
+                .or_else(|| quoted(header, "Failed to render '"))
+                .or_else(|| quoted(header, "Failed to parse \""))
+                .or_else(|| quoted(header, "Failed to parse '"));
+            let file = name.and_then(|name| match Path::new(&name).is_file() {
+                true => Some(PathBuf::from(name)),
+                false => path_of(&name),
+            });
+
+            let mut diagnostic = Diagnostic::error(message);
+            let Some(file) = file else {
+                return diagnostic;
+            };
+            if let Ok(source) = std::fs::read_to_string(&file) {
+                let quoted_name = message.split('`').nth(1).filter(|name| !name.is_empty());
+                if let Some((line, column)) = position {
+                    diagnostic = diagnostic.at_line(&source, line, Some(column));
+                } else if let Some(offset) = quoted_name.and_then(|name| source.find(name)) {
+                    diagnostic = diagnostic.at_offset(&source, offset);
+                }
+            }
+            // Tera makes theme paths absolute
+            let relative = std::env::current_dir().ok().and_then(|cwd| file.strip_prefix(cwd).ok().map(Path::to_path_buf));
+            diagnostic.in_file(relative.unwrap_or(file))
+        })
+        .collect()
+}
+
+/// Built-in template used when a theme has neither the requested template
+/// nor `page.html`
+pub const FALLBACK_TEMPLATE: &str = "__zap_fallback.html";
+
+const FALLBACK_SOURCE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+   <meta charset="UTF-8">
+   <meta name="viewport" content="width=device-width, initial-scale=1.0">
+   <title>{{ site.title | default(value="Zap") }}</title>
+</head>
+<body>
+   <main>
+      {% if page_content %}{{ page_content | safe }}{% endif %}
+   </main>
+</body>
+</html>
+"#;
+
+// Unknown names are returned as-is so files built outside the theme, like a
+// compiled stylesheet, keep working
+struct AssetUrls(HashMap<String, String>);
+
+impl tera::Function for AssetUrls {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = args
+            .get("path")
+            .and_then(|path| path.as_str())
+            .ok_or_else(|| tera::Error::msg("asset() requires a `path` argument"))?
+            .trim_start_matches('/');
+        let url = self.0.get(path).cloned().unwrap_or_else(|| format!("/{}", path));
+        Ok(tera::Value::String(url))
+    }
+
+    // Asset paths come from the theme's own files
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
 pub struct TemplateRenderer {
     tera: Tera,
     context: Context,
@@ -38,12 +161,43 @@ pub struct TemplateRenderer {
 
 impl TemplateRenderer {
     pub fn new(theme_path: &str) -> Result<Self, TemplateError> {
-        let tera = Tera::new(theme_path)?;
+        let mut tera = Tera::new(theme_path).map_err(|e| TemplateError::Template(tera_diagnostics(&e, |_| None)))?;
+        tera.add_raw_template(FALLBACK_TEMPLATE, FALLBACK_SOURCE)?;
         let context = Context::new();
         
         Ok(Self { tera, context })
     }
     
+    /// Whether the theme (or the built-in fallback) defines a template
+    pub fn has_template(&self, template: &str) -> bool {
+        self.tera.get_template_names().any(|name| name == template)
+    }
+
+    /// Register `asset(path="...")`, which resolves a theme asset name to
+    /// its (possibly fingerprinted) URL
+    pub fn register_assets(&mut self, urls: HashMap<String, String>) {
+        self.tera.register_function("asset", AssetUrls(urls));
+    }
+
+    /// Register `sample()` and `shuffle()`, which pick pages the same way
+    /// for the same seed
+    pub fn register_sampling(&mut self, seed: u64) {
+        self.tera.register_function("sample", crate::sampling::Sample(seed));
+        self.tera.register_function("shuffle", crate::sampling::Shuffle(seed));
+    }
+
+    /// Register `trans()`, which looks up the theme's text in the page's
+    /// language, `language` when templates don't pass one
+    pub fn register_translations(&mut self, catalogs: crate::i18n::Catalogs, language: &str) {
+        self.tera.register_function(
+            "trans",
+            crate::i18n::Trans {
+                catalogs,
+                language: language.to_string(),
+            },
+        );
+    }
+
     /// Add a value to the template context
     pub fn add_to_context<T: Serialize>(&mut self, key: &str, value: &T) {
         self.context.insert(key, value);
@@ -56,7 +210,15 @@ impl TemplateRenderer {
     
     /// Render a template with the current context
     pub fn render(&self, template: &str) -> Result<String, TemplateError> {
-        Ok(self.tera.render(template, &self.context)?)
+        self.tera.render(template, &self.context).map_err(|e| self.render_error(e))
+    }
+
+    // Pointed at the theme's file for the template that failed
+    fn render_error(&self, error: tera::Error) -> TemplateError {
+        TemplateError::Template(tera_diagnostics(&error, |name| {
+            let template = self.tera.get_template(name).ok()?;
+            template.path.as_ref().map(PathBuf::from)
+        }))
     }
     
     /// Render a template and write it directly to a file
@@ -74,12 +236,12 @@ impl TemplateRenderer {
     
     /// Render a template with an external context (for new renderer)
     pub fn render_with_context(&self, template: &str, context: &Context) -> Result<String, TemplateError> {
-        Ok(self.tera.render(template, context)?)
+        self.tera.render(template, context).map_err(|e| self.render_error(e))
     }
     
     /// Render a template with an external context and write to file
     pub fn render_to_file_with_context(&self, template: &str, context: &Context, output_path: &Path) -> Result<(), TemplateError> {
-        let rendered = self.tera.render(template, context)?;
+        let rendered = self.render_with_context(template, context)?;
         
         // Ensure parent directory exists
         if let Some(parent) = output_path.parent() {
@@ -89,4 +251,34 @@ impl TemplateRenderer {
         std::fs::write(output_path, rendered)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_diagnostics() {
+        let dir = std::env::temp_dir().join(format!("zap-templates-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let glob = format!("{}/**/*.html", dir.display());
+
+        std::fs::write(dir.join("page.html"), "<main>\n{{ page.title }}\n</main>\n").unwrap();
+        let renderer = TemplateRenderer::new(&glob).unwrap();
+        let Err(TemplateError::Template(diagnostics)) = renderer.render("page.html") else {
+            panic!("rendered without a page");
+        };
+        assert_eq!(diagnostics[0].file, Some(dir.join("page.html")));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(2), Some(4)));
+        assert!(diagnostics[0].message.starts_with("Variable `page.title` not found in context"));
+
+        std::fs::write(dir.join("list.html"), "<ul>\n{% for page in pages %}\n{{ page.title | }}\n").unwrap();
+        let Err(TemplateError::Template(diagnostics)) = TemplateRenderer::new(&glob) else {
+            panic!("parsed a broken template");
+        };
+        assert_eq!(diagnostics[0].file, Some(dir.join("list.html")));
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].source_line.as_deref(), Some("{{ page.title | }}"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
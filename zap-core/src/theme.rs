@@ -0,0 +1,433 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use walkdir::WalkDir;
+
+use crate::config::ConfigError;
+
+/// Optional theme metadata, read from `theme.toml` in the theme directory
+pub const MANIFEST_FILE: &str = "theme.toml";
+
+/// Version of the template context this build of zap provides. Bump it, and
+/// add an entry to `CONTEXT_CHANGES`, whenever a context variable is renamed
+/// or removed.
+pub const CONTEXT_VERSION: u32 = 2;
+
+// What changed in each context version, shown to themes targeting an older one
+const CONTEXT_CHANGES: [(u32, &str); 1] = [(
+    2,
+    "`secondary_nav` was renamed to `navigation`. The old name still works but will be removed.",
+)];
+
+// Everything in here is copied to the output root
+const STATIC_DIR: &str = "static";
+
+// Copied to `/assets/` without being declared in the manifest
+const ASSETS_DIR: &str = "assets";
+
+// Only these are fingerprinted. Other assets (fonts, images) are often
+// referenced relatively from stylesheets, so their names must not change.
+const FINGERPRINTED: [&str; 2] = ["css", "js"];
+
+// Compiled to CSS. Partials, named `_*.scss`, are only imported.
+const SCSS: &str = "scss";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThemeManifest {
+    pub name: Option<String>,
+    /// Context version the theme was written for. Themes without one are
+    /// assumed to be up to date.
+    pub context_version: Option<u32>,
+    /// Files or directories, relative to the theme, copied to the output
+    /// alongside `static/`
+    pub assets: Vec<String>,
+    /// Add a content hash to stylesheet and script names
+    pub fingerprint: bool,
+    /// Options sites can set under `[theme]`, by name
+    pub options: BTreeMap<String, ThemeOption>,
+}
+
+/// An option a theme declares in `[options.<name>]`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThemeOption {
+    /// Used when the site doesn't set it
+    pub default: Option<toml::Value>,
+    /// Fail the build when the site doesn't set it
+    pub required: bool,
+    pub description: Option<String>,
+}
+
+impl Default for ThemeManifest {
+    fn default() -> Self {
+        Self {
+            name: None,
+            context_version: None,
+            assets: Vec::new(),
+            fingerprint: true,
+            options: BTreeMap::new(),
+        }
+    }
+}
+
+impl ThemeManifest {
+    /// Read the theme's manifest, defaulting when it has none
+    pub fn read(theme_dir: &Path) -> Result<Self, ConfigError> {
+        let path = theme_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&data)?)
+    }
+
+    /// The site's `[theme]` options over the theme's defaults, given to
+    /// templates as `theme_config`. Fails when required options are missing.
+    pub fn options(
+        &self,
+        site: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, ConfigError> {
+        let missing: Vec<String> = self
+            .options
+            .iter()
+            .filter(|(name, option)| option.required && !site.contains_key(*name))
+            .map(|(name, option)| match &option.description {
+                Some(description) => format!("`{}` ({})", name, description),
+                None => format!("`{}`", name),
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(ConfigError::Invalid(format!(
+                "{} requires these options under [theme] in zap.toml: {}",
+                self.name.as_deref().unwrap_or("The theme"),
+                missing.join(", ")
+            )));
+        }
+
+        let mut merged: serde_json::Map<String, serde_json::Value> = self
+            .options
+            .iter()
+            .filter_map(|(name, option)| {
+                let default = option.default.clone()?;
+                Some((name.clone(), crate::front_matter::toml_to_json(default)))
+            })
+            .collect();
+        merged.extend(site.clone());
+        Ok(merged)
+    }
+
+    /// Warnings for options set under `[theme]` that a theme declaring its
+    /// options doesn't have, usually a typo
+    pub fn option_warnings(&self, site: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+        if self.options.is_empty() {
+            return Vec::new();
+        }
+        site.keys()
+            .filter(|name| !self.options.contains_key(*name))
+            .map(|name| {
+                format!(
+                    "{} has no option `{}`, set under [theme]. Its options are: {}",
+                    self.name.as_deref().unwrap_or("The theme"),
+                    name,
+                    self.options.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            })
+            .collect()
+    }
+
+    /// Warnings for a theme written against a different context version
+    pub fn compatibility_warnings(&self) -> Vec<String> {
+        let Some(version) = self.context_version else {
+            return Vec::new();
+        };
+        let theme = self.name.as_deref().unwrap_or("The theme");
+
+        if version > CONTEXT_VERSION {
+            return vec![format!(
+                "{} targets context version {}, but this version of zap provides {}. Upgrade zap or expect missing template variables.",
+                theme, version, CONTEXT_VERSION
+            )];
+        }
+
+        CONTEXT_CHANGES
+            .iter()
+            .filter(|(changed_in, _)| *changed_in > version)
+            .map(|(changed_in, change)| {
+                format!(
+                    "{} targets context version {}. Since version {}: {} Set `context_version = {}` in {} once updated.",
+                    theme, version, changed_in, change, CONTEXT_VERSION, MANIFEST_FILE
+                )
+            })
+            .collect()
+    }
+}
+
+/// A theme file copied into the output
+#[derive(Debug, Clone)]
+pub struct ThemeAsset {
+    pub source: PathBuf,
+    /// Name templates refer to it by, e.g. `js/app.js`
+    pub name: String,
+    /// Path in the output directory, e.g. `js/app.1a2b3c4d.js`
+    pub output: String,
+    /// Output contents, for assets compiled rather than copied
+    pub compiled: Option<String>,
+}
+
+/// Find the theme's static files and declared assets, compiling SCSS.
+/// `minify_css` compresses the compiled CSS, for production builds.
+pub fn collect_assets(
+    theme_dir: &Path,
+    manifest: &ThemeManifest,
+    minify_css: bool,
+) -> std::io::Result<Vec<ThemeAsset>> {
+    let mut assets = Vec::new();
+    let asset = |source: PathBuf, name: String| asset(source, name, manifest.fingerprint, minify_css);
+
+    let static_dir = theme_dir.join(STATIC_DIR);
+    for source in files_in(&static_dir) {
+        let name = relative_name(&source, &static_dir);
+        assets.extend(asset(source, name)?);
+    }
+
+    let mut declared_assets = manifest.assets.clone();
+    let assets_dir = theme_dir.join(ASSETS_DIR);
+    if assets_dir.is_dir()
+        && !declared_assets
+            .iter()
+            .any(|declared| theme_dir.join(declared).starts_with(&assets_dir))
+    {
+        declared_assets.push(ASSETS_DIR.to_string());
+    }
+
+    for declared in &declared_assets {
+        let path = theme_dir.join(declared);
+        if !path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("theme asset {} does not exist", path.display()),
+            ));
+        }
+        for source in files_in(&path) {
+            let name = relative_name(&source, theme_dir);
+            assets.extend(asset(source, name)?);
+        }
+    }
+
+    Ok(assets)
+}
+
+fn files_in(path: &Path) -> Vec<PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn relative_name(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn asset(source: PathBuf, name: String, fingerprint: bool, minify_css: bool) -> std::io::Result<Option<ThemeAsset>> {
+    let mut extension = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let stem = name.strip_suffix(&format!(".{}", extension)).unwrap_or(&name).to_string();
+
+    let compiled = if extension == SCSS {
+        let is_partial = source
+            .file_name()
+            .is_some_and(|file| file.to_string_lossy().starts_with('_'));
+        if is_partial {
+            return Ok(None);
+        }
+        extension = "css".to_string();
+        Some(compile_scss(&source, minify_css)?)
+    } else {
+        None
+    };
+
+    let output = if fingerprint && FINGERPRINTED.contains(&extension.as_str()) {
+        let hash = match &compiled {
+            Some(css) => content_hash(css.as_bytes()),
+            None => content_hash(&std::fs::read(&source)?),
+        };
+        format!("{}.{:08x}.{}", stem, hash, extension)
+    } else if compiled.is_some() {
+        format!("{}.{}", stem, extension)
+    } else {
+        name.clone()
+    };
+
+    Ok(Some(ThemeAsset {
+        source,
+        name,
+        output,
+        compiled,
+    }))
+}
+
+fn compile_scss(source: &Path, minify: bool) -> std::io::Result<String> {
+    let style = if minify {
+        grass::OutputStyle::Compressed
+    } else {
+        grass::OutputStyle::Expanded
+    };
+    let options = grass::Options::default().style(style);
+
+    grass::from_path(source, &options).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to compile {}: {}", source.display(), e),
+        )
+    })
+}
+
+// FNV-1a, folded to 32 bits
+fn content_hash(bytes: &[u8]) -> u32 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash >> 32) as u32 ^ hash as u32
+}
+
+/// Write assets to the output, returning the total bytes written
+pub fn copy_assets(assets: &[ThemeAsset], output_dir: &Path) -> std::io::Result<u64> {
+    let mut bytes = 0;
+    for asset in assets {
+        let target = output_dir.join(&asset.output);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        bytes += match &asset.compiled {
+            Some(contents) => {
+                std::fs::write(target, contents)?;
+                contents.len() as u64
+            }
+            None => std::fs::copy(&asset.source, target)?,
+        };
+    }
+
+    Ok(bytes)
+}
+
+/// Copy everything in `dir` into the output, keeping its structure.
+/// Returns the number of files and bytes copied.
+pub fn copy_dir(dir: &Path, output_dir: &Path) -> std::io::Result<(usize, u64)> {
+    let mut copied = (0, 0);
+    for source in files_in(dir) {
+        let target = output_dir.join(source.strip_prefix(dir).unwrap_or(&source));
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        copied.1 += std::fs::copy(&source, target)?;
+        copied.0 += 1;
+    }
+
+    Ok(copied)
+}
+
+/// Output URL of each asset by name, for the `asset()` template function
+pub fn asset_urls(assets: &[ThemeAsset]) -> HashMap<String, String> {
+    assets
+        .iter()
+        .map(|asset| (asset.name.clone(), format!("/{}", asset.output)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(context_version: Option<u32>) -> ThemeManifest {
+        ThemeManifest {
+            context_version,
+            ..ThemeManifest::default()
+        }
+    }
+
+    #[test]
+    fn test_compatibility_warnings() {
+        assert!(manifest(None).compatibility_warnings().is_empty());
+        assert!(manifest(Some(CONTEXT_VERSION)).compatibility_warnings().is_empty());
+
+        let warnings = manifest(Some(1)).compatibility_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("secondary_nav"));
+
+        let warnings = manifest(Some(CONTEXT_VERSION + 1)).compatibility_warnings();
+        assert!(warnings[0].contains("Upgrade zap"));
+    }
+
+    #[test]
+    fn test_options() {
+        let manifest: ThemeManifest = toml::from_str(
+            "name = \"docs\"\n[options.hero]\ndefault = \"wide\"\n[options.accent]\ndefault = \"blue\"\n[options.org]\nrequired = true\n",
+        )
+        .unwrap();
+        let site = |value: serde_json::Value| value.as_object().cloned().unwrap();
+
+        let error = manifest.options(&site(serde_json::json!({ "hero": "narrow" }))).unwrap_err();
+        assert!(error.to_string().contains("docs requires these options under [theme] in zap.toml: `org`"));
+
+        let options = manifest.options(&site(serde_json::json!({ "org": "zap", "hero": "narrow" }))).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(options),
+            serde_json::json!({ "hero": "narrow", "accent": "blue", "org": "zap" })
+        );
+
+        let warnings = manifest.option_warnings(&site(serde_json::json!({ "org": "zap", "acent": "red" })));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no option `acent`"));
+    }
+
+    #[test]
+    fn test_collect_assets() {
+        let theme_dir = std::env::temp_dir().join("zap-theme-assets-test");
+        let _ = std::fs::remove_dir_all(&theme_dir);
+        std::fs::create_dir_all(theme_dir.join("assets/fonts")).unwrap();
+        std::fs::write(theme_dir.join("assets/app.css"), "body {}").unwrap();
+        std::fs::write(theme_dir.join("assets/fonts/inter.woff2"), "font").unwrap();
+
+        let assets = collect_assets(&theme_dir, &ThemeManifest::default(), false).unwrap();
+        let urls = asset_urls(&assets);
+
+        assert_eq!(urls.len(), 2);
+        assert!(urls["assets/app.css"].starts_with("/assets/app."));
+        assert!(urls["assets/app.css"].ends_with(".css"));
+        assert_ne!(urls["assets/app.css"], "/assets/app.css");
+        assert_eq!(urls["assets/fonts/inter.woff2"], "/assets/fonts/inter.woff2");
+    }
+
+    #[test]
+    fn test_compile_scss() {
+        let theme_dir = std::env::temp_dir().join("zap-theme-scss-test");
+        let _ = std::fs::remove_dir_all(&theme_dir);
+        std::fs::create_dir_all(theme_dir.join("assets")).unwrap();
+        std::fs::write(theme_dir.join("assets/_colors.scss"), "$primary: #f00;").unwrap();
+        std::fs::write(
+            theme_dir.join("assets/app.scss"),
+            "@use 'colors';\na { color: colors.$primary; }",
+        )
+        .unwrap();
+
+        let assets = collect_assets(&theme_dir, &ThemeManifest::default(), true).unwrap();
+
+        assert_eq!(assets.len(), 1);
+        assert!(assets[0].output.starts_with("assets/app.") && assets[0].output.ends_with(".css"));
+        assert_eq!(assets[0].compiled.as_deref(), Some("a{color:red}"));
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::markdown::{PageElement, render_inline_elements_text, slugify};
+
+/// A heading in a page's table of contents, with its sub-headings nested
+#[derive(Debug, Clone, Serialize)]
+pub struct TocEntry {
+    pub level: u32,
+    pub text: String,
+    pub slug: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Build a nested heading tree for a page. The H1 is treated as the page
+/// title and left out, same as the `on_this_page` list.
+pub fn build_toc(elements: &[PageElement]) -> Vec<TocEntry> {
+    let mut roots: Vec<TocEntry> = Vec::new();
+
+    for element in elements {
+        let PageElement::Heading { level, content } = element else {
+            continue;
+        };
+        if *level == 1 {
+            continue;
+        }
+
+        let text = render_inline_elements_text(content);
+        let entry = TocEntry {
+            level: *level,
+            slug: slugify(&text),
+            text,
+            children: Vec::new(),
+        };
+        insert_entry(&mut roots, entry);
+    }
+
+    roots
+}
+
+// Descend along the last entry of each level until we find a parent with a
+// lower level than the new entry
+fn insert_entry(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+    match entries.last_mut() {
+        Some(last) if last.level < entry.level => insert_entry(&mut last.children, entry),
+        _ => entries.push(entry),
+    }
+}
+
+/// Render a table of contents as nested lists
+pub fn render_toc_html(entries: &[TocEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from("<ul>\n");
+    for entry in entries {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            entry.slug,
+            html_escape::encode_text(&entry.text)
+        ));
+        if !entry.children.is_empty() {
+            html.push('\n');
+            html.push_str(&render_toc_html(&entry.children));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parse_structured;
+
+    fn outline(entries: &[TocEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .flat_map(|entry| {
+                let mut lines = vec![format!("{}{}", "  ".repeat(entry.level as usize - 2), entry.text)];
+                lines.extend(outline(&entry.children));
+                lines
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_toc() {
+        let elements = parse_structured("# Title\n\n### Before\n\n## Install\n\n#### From source\n\n### Requirements\n\n## Usage\n\n##### Flags\n");
+        let toc = build_toc(&elements);
+        // Skipped levels nest under the nearest heading above them
+        assert_eq!(
+            outline(&toc),
+            ["  Before", "Install", "    From source", "  Requirements", "Usage", "      Flags"]
+        );
+        assert_eq!(toc.len(), 3);
+        assert_eq!(toc[1].children.len(), 2);
+        assert_eq!(toc[1].children[0].slug, "from-source");
+
+        let html = render_toc_html(&toc[1..2]);
+        assert_eq!(
+            html,
+            "<ul>\n<li><a href=\"#install\">Install</a>\n<ul>\n<li><a href=\"#from-source\">From source</a></li>\n<li><a href=\"#requirements\">Requirements</a></li>\n</ul>\n</li>\n</ul>\n"
+        );
+        assert!(render_toc_html(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_toc_marker() {
+        let elements = parse_structured("# Title\n\n[TOC]\n\n## Install\n");
+        assert!(matches!(elements[1], PageElement::TableOfContents));
+
+        // Only a paragraph of its own, not code showing the marker
+        let elements = parse_structured("Add `[TOC]` to a page.\n\n`[TOC]`\n\n```markdown\n[TOC]\n```\n\n    [TOC]\n");
+        assert!(!elements.iter().any(|element| matches!(element, PageElement::TableOfContents)));
+        assert_eq!(elements.len(), 4);
+    }
+}
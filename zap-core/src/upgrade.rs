@@ -0,0 +1,318 @@
+// Migrations for projects written against older zap releases. Renamed config
+// keys, front matter fields and template variables are rewritten in place,
+// line by line, so formatting and comments survive.
+
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::front_matter::split_front_matter;
+use crate::theme::{CONTEXT_VERSION, MANIFEST_FILE};
+
+/// A key or variable renamed in a zap release
+#[derive(Debug, Clone, Copy)]
+pub struct Rename {
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+// Dotted `zap.toml` keys. A key can only be renamed within its own table.
+const CONFIG_RENAMES: [Rename; 0] = [];
+
+const FRONT_MATTER_RENAMES: [Rename; 0] = [];
+
+// Template context variables, see `theme::CONTEXT_CHANGES`
+const THEME_RENAMES: [Rename; 1] = [Rename {
+    old: "secondary_nav",
+    new: "navigation",
+}];
+
+/// A file that needs changes to work with this version of zap
+#[derive(Debug, Clone)]
+pub struct FileUpgrade {
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+    /// What changed, one entry per rename
+    pub changes: Vec<String>,
+}
+
+impl FileUpgrade {
+    /// Changed lines, prefixed with `-` and `+` like a unified diff.
+    /// Renames never add or remove lines, so lines are compared pairwise.
+    pub fn diff(&self) -> String {
+        let mut diff = format!("--- {0}\n+++ {0}\n", self.path.display());
+        for (number, (old, new)) in self.original.lines().zip(self.updated.lines()).enumerate() {
+            if old != new {
+                diff.push_str(&format!("@@ line {} @@\n-{}\n+{}\n", number + 1, old, new));
+            }
+        }
+        diff
+    }
+
+    pub fn apply(&self) -> std::io::Result<()> {
+        std::fs::write(&self.path, &self.updated)
+    }
+}
+
+/// Find everything in a project that needs upgrading
+pub fn plan_upgrade(
+    config_path: &Path,
+    source_dir: &Path,
+    theme_dir: &Path,
+) -> std::io::Result<Vec<FileUpgrade>> {
+    let mut upgrades = Vec::new();
+
+    if config_path.is_file() {
+        let original = std::fs::read_to_string(config_path)?;
+        let (updated, changes) = rename_toml_keys(&original, &CONFIG_RENAMES);
+        upgrades.push(file_upgrade(config_path, original, updated, changes));
+    }
+
+    for path in files_with_extension(source_dir, "md") {
+        let original = std::fs::read_to_string(&path)?;
+        let (updated, changes) = rename_front_matter(&original, &FRONT_MATTER_RENAMES);
+        upgrades.push(file_upgrade(&path, original, updated, changes));
+    }
+
+    for path in files_with_extension(theme_dir, "html") {
+        let original = std::fs::read_to_string(&path)?;
+        let (updated, changes) = rename_variables(&original, &THEME_RENAMES);
+        upgrades.push(file_upgrade(&path, original, updated, changes));
+    }
+
+    let manifest = theme_dir.join(MANIFEST_FILE);
+    if manifest.is_file() {
+        let original = std::fs::read_to_string(&manifest)?;
+        let (updated, changes) = bump_context_version(&original);
+        upgrades.push(file_upgrade(&manifest, original, updated, changes));
+    }
+
+    upgrades.retain(|upgrade| !upgrade.changes.is_empty());
+    Ok(upgrades)
+}
+
+fn file_upgrade(path: &Path, original: String, updated: String, changes: Vec<String>) -> FileUpgrade {
+    FileUpgrade {
+        path: path.to_path_buf(),
+        original,
+        updated,
+        changes,
+    }
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Rename keys and table headers in TOML source, given dotted key paths
+pub fn rename_toml_keys(source: &str, renames: &[Rename]) -> (String, Vec<String>) {
+    let mut table = String::new();
+    let mut changes = Vec::new();
+
+    let lines = source.split_inclusive('\n').map(|line| {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        // `[table]` and `[[array]]` headers
+        if trimmed.starts_with('[') {
+            let brackets = if trimmed.starts_with("[[") { 2 } else { 1 };
+            let Some(end) = trimmed.find(&"]".repeat(brackets)) else {
+                return line.to_string();
+            };
+            let name = trimmed[brackets..end].trim();
+            table = name.to_string();
+
+            for rename in renames {
+                let renamed = if name == rename.old {
+                    Some(rename.new.to_string())
+                } else {
+                    name.strip_prefix(&format!("{}.", rename.old))
+                        .map(|rest| format!("{}.{}", rename.new, rest))
+                };
+                if let Some(renamed) = renamed {
+                    changes.push(format!("[{}] is now [{}]", name, renamed));
+                    table = renamed.clone();
+                    return format!(
+                        "{}{}{}{}",
+                        indent,
+                        &trimmed[..brackets],
+                        renamed,
+                        &trimmed[end..]
+                    );
+                }
+            }
+            return line.to_string();
+        }
+
+        // `key = value`
+        let Some(equals) = trimmed.find('=') else {
+            return line.to_string();
+        };
+        let key = trimmed[..equals].trim();
+        if key.is_empty() || key.starts_with('#') {
+            return line.to_string();
+        }
+        let full_key = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", table, key)
+        };
+
+        for rename in renames {
+            if full_key != rename.old {
+                continue;
+            }
+            let new_key = rename.new.rsplit('.').next().unwrap_or(rename.new);
+            changes.push(format!("{} is now {}", rename.old, rename.new));
+            return format!("{}{}{}", indent, new_key, &trimmed[key.len()..]);
+        }
+
+        line.to_string()
+    });
+
+    (lines.collect(), changes)
+}
+
+/// Rename fields in a page's `+++` front matter, leaving the body alone
+pub fn rename_front_matter(content: &str, renames: &[Rename]) -> (String, Vec<String>) {
+    let (Some(front_matter), body) = split_front_matter(content) else {
+        return (content.to_string(), Vec::new());
+    };
+
+    let (updated, changes) = rename_toml_keys(front_matter, renames);
+    if changes.is_empty() {
+        return (content.to_string(), changes);
+    }
+
+    // `front_matter` borrows from `content`, after the opening delimiter
+    let start = front_matter.as_ptr() as usize - content.as_ptr() as usize;
+    let end = content.len() - body.len();
+    let closing = &content[start + front_matter.len()..end];
+    (
+        format!("{}{}{}{}", &content[..start], updated, closing, body),
+        changes,
+    )
+}
+
+/// Rename identifiers in template source. Only whole identifiers match, so
+/// renaming `nav` leaves `main_nav` alone.
+pub fn rename_variables(source: &str, renames: &[Rename]) -> (String, Vec<String>) {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut updated = source.to_string();
+    let mut changes = Vec::new();
+
+    for rename in renames {
+        let mut out = String::with_capacity(updated.len());
+        let mut rest = updated.as_str();
+        let mut count = 0;
+
+        while let Some(found) = rest.find(rename.old) {
+            let before = rest[..found].chars().next_back();
+            let after = rest[found + rename.old.len()..].chars().next();
+            out.push_str(&rest[..found]);
+            if before.is_some_and(is_identifier) || after.is_some_and(is_identifier) {
+                out.push_str(rename.old);
+            } else {
+                out.push_str(rename.new);
+                count += 1;
+            }
+            rest = &rest[found + rename.old.len()..];
+        }
+        out.push_str(rest);
+
+        if count > 0 {
+            changes.push(format!("`{}` is now `{}`", rename.old, rename.new));
+        }
+        updated = out;
+    }
+
+    (updated, changes)
+}
+
+// Template renames are applied alongside, so the theme can claim the
+// current context version
+fn bump_context_version(source: &str) -> (String, Vec<String>) {
+    let mut changes = Vec::new();
+    let lines = source.split_inclusive('\n').map(|line| {
+        let Some(value) = line
+            .trim_start()
+            .strip_prefix("context_version")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+        else {
+            return line.to_string();
+        };
+
+        match value.trim().parse::<u32>() {
+            Ok(version) if version < CONTEXT_VERSION => {
+                changes.push(format!(
+                    "context_version {} is now {}",
+                    version, CONTEXT_VERSION
+                ));
+                let ending = if line.ends_with('\n') { "\n" } else { "" };
+                format!("context_version = {}{}", CONTEXT_VERSION, ending)
+            }
+            _ => line.to_string(),
+        }
+    });
+
+    (lines.collect(), changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RENAMES: [Rename; 2] = [
+        Rename {
+            old: "site.subtitle",
+            new: "site.tagline",
+        },
+        Rename {
+            old: "perf",
+            new: "performance",
+        },
+    ];
+
+    #[test]
+    fn test_rename_toml_keys() {
+        let source = "[site]\ntitle = \"Zap\"\n  subtitle = \"Fast\" # shown on the home page\n\n[perf]\nprefetch = true\n";
+        let (updated, changes) = rename_toml_keys(source, &RENAMES);
+
+        assert_eq!(
+            updated,
+            "[site]\ntitle = \"Zap\"\n  tagline = \"Fast\" # shown on the home page\n\n[performance]\nprefetch = true\n"
+        );
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn test_rename_front_matter() {
+        let renames = [Rename {
+            old: "category",
+            new: "tags",
+        }];
+        let content = "+++\ncategory = [\"cli\"]\n+++\n\ncategory = body text\n";
+        let (updated, changes) = rename_front_matter(content, &renames);
+
+        assert_eq!(updated, "+++\ntags = [\"cli\"]\n+++\n\ncategory = body text\n");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn test_rename_variables() {
+        let source = "{% for item in secondary_nav %}{{ my_secondary_nav }}";
+        let (updated, changes) = rename_variables(source, &THEME_RENAMES);
+
+        assert_eq!(updated, "{% for item in navigation %}{{ my_secondary_nav }}");
+        assert_eq!(changes.len(), 1);
+    }
+}
@@ -0,0 +1,247 @@
+// Checks for config files that parse but don't do what was meant: keys
+// serde silently ignores, usually typos, and values that are the right type
+// but can't work.
+
+use toml::de::{DeTable, DeValue};
+
+use crate::config::Config;
+
+/// A problem in a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// 1-based
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Keys in the TOML `source` that the config loaded from it doesn't have.
+/// `known` is that config serialized, so every key it read is in it.
+/// Top-level tables in `skip` aren't checked, and tables under `profiles`
+/// are checked like the top level.
+pub fn unknown_keys(source: &str, known: &serde_json::Value, skip: &[&str]) -> Vec<ConfigIssue> {
+    let Ok(table) = DeTable::parse(source) else {
+        // Parse errors are reported by whoever loads the file
+        return Vec::new();
+    };
+
+    let mut issues = Vec::new();
+    for (key, value) in table.get_ref() {
+        let name = key.get_ref().as_ref();
+        if skip.contains(&name) {
+            continue;
+        }
+        if name == "profiles" {
+            if let DeValue::Table(profiles) = value.get_ref() {
+                for (profile, value) in profiles {
+                    if let DeValue::Table(profile_table) = value.get_ref() {
+                        let path = format!("profiles.{}", profile.get_ref());
+                        check_table(source, profile_table, known, &path, &mut issues);
+                    }
+                }
+            }
+            continue;
+        }
+        check_key(source, key.span().start, name, value.get_ref(), known, "", &mut issues);
+    }
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+fn check_table(source: &str, table: &DeTable, known: &serde_json::Value, path: &str, issues: &mut Vec<ConfigIssue>) {
+    for (key, value) in table {
+        check_key(source, key.span().start, key.get_ref(), value.get_ref(), known, path, issues);
+    }
+}
+
+fn check_key(
+    source: &str,
+    offset: usize,
+    name: &str,
+    value: &DeValue,
+    known: &serde_json::Value,
+    path: &str,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    // Sections that weren't set, and free-form tables, can't be checked
+    let Some(fields) = known.as_object() else {
+        return;
+    };
+    let full_name = match path {
+        "" => name.to_string(),
+        path => format!("{}.{}", path, name),
+    };
+
+    let Some(known_value) = fields.get(name) else {
+        let mut message = format!("unknown key `{}`, it is ignored", full_name);
+        if let Some(suggestion) = closest(name, fields.keys()) {
+            message.push_str(&format!(". Did you mean `{}`?", suggestion));
+        }
+        issues.push(ConfigIssue {
+            line: line_of(source, offset),
+            message,
+        });
+        return;
+    };
+
+    match value {
+        DeValue::Table(table) => check_table(source, table, known_value, &full_name, issues),
+        DeValue::Array(items) => {
+            let Some(known_items) = known_value.as_array() else {
+                return;
+            };
+            for (i, (item, known_item)) in items.iter().zip(known_items).enumerate() {
+                if let DeValue::Table(table) = item.get_ref() {
+                    check_table(source, table, known_item, &format!("{}[{}]", full_name, i), issues);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// A known key one or two typos away
+fn closest<'a>(name: &str, keys: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    keys.map(|key| (crate::externals::typo_distance(name, key), key))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key.as_str())
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// Values that parse but can't work, like a home page action without a link
+pub fn check_values(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let empty = |value: &str| value.trim().is_empty();
+
+    if let Some(url) = config.site.as_ref().and_then(|site| site.url.as_deref())
+        && !(url.starts_with("https://") || url.starts_with("http://"))
+    {
+        problems.push(format!("site.url \"{}\" should start with https:// or http://", url));
+    }
+    if let Err(e) = config.timezone() {
+        problems.push(format!("site.timezone: {}", e));
+    }
+
+    if let Some(home) = &config.home {
+        let actions = [("primary_action", &home.primary_action), ("secondary_action", &home.secondary_action)];
+        for (name, action) in actions {
+            if let Some(action) = action
+                && (empty(&action.text) || empty(&action.link))
+            {
+                problems.push(format!("home.{} needs both `text` and `link`", name));
+            }
+        }
+    }
+
+    // A HashMap, sorted so the messages come out in the same order each time
+    let mut collections: Vec<_> = config.collections.iter().collect();
+    collections.sort_by_key(|(name, _)| name.as_str());
+    for (name, collection) in collections {
+        if collection.paginate == 0 {
+            problems.push(format!("collections.{}.paginate should be at least 1", name));
+        }
+    }
+    for (name, collection) in &config.virtual_collections {
+        if collection.paginate == 0 {
+            problems.push(format!("virtual_collections.{}.paginate should be at least 1", name));
+        }
+    }
+
+    if let Some(counter) = &config.counter
+        && !(counter.endpoint.starts_with("https://") || counter.endpoint.starts_with("http://"))
+    {
+        problems.push(format!("counter.endpoint \"{}\" should be an http(s) URL", counter.endpoint));
+    }
+
+    for source in &config.sources {
+        if source.dir.trim().is_empty() {
+            problems.push("a [[sources]] entry has no `dir` to write to".to_string());
+        }
+        if source.git.is_some() == source.url.is_some() {
+            problems.push(format!("sources \"{}\" needs either `git` or `url`", source.dir));
+        }
+    }
+
+    for (name, form) in &config.forms {
+        if form.backend != crate::config::FormBackend::Netlify && form.action.as_deref().is_none_or(empty) {
+            problems.push(format!("forms.{} needs an `action`, the Formspree form ID or the endpoint's URL", name));
+        }
+        if form.fields.is_empty() {
+            problems.push(format!("forms.{} has no `fields`", name));
+        }
+        for (i, field) in form.fields.iter().enumerate() {
+            let kind = field.kind.as_deref().unwrap_or("text");
+            if empty(&field.name) {
+                problems.push(format!("forms.{}.fields[{}] has no `name`", name, i));
+            } else if form.fields[..i].iter().any(|other| other.name == field.name) {
+                problems.push(format!("forms.{} has two fields named \"{}\"", name, field.name));
+            }
+            if !crate::forms::FIELD_TYPES.contains(&kind) {
+                problems.push(format!(
+                    "forms.{} field \"{}\" has type \"{}\", it should be one of {}",
+                    name,
+                    field.name,
+                    kind,
+                    crate::forms::FIELD_TYPES.join(", ")
+                ));
+            } else if kind == "select" && field.options.is_empty() {
+                problems.push(format!("forms.{} field \"{}\" is a select without `options`", name, field.name));
+            }
+        }
+    }
+
+    if let Some(consent) = &config.consent {
+        for (i, category) in consent.categories.iter().enumerate() {
+            if empty(&category.name) {
+                problems.push(format!("consent.categories[{}] has no `name`", i));
+            } else if consent.categories[..i].iter().any(|other| other.name == category.name) {
+                problems.push(format!("consent has two categories named \"{}\"", category.name));
+            }
+        }
+        if consent.categories.is_empty() {
+            problems.push("consent has no `categories` to ask about".to_string());
+        }
+    }
+
+    if let Some(footer) = &config.footer {
+        for column in &footer.columns {
+            if column.links.iter().any(|link| empty(&link.text) || empty(&link.link)) {
+                problems.push(format!("footer column \"{}\" has a link without `text` or `link`", column.title));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = "static_dir = \"public\"\n\n[site]\ntitel = \"Zap\"\nurl = \"zap.dev\"\n\n[build]\nsource = \"docs\"\n\n[[home.features]]\ntitle = \"Fast\"\ndescription = \"Very\"\nicon = \"bolt\"\n\n[profiles.production.site]\ntitle = \"Zap\"\ncolour_scheme = \"rose\"\n";
+        let config: Config = toml::from_str(source).unwrap();
+        let known = serde_json::to_value(&config).unwrap();
+
+        let issues: Vec<String> = unknown_keys(source, &known, &["build"]).iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 4: unknown key `site.titel`, it is ignored. Did you mean `title`?",
+                "line 13: unknown key `home.features[0].icon`, it is ignored",
+                "line 17: unknown key `profiles.production.site.colour_scheme`, it is ignored. Did you mean `color_scheme`?",
+            ]
+        );
+        assert_eq!(check_values(&config), vec!["site.url \"zap.dev\" should start with https:// or http://"]);
+    }
+}
@@ -0,0 +1,102 @@
+// Content variants, for trying out different copy on a page before picking
+// one. A page lists its variants in front matter and marks the parts that
+// differ:
+//
+//     +++
+//     variants = ["short", "long"]
+//     +++
+//     <!-- variant: short -->
+//     # Docs in seconds
+//     <!-- /variant -->
+//     <!-- variant: long -->
+//     # Turn a folder of markdown into a documentation site
+//     <!-- /variant -->
+//
+// Everything outside the markers is shared. Builds publish one variant, the
+// first unless front matter names another with `variant`, and `zap serve`
+// also renders the others as previews next to the page.
+
+use std::path::{Path, PathBuf};
+
+use crate::site::Page;
+
+/// The page's markdown with only `variant`'s blocks kept
+pub fn select(markdown: &str, variant: &str) -> String {
+    let mut selected = String::with_capacity(markdown.len());
+    let mut keep = true;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(names) = opening_marker(trimmed) {
+            keep = names.split(',').any(|name| name.trim() == variant);
+            continue;
+        }
+        if is_closing_marker(trimmed) {
+            keep = true;
+            continue;
+        }
+        if keep {
+            selected.push_str(line);
+        }
+    }
+
+    selected
+}
+
+/// Names usable in a preview's file name and `?variant=`
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Where a preview of `variant` is written, next to the page's output:
+/// `index.long.html` for `index.html`
+pub fn preview_path(out_path: &Path, variant: &str) -> PathBuf {
+    let stem = out_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "index".to_string());
+    out_path.with_file_name(format!("{}.{}.html", stem, variant))
+}
+
+/// A copy of `page` for each variant besides the published one
+pub fn previews(page: &Page) -> Vec<Page> {
+    page.variants
+        .iter()
+        .skip(1)
+        .map(|variant| Page {
+            variant: Some(variant.clone()),
+            elements: Default::default(),
+            ..page.clone()
+        })
+        .collect()
+}
+
+fn opening_marker(line: &str) -> Option<&str> {
+    let comment = line.strip_prefix("<!--")?.strip_suffix("-->")?.trim();
+    comment.strip_prefix("variant:")
+}
+
+fn is_closing_marker(line: &str) -> bool {
+    line.strip_prefix("<!--")
+        .and_then(|line| line.strip_suffix("-->"))
+        .is_some_and(|comment| comment.trim() == "/variant")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let markdown = "# Zap\n<!-- variant: short -->\nFast.\n<!-- /variant -->\n<!-- variant: long, longer -->\nFast, and small.\n<!-- /variant -->\nShared.\n";
+        assert_eq!(select(markdown, "short"), "# Zap\nFast.\nShared.\n");
+        assert_eq!(select(markdown, "longer"), "# Zap\nFast, and small.\nShared.\n");
+        assert_eq!(
+            preview_path(Path::new("landing/index.html"), "long"),
+            PathBuf::from("landing/index.long.html")
+        );
+    }
+}
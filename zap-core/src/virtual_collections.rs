@@ -0,0 +1,173 @@
+// `[virtual_collections.<name>]` in zap.toml: a listing of pages picked by
+// tag or path from anywhere in the site, with its own index page at
+// `/<name>/`, without moving files on disk.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use globset::GlobBuilder;
+
+use crate::config::{ListingSort, VirtualCollectionConfig};
+use crate::markdown::slugify;
+use crate::site::{Collection, Page, PageType};
+
+#[derive(Debug)]
+pub struct VirtualCollectionError {
+    pub name: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for VirtualCollectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "virtual collection \"{}\": {}", self.name, self.message)
+    }
+}
+
+impl std::error::Error for VirtualCollectionError {}
+
+#[derive(Debug, Clone)]
+pub struct VirtualCollection {
+    pub name: String,
+    pub title: String,
+    /// Add a link to the index page to the site navigation
+    pub nav: bool,
+    pub paginate: usize,
+    pub pages: Vec<Page>,
+}
+
+impl VirtualCollection {
+    pub fn url(&self) -> String {
+        format!("/{}/", self.name)
+    }
+}
+
+/// Pick each virtual collection's pages from the site's pages, which must
+/// already be sorted
+pub fn build(
+    configs: &BTreeMap<String, VirtualCollectionConfig>,
+    pages: &[Page],
+    collections: &[Collection],
+    source_dir: &Path,
+) -> Result<Vec<VirtualCollection>, VirtualCollectionError> {
+    let mut virtual_collections = Vec::new();
+
+    for (name, config) in configs {
+        let error = |message: String| VirtualCollectionError {
+            name: name.clone(),
+            message,
+        };
+
+        // Served at `/<name>/`, which can't already be taken
+        let url = format!("/{}/", name);
+        if collections.iter().any(|collection| collection.url() == url) {
+            return Err(error(format!("{} is already a collection", url)));
+        }
+        let all_pages = pages.iter().chain(collections.iter().flat_map(|c| &c.pages));
+        if let Some(page) = all_pages.clone().find(|page| page.url(source_dir) == url) {
+            return Err(error(format!("{} is already used by {}", url, page.path.display())));
+        }
+
+        let matcher = match &config.pattern {
+            Some(pattern) => Some(
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| error(format!("pattern \"{}\": {}", pattern, e.kind())))?
+                    .compile_matcher(),
+            ),
+            None => None,
+        };
+        let tags: Vec<String> = config.tags.iter().map(|tag| slugify(tag)).collect();
+
+        let mut selected: Vec<Page> = all_pages
+            .filter(|page| !matches!(page.page_type, PageType::Home | PageType::Changelog | PageType::Index))
+            .filter(|page| {
+                let relative = page.path.strip_prefix(source_dir).unwrap_or(&page.path);
+                matcher.as_ref().is_none_or(|matcher| matcher.is_match(relative))
+            })
+            .filter(|page| {
+                tags.iter()
+                    .all(|tag| page.tags.iter().any(|page_tag| slugify(page_tag) == *tag))
+            })
+            .cloned()
+            .collect();
+
+        match config.sort {
+            Some(ListingSort::Date) => selected.sort_by_key(|page| std::cmp::Reverse(page.date.map(|date| date.timestamp()))),
+            Some(ListingSort::Title) => selected.sort_by_key(|page| page.title.to_lowercase()),
+            None => {}
+        }
+        if let Some(limit) = config.limit {
+            selected.truncate(limit);
+        }
+
+        virtual_collections.push(VirtualCollection {
+            name: name.clone(),
+            title: config.title.clone().unwrap_or_else(|| crate::builder::title_case(name)),
+            nav: config.nav,
+            paginate: config.paginate,
+            pages: selected,
+        });
+    }
+
+    Ok(virtual_collections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn page(path: &str, tags: &[&str], date: &str) -> Page {
+        Page {
+            title: path.to_string(),
+            path: PathBuf::from("/site").join(path),
+            page_type: PageType::Regular,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            weight: None,
+            date: crate::dates::parse_date(date, chrono_tz::Tz::UTC).ok(),
+            expires: None,
+            draft: false,
+            description: None,
+            image: None,
+            language: None,
+            dir: None,
+            extra: Default::default(),
+            encoding: crate::encoding::Encoding::Utf8,
+            template: None,
+            permalink: None,
+            variants: Vec::new(),
+            variant: None,
+            elements: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let pages = vec![page("about.md", &["Tutorial"], "2024-01-01")];
+        let collections = vec![Collection {
+            name: "guides".to_string(),
+            pages: vec![
+                page("guides/a.md", &["tutorial", "rust"], "2024-03-01"),
+                page("guides/b.md", &["reference"], "2024-04-01"),
+                page("guides/c.md", &["tutorial"], "2024-02-01"),
+            ],
+        }];
+        let config = |toml: &str| -> BTreeMap<String, VirtualCollectionConfig> { toml::from_str(toml).unwrap() };
+        let titles = |configs| {
+            let built = build(&configs, &pages, &collections, Path::new("/site")).unwrap();
+            built[0].pages.iter().map(|page| page.title.clone()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            titles(config("[tutorials]\ntags = [\"tutorial\"]\nsort = \"date\"")),
+            vec!["guides/a.md", "guides/c.md", "about.md"]
+        );
+        assert_eq!(
+            titles(config("[latest]\npattern = \"guides/*.md\"\nsort = \"date\"\nlimit = 2")),
+            vec!["guides/b.md", "guides/a.md"]
+        );
+        let error = build(&config("[guides]"), &pages, &collections, Path::new("/site")).unwrap_err();
+        assert!(error.to_string().contains("already a collection"));
+    }
+}
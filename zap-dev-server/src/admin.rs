@@ -0,0 +1,373 @@
+// Admin mode: a small editor at `/__admin` for the site's markdown, so
+// contributors who don't use a code editor can change pages locally. Pages
+// are listed from the source directory and saved straight back to it,
+// where the source watcher rebuilds the site as for any other edit. Saving
+// takes a JSON PUT, which other sites open in the same browser can't send
+// without a CORS preflight the server never answers.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+const DELIMITER: &str = "+++";
+
+/// A page split into its front matter, without the delimiters, and body
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct EditablePage {
+    pub front_matter: String,
+    pub body: String,
+}
+
+impl EditablePage {
+    fn parse(content: &str) -> Self {
+        let split = content
+            .strip_prefix(DELIMITER)
+            .and_then(|rest| rest.strip_prefix('\n').or_else(|| rest.strip_prefix("\r\n")))
+            .and_then(|rest| {
+                let mut offset = 0;
+                for line in rest.split_inclusive('\n') {
+                    if line.trim_end() == DELIMITER {
+                        return Some((&rest[..offset], &rest[offset + line.len()..]));
+                    }
+                    offset += line.len();
+                }
+                None
+            });
+        match split {
+            Some((front_matter, body)) => Self {
+                front_matter: front_matter.trim_end().to_string(),
+                body: body.to_string(),
+            },
+            None => Self {
+                front_matter: String::new(),
+                body: content.to_string(),
+            },
+        }
+    }
+
+    fn content(&self) -> String {
+        let front_matter = self.front_matter.trim();
+        match front_matter.is_empty() {
+            true => self.body.clone(),
+            false => format!("{}\n{}\n{}\n{}", DELIMITER, front_matter, DELIMITER, self.body),
+        }
+    }
+}
+
+pub(crate) struct PageStore {
+    root: PathBuf,
+}
+
+impl PageStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Markdown files in the source directory, as paths relative to it
+    pub fn list(&self) -> std::io::Result<Vec<String>> {
+        let mut pages = Vec::new();
+        collect(&self.root, "", &mut pages)?;
+        pages.sort();
+        Ok(pages)
+    }
+
+    pub fn read(&self, path: &str) -> std::io::Result<EditablePage> {
+        let content = std::fs::read_to_string(self.resolve(path)?)?;
+        Ok(EditablePage::parse(&content))
+    }
+
+    /// Save a page, creating it and its directory if it's new
+    pub fn write(&self, path: &str, page: &EditablePage) -> std::io::Result<()> {
+        let file = self.resolve(path)?;
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, page.content())
+    }
+
+    // Only markdown files, and only inside the source directory, following
+    // symlinks
+    fn resolve(&self, path: &str) -> std::io::Result<PathBuf> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} isn't a markdown file in the source directory", path),
+            )
+        };
+        let relative = Path::new(path);
+        let inside = relative.components().all(|component| matches!(component, Component::Normal(_)));
+        if !inside || !path.ends_with(".md") {
+            return Err(invalid());
+        }
+
+        // New pages are checked by the closest directory that exists. A
+        // link that points nowhere could still be written through.
+        let file = self.root.join(relative);
+        let mut existing = file.as_path();
+        while existing.symlink_metadata().is_err() && existing != self.root {
+            existing = existing.parent().unwrap_or(&self.root);
+        }
+        let root = self.root.canonicalize()?;
+        match existing.canonicalize() {
+            Ok(resolved) if resolved.starts_with(&root) => Ok(file),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Hidden directories, like `.git`, aren't content
+fn collect(dir: &Path, prefix: &str, pages: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            collect(&entry.path(), &format!("{}/", path), pages)?;
+        } else if name.ends_with(".md") {
+            pages.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The body as HTML, for previewing it while it's edited. Shortcodes and
+/// the theme only apply to the built page.
+pub(crate) fn preview(body: &str) -> String {
+    let options = pulldown_cmark::Options::ENABLE_TABLES
+        | pulldown_cmark::Options::ENABLE_FOOTNOTES
+        | pulldown_cmark::Options::ENABLE_STRIKETHROUGH
+        | pulldown_cmark::Options::ENABLE_TASKLISTS;
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new_ext(body, options));
+    html
+}
+
+pub(crate) const PAGE: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Edit pages</title>
+<style>
+  * { box-sizing: border-box; }
+  body { margin: 0; height: 100vh; display: flex; font: 14px/1.5 system-ui, sans-serif; color: #222; }
+  nav { width: 260px; border-right: 1px solid #ddd; display: flex; flex-direction: column; background: #fafafa; }
+  nav div { display: flex; gap: 6px; padding: 10px; border-bottom: 1px solid #ddd; }
+  nav input { flex: 1; min-width: 0; font: inherit; padding: 4px 6px; }
+  nav ul { list-style: none; margin: 0; padding: 0; overflow: auto; flex: 1; }
+  nav li { padding: 5px 12px; cursor: pointer; word-break: break-all; }
+  nav li:hover { background: #eee; }
+  nav li.current { background: #dbeafe; }
+  main { flex: 1; display: flex; flex-direction: column; min-width: 0; }
+  header { display: flex; align-items: center; gap: 12px; padding: 10px 14px; border-bottom: 1px solid #ddd; }
+  header strong { flex: 1; word-break: break-all; }
+  header span { color: #666; }
+  button { font: inherit; cursor: pointer; padding: 4px 12px; }
+  label { display: block; padding: 8px 14px 0; color: #666; }
+  textarea { width: 100%; font: 13px/1.5 ui-monospace, monospace; padding: 8px; border: 1px solid #ccc; resize: vertical; }
+  #front { height: 110px; margin-top: 4px; }
+  .split { flex: 1; display: flex; gap: 12px; padding: 8px 14px 14px; min-height: 0; }
+  .split textarea, .split iframe { flex: 1; height: 100%; resize: none; }
+  .split iframe { border: 1px solid #eee; }
+  #empty { margin: auto; color: #666; }
+</style>
+</head>
+<body>
+<nav>
+  <div><input type="search" placeholder="Filter pages"><button id="new" type="button">New</button></div>
+  <ul></ul>
+</nav>
+<main>
+  <p id="empty">Choose a page to edit. Saved pages are rebuilt like any other change.</p>
+  <header hidden><strong></strong><span></span><button id="save" type="button">Save</button></header>
+  <label hidden>Front matter (TOML)<textarea id="front" spellcheck="false"></textarea></label>
+  <div class="split" hidden><textarea id="body"></textarea><iframe sandbox title="Preview"></iframe></div>
+</main>
+<script>
+(() => {
+  const api = '/__admin';
+  const list = document.querySelector('nav ul');
+  const filter = document.querySelector('nav input');
+  const title = document.querySelector('header strong');
+  const status = document.querySelector('header span');
+  const front = document.getElementById('front');
+  const body = document.getElementById('body');
+  // Sandboxed, so scripts and forms in the markdown's HTML can't run with
+  // access to the editor
+  const preview = document.querySelector('iframe');
+  const previewStyle = '<style>body { font: 14px/1.5 system-ui, sans-serif; color: #222; margin: 0 16px; } img { max-width: 100%; }</style>';
+  let pages = [];
+  let current = null;
+  let dirty = false;
+
+  const show = () => {
+    document.getElementById('empty').hidden = current !== null;
+    for (const element of document.querySelectorAll('header, label, .split')) element.hidden = current === null;
+  };
+
+  const renderList = () => {
+    const query = filter.value.toLowerCase();
+    list.innerHTML = '';
+    for (const page of pages.filter((page) => page.toLowerCase().includes(query))) {
+      const item = document.createElement('li');
+      item.textContent = page;
+      item.className = page === current ? 'current' : '';
+      item.onclick = () => open(page);
+      list.appendChild(item);
+    }
+  };
+
+  const loadList = async () => {
+    const response = await fetch(`${api}/pages`);
+    pages = response.ok ? await response.json() : [];
+    renderList();
+  };
+
+  let previewTimer = null;
+  const updatePreview = () => {
+    clearTimeout(previewTimer);
+    previewTimer = setTimeout(async () => {
+      const response = await fetch(`${api}/preview`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ body: body.value }),
+      });
+      if (response.ok) preview.srcdoc = previewStyle + (await response.json()).html;
+    }, 250);
+  };
+
+  const setDirty = (value) => {
+    dirty = value;
+    status.textContent = value ? 'Unsaved changes' : '';
+  };
+
+  const open = async (page) => {
+    if (dirty && !confirm('Discard unsaved changes?')) return;
+    const response = await fetch(`${api}/page?path=${encodeURIComponent(page)}`);
+    if (!response.ok) {
+      alert(await response.text());
+      return;
+    }
+    const data = await response.json();
+    current = page;
+    title.textContent = page;
+    front.value = data.front_matter;
+    body.value = data.body;
+    setDirty(false);
+    show();
+    renderList();
+    updatePreview();
+  };
+
+  const save = async () => {
+    if (current === null) return;
+    status.textContent = 'Saving...';
+    const response = await fetch(`${api}/page?path=${encodeURIComponent(current)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ front_matter: front.value, body: body.value }),
+    });
+    if (response.ok) {
+      setDirty(false);
+      status.textContent = 'Saved, rebuilding';
+    } else {
+      status.textContent = `Not saved: ${await response.text()}`;
+    }
+  };
+
+  document.getElementById('new').onclick = async () => {
+    const page = prompt('Path of the new page in the source directory, e.g. guides/setup.md');
+    if (!page) return;
+    const response = await fetch(`${api}/page?path=${encodeURIComponent(page)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ front_matter: 'title = "New page"', body: '' }),
+    });
+    if (!response.ok) {
+      alert(await response.text());
+      return;
+    }
+    await loadList();
+    open(page);
+  };
+
+  document.getElementById('save').onclick = save;
+  filter.oninput = renderList;
+  front.oninput = () => setDirty(true);
+  body.oninput = () => {
+    setDirty(true);
+    updatePreview();
+  };
+  document.addEventListener('keydown', (event) => {
+    if ((event.ctrlKey || event.metaKey) && event.key === 's') {
+      event.preventDefault();
+      save();
+    }
+  });
+  window.addEventListener('beforeunload', (event) => {
+    if (dirty) event.preventDefault();
+  });
+
+  show();
+  loadList();
+})();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("zap-admin-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("site/guides")).unwrap();
+        std::fs::create_dir_all(dir.join("outside")).unwrap();
+        std::fs::write(dir.join("outside/secret.md"), "").unwrap();
+        let store = PageStore::new(dir.join("site"));
+        let rejected = |path: &str| {
+            store
+                .resolve(path)
+                .is_err_and(|e| e.kind() == std::io::ErrorKind::InvalidInput)
+        };
+
+        assert_eq!(store.resolve("guides/setup.md").unwrap(), dir.join("site/guides/setup.md"));
+        assert_eq!(store.resolve("new/page.md").unwrap(), dir.join("site/new/page.md"));
+        assert!(rejected("../outside/secret.md"));
+        assert!(rejected("guides/../../outside/secret.md"));
+        assert!(rejected("./guides/setup.md"));
+        assert!(rejected(&dir.join("outside/secret.md").display().to_string()));
+        assert!(rejected("/etc/passwd.md"));
+        assert!(rejected("guides/setup.html"));
+        assert!(rejected("zap.toml"));
+        assert!(rejected("guides"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+            symlink(dir.join("outside"), dir.join("site/linked")).unwrap();
+            symlink(dir.join("outside/secret.md"), dir.join("site/secret.md")).unwrap();
+            symlink(dir.join("outside/missing.md"), dir.join("site/dangling.md")).unwrap();
+            symlink(dir.join("site/guides"), dir.join("site/docs")).unwrap();
+            assert!(rejected("linked/secret.md"));
+            assert!(rejected("linked/new.md"));
+            assert!(rejected("secret.md"));
+            assert!(rejected("dangling.md"));
+            assert!(store.write("dangling.md", &EditablePage::default()).is_err());
+            assert!(!dir.join("outside/missing.md").exists());
+            // Links within the source directory are fine
+            assert!(store.resolve("docs/setup.md").is_ok());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+// Browsers connected for live reload, and which page each is showing, so
+// messages can go to only the clients viewing a given page.
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+/// How a browser is connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    WebSocket,
+    Sse,
+}
+
+/// A connected live reload client
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub id: u64,
+    /// Page the client is showing, normalized with [`normalize_page`]
+    pub page: String,
+    pub user_agent: String,
+    pub transport: Transport,
+    /// Unix seconds
+    pub connected_at: u64,
+}
+
+/// A message for connected clients, either all of them or only those on
+/// the given pages
+#[derive(Debug, Clone)]
+pub(crate) struct Outgoing {
+    pub pages: Option<Vec<String>>,
+    pub text: String,
+}
+
+impl Outgoing {
+    pub fn all(text: impl Into<String>) -> Self {
+        Self {
+            pages: None,
+            text: text.into(),
+        }
+    }
+
+    pub fn to_pages(pages: Vec<String>, text: impl Into<String>) -> Self {
+        Self {
+            pages: Some(pages.iter().map(|page| normalize_page(page)).collect()),
+            text: text.into(),
+        }
+    }
+
+    pub fn is_for(&self, page: &str) -> bool {
+        self.pages
+            .as_ref()
+            .is_none_or(|pages| pages.iter().any(|p| p == page))
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct ClientRegistry {
+    next_id: Arc<AtomicU64>,
+    clients: Arc<Mutex<HashMap<u64, ClientInfo>>>,
+}
+
+impl ClientRegistry {
+    /// Track a client until the returned guard is dropped
+    pub fn register(&self, page: &str, user_agent: &str, transport: Transport) -> ClientGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let connected_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let client = ClientInfo {
+            id,
+            page: normalize_page(page),
+            user_agent: user_agent.to_string(),
+            transport,
+            connected_at,
+        };
+        self.clients.lock().unwrap().insert(id, client.clone());
+
+        ClientGuard {
+            registry: self.clone(),
+            client,
+        }
+    }
+
+    /// Connected clients, oldest first
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self.clients.lock().unwrap().values().cloned().collect();
+        clients.sort_by_key(|client| client.id);
+        clients
+    }
+}
+
+pub(crate) struct ClientGuard {
+    registry: ClientRegistry,
+    pub client: ClientInfo,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.registry.clients.lock().unwrap().remove(&self.client.id);
+    }
+}
+
+/// The URL path a page is served at, so `/docs/`, `/docs/index.html` and
+/// `/docs?tab=2` all name the same page
+pub fn normalize_page(page: &str) -> String {
+    let path = page.split(['?', '#']).next().unwrap_or_default();
+    let path = path.strip_suffix("index.html").unwrap_or(path);
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        "/".to_string()
+    } else if path.rsplit('/').next().is_some_and(|name| name.contains('.')) {
+        format!("/{}", path)
+    } else {
+        format!("/{}/", path)
+    }
+}
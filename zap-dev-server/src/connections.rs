@@ -0,0 +1,162 @@
+// Accepting connections ourselves rather than with `axum::serve`, which
+// doesn't expose HTTP/2, keep-alive or connection limits. Behind a reverse
+// proxy these matter more than they do for one browser on localhost.
+
+use axum::{serve::Listener, Router};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use std::{
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::Semaphore,
+};
+
+/// How the server listens and treats connections
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionConfig {
+    /// Accept HTTP/2 without TLS (h2c) as well as HTTP/1.1
+    pub http2: bool,
+    /// How long a connection is kept open without traffic. Zero closes
+    /// connections after each response, `None` never closes them.
+    pub keep_alive: Option<Duration>,
+    /// Connections served at once, further ones wait to be accepted
+    pub max_connections: Option<usize>,
+    /// Listen on this unix socket instead of the host and port
+    pub socket: Option<PathBuf>,
+}
+
+pub(crate) async fn serve<L: Listener>(mut listener: L, app: Router, config: &ConnectionConfig) {
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder.http1().timer(TokioTimer::new());
+    builder.http2().timer(TokioTimer::new());
+    if config.keep_alive == Some(Duration::ZERO) {
+        builder.http1().keep_alive(false);
+    }
+    if !config.http2 {
+        builder = builder.http1_only();
+    }
+    let builder = Arc::new(builder);
+    let idle_timeout = config.keep_alive.filter(|timeout| !timeout.is_zero());
+    let limit = config.max_connections.map(|max| Arc::new(Semaphore::new(max)));
+
+    loop {
+        // Wait for a free slot before accepting, so waiting connections
+        // queue in the listen backlog
+        let permit = match &limit {
+            Some(limit) => Some(limit.clone().acquire_owned().await.expect("semaphore is never closed")),
+            None => None,
+        };
+        let (io, _) = listener.accept().await;
+        let builder = builder.clone();
+        let service = TowerToHyperService::new(app.clone());
+
+        tokio::spawn(async move {
+            let io = Tracked::new(io);
+            let activity = io.activity.clone();
+            let conn = builder.serve_connection_with_upgrades(TokioIo::new(io), service);
+            tokio::pin!(conn);
+
+            // Errors are clients going away mid-request, nothing to report
+            match idle_timeout {
+                Some(timeout) => {
+                    let mut closing = false;
+                    loop {
+                        let idle = activity.idle();
+                        tokio::select! {
+                            _ = conn.as_mut() => break,
+                            _ = tokio::time::sleep(timeout.saturating_sub(idle)), if !closing => {
+                                // Finishes the response in flight, if any,
+                                // then closes
+                                if activity.idle() >= timeout {
+                                    conn.as_mut().graceful_shutdown();
+                                    closing = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let _ = conn.await;
+                }
+            }
+            drop(permit);
+        });
+    }
+}
+
+// When a connection last read or wrote anything
+struct Activity {
+    started: Instant,
+    last_ms: AtomicU64,
+}
+
+impl Activity {
+    fn touch(&self) {
+        self.last_ms
+            .store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn idle(&self) -> Duration {
+        let last = Duration::from_millis(self.last_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last)
+    }
+}
+
+// A connection's IO, recording activity for the idle timeout
+struct Tracked<I> {
+    io: I,
+    activity: Arc<Activity>,
+}
+
+impl<I> Tracked<I> {
+    fn new(io: I) -> Self {
+        Self {
+            io,
+            activity: Arc::new(Activity {
+                started: Instant::now(),
+                last_ms: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for Tracked<I> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.io).poll_read(cx, buf);
+        if poll.is_ready() {
+            self.activity.touch();
+        }
+        poll
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for Tracked<I> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.io).poll_write(cx, buf);
+        if poll.is_ready() {
+            self.activity.touch();
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,69 @@
+// Response headers from config, for things like CORS, the cross-origin
+// isolation SharedArrayBuffer needs, or a Content-Security-Policy to test
+// against.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use globset::{Glob, GlobMatcher};
+
+/// Headers for the URL paths matching `pattern`, a glob like `/demos/**`.
+/// `None` applies them to every path.
+#[derive(Debug, Clone)]
+pub struct HeaderRule {
+    pub pattern: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+pub(crate) struct CompiledRule {
+    matcher: Option<GlobMatcher>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+// Invalid patterns and headers are skipped with a warning rather than
+// stopping the server. Global rules go first, then patterns from shortest
+// to longest, so more specific patterns override broader ones.
+pub(crate) fn compile(rules: &[HeaderRule]) -> Vec<CompiledRule> {
+    let mut rules: Vec<&HeaderRule> = rules.iter().collect();
+    rules.sort_by_key(|rule| rule.pattern.as_ref().map(|pattern| pattern.len()));
+
+    rules
+        .into_iter()
+        .filter_map(|rule| {
+            let matcher = match &rule.pattern {
+                Some(pattern) => match Glob::new(pattern) {
+                    Ok(glob) => Some(glob.compile_matcher()),
+                    Err(e) => {
+                        tracing::warn!("skipping headers for {}: {}", pattern, e);
+                        return None;
+                    }
+                },
+                None => None,
+            };
+
+            let headers = rule
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    match (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+                        (Ok(name), Ok(value)) => Some((name, value)),
+                        _ => {
+                            tracing::warn!("skipping invalid header {}: {}", name, value);
+                            None
+                        }
+                    }
+                })
+                .collect();
+
+            Some(CompiledRule { matcher, headers })
+        })
+        .collect()
+}
+
+pub(crate) fn apply(rules: &[CompiledRule], path: &str, headers: &mut HeaderMap) {
+    for rule in rules {
+        if rule.matcher.as_ref().is_none_or(|matcher| matcher.is_match(path)) {
+            for (name, value) in &rule.headers {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -1,16 +1,42 @@
+mod admin;
+mod clients;
+mod connections;
+mod headers;
+mod production;
+mod review;
+mod webhook;
+
+pub use clients::{normalize_page, ClientInfo, Transport};
+pub use connections::ConnectionConfig;
+pub use headers::HeaderRule;
+pub use production::{ProductionServer, ProductionServerConfig};
+pub use review::Comment;
+
 use anyhow::Result;
 use axum::{
+    body::{Body, Bytes},
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    extract::State,
-    response::IntoResponse,
-    routing::get,
-    Router,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware,
+    response::sse::{Event, KeepAlive, Sse},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
 };
+use admin::{EditablePage, PageStore};
+use clients::{ClientGuard, ClientRegistry, Outgoing};
+use review::{CommentStore, NewComment};
+use futures_util::stream::{self, Stream, StreamExt};
 use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use serde::Deserialize;
 use std::{
+    convert::Infallible,
     net::SocketAddr,
     path::PathBuf,
-    time::Duration,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
 use tokio::sync::broadcast;
 use tower_http::services::ServeDir;
@@ -26,8 +52,23 @@ pub struct LiveServerConfig {
     pub root: PathBuf,
     /// Auto-open browser
     pub open: bool,
+    /// Page to open, relative to the site root, e.g. `docs/intro/`
+    pub open_path: String,
+    /// Extra directories to serve, as URL prefix and directory. They take
+    /// precedence over files in `root` under the same prefix.
+    pub mounts: Vec<(String, PathBuf)>,
+    /// Response headers, by the URL paths they apply to
+    pub headers: Vec<HeaderRule>,
     /// Patterns to ignore when watching
     pub ignore: Vec<String>,
+    pub connections: ConnectionConfig,
+    /// Comments file for review mode, which is off when unset
+    pub review: Option<PathBuf>,
+    /// Secret that POSTs to `/__webhook` must carry to ask for a rebuild.
+    /// The endpoint doesn't exist when unset.
+    pub webhook_secret: Option<String>,
+    /// Source directory whose pages `/__admin` edits, which is off when unset
+    pub admin: Option<PathBuf>,
 }
 
 impl Default for LiveServerConfig {
@@ -37,7 +78,14 @@ impl Default for LiveServerConfig {
             port: 3000,
             root: PathBuf::from("."),
             open: false,
+            open_path: "/".to_string(),
+            mounts: vec![],
+            headers: vec![],
             ignore: vec![],
+            connections: ConnectionConfig::default(),
+            review: None,
+            webhook_secret: None,
+            admin: None,
         }
     }
 }
@@ -45,19 +93,92 @@ impl Default for LiveServerConfig {
 /// A live-reload static file server
 pub struct LiveServer {
     config: LiveServerConfig,
+    handle: LiveServerHandle,
+}
+
+/// Cloneable handle for pushing messages to connected browsers
+#[derive(Clone)]
+pub struct LiveServerHandle {
+    reload_tx: broadcast::Sender<Outgoing>,
+    last_error: Arc<Mutex<Option<String>>>,
+    build_id: Arc<Mutex<String>>,
+    clients: ClientRegistry,
+    webhook_tx: broadcast::Sender<String>,
+}
+
+impl LiveServerHandle {
+    /// Ask every connected browser to reload
+    pub fn reload(&self) {
+        *self.build_id.lock().unwrap() = new_build_id();
+        let _ = self.reload_tx.send(Outgoing::all("reload"));
+    }
+
+    /// Reload only the browsers showing one of `pages`, given as URL paths
+    /// like `/docs/intro/`
+    pub fn reload_pages(&self, pages: Vec<String>) {
+        *self.build_id.lock().unwrap() = new_build_id();
+        let _ = self.reload_tx.send(Outgoing::to_pages(pages, "reload"));
+    }
+
+    /// Browsers currently connected for live reload
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        self.clients.list()
+    }
+
+    /// Changes on every reload. Served pages carry the ID they were served
+    /// with, and browsers get the current one when they connect, so a page
+    /// that missed a reload while connecting or disconnected knows it's stale.
+    pub fn build_id(&self) -> String {
+        self.build_id.lock().unwrap().clone()
+    }
+
+    /// Show an error overlay in connected browsers. The error is kept and
+    /// replayed to clients that connect later (e.g. after a reload).
+    pub fn report_error<S: Into<String>>(&self, message: S) {
+        let message = message.into();
+        *self.last_error.lock().unwrap() = Some(message.clone());
+        let _ = self.reload_tx.send(Outgoing::all(format!("error:{}", message)));
+    }
+
+    /// Rebuilds asked for through the webhook, each described by what sent
+    /// it, e.g. `GitHub push`
+    pub fn webhooks(&self) -> broadcast::Receiver<String> {
+        self.webhook_tx.subscribe()
+    }
+
+    /// Dismiss the error overlay, if one is showing
+    pub fn clear_error(&self) {
+        if self.last_error.lock().unwrap().take().is_some() {
+            let _ = self.reload_tx.send(Outgoing::all("clear-error"));
+        }
+    }
 }
 
 impl LiveServer {
     /// Create a new live server with the given configuration
     pub fn new(config: LiveServerConfig) -> Self {
-        Self { config }
+        // Create broadcast channel for live reload
+        let (reload_tx, _) = broadcast::channel::<Outgoing>(100);
+
+        Self {
+            config,
+            handle: LiveServerHandle {
+                reload_tx,
+                last_error: Arc::new(Mutex::new(None)),
+                build_id: Arc::new(Mutex::new(new_build_id())),
+                clients: ClientRegistry::default(),
+                webhook_tx: broadcast::channel(16).0,
+            },
+        }
+    }
+
+    /// Get a handle for sending messages to connected browsers
+    pub fn handle(&self) -> LiveServerHandle {
+        self.handle.clone()
     }
 
     /// Run the live server
     pub async fn run(self) -> Result<()> {
-        // Create broadcast channel for live reload
-        let (reload_tx, _) = broadcast::channel::<String>(100);
-
         // Ensure root directory exists
         if !self.config.root.exists() {
             return Err(anyhow::anyhow!(
@@ -66,83 +187,392 @@ impl LiveServer {
             ));
         }
 
-        let state = AppState {
-            reload_tx: reload_tx.clone(),
-        };
+        // Start file watchers, mounted directories reload browsers too
+        let mounts: Vec<(String, PathBuf)> = self
+            .config
+            .mounts
+            .iter()
+            .filter_map(|(prefix, dir)| Some((mount_prefix(prefix, dir)?, dir.clone())))
+            .collect();
+        let watch_paths = mounts.iter().map(|(_, dir)| dir.clone());
+        for watch_path in std::iter::once(self.config.root.clone()).chain(watch_paths) {
+            let watcher_handle = self.handle.clone();
+            let ignore_patterns = self.config.ignore.clone();
+            tokio::spawn(async move {
+                if let Err(e) = start_file_watcher(watch_path, watcher_handle, ignore_patterns).await {
+                    tracing::error!("File watcher error: {}", e);
+                }
+            });
+        }
 
-        // Start file watcher
-        let watcher_reload_tx = reload_tx.clone();
-        let watch_path = self.config.root.clone();
-        let ignore_patterns = self.config.ignore.clone();
-        
-        tokio::spawn(async move {
-            if let Err(e) = start_file_watcher(watch_path, watcher_reload_tx, ignore_patterns).await {
-                eprintln!("File watcher error: {}", e);
-            }
-        });
+        let app = self.router(self.state(), &mounts);
 
-        // Create router
-        let serve_dir = ServeDir::new(&self.config.root);
-        let app = Router::new()
-            .route("/__livereload", get(websocket_handler))
-            .fallback_service(serve_dir)
-            .with_state(state);
+        let connections = &self.config.connections;
+        if let Some(socket) = &connections.socket {
+            let listener = bind_socket(socket)?;
+            tracing::info!("Serving at unix:{}", socket.display());
+            tracing::info!("Watching: {}", self.config.root.display());
+            connections::serve(listener, app, connections).await;
+            return Ok(());
+        }
 
         // Build address
         let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port).parse()?;
 
-        println!("Serving at http://{}", addr);
-        println!("Watching: {}", self.config.root.display());
-        println!("Live reload enabled at ws://{}/__livereload", addr);
+        tracing::info!("Serving at http://{}", addr);
+        tracing::info!("Watching: {}", self.config.root.display());
+        tracing::info!("Live reload enabled at ws://{}/__livereload", addr);
+        tracing::info!("Server-Sent Events fallback at http://{}/__livereload/sse", addr);
+        tracing::info!("Connected browsers listed at http://{}/__clients", addr);
 
         // Open browser if requested
         if self.config.open {
-            if let Err(e) = open::that(format!("http://{}", addr)) {
-                eprintln!("Failed to open browser: {}", e);
-            }
+            self.open_browser();
         }
 
         // Start server
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        connections::serve(listener, app, connections).await;
 
         Ok(())
     }
 }
 
+impl LiveServer {
+    fn state(&self) -> AppState {
+        AppState {
+            handle: self.handle.clone(),
+            headers: Arc::new(headers::compile(&self.config.headers)),
+            review: self.config.review.clone().map(|path| Arc::new(CommentStore::new(path))),
+            // Behind a proxy on a unix socket, any name can reach the server
+            host: match self.config.connections.socket {
+                Some(_) => None,
+                None => Some(Arc::new(self.config.host.clone())),
+            },
+            webhook_secret: self.config.webhook_secret.clone().map(Arc::new),
+            admin: self.config.admin.clone().map(|path| Arc::new(PageStore::new(path))),
+        }
+    }
+
+    fn router(&self, state: AppState, mounts: &[(String, PathBuf)]) -> Router {
+        let serve_dir = ServeDir::new(&self.config.root);
+        let mut app = Router::new()
+            .route("/__livereload", get(websocket_handler))
+            .route("/__livereload/sse", get(sse_handler))
+            .route("/__clients", get(clients_handler));
+        if let Some(review) = &state.review {
+            tracing::info!("Review mode on, comments saved to {}", review.path().display());
+            let comments = Router::new()
+                .route("/__comments", get(list_comments_handler).post(add_comment_handler))
+                .route("/__comments/{id}", delete(remove_comment_handler))
+                .route_layer(middleware::from_fn_with_state(state.clone(), check_comment_request));
+            app = app.route("/__review.js", get(review_script_handler)).merge(comments);
+        }
+        if state.webhook_secret.is_some() {
+            tracing::info!("Rebuilding on authenticated POSTs to /__webhook");
+            app = app.route("/__webhook", post(webhook_handler));
+        }
+        if let Some(admin) = &state.admin {
+            tracing::info!("Editing pages in {} at /__admin", admin.root().display());
+            if !is_loopback(&self.config.host) {
+                tracing::warn!("anyone who can reach {} can edit the site through /__admin", self.config.host);
+            }
+            app = app
+                .route("/__admin", get(admin_handler))
+                .route("/__admin/pages", get(admin_pages_handler))
+                .route("/__admin/page", get(admin_read_handler).put(admin_write_handler))
+                .route("/__admin/preview", post(admin_preview_handler));
+        }
+        for (prefix, dir) in mounts {
+            tracing::info!("Serving {} at {}", dir.display(), prefix);
+            app = app.nest_service(prefix, ServeDir::new(dir));
+        }
+        app.fallback_service(serve_dir)
+            .layer(middleware::map_response_with_state(state.clone(), add_build_id))
+            .layer(middleware::from_fn_with_state(state.headers.clone(), add_headers))
+            .layer(middleware::from_fn(select_variant))
+            .with_state(state)
+    }
+
+    /// URL of the page to open. Servers bound to every interface are opened
+    /// on localhost, since browsers can't always reach `0.0.0.0`.
+    fn open_url(&self) -> String {
+        let host = match self.config.host.as_str() {
+            "0.0.0.0" | "::" | "[::]" => "localhost",
+            host => host,
+        };
+        let path = self.config.open_path.trim_start_matches('/');
+        format!("http://{}:{}/{}", host, self.config.port, path)
+    }
+
+    fn open_browser(&self) {
+        let url = self.open_url();
+
+        let path = self.config.open_path.trim_start_matches('/');
+        let page = self.config.root.join(path);
+        if !page.is_file() && !page.join("index.html").is_file() {
+            tracing::warn!("no page at /{}, opening it anyway", path);
+        }
+
+        if !has_browser() {
+            tracing::info!("No browser available in this session, open {} to view the site", url);
+            return;
+        }
+        if let Err(e) = open::that(&url) {
+            tracing::warn!("Failed to open browser ({}), open {} to view the site", e, url);
+        }
+    }
+}
+
+// Over SSH, or on a Linux box without a display, there's nothing to open a
+// browser on. `open` would either fail or pop one up on the remote screen.
+fn has_browser() -> bool {
+    let set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if set("SSH_CONNECTION") || set("SSH_TTY") {
+        return false;
+    }
+    if cfg!(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd")) {
+        return set("DISPLAY") || set("WAYLAND_DISPLAY");
+    }
+    true
+}
+
 #[derive(Clone)]
 struct AppState {
-    reload_tx: broadcast::Sender<String>,
+    handle: LiveServerHandle,
+    headers: Arc<Vec<headers::CompiledRule>>,
+    review: Option<Arc<CommentStore>>,
+    /// Host the server was started on, for checking requests' `Host`
+    host: Option<Arc<String>>,
+    webhook_secret: Option<Arc<String>>,
+    admin: Option<Arc<PageStore>>,
+}
+
+// Clients say which page they're on when connecting
+#[derive(Deserialize)]
+struct ConnectParams {
+    #[serde(default)]
+    page: String,
+}
+
+fn user_agent(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+async fn clients_handler(State(state): State<AppState>) -> Json<Vec<ClientInfo>> {
+    Json(state.handle.clients())
+}
+
+async fn review_script_handler() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/javascript")], review::SCRIPT)
+}
+
+#[derive(Deserialize)]
+struct CommentParams {
+    page: Option<String>,
+}
+
+// Comments change a file in the project, so they're only taken from the
+// server's own pages: not from another site's page, which browsers let
+// post forms cross-origin, nor through a DNS name pointed at the server
+async fn check_comment_request(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if request.method() == Method::GET {
+        return next.run(request).await;
+    }
+    if !is_same_origin(request.headers(), state.host.as_deref().map(String::as_str)) {
+        return (StatusCode::FORBIDDEN, "Request isn't from this server's pages").into_response();
+    }
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if request.method() == Method::POST && !is_json {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Expected a JSON body").into_response();
+    }
+    next.run(request).await
+}
+
+// The request's `Host` is one of the server's names, and its `Origin`, which
+// browsers send with every POST and DELETE, is that host
+fn is_same_origin(headers: &HeaderMap, host: Option<&str>) -> bool {
+    let Some(request_host) = headers.get(header::HOST).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    if host.is_some_and(|host| !is_own_host(request_host, host)) {
+        return false;
+    }
+    match headers.get(header::ORIGIN) {
+        Some(origin) => origin
+            .to_str()
+            .ok()
+            .and_then(|origin| origin.split_once("://"))
+            .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(request_host)),
+        None => true,
+    }
+}
+
+// The name the server was started with, localhost or an IP address. Other
+// names could be ones somebody else points at the server.
+fn is_own_host(request_host: &str, host: &str) -> bool {
+    let name = match request_host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => request_host,
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.eq_ignore_ascii_case(host) || name.eq_ignore_ascii_case("localhost") || name.parse::<std::net::IpAddr>().is_ok()
+}
+
+async fn list_comments_handler(
+    State(state): State<AppState>,
+    Query(params): Query<CommentParams>,
+) -> Result<Json<Vec<Comment>>, (StatusCode, String)> {
+    let review = state.review.as_ref().expect("route only exists in review mode");
+    review.list(params.page.as_deref()).map(Json).map_err(comment_error)
+}
+
+async fn add_comment_handler(
+    State(state): State<AppState>,
+    Json(comment): Json<NewComment>,
+) -> Result<(StatusCode, Json<Comment>), (StatusCode, String)> {
+    if comment.text.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Comment text is empty".to_string()));
+    }
+    let review = state.review.as_ref().expect("route only exists in review mode");
+    let comment = review.add(comment).map_err(comment_error)?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+async fn remove_comment_handler(State(state): State<AppState>, Path(id): Path<u64>) -> StatusCode {
+    let review = state.review.as_ref().expect("route only exists in review mode");
+    match review.remove(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => comment_error(e).0,
+    }
+}
+
+async fn webhook_handler(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let secret = state.webhook_secret.as_ref().expect("route only exists with a webhook secret");
+    if !webhook::authorized(secret, &headers, &body) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    if webhook::is_ping(&headers) {
+        return StatusCode::OK;
+    }
+    // Nobody listening only means no rebuild is wanted
+    let _ = state.handle.webhook_tx.send(webhook::describe(&headers));
+    StatusCode::ACCEPTED
+}
+
+fn is_loopback(host: &str) -> bool {
+    host == "localhost" || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+async fn admin_handler() -> axum::response::Html<&'static str> {
+    axum::response::Html(admin::PAGE)
+}
+
+async fn admin_pages_handler(State(state): State<AppState>) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    let admin = state.admin.as_ref().expect("route only exists in admin mode");
+    admin.list().map(Json).map_err(admin_error)
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    path: String,
+}
+
+async fn admin_read_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<EditablePage>, (StatusCode, String)> {
+    let admin = state.admin.as_ref().expect("route only exists in admin mode");
+    admin.read(&params.path).map(Json).map_err(admin_error)
+}
+
+// The source watcher picks the saved file up and rebuilds
+async fn admin_write_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PageParams>,
+    Json(page): Json<EditablePage>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let admin = state.admin.as_ref().expect("route only exists in admin mode");
+    admin.write(&params.path, &page).map_err(admin_error)?;
+    tracing::info!("Saved {} from /__admin", params.path);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_preview_handler(Json(page): Json<EditablePage>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "html": admin::preview(&page.body) }))
+}
+
+fn admin_error(e: std::io::Error) -> (StatusCode, String) {
+    let status = match e.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        std::io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        _ => {
+            tracing::error!("Admin error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, e.to_string())
+}
+
+fn comment_error(e: std::io::Error) -> (StatusCode, String) {
+    tracing::error!("Review comments error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
 }
 
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    Query(params): Query<ConnectParams>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| websocket_connection(socket, state.reload_tx))
+    let guard = state
+        .handle
+        .clients
+        .register(&params.page, user_agent(&headers), Transport::WebSocket);
+    ws.on_upgrade(|socket| websocket_connection(socket, state.handle, guard))
 }
 
-async fn websocket_connection(mut socket: WebSocket, reload_tx: broadcast::Sender<String>) {
-    let mut rx = reload_tx.subscribe();
+async fn websocket_connection(mut socket: WebSocket, handle: LiveServerHandle, guard: ClientGuard) {
+    let mut rx = handle.reload_tx.subscribe();
 
     // Send initial connection confirmation
     if socket
-        .send(Message::Text("connected".to_string().into()))
+        .send(Message::Text(format!("connected:{}", handle.build_id()).into()))
         .await
         .is_err()
     {
         return;
     }
 
+    // Replay any outstanding error so the overlay survives reloads
+    let last_error = handle.last_error.lock().unwrap().clone();
+    if let Some(error) = last_error
+        && socket
+            .send(Message::Text(format!("error:{}", error).into()))
+            .await
+            .is_err()
+    {
+        return;
+    }
+
     loop {
         tokio::select! {
             msg = rx.recv() => {
                 match msg {
-                    Ok(reload_msg) => {
-                        if socket.send(Message::Text(reload_msg.into())).await.is_err() {
+                    Ok(outgoing) if outgoing.is_for(&guard.client.page) => {
+                        if socket.send(Message::Text(outgoing.text.into())).await.is_err() {
                             break;
                         }
                     }
+                    Ok(_) => {}
                     Err(_) => break,
                 }
             }
@@ -155,9 +585,159 @@ async fn websocket_connection(mut socket: WebSocket, reload_tx: broadcast::Sende
     }
 }
 
+// For networks whose proxies block WebSockets. Sends the same messages as
+// the socket, one event each.
+async fn sse_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ConnectParams>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let handle = state.handle;
+    // Dropped with the stream when the client goes away
+    let guard = handle
+        .clients
+        .register(&params.page, user_agent(&headers), Transport::Sse);
+
+    // Replay any outstanding error so the overlay survives reloads
+    let mut initial = vec![format!("connected:{}", handle.build_id())];
+    if let Some(error) = handle.last_error.lock().unwrap().clone() {
+        initial.push(format!("error:{}", error));
+    }
+
+    let updates = stream::unfold((handle.reload_tx.subscribe(), guard), |(mut rx, guard)| async move {
+        loop {
+            match rx.recv().await {
+                Ok(outgoing) if outgoing.is_for(&guard.client.page) => {
+                    return Some((outgoing.text, (rx, guard)));
+                }
+                Ok(_) => continue,
+                // Missed messages are only reloads and errors, the next one
+                // brings the page up to date
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let events = stream::iter(initial)
+        .chain(updates)
+        .map(|message| Ok(Event::default().data(message)));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+// A mount's URL prefix as the router needs it, or `None` with a warning
+// if the mount can't be served
+fn mount_prefix(prefix: &str, dir: &std::path::Path) -> Option<String> {
+    let trimmed = prefix.trim_matches('/');
+    if trimmed.is_empty() || trimmed.starts_with("__") {
+        tracing::warn!("can't mount {} at {}, use a path like /assets", dir.display(), prefix);
+        return None;
+    }
+    if !dir.is_dir() {
+        tracing::warn!("not mounting {}, it isn't a directory", dir.display());
+        return None;
+    }
+    Some(format!("/{}", trimmed))
+}
+
+// Configured headers, for site pages and files rather than live reload
+async fn add_headers(State(rules): State<Arc<Vec<headers::CompiledRule>>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    if !path.starts_with("/__") {
+        headers::apply(&rules, &path, response.headers_mut());
+    }
+    response
+}
+
+// Hex nanoseconds since the epoch, so IDs from a restarted server don't
+// repeat earlier ones
+fn new_build_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:x}", nanos)
+}
+
+// Tag served pages with the current build ID. They aren't cached, or a
+// revalidated page would keep the ID it was first served with.
+async fn add_build_id(State(state): State<AppState>, response: Response) -> Response {
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if response.status() != StatusCode::OK || !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let html = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::ETAG);
+    parts.headers.remove(header::LAST_MODIFIED);
+    parts
+        .headers
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    let mut html = insert_build_id(&html, &state.handle.build_id());
+    if state.review.is_some() {
+        html = review::insert_script(&html);
+    }
+    Response::from_parts(parts, Body::from(html))
+}
+
+// `?variant=name` serves the preview of a page's content variant, written
+// next to the page as `index.name.html` (or `page.name.html`)
+async fn select_variant(mut request: Request, next: Next) -> Response {
+    let variant = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("variant="))
+            .filter(|name| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+            .map(str::to_string)
+    });
+    let Some(variant) = variant else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path();
+    let preview = if path.ends_with('/') {
+        format!("{}index.{}.html", path, variant)
+    } else if let Some(stem) = path.strip_suffix(".html") {
+        format!("{}.{}.html", stem, variant)
+    } else {
+        format!("{}/index.{}.html", path, variant)
+    };
+    if let Ok(uri) = preview.parse() {
+        *request.uri_mut() = uri;
+    }
+    next.run(request).await
+}
+
+/// Add a `zap-build-id` meta tag at the start of the page's head
+pub fn insert_build_id(html: &str, build_id: &str) -> String {
+    let meta = format!("<meta name=\"zap-build-id\" content=\"{}\">", build_id);
+    let head_end = html
+        .match_indices("<head")
+        .map(|(start, _)| start)
+        .find(|&start| html[start + 5..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace()))
+        .and_then(|start| html[start..].find('>').map(|end| start + end + 1));
+    match head_end {
+        Some(pos) => format!("{}{}{}", &html[..pos], meta, &html[pos..]),
+        None => format!("{}{}", meta, html),
+    }
+}
+
 async fn start_file_watcher(
     watch_path: PathBuf,
-    reload_tx: broadcast::Sender<String>,
+    handle: LiveServerHandle,
     ignore_patterns: Vec<String>,
 ) -> Result<()> {
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
@@ -186,43 +766,107 @@ async fn start_file_watcher(
         .watcher()
         .watch(&watch_path, notify::RecursiveMode::Recursive)?;
 
-    println!("File watcher started for: {}", watch_path.display());
+    tracing::debug!("File watcher started for: {}", watch_path.display());
 
     // Process file change events with simple deduplication
     let mut last_reload = std::time::Instant::now();
     while let Some(path) = rx.recv().await {
-        println!("File changed: {}", path.display());
+        tracing::debug!("File changed: {}", path.display());
         
         // Only send reload if enough time has passed since last reload
         let now = std::time::Instant::now();
         if now.duration_since(last_reload) > Duration::from_millis(1000) {
             // Send reload message to all connected clients
-            let _ = reload_tx.send("reload".to_string());
+            handle.reload();
             last_reload = now;
-            println!("Sent reload signal");
+            tracing::debug!("Sent reload signal");
         } else {
-            println!("Skipping reload (too soon)");
+            tracing::debug!("Skipping reload (too soon)");
         }
     }
 
     Ok(())
 }
 
+// A socket left behind by a previous run would make binding fail
+#[cfg(unix)]
+fn bind_socket(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(tokio::net::UnixListener::bind(path)?)
+}
+
+#[cfg(not(unix))]
+fn bind_socket(_path: &std::path::Path) -> Result<tokio::net::TcpListener> {
+    Err(anyhow::anyhow!("Unix sockets aren't supported on this platform"))
+}
+
 /// Inject live reload script into HTML content
 pub fn inject_livereload_script(html: &str, host: &str, port: u16) -> String {
     let script = format!(
         r#"
 <script>
 (function() {{
-    const socket = new WebSocket('ws://{}:{}/__livereload');
-    socket.onmessage = function(event) {{
+    // Reload if a rebuild finished while the page was loading or
+    // disconnected, going by the build ID the server sends on connect
+    let everConnected = false;
+    function onMessage(event) {{
         if (event.data === 'reload') {{
             location.reload();
+        }} else if (event.data.startsWith('connected:')) {{
+            const meta = document.querySelector('meta[name="zap-build-id"]');
+            const stale = meta ? meta.content !== event.data.slice(10) : everConnected;
+            everConnected = true;
+            if (stale) {{
+                location.reload();
+            }}
+        }}
+    }}
+    let query = '?page=' + encodeURIComponent(location.pathname);
+    let connection = null;
+    // Fall back to Server-Sent Events where WebSockets are blocked
+    function useEventSource() {{
+        connection = new EventSource('/__livereload/sse' + query);
+        connection.onmessage = onMessage;
+    }}
+    let retryDelay = 250;
+    function useWebSocket() {{
+        const socket = new WebSocket('ws://{}:{}/__livereload' + query);
+        connection = socket;
+        let opened = false;
+        socket.onopen = function() {{
+            opened = true;
+            retryDelay = 250;
+        }};
+        socket.onmessage = onMessage;
+        socket.onclose = function() {{
+            if (!opened && !everConnected) {{
+                return useEventSource();
+            }}
+            setTimeout(useWebSocket, retryDelay);
+            retryDelay = Math.min(retryDelay * 2, 10000);
+        }};
+    }}
+    if (window.WebSocket) {{
+        useWebSocket();
+    }} else {{
+        useEventSource();
+    }}
+    // Instant navigation shows another page without a reload
+    document.addEventListener('zap:navigate', function() {{
+        query = '?page=' + encodeURIComponent(location.pathname);
+        const previous = connection;
+        previous.onclose = null;
+        previous.close();
+        if (previous instanceof EventSource) {{
+            useEventSource();
+        }} else {{
+            useWebSocket();
         }}
-    }};
-    socket.onclose = function() {{
-        console.log('Live reload disconnected');
-    }};
+    }});
 }})();
 </script>
 "#,
@@ -240,3 +884,123 @@ pub fn inject_livereload_script(html: &str, host: &str, port: u16) -> String {
         format!("{}{}", html, script)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_router_headers() {
+        let root = std::env::temp_dir().join(format!("zap-live-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("index.html"), "<html><head></head></html>").unwrap();
+        std::fs::write(root.join("app.js"), "").unwrap();
+
+        let server = LiveServer::new(LiveServerConfig {
+            root: root.clone(),
+            headers: vec![HeaderRule {
+                pattern: None,
+                headers: vec![("Access-Control-Allow-Origin".to_string(), "*".to_string())],
+            }],
+            ..LiveServerConfig::default()
+        });
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = server.router(server.state(), &[]).oneshot(get("/")).await.unwrap();
+        assert_eq!(response.headers()["access-control-allow-origin"], "*");
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("<meta name=\"zap-build-id\""));
+
+        let response = server.router(server.state(), &[]).oneshot(get("/app.js")).await.unwrap();
+        assert_eq!(response.headers()["access-control-allow-origin"], "*");
+        assert!(response.headers().get("cache-control").is_none());
+
+        // Not for the server's own endpoints
+        let response = server.router(server.state(), &[]).oneshot(get("/__clients")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let root = std::env::temp_dir().join(format!("zap-webhook-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let post = |token: &str| {
+            Request::post("/__webhook")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // There's no endpoint to authenticate against without a secret
+        let server = LiveServer::new(LiveServerConfig { root: root.clone(), ..LiveServerConfig::default() });
+        let mut webhooks = server.handle().webhooks();
+        let response = server.router(server.state(), &[]).oneshot(post("")).await.unwrap();
+        assert!(!response.status().is_success());
+        assert!(webhooks.try_recv().is_err());
+
+        let server = LiveServer::new(LiveServerConfig {
+            root: root.clone(),
+            webhook_secret: Some("s3cret".to_string()),
+            ..LiveServerConfig::default()
+        });
+        let mut webhooks = server.handle().webhooks();
+        let response = server.router(server.state(), &[]).oneshot(post("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(webhooks.try_recv().is_err());
+
+        let response = server.router(server.state(), &[]).oneshot(post("s3cret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(webhooks.try_recv().unwrap(), "webhook");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_comment_requests() {
+        let root = std::env::temp_dir().join(format!("zap-comments-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let server = LiveServer::new(LiveServerConfig {
+            root: root.clone(),
+            review: Some(root.join("comments.json")),
+            ..LiveServerConfig::default()
+        });
+        let send = |method: Method, uri: &str, host: &str, origin: Option<&str>, content_type: &str| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::HOST, host)
+                .header(header::CONTENT_TYPE, content_type);
+            if let Some(origin) = origin {
+                request = request.header(header::ORIGIN, origin);
+            }
+            let body = r#"{"page": "/guide/", "text": "Typo"}"#;
+            server.router(server.state(), &[]).oneshot(request.body(Body::from(body)).unwrap())
+        };
+        let json = "application/json";
+
+        let response = send(Method::POST, "/__comments", "localhost:3000", Some("https://evil.example"), json);
+        assert_eq!(response.await.unwrap().status(), StatusCode::FORBIDDEN);
+        let response = send(Method::POST, "/__comments", "evil.example:3000", Some("http://evil.example:3000"), json);
+        assert_eq!(response.await.unwrap().status(), StatusCode::FORBIDDEN);
+        let response = send(Method::POST, "/__comments", "127.0.0.1:3000", None, "text/plain");
+        assert_eq!(response.await.unwrap().status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert!(!root.join("comments.json").exists());
+
+        let response = send(Method::POST, "/__comments", "localhost:3000", Some("http://localhost:3000"), json);
+        assert_eq!(response.await.unwrap().status(), StatusCode::CREATED);
+        let response = send(Method::GET, "/__comments?page=/guide/", "evil.example", Some("https://evil.example"), "");
+        let body = axum::body::to_bytes(response.await.unwrap().into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("\"text\":\"Typo\""));
+
+        let response = send(Method::DELETE, "/__comments/1", "127.0.0.1:3000", Some("https://evil.example"), "");
+        assert_eq!(response.await.unwrap().status(), StatusCode::FORBIDDEN);
+        let response = send(Method::DELETE, "/__comments/1", "[::1]:3000", Some("http://[::1]:3000"), "");
+        assert_eq!(response.await.unwrap().status(), StatusCode::NO_CONTENT);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
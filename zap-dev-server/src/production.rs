@@ -0,0 +1,172 @@
+// Serving a built site in production, for `zap serve --production`. The
+// site's files and mounts with the configured headers, and nothing else:
+// no live reload, file watching, build IDs or `/__` endpoints.
+
+use crate::{add_headers, bind_socket, connections, headers, mount_prefix, ConnectionConfig, HeaderRule};
+use anyhow::Result;
+use axum::{middleware, Router};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower_http::services::ServeDir;
+
+/// Configuration for the production server
+#[derive(Debug, Clone)]
+pub struct ProductionServerConfig {
+    /// Host to bind to
+    pub host: String,
+    /// Port to serve on
+    pub port: u16,
+    /// Built site to serve
+    pub root: PathBuf,
+    /// Extra directories to serve, as URL prefix and directory
+    pub mounts: Vec<(String, PathBuf)>,
+    /// Response headers, by the URL paths they apply to
+    pub headers: Vec<HeaderRule>,
+    pub connections: ConnectionConfig,
+}
+
+/// A static file server for a built site
+pub struct ProductionServer {
+    config: ProductionServerConfig,
+}
+
+impl ProductionServer {
+    pub fn new(config: ProductionServerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Run the server
+    pub async fn run(self) -> Result<()> {
+        if !self.config.root.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Root directory does not exist: {}",
+                self.config.root.display()
+            ));
+        }
+
+        let app = self.router();
+        let connections = &self.config.connections;
+        if let Some(socket) = &connections.socket {
+            let listener = bind_socket(socket)?;
+            tracing::info!("Serving {} at unix:{}", self.config.root.display(), socket.display());
+            connections::serve(listener, app, connections).await;
+            return Ok(());
+        }
+
+        let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port).parse()?;
+        tracing::info!("Serving {} at http://{}", self.config.root.display(), addr);
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        connections::serve(listener, app, connections).await;
+
+        Ok(())
+    }
+
+    pub(crate) fn router(&self) -> Router {
+        let mut app = Router::new();
+        for (prefix, dir) in &self.config.mounts {
+            if let Some(prefix) = mount_prefix(prefix, dir) {
+                app = app.nest_service(&prefix, ServeDir::new(dir));
+            }
+        }
+        app.fallback_service(ServeDir::new(&self.config.root))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(headers::compile(&self.config.headers)),
+                add_headers,
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_router_headers() {
+        let root = std::env::temp_dir().join(format!("zap-production-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("demos")).unwrap();
+        std::fs::write(root.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(root.join("demos/index.html"), "<html></html>").unwrap();
+
+        let server = ProductionServer::new(ProductionServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            root: root.clone(),
+            mounts: vec![],
+            headers: vec![
+                HeaderRule {
+                    pattern: None,
+                    headers: vec![("Cross-Origin-Opener-Policy".to_string(), "unsafe-none".to_string())],
+                },
+                HeaderRule {
+                    pattern: Some("/demos/**".to_string()),
+                    headers: vec![("Cross-Origin-Opener-Policy".to_string(), "same-origin".to_string())],
+                },
+            ],
+            connections: ConnectionConfig::default(),
+        });
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = server.router().oneshot(get("/")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["cross-origin-opener-policy"], "unsafe-none");
+        // Pages are cached like any other file, not tagged with a build ID
+        assert!(response.headers().get("cache-control").is_none());
+        assert!(response.headers().get("last-modified").is_some());
+
+        let response = server.router().oneshot(get("/demos/")).await.unwrap();
+        assert_eq!(response.headers()["cross-origin-opener-policy"], "same-origin");
+
+        for path in ["/__livereload/sse", "/__clients", "/__admin"] {
+            let response = server.router().oneshot(get(path)).await.unwrap();
+            assert_eq!(response.status(), 404, "{}", path);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    // Through the listener, so the connection options apply
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_keep_alive() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let root = std::env::temp_dir().join(format!("zap-production-socket-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("index.html"), "<html></html>").unwrap();
+        let socket = root.join("zap.sock");
+
+        let server = ProductionServer::new(ProductionServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            root: root.clone(),
+            mounts: vec![],
+            headers: vec![HeaderRule {
+                pattern: None,
+                headers: vec![("X-Frame-Options".to_string(), "DENY".to_string())],
+            }],
+            connections: ConnectionConfig {
+                keep_alive: Some(std::time::Duration::ZERO),
+                socket: Some(socket.clone()),
+                ..ConnectionConfig::default()
+            },
+        });
+        tokio::spawn(server.run());
+
+        let mut stream = loop {
+            match tokio::net::UnixStream::connect(&socket).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        // Keep-alive of zero closes the connection after the response
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("x-frame-options: DENY"));
+        assert!(response.ends_with("<html></html>"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+// Review mode: reviewers select text on a preview page and comment on it.
+// Comments are kept in a JSON file in the project, so they can be committed
+// alongside the docs or read by other tools, and the file is re-read on
+// every request so edits to it by hand show up too.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+/// A comment on a passage of a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: u64,
+    /// Page the comment is on, normalized with [`crate::normalize_page`]
+    pub page: String,
+    /// The text that was selected
+    pub quote: String,
+    pub text: String,
+    #[serde(default)]
+    pub author: String,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+/// A comment as sent by the browser
+#[derive(Debug, Deserialize)]
+pub(crate) struct NewComment {
+    pub page: String,
+    #[serde(default)]
+    pub quote: String,
+    pub text: String,
+    #[serde(default)]
+    pub author: String,
+}
+
+pub(crate) struct CommentStore {
+    path: PathBuf,
+    // Held across read-modify-write so concurrent comments aren't lost
+    lock: Mutex<()>,
+}
+
+impl CommentStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Comments on `page`, or on every page, oldest first
+    pub fn list(&self, page: Option<&str>) -> std::io::Result<Vec<Comment>> {
+        let _guard = self.lock.lock().unwrap();
+        let mut comments = self.read()?;
+        if let Some(page) = page {
+            let page = crate::normalize_page(page);
+            comments.retain(|comment| comment.page == page);
+        }
+        Ok(comments)
+    }
+
+    pub fn add(&self, new: NewComment) -> std::io::Result<Comment> {
+        let _guard = self.lock.lock().unwrap();
+        let mut comments = self.read()?;
+        let comment = Comment {
+            id: comments.iter().map(|comment| comment.id).max().unwrap_or(0) + 1,
+            page: crate::normalize_page(&new.page),
+            quote: new.quote.trim().to_string(),
+            text: new.text.trim().to_string(),
+            author: new.author.trim().to_string(),
+            created_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        comments.push(comment.clone());
+        self.write(&comments)?;
+        Ok(comment)
+    }
+
+    /// Remove a comment, returning whether it existed
+    pub fn remove(&self, id: u64) -> std::io::Result<bool> {
+        let _guard = self.lock.lock().unwrap();
+        let mut comments = self.read()?;
+        let before = comments.len();
+        comments.retain(|comment| comment.id != id);
+        if comments.len() == before {
+            return Ok(false);
+        }
+        self.write(&comments)?;
+        Ok(true)
+    }
+
+    fn read(&self) -> std::io::Result<Vec<Comment>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) if json.trim().is_empty() => Ok(Vec::new()),
+            Ok(json) => serde_json::from_str(&json).map_err(std::io::Error::other),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Written to a temporary file and renamed over the old one, so a crash
+    // can't leave half a file
+    fn write(&self, comments: &[Comment]) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(comments).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json + "\n")?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Add the review script to the page's head
+pub(crate) fn insert_script(html: &str) -> String {
+    let script = "<script src=\"/__review.js\" defer></script>";
+    match html.find("</head>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], script, &html[pos..]),
+        None => format!("{}{}", html, script),
+    }
+}
+
+// Selecting text in the page shows a Comment button. Comments are listed in
+// a panel in the corner, and the passages they quote are highlighted.
+pub(crate) const SCRIPT: &str = r#"(() => {
+  const page = location.pathname;
+  const api = '/__comments';
+  let comments = [];
+
+  const style = document.createElement('style');
+  style.textContent = `
+    .zap-review-button { position: absolute; z-index: 10000; padding: 4px 10px; border-radius: 6px; border: none; background: #2563eb; color: #fff; font: 13px system-ui, sans-serif; cursor: pointer; box-shadow: 0 2px 8px rgba(0,0,0,.2); }
+    .zap-review-panel { position: fixed; right: 16px; bottom: 16px; z-index: 10000; width: 320px; max-height: 60vh; overflow: auto; background: #fff; color: #111; border: 1px solid #ddd; border-radius: 8px; box-shadow: 0 4px 16px rgba(0,0,0,.15); font: 13px system-ui, sans-serif; }
+    .zap-review-panel header { display: flex; justify-content: space-between; align-items: center; padding: 8px 12px; font-weight: 600; cursor: pointer; }
+    .zap-review-panel ol { list-style: none; margin: 0; padding: 0 12px 8px; }
+    .zap-review-panel li { border-top: 1px solid #eee; padding: 8px 0; }
+    .zap-review-panel blockquote { margin: 0 0 4px; padding-left: 8px; border-left: 3px solid #fbbf24; color: #555; cursor: pointer; }
+    .zap-review-panel small { color: #777; }
+    .zap-review-panel textarea { width: 100%; box-sizing: border-box; min-height: 70px; font: inherit; }
+    .zap-review-panel button { font: inherit; cursor: pointer; }
+    .zap-review-panel.collapsed ol, .zap-review-panel.collapsed form { display: none; }
+    mark.zap-review-quote { background: #fde68a; }
+  `;
+  document.head.appendChild(style);
+
+  const panel = document.createElement('aside');
+  panel.className = 'zap-review-panel collapsed';
+  panel.innerHTML = '<header><span></span><span>&#9662;</span></header><form hidden></form><ol></ol>';
+  const header = panel.querySelector('header');
+  const form = panel.querySelector('form');
+  const list = panel.querySelector('ol');
+  header.onclick = () => panel.classList.toggle('collapsed');
+
+  const button = document.createElement('button');
+  button.className = 'zap-review-button';
+  button.textContent = 'Comment';
+  button.hidden = true;
+
+  const escape = (text) => {
+    const div = document.createElement('div');
+    div.textContent = text;
+    return div.innerHTML;
+  };
+
+  // Wrap the first occurrence of the quote in a mark, when it's within one
+  // text node
+  const highlight = (comment) => {
+    if (!comment.quote) return null;
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+      acceptNode: (node) => panel.contains(node) ? NodeFilter.FILTER_REJECT : NodeFilter.FILTER_ACCEPT,
+    });
+    while (walker.nextNode()) {
+      const node = walker.currentNode;
+      const at = node.textContent.indexOf(comment.quote);
+      if (at < 0) continue;
+      const range = document.createRange();
+      range.setStart(node, at);
+      range.setEnd(node, at + comment.quote.length);
+      const mark = document.createElement('mark');
+      mark.className = 'zap-review-quote';
+      mark.title = comment.text;
+      range.surroundContents(mark);
+      return mark;
+    }
+    return null;
+  };
+
+  const unhighlight = () => {
+    document.querySelectorAll('mark.zap-review-quote').forEach((mark) => {
+      mark.replaceWith(...mark.childNodes);
+    });
+    document.body.normalize();
+  };
+
+  const render = () => {
+    unhighlight();
+    header.firstChild.textContent = comments.length === 1 ? '1 comment' : `${comments.length} comments`;
+    list.innerHTML = '';
+    for (const comment of comments) {
+      const mark = highlight(comment);
+      const item = document.createElement('li');
+      const when = new Date(comment.created_at * 1000).toLocaleString();
+      item.innerHTML = (comment.quote ? `<blockquote>${escape(comment.quote)}</blockquote>` : '') +
+        `<div>${escape(comment.text)}</div>` +
+        `<small>${escape(comment.author || 'Anonymous')}, ${escape(when)}</small> <button type="button">Resolve</button>`;
+      const quote = item.querySelector('blockquote');
+      if (quote && mark) quote.onclick = () => mark.scrollIntoView({ behavior: 'smooth', block: 'center' });
+      item.querySelector('button').onclick = async () => {
+        await fetch(`${api}/${comment.id}`, { method: 'DELETE' });
+        load();
+      };
+      list.appendChild(item);
+    }
+  };
+
+  const load = async () => {
+    const response = await fetch(`${api}?page=${encodeURIComponent(page)}`);
+    if (response.ok) {
+      comments = await response.json();
+      render();
+    }
+  };
+
+  const compose = (quote) => {
+    panel.classList.remove('collapsed');
+    form.hidden = false;
+    form.innerHTML = (quote ? `<blockquote>${escape(quote)}</blockquote>` : '') +
+      '<textarea placeholder="Comment" required></textarea>' +
+      '<div><button type="submit">Add comment</button> <button type="button">Cancel</button></div>';
+    const textarea = form.querySelector('textarea');
+    form.querySelector('button[type=button]').onclick = () => { form.hidden = true; };
+    form.onsubmit = async (event) => {
+      event.preventDefault();
+      let author = localStorage.getItem('zap-review-author');
+      if (author === null) {
+        author = prompt('Your name, shown with your comments') || '';
+        localStorage.setItem('zap-review-author', author);
+      }
+      await fetch(api, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ page, quote, text: textarea.value, author }),
+      });
+      form.hidden = true;
+      load();
+    };
+    textarea.focus();
+  };
+
+  document.addEventListener('mouseup', (event) => {
+    if (event.target === button || panel.contains(event.target)) return;
+    setTimeout(() => {
+      const selection = window.getSelection();
+      const quote = selection.toString().trim();
+      if (!quote || selection.rangeCount === 0) {
+        button.hidden = true;
+        return;
+      }
+      const rect = selection.getRangeAt(0).getBoundingClientRect();
+      button.style.left = `${rect.left + window.scrollX}px`;
+      button.style.top = `${rect.bottom + window.scrollY + 6}px`;
+      button.hidden = false;
+      button.onclick = () => {
+        button.hidden = true;
+        compose(quote);
+      };
+    });
+  });
+
+  document.body.append(panel, button);
+  load();
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new(page: &str, text: &str) -> NewComment {
+        NewComment {
+            page: page.to_string(),
+            quote: " a passage ".to_string(),
+            text: text.to_string(),
+            author: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_comment_store() {
+        let root = std::env::temp_dir().join(format!("zap-review-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("comments.json");
+
+        let store = CommentStore::new(path.clone());
+        assert!(store.list(None).unwrap().is_empty());
+        let first = store.add(new("/guide/index.html", " Typo ")).unwrap();
+        let second = store.add(new("/intro/", "Unclear")).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+        assert_eq!(first.page, crate::normalize_page("/guide/index.html"));
+        assert_eq!((first.quote.as_str(), first.text.as_str()), ("a passage", "Typo"));
+
+        let guide = store.list(Some("/guide/")).unwrap();
+        assert_eq!(guide.len(), 1);
+        assert_eq!(guide[0].text, "Typo");
+
+        // Kept in the file, for another store or a restarted server
+        let reopened = CommentStore::new(path.clone());
+        assert_eq!(reopened.list(None).unwrap().len(), 2);
+        assert!(reopened.remove(1).unwrap());
+        assert!(!reopened.remove(1).unwrap());
+        let left = store.list(None).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, 2);
+        // IDs go on from the highest left
+        assert_eq!(store.add(new("/intro/", "Again")).unwrap().id, 3);
+        assert!(!root.join("comments.json.tmp").exists());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(store.list(None).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+// Rebuilds triggered from outside, for a server publishing a site that's
+// edited elsewhere: a headless CMS saving an entry, or a push to the
+// repository on GitHub or GitLab. Requests carry a shared secret, either
+// as-is in a header or as an HMAC-SHA256 signature of the body the way
+// GitHub signs its webhooks.
+
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Whether the request proves it knows `secret`, through GitHub's
+/// `X-Hub-Signature-256`, GitLab's `X-Gitlab-Token` or a bearer token
+pub(crate) fn authorized(secret: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+    // An empty bearer token would match
+    if secret.is_empty() {
+        return false;
+    }
+    if let Some(signature) = header(headers, "x-hub-signature-256") {
+        let Some(signature) = signature.strip_prefix("sha256=").and_then(decode_hex) else {
+            return false;
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+        mac.update(body);
+        return mac.verify_slice(&signature).is_ok();
+    }
+
+    let token = header(headers, "x-gitlab-token").or_else(|| {
+        header(headers, "authorization").and_then(|value| value.strip_prefix("Bearer "))
+    });
+    token.is_some_and(|token| same(token.trim().as_bytes(), secret.as_bytes()))
+}
+
+// Compares every byte, so how long a comparison takes doesn't tell how much
+// of the secret was guessed right
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Whether the request is GitHub checking a new webhook can reach the
+/// server, rather than asking for a rebuild
+pub(crate) fn is_ping(headers: &HeaderMap) -> bool {
+    header(headers, "x-github-event") == Some("ping")
+}
+
+/// What sent the request, for the log, e.g. `GitHub push`
+pub(crate) fn describe(headers: &HeaderMap) -> String {
+    if let Some(event) = header(headers, "x-github-event") {
+        return format!("GitHub {}", event);
+    }
+    if let Some(event) = header(headers, "x-gitlab-event") {
+        return format!("GitLab {}", event.to_lowercase());
+    }
+    match header(headers, "user-agent") {
+        Some(agent) if !agent.is_empty() => agent.to_string(),
+        _ => "webhook".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (axum::http::HeaderName::from_static(name), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn signature(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let hex: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("sha256={}", hex)
+    }
+
+    #[test]
+    fn test_authorized() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+        let signed = signature("s3cret", body);
+        assert!(authorized("s3cret", &headers(&[("x-hub-signature-256", &signed)]), body));
+        assert!(authorized("s3cret", &headers(&[("x-gitlab-token", "s3cret")]), body));
+        assert!(authorized("s3cret", &headers(&[("authorization", "Bearer s3cret")]), body));
+
+        // Wrong signatures, for another secret or body, or not hex
+        let other = signature("other", body);
+        assert!(!authorized("s3cret", &headers(&[("x-hub-signature-256", &other)]), body));
+        assert!(!authorized("s3cret", &headers(&[("x-hub-signature-256", &signed)]), b"{}"));
+        assert!(!authorized("s3cret", &headers(&[("x-hub-signature-256", "sha256=zz")]), body));
+        // A signature wins over a correct token alongside it
+        let both = headers(&[("x-hub-signature-256", &other), ("x-gitlab-token", "s3cret")]);
+        assert!(!authorized("s3cret", &both, body));
+
+        // Missing, or the secret's prefix or a longer token
+        assert!(!authorized("s3cret", &headers(&[]), body));
+        assert!(!authorized("s3cret", &headers(&[("authorization", "s3cret")]), body));
+        assert!(!authorized("s3cret", &headers(&[("x-gitlab-token", "s3cre")]), body));
+        assert!(!authorized("s3cret", &headers(&[("x-gitlab-token", "s3cret!")]), body));
+
+        assert!(!authorized("", &headers(&[("authorization", "Bearer ")]), body));
+        assert!(!authorized("", &headers(&[("x-gitlab-token", "")]), body));
+    }
+}